@@ -0,0 +1,34 @@
+use crate::Message;
+use ockam_core::compat::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+/// Messages sent by a [`FileSender`](super::FileSender)
+#[derive(Debug, Serialize, Deserialize, Message)]
+pub(crate) enum FileTransferRequest {
+    /// Start or resume a transfer
+    Start {
+        transfer_id: String,
+        file_name: String,
+        size: u64,
+        chunk_size: u32,
+    },
+    /// Chunk of the file, starting at `index * chunk_size`
+    Chunk {
+        transfer_id: String,
+        index: u64,
+        data: Vec<u8>,
+    },
+    /// All the chunks were sent
+    Finish { transfer_id: String },
+}
+
+/// Messages sent back by a [`FileReceiver`](super::FileReceiver)
+#[derive(Debug, Serialize, Deserialize, Message)]
+pub(crate) enum FileTransferResponse {
+    /// Index of the next chunk expected by the receiver
+    Ack { next_chunk: u64 },
+    /// The file was received and its content was checked
+    Completed,
+    /// The transfer can't go on
+    Failed { reason: String },
+}
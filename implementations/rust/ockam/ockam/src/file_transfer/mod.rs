@@ -0,0 +1,20 @@
+//! Transfer of large files in chunks, with resumption after an interruption.
+//!
+//! A [`FileSender`] cuts a file in chunks and sends them one by one to a [`FileReceiver`],
+//! which acknowledges each of them. When the route is a secure channel, every chunk is
+//! encrypted like any other message.
+//!
+//! The receiver keeps the chunks received so far in a partial file, named after the
+//! transfer id (the SHA-256 hash of the file content). If a transfer is interrupted,
+//! sending the same file again resumes it from the last acknowledged chunk. Once all the
+//! chunks are received, the content is checked against its hash before the file is
+//! moved to its final name.
+
+mod messages;
+mod options;
+mod receiver;
+mod sender;
+
+pub use options::*;
+pub use receiver::*;
+pub use sender::*;
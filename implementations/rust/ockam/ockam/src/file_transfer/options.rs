@@ -0,0 +1,141 @@
+use core::fmt::{Debug, Formatter};
+use core::time::Duration;
+use ockam_core::compat::string::String;
+use ockam_core::compat::sync::Arc;
+
+/// Default size of a chunk, small enough to fit in a single transport message
+pub const DEFAULT_CHUNK_SIZE: u32 = 48 * 1024;
+
+/// Default time to wait for the acknowledgement of a chunk
+pub const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Progress of a file transfer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileTransferProgress {
+    transfer_id: String,
+    total_bytes: u64,
+    transferred_bytes: u64,
+    resumed_from_bytes: u64,
+}
+
+impl FileTransferProgress {
+    pub(super) fn new(transfer_id: String, total_bytes: u64, resumed_from_bytes: u64) -> Self {
+        Self {
+            transfer_id,
+            total_bytes,
+            transferred_bytes: resumed_from_bytes,
+            resumed_from_bytes,
+        }
+    }
+
+    pub(super) fn set_transferred_bytes(&mut self, transferred_bytes: u64) {
+        self.transferred_bytes = transferred_bytes;
+    }
+
+    /// Id of the transfer, which is the hex-encoded SHA-256 hash of the file content
+    pub fn transfer_id(&self) -> &str {
+        &self.transfer_id
+    }
+
+    /// Size of the file
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// Number of bytes acknowledged by the receiver, including the ones acknowledged
+    /// before the transfer was resumed
+    pub fn transferred_bytes(&self) -> u64 {
+        self.transferred_bytes
+    }
+
+    /// Number of bytes the receiver already had when the transfer started,
+    /// 0 if the transfer was not resumed
+    pub fn resumed_from_bytes(&self) -> u64 {
+        self.resumed_from_bytes
+    }
+
+    /// Return true if the whole file was acknowledged
+    pub fn is_complete(&self) -> bool {
+        self.transferred_bytes == self.total_bytes
+    }
+}
+
+/// Callback invoked each time a chunk is acknowledged
+pub type FileTransferProgressCallback = Arc<dyn Fn(&FileTransferProgress) + Send + Sync>;
+
+/// Options of a [`FileSender`](super::FileSender)
+#[derive(Clone)]
+pub struct FileTransferOptions {
+    pub(super) chunk_size: u32,
+    pub(super) ack_timeout: Duration,
+    pub(super) progress_callback: Option<FileTransferProgressCallback>,
+}
+
+impl Debug for FileTransferOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FileTransferOptions")
+            .field("chunk_size", &self.chunk_size)
+            .field("ack_timeout", &self.ack_timeout)
+            .finish()
+    }
+}
+
+impl Default for FileTransferOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileTransferOptions {
+    /// Default options with [`DEFAULT_CHUNK_SIZE`] and [`DEFAULT_ACK_TIMEOUT`]
+    pub fn new() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            progress_callback: None,
+        }
+    }
+
+    /// Set the size of the chunks.
+    ///
+    /// A transfer can only be resumed with the same chunk size.
+    pub fn with_chunk_size(mut self, chunk_size: u32) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set the time to wait for the acknowledgement of each chunk.
+    /// The transfer is interrupted if an acknowledgement doesn't arrive in time.
+    pub fn with_ack_timeout(mut self, ack_timeout: Duration) -> Self {
+        self.ack_timeout = ack_timeout;
+        self
+    }
+
+    /// Set a callback invoked each time a chunk is acknowledged
+    pub fn with_progress_callback(
+        mut self,
+        progress_callback: impl Fn(&FileTransferProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress_callback = Some(Arc::new(progress_callback));
+        self
+    }
+}
+
+/// Options of a [`FileReceiver`](super::FileReceiver)
+#[derive(Clone, Debug, Default)]
+pub struct FileReceiverOptions {
+    pub(super) overwrite: bool,
+}
+
+impl FileReceiverOptions {
+    /// Default options, which don't overwrite existing files
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the files which already exist with the received ones
+    pub fn with_overwrite(mut self) -> Self {
+        self.overwrite = true;
+        self
+    }
+}
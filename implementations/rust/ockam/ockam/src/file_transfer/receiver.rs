@@ -0,0 +1,279 @@
+use crate::compat::tokio::task;
+use crate::file_transfer::messages::{FileTransferRequest, FileTransferResponse};
+use crate::file_transfer::FileReceiverOptions;
+use crate::Context;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::collections::HashMap;
+use ockam_core::{Address, Result, Routed, Worker};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// State of a transfer in progress
+struct IncomingTransfer {
+    file_name: String,
+    size: u64,
+    chunk_size: u32,
+    file: File,
+    next_chunk: u64,
+}
+
+/// Worker receiving the files sent by a [`FileSender`](super::FileSender)
+///
+/// Files are written to a directory, under the name given by the sender.
+/// Chunks are first written to a partial file so that an interrupted transfer can be resumed,
+/// including after a restart of the receiver.
+///
+/// A transfer fails if a file with the same name already exists in the directory, unless
+/// [`FileReceiverOptions::with_overwrite`] is set.
+pub struct FileReceiver {
+    directory: PathBuf,
+    overwrite: bool,
+    transfers: HashMap<String, IncomingTransfer>,
+}
+
+impl FileReceiver {
+    /// Start a [`FileReceiver`] storing the received files in `directory`
+    pub async fn create(
+        ctx: &Context,
+        address: impl Into<Address>,
+        directory: impl Into<PathBuf>,
+        options: FileReceiverOptions,
+    ) -> Result<()> {
+        let receiver = Self {
+            directory: directory.into(),
+            overwrite: options.overwrite,
+            transfers: Default::default(),
+        };
+        ctx.start_worker(address.into(), receiver).await
+    }
+
+    fn partial_file_path(&self, transfer_id: &str) -> PathBuf {
+        self.directory.join(format!(".{transfer_id}.part"))
+    }
+
+    async fn start(
+        &mut self,
+        transfer_id: String,
+        file_name: String,
+        size: u64,
+        chunk_size: u32,
+    ) -> io::Result<FileTransferResponse> {
+        // The transfer id is used in a path, so it must be a SHA-256 hash
+        if transfer_id.len() != 2 * Sha256::output_size()
+            || !transfer_id.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Ok(failed(format!("invalid transfer id {transfer_id}")));
+        }
+        // Only accept plain file names, to stay in our directory
+        if chunk_size == 0
+            || Path::new(&file_name).file_name() != Some(file_name.as_ref())
+            || file_name.starts_with('.')
+        {
+            return Ok(failed(format!("invalid file name {file_name}")));
+        }
+
+        let file_path = self.directory.join(&file_name);
+        let partial_file_path = self.partial_file_path(&transfer_id);
+        let overwrite = self.overwrite;
+        let opened = blocking(move || {
+            // Fail before receiving anything if the file can't be stored
+            if !overwrite && file_path.try_exists()? {
+                return Ok(None);
+            }
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .open(partial_file_path)?;
+
+            // Only keep the complete chunks received by a previous attempt
+            let received = file.metadata()?.len().min(size);
+            let next_chunk = received / chunk_size as u64;
+            file.set_len(next_chunk * chunk_size as u64)?;
+            Ok(Some((file, next_chunk)))
+        })
+        .await?;
+        let (file, next_chunk) = match opened {
+            Some(opened) => opened,
+            None => return Ok(failed(format!("the file {file_name} already exists"))),
+        };
+        if next_chunk > 0 {
+            debug!("resuming the transfer {transfer_id} of {file_name} from chunk {next_chunk}");
+        }
+
+        self.transfers.insert(
+            transfer_id,
+            IncomingTransfer {
+                file_name,
+                size,
+                chunk_size,
+                file,
+                next_chunk,
+            },
+        );
+
+        Ok(FileTransferResponse::Ack { next_chunk })
+    }
+
+    async fn chunk(
+        &mut self,
+        transfer_id: String,
+        index: u64,
+        data: Vec<u8>,
+    ) -> io::Result<FileTransferResponse> {
+        let mut transfer = match self.transfers.remove(&transfer_id) {
+            Some(transfer) => transfer,
+            None => return Ok(failed(format!("unknown transfer {transfer_id}"))),
+        };
+
+        // Duplicated or out of order chunk, tell the sender where to resume
+        if index != transfer.next_chunk {
+            let next_chunk = transfer.next_chunk;
+            self.transfers.insert(transfer_id, transfer);
+            return Ok(FileTransferResponse::Ack { next_chunk });
+        }
+
+        let offset = index * transfer.chunk_size as u64;
+        let expected_length = transfer
+            .size
+            .saturating_sub(offset)
+            .min(transfer.chunk_size as u64);
+        if expected_length == 0 || data.len() as u64 != expected_length {
+            let response = failed(format!(
+                "invalid chunk {index} of the transfer {transfer_id}"
+            ));
+            self.transfers.insert(transfer_id, transfer);
+            return Ok(response);
+        }
+
+        // The transfer is forgotten if the chunk can't be written, and the sender must
+        // start it again
+        let mut transfer = blocking(move || {
+            transfer.file.seek(SeekFrom::Start(offset))?;
+            transfer.file.write_all(&data)?;
+            Ok(transfer)
+        })
+        .await?;
+        transfer.next_chunk += 1;
+
+        let next_chunk = transfer.next_chunk;
+        self.transfers.insert(transfer_id, transfer);
+        Ok(FileTransferResponse::Ack { next_chunk })
+    }
+
+    async fn finish(&mut self, transfer_id: String) -> io::Result<FileTransferResponse> {
+        let transfer = match self.transfers.remove(&transfer_id) {
+            Some(transfer) => transfer,
+            None => return Ok(failed(format!("unknown transfer {transfer_id}"))),
+        };
+        let partial_file_path = self.partial_file_path(&transfer_id);
+        let file_path = self.directory.join(&transfer.file_name);
+        let overwrite = self.overwrite;
+
+        blocking(move || {
+            let IncomingTransfer {
+                file_name,
+                size,
+                mut file,
+                ..
+            } = transfer;
+            file.flush()?;
+
+            if file.metadata()?.len() != size {
+                // Keep the partial file, the transfer can still be resumed
+                return Ok(failed(format!("the transfer {transfer_id} is incomplete")));
+            }
+
+            file.seek(SeekFrom::Start(0))?;
+            if hex::encode(sha256(&mut file)?) != transfer_id {
+                fs::remove_file(&partial_file_path)?;
+                return Ok(failed(format!(
+                    "the content of {file_name} doesn't match its hash"
+                )));
+            }
+            drop(file);
+
+            if overwrite {
+                fs::rename(&partial_file_path, &file_path)?;
+            } else {
+                // Unlike a rename, a link fails if the file was created during the transfer
+                match fs::hard_link(&partial_file_path, &file_path) {
+                    Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                        // Keep the partial file, the transfer can be resumed once the
+                        // existing file is removed
+                        return Ok(failed(format!("the file {file_name} already exists")));
+                    }
+                    res => res?,
+                }
+                fs::remove_file(&partial_file_path)?;
+            }
+            info!("received the file {file_name}");
+
+            Ok(FileTransferResponse::Completed)
+        })
+        .await
+    }
+}
+
+#[crate::worker]
+impl Worker for FileReceiver {
+    type Message = FileTransferRequest;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<FileTransferRequest>,
+    ) -> Result<()> {
+        let return_route = msg.return_route();
+        let response = match msg.body() {
+            FileTransferRequest::Start {
+                transfer_id,
+                file_name,
+                size,
+                chunk_size,
+            } => self.start(transfer_id, file_name, size, chunk_size).await,
+            FileTransferRequest::Chunk {
+                transfer_id,
+                index,
+                data,
+            } => self.chunk(transfer_id, index, data).await,
+            FileTransferRequest::Finish { transfer_id } => self.finish(transfer_id).await,
+        };
+
+        let response = response.unwrap_or_else(|err| {
+            warn!("file transfer error: {err}");
+            failed(err.to_string())
+        });
+
+        ctx.send(return_route, response).await
+    }
+}
+
+fn failed(reason: String) -> FileTransferResponse {
+    FileTransferResponse::Failed { reason }
+}
+
+/// Run some file system operations on a thread where blocking is acceptable,
+/// so that they don't block the workers running on the same thread
+pub(super) async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> io::Result<T> {
+    task::spawn_blocking(f)
+        .await
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
+}
+
+/// Compute the SHA-256 hash of some content
+pub(super) fn sha256(reader: &mut impl Read) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        match reader.read(&mut buffer)? {
+            0 => return Ok(hasher.finalize().into()),
+            n => hasher.update(&buffer[..n]),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crate::file_transfer::messages::{FileTransferRequest, FileTransferResponse};
+use crate::file_transfer::receiver::{blocking, sha256};
+use crate::file_transfer::{FileTransferOptions, FileTransferProgress};
+use crate::{Context, MessageSendReceiveOptions};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result, Route};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Sender of a file to a [`FileReceiver`](super::FileReceiver)
+pub struct FileSender;
+
+impl FileSender {
+    /// Send a file to the [`FileReceiver`](super::FileReceiver) at the end of `route`,
+    /// and return the final progress of the transfer once the receiver checked the file.
+    ///
+    /// Chunks are sent one at a time, each one waiting for the previous one to be acknowledged.
+    /// If an acknowledgement doesn't arrive in time the transfer is interrupted and an error
+    /// is returned. Calling this function again with the same file resumes the transfer from
+    /// the last chunk acknowledged by the receiver.
+    pub async fn send(
+        ctx: &Context,
+        route: impl Into<Route>,
+        path: impl AsRef<Path>,
+        options: FileTransferOptions,
+    ) -> Result<FileTransferProgress> {
+        let route = route.into();
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| {
+                Error::new(
+                    Origin::Ockam,
+                    Kind::Invalid,
+                    format!("invalid file name {}", path.display()),
+                )
+            })?
+            .to_string();
+
+        let path_buf = PathBuf::from(path);
+        let (mut file, size, transfer_id) = blocking(move || {
+            let mut file = File::open(path_buf)?;
+            let size = file.metadata()?.len();
+            let transfer_id = hex::encode(sha256(&mut file)?);
+            Ok((file, size, transfer_id))
+        })
+        .await
+        .map_err(io_error)?;
+        let chunk_size = options.chunk_size as u64;
+
+        let start = FileTransferRequest::Start {
+            transfer_id: transfer_id.clone(),
+            file_name: file_name.clone(),
+            size,
+            chunk_size: options.chunk_size,
+        };
+        let mut next_chunk = Self::request_ack(ctx, &route, start, &options).await?;
+
+        let mut progress = FileTransferProgress::new(
+            transfer_id.clone(),
+            size,
+            (next_chunk * chunk_size).min(size),
+        );
+        debug!(
+            "sending {file_name} ({size} bytes), starting at byte {}",
+            progress.resumed_from_bytes()
+        );
+
+        while next_chunk * chunk_size < size {
+            let offset = next_chunk * chunk_size;
+            let length = (size - offset).min(chunk_size) as usize;
+            let data;
+            (file, data) = blocking(move || {
+                let mut data = vec![0u8; length];
+                file.seek(SeekFrom::Start(offset))?;
+                file.read_exact(&mut data)?;
+                Ok((file, data))
+            })
+            .await
+            .map_err(io_error)?;
+
+            let chunk = FileTransferRequest::Chunk {
+                transfer_id: transfer_id.clone(),
+                index: next_chunk,
+                data,
+            };
+            next_chunk = Self::request_ack(ctx, &route, chunk, &options).await?;
+
+            progress.set_transferred_bytes((next_chunk * chunk_size).min(size));
+            if let Some(progress_callback) = &options.progress_callback {
+                progress_callback(&progress);
+            }
+        }
+
+        let finish = FileTransferRequest::Finish {
+            transfer_id: transfer_id.clone(),
+        };
+        match Self::request(ctx, &route, finish, &options).await? {
+            FileTransferResponse::Completed => {
+                info!("sent the file {file_name}");
+                Ok(progress)
+            }
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    /// Send a request and return the index of the next chunk expected by the receiver
+    async fn request_ack(
+        ctx: &Context,
+        route: &Route,
+        request: FileTransferRequest,
+        options: &FileTransferOptions,
+    ) -> Result<u64> {
+        match Self::request(ctx, route, request, options).await? {
+            FileTransferResponse::Ack { next_chunk } => Ok(next_chunk),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn request(
+        ctx: &Context,
+        route: &Route,
+        request: FileTransferRequest,
+        options: &FileTransferOptions,
+    ) -> Result<FileTransferResponse> {
+        Ok(ctx
+            .send_and_receive_extended::<FileTransferResponse>(
+                route.clone(),
+                request,
+                MessageSendReceiveOptions::new().with_timeout(options.ack_timeout),
+            )
+            .await?
+            .body())
+    }
+}
+
+fn unexpected_response(response: FileTransferResponse) -> Error {
+    let reason = match response {
+        FileTransferResponse::Failed { reason } => reason,
+        response => format!("unexpected response {response:?}"),
+    };
+    Error::new(
+        Origin::Ockam,
+        Kind::Protocol,
+        format!("file transfer failed: {reason}"),
+    )
+}
+
+fn io_error(err: io::Error) -> Error {
+    Error::new(Origin::Ockam, Kind::Io, err)
+}
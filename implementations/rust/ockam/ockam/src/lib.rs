@@ -60,8 +60,9 @@ pub use ockam_macros::{node, test};
 
 // Export node implementation
 pub use ockam_node::{
-    debugger, Context, DelayedEvent, Executor, MessageReceiveOptions, MessageSendReceiveOptions,
-    NodeBuilder, WorkerBuilder,
+    debugger, Context, DelayedEvent, DispatchBatching, DispatchStats, Executor, MailboxCapacity,
+    MessageReceiveOptions, MessageSendReceiveOptions, Metrics, NodeBuilder, NoopMetrics,
+    OverflowPolicy, RouteTraceReceiver, WorkerBuilder,
 };
 // ---
 
@@ -80,6 +81,8 @@ pub use system::{SystemBuilder, SystemHandler, WorkerSystem};
 pub use unique::unique_with_prefix;
 
 pub mod channel;
+#[cfg(feature = "std")]
+pub mod file_transfer;
 pub mod pipe;
 pub mod pipe2;
 pub mod protocols;
@@ -95,10 +98,13 @@ pub use ockam_identity::storage::lmdb_storage::*;
 
 pub use ockam_core::{
     allow, deny, errcode, route, Address, Any, AsyncTryClone, Encoded, Error, LocalMessage,
-    Mailbox, Mailboxes, Message, Processor, ProtocolId, Result, Route, Routed, TransportMessage,
-    Worker,
+    Mailbox, Mailboxes, Message, MessageHeaders, Processor, ProtocolId, Result, Route, RouteTrace,
+    Routed, TraceHop, TransportMessage, Worker,
 };
 
+#[cfg(feature = "std")]
+pub use ockam_core::Codec;
+
 /// Access Control
 pub mod access_control {
     pub use ockam_core::access_control::*;
@@ -0,0 +1,238 @@
+use crate::{Context, Message, OckamError, Result, Routed, Worker};
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::flow_control::FlowControlId;
+use ockam_core::{
+    Address, AllowAll, Any, DenyAll, IncomingAccessControl, Mailbox, Mailboxes, RelayMessage, Route,
+};
+use ockam_node::WorkerBuilder;
+use serde::{Deserialize, Serialize};
+
+/// Options for a [`Forwarder`]
+///
+/// By default the route updates are denied, see
+/// [`ForwarderOptions::with_control_incoming_access_control`]
+pub struct ForwarderOptions {
+    incoming_access_control: Arc<dyn IncomingAccessControl>,
+    control_incoming_access_control: Arc<dyn IncomingAccessControl>,
+    consumer: Vec<FlowControlId>,
+}
+
+impl ForwarderOptions {
+    /// Default constructor without Access Control on the messages to forward
+    pub fn new() -> Self {
+        Self {
+            incoming_access_control: Arc::new(AllowAll),
+            control_incoming_access_control: Arc::new(DenyAll),
+            consumer: vec![],
+        }
+    }
+
+    /// Mark that this Forwarder is a Consumer for the given [`FlowControlId`], so that it
+    /// can receive the messages coming through that transport or secure channel
+    pub fn as_consumer(mut self, id: &FlowControlId) -> Self {
+        self.consumer.push(id.clone());
+
+        self
+    }
+
+    /// Set the Incoming Access Control of the messages to forward
+    pub fn with_incoming_access_control(
+        mut self,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Self {
+        self.incoming_access_control = access_control;
+        self
+    }
+
+    /// Set the Incoming Access Control of the route updates.
+    /// Only trusted peers must be allowed to redirect the forwarded messages, the other ones
+    /// get an [`OckamError::ForwarderRouteUpdateDenied`] from [`Forwarder::update_route`]
+    pub fn with_control_incoming_access_control(
+        mut self,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Self {
+        self.control_incoming_access_control = access_control;
+        self
+    }
+}
+
+impl Default for ForwarderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Addresses of a started [`Forwarder`]
+#[derive(Clone, Debug)]
+pub struct ForwarderInfo {
+    address: Address,
+    control_address: Address,
+}
+
+impl ForwarderInfo {
+    /// Address receiving the messages to forward
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Address receiving the route updates, see [`Forwarder::update_route`]
+    pub fn control_address(&self) -> &Address {
+        &self.control_address
+    }
+}
+
+/// Request to forward the next messages to a new route
+#[derive(Serialize, Deserialize, Message)]
+struct UpdateRoute {
+    onward_route: Route,
+}
+
+/// Outcome of an [`UpdateRoute`] request
+#[derive(Serialize, Deserialize, Message)]
+enum UpdateRouteResponse {
+    Updated,
+    Denied,
+}
+
+/// A worker forwarding all the messages it receives to a configured route.
+///
+/// It gives a stable address to a service which can't be reached directly, for example a
+/// mobile service connecting to a rendezvous node from changing networks. A message sent to
+/// `route![forwarder, ...rest]` is forwarded to `onward_route` followed by `rest`, with its
+/// return route untouched, so that replies flow back to the original sender.
+///
+/// When the service reconnects, the onward route is replaced with [`Forwarder::update_route`].
+/// Messages are handled in order: the ones received before the update are forwarded to the
+/// previous route and none are dropped.
+///
+/// The forwarder doesn't change the flow controls: like for the relay service, the
+/// forwarder and the senders of the replies must be added explicitly as consumers of the
+/// transports or secure channels the messages come from, see [`ForwarderOptions::as_consumer`].
+pub struct Forwarder {
+    address: Address,
+    onward_route: Route,
+    control_incoming_access_control: Arc<dyn IncomingAccessControl>,
+}
+
+impl Forwarder {
+    /// Start a forwarder at `address`, forwarding messages to `onward_route`
+    pub async fn create(
+        ctx: &Context,
+        address: impl Into<Address>,
+        onward_route: impl Into<Route>,
+        options: ForwarderOptions,
+    ) -> Result<ForwarderInfo> {
+        let address = address.into();
+        let control_address = Address::random_tagged("Forwarder.control");
+
+        for id in &options.consumer {
+            ctx.flow_controls().add_consumer(address.clone(), id);
+        }
+
+        // The route updates are authorized by the forwarder itself, so that the denied
+        // senders get an answer instead of waiting for one forever
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(
+                address.clone(),
+                options.incoming_access_control,
+                Arc::new(AllowAll),
+            ),
+            vec![Mailbox::new(
+                control_address.clone(),
+                Arc::new(AllowAll),
+                Arc::new(AllowAll),
+            )],
+        );
+
+        let forwarder = Self {
+            address: address.clone(),
+            onward_route: onward_route.into(),
+            control_incoming_access_control: options.control_incoming_access_control,
+        };
+        WorkerBuilder::new(forwarder)
+            .with_mailboxes(mailboxes)
+            .start(ctx)
+            .await?;
+
+        Ok(ForwarderInfo {
+            address,
+            control_address,
+        })
+    }
+
+    /// Replace the onward route of the forwarder reachable with `control_route`.
+    /// Returns once the messages are forwarded to the new route, or fails with
+    /// [`OckamError::ForwarderRouteUpdateDenied`] if `ctx` isn't allowed to update it,
+    /// see [`ForwarderOptions::with_control_incoming_access_control`]
+    pub async fn update_route(
+        ctx: &Context,
+        control_route: impl Into<Route>,
+        onward_route: impl Into<Route>,
+    ) -> Result<()> {
+        let response = ctx
+            .send_and_receive(
+                control_route,
+                UpdateRoute {
+                    onward_route: onward_route.into(),
+                },
+            )
+            .await?;
+
+        match response {
+            UpdateRouteResponse::Updated => Ok(()),
+            UpdateRouteResponse::Denied => Err(OckamError::ForwarderRouteUpdateDenied.into()),
+        }
+    }
+
+    async fn forward(&self, ctx: &Context, msg: Routed<Any>) -> Result<()> {
+        let mut message = msg.into_local_message();
+        let transport_message = message.transport_mut();
+
+        transport_message.onward_route.step()?;
+        transport_message
+            .onward_route
+            .modify()
+            .prepend_route(self.onward_route.clone());
+
+        ctx.forward(message).await
+    }
+}
+
+#[crate::worker]
+impl Worker for Forwarder {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        if msg.msg_addr() == self.address {
+            return self.forward(ctx, msg).await;
+        }
+
+        let return_route = msg.return_route();
+        let relay_msg =
+            RelayMessage::new(msg.src_addr(), msg.msg_addr(), msg.local_message().clone());
+        if !self
+            .control_incoming_access_control
+            .is_authorized(&relay_msg)
+            .await?
+        {
+            warn!(
+                "Route update of the forwarder {} denied for {}",
+                self.address,
+                msg.src_addr()
+            );
+            return ctx.send(return_route, UpdateRouteResponse::Denied).await;
+        }
+
+        let update = msg.cast::<UpdateRoute>()?.body();
+        info!(
+            "Forwarder {} now forwards messages to {}",
+            self.address, update.onward_route
+        );
+        self.onward_route = update.onward_route;
+
+        ctx.send(return_route, UpdateRouteResponse::Updated).await
+    }
+}
@@ -1,7 +1,10 @@
 //! A collection of utility workers for various use cases.
 //!
 //! Currently, this contains an echoer worker which is used in many examples,
-//! and is useful for debugging.
+//! and is useful for debugging, and a forwarder giving a stable address to a
+//! service reachable through changing routes.
 mod echoer;
+mod forwarder;
 
 pub use echoer::*;
+pub use forwarder::*;
@@ -0,0 +1,158 @@
+use core::time::Duration;
+use ockam::file_transfer::{FileReceiver, FileReceiverOptions, FileSender, FileTransferOptions};
+use ockam::identity::{
+    secure_channels, SecureChannelListenerOptions, SecureChannelOptions, TrustEveryonePolicy,
+};
+use ockam_core::compat::rand::{self, RngCore};
+use ockam_core::{route, Any, Result, Routed, Worker};
+use ockam_node::Context;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Forward a limited number of messages, then drop all of them
+struct Interrupter {
+    remaining: Arc<AtomicUsize>,
+}
+
+#[ockam::worker]
+impl Worker for Interrupter {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        if self.remaining.load(Ordering::Relaxed) == 0 {
+            return Ok(());
+        }
+        self.remaining.fetch_sub(1, Ordering::Relaxed);
+
+        let mut message = msg.into_local_message();
+        message.transport_mut().onward_route.step()?;
+        ctx.forward(message).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn file_transfer__interrupted__should_resume(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().with_trust_policy(TrustEveryonePolicy),
+        )
+        .await?;
+    let channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_trust_policy(TrustEveryonePolicy),
+        )
+        .await?;
+
+    let source_dir = tempfile::tempdir().unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+
+    let mut content = vec![0u8; 1024 * 1024 + 123];
+    rand::thread_rng().fill_bytes(&mut content);
+    let source = source_dir.path().join("large_file.bin");
+    std::fs::write(&source, &content).unwrap();
+
+    FileReceiver::create(
+        ctx,
+        "file_receiver",
+        target_dir.path(),
+        FileReceiverOptions::new(),
+    )
+    .await?;
+    ctx.flow_controls()
+        .add_consumer("file_receiver", bob_listener.flow_control_id());
+
+    // Only let the start message and 5 chunks go through
+    let remaining = Arc::new(AtomicUsize::new(6));
+    ctx.start_worker(
+        "interrupter",
+        Interrupter {
+            remaining: remaining.clone(),
+        },
+    )
+    .await?;
+    ctx.flow_controls()
+        .add_consumer("interrupter", bob_listener.flow_control_id());
+
+    let chunk_size = 64 * 1024;
+    let options = FileTransferOptions::new()
+        .with_chunk_size(chunk_size)
+        .with_ack_timeout(Duration::from_millis(500));
+
+    let res = FileSender::send(
+        ctx,
+        route![channel.clone(), "interrupter", "file_receiver"],
+        &source,
+        options.clone(),
+    )
+    .await;
+    assert!(res.is_err(), "the transfer should be interrupted");
+    assert!(!target_dir.path().join("large_file.bin").exists());
+
+    // Send the same file again, the transfer resumes after the acknowledged chunks
+    let progress =
+        FileSender::send(ctx, route![channel, "file_receiver"], &source, options).await?;
+    assert!(progress.is_complete());
+    assert_eq!(progress.total_bytes(), content.len() as u64);
+    assert_eq!(progress.resumed_from_bytes(), 5 * chunk_size as u64);
+
+    let received = std::fs::read(target_dir.path().join("large_file.bin")).unwrap();
+    assert_eq!(received, content);
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn file_transfer__existing_file__should_only_be_replaced_with_overwrite(
+    ctx: &mut Context,
+) -> Result<()> {
+    let source_dir = tempfile::tempdir().unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+
+    let source = source_dir.path().join("file.txt");
+    std::fs::write(&source, b"new content").unwrap();
+    let target = target_dir.path().join("file.txt");
+    std::fs::write(&target, b"existing content").unwrap();
+
+    FileReceiver::create(
+        ctx,
+        "receiver",
+        target_dir.path(),
+        FileReceiverOptions::new(),
+    )
+    .await?;
+    let res = FileSender::send(ctx, route!["receiver"], &source, FileTransferOptions::new()).await;
+    assert!(res.is_err(), "an existing file must not be replaced");
+    assert_eq!(std::fs::read(&target).unwrap(), b"existing content");
+
+    FileReceiver::create(
+        ctx,
+        "overwriting_receiver",
+        target_dir.path(),
+        FileReceiverOptions::new().with_overwrite(),
+    )
+    .await?;
+    FileSender::send(
+        ctx,
+        route!["overwriting_receiver"],
+        &source,
+        FileTransferOptions::new(),
+    )
+    .await?;
+    assert_eq!(std::fs::read(&target).unwrap(), b"new content");
+
+    ctx.stop().await
+}
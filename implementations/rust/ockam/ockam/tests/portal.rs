@@ -0,0 +1,134 @@
+use ockam::compat::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use ockam::compat::tokio::net::{TcpListener, TcpStream};
+use ockam::compat::tokio::time::timeout;
+use ockam::identity::{secure_channels, SecureChannelListenerOptions, SecureChannelOptions};
+use ockam_core::{route, Result};
+use ockam_node::Context;
+use ockam_transport_tcp::{
+    TcpConnectionOptions, TcpInletOptions, TcpListenerOptions, TcpOutletOptions, TcpTransport,
+};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Create a portal whose inlet and outlet are connected by a secure channel over TCP,
+/// and return the address of the inlet and the listener of the service behind the outlet
+async fn setup_portal(ctx: &Context) -> Result<(SocketAddr, TcpListener)> {
+    let tcp = TcpTransport::create(ctx).await?;
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    // Outlet side
+    let tcp_listener = tcp.listen("127.0.0.1:0", TcpListenerOptions::new()).await?;
+    let secure_channel_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().as_consumer(tcp_listener.flow_control_id()),
+        )
+        .await?;
+    let service = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    tcp.create_outlet(
+        "outlet",
+        service.local_addr().unwrap().to_string(),
+        TcpOutletOptions::new().as_consumer(secure_channel_listener.flow_control_id()),
+    )
+    .await?;
+
+    // Inlet side
+    let connection = tcp
+        .connect(tcp_listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![connection, "bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    let (inlet_address, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route![channel, "outlet"],
+            TcpInletOptions::new(),
+        )
+        .await?;
+
+    Ok((inlet_address, service))
+}
+
+/// Read from the stream until it is closed by the other side
+async fn read_until_closed(stream: &mut TcpStream) -> Vec<u8> {
+    let mut received = vec![];
+    timeout(Duration::from_secs(10), stream.read_to_end(&mut received))
+        .await
+        .expect("the connection should be closed")
+        .unwrap();
+    received
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 30000)]
+async fn portal_over_secure_channel__concurrent_connections(ctx: &mut Context) -> Result<()> {
+    let (inlet_address, service) = setup_portal(ctx).await?;
+
+    // The service echoes the bytes of each connection until the client closes it
+    let server = ockam::compat::tokio::spawn(async move {
+        for _ in 0..3 {
+            let (mut stream, _) = service.accept().await.unwrap();
+            ockam::compat::tokio::spawn(async move {
+                let (mut read_half, mut write_half) = stream.split();
+                ockam::compat::tokio::io::copy(&mut read_half, &mut write_half)
+                    .await
+                    .unwrap();
+            });
+        }
+    });
+
+    let mut clients = vec![];
+    for n in 0..3u8 {
+        clients.push(ockam::compat::tokio::spawn(async move {
+            let mut stream = TcpStream::connect(inlet_address).await.unwrap();
+            let payload = vec![n; 64 * 1024];
+            stream.write_all(&payload).await.unwrap();
+
+            let mut echoed = vec![0u8; payload.len()];
+            timeout(Duration::from_secs(10), stream.read_exact(&mut echoed))
+                .await
+                .expect("the payload should be echoed")
+                .unwrap();
+            assert_eq!(echoed, payload);
+        }));
+    }
+    for client in clients {
+        client.await.unwrap();
+    }
+    server.await.unwrap();
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 30000)]
+async fn portal_over_secure_channel__close_propagation(ctx: &mut Context) -> Result<()> {
+    let (inlet_address, service) = setup_portal(ctx).await?;
+
+    // The service closes the connection: the inlet closes the client connection
+    let mut client = TcpStream::connect(inlet_address).await.unwrap();
+    let (mut stream, _) = service.accept().await.unwrap();
+    stream.write_all(b"bye").await.unwrap();
+    drop(stream);
+    assert_eq!(read_until_closed(&mut client).await, b"bye");
+
+    // The client closes the connection: the outlet closes the service connection
+    let mut client = TcpStream::connect(inlet_address).await.unwrap();
+    let (mut stream, _) = service.accept().await.unwrap();
+    client.write_all(b"bye").await.unwrap();
+    drop(client);
+    assert_eq!(read_until_closed(&mut stream).await, b"bye");
+
+    ctx.stop().await
+}
@@ -1,10 +1,11 @@
 use ockam::identity::{secure_channels, SecureChannelListenerOptions, SecureChannelOptions};
 use ockam::remote::{RemoteRelay, RemoteRelayOptions};
-use ockam::workers::Echoer;
-use ockam::{RelayService, RelayServiceOptions};
-use ockam_core::{route, AllowAll, Result};
+use ockam::workers::{Echoer, Forwarder, ForwarderOptions};
+use ockam::{OckamError, RelayService, RelayServiceOptions};
+use ockam_core::{route, AllowAll, AllowSourceAddress, Any, Result, Routed, Worker};
 use ockam_node::{Context, MessageReceiveOptions};
 use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions, TcpTransport};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 // Node creates a Relay service and a Remote Relay, Echoer is reached through the Relay. No flow control
@@ -253,3 +254,225 @@ async fn test4(ctx: &mut Context) -> Result<()> {
 
     ctx.stop().await
 }
+
+// Forwards messages to the next hop and keeps a copy of every forwarded payload
+struct Tap {
+    payloads: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+#[ockam::worker]
+impl Worker for Tap {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut message = msg.into_local_message();
+        let transport_message = message.transport_mut();
+        self.payloads
+            .lock()
+            .unwrap()
+            .push(transport_message.payload.clone());
+
+        transport_message.onward_route.step()?;
+        transport_message
+            .return_route
+            .modify()
+            .prepend(ctx.address());
+
+        ctx.forward(message).await
+    }
+}
+
+// Cloud:
+//  - Hosts a Relay service
+//  - Listens on a tcp port
+//
+// Server:
+//  - Connects to the Cloud using tcp with a flow control
+//  - Creates a dynamic Relay (second hop)
+//  - Runs a Secure Channel listener and an Echoer
+//
+// Middle:
+//  - Connects to the Cloud using tcp with a flow control
+//  - Creates a dynamic Relay (first hop) forwarding through a Tap to the second hop
+//
+// Client:
+//  - Connects to the Cloud using tcp
+//  - Creates an end-to-end secure channel to the Server through both Relays
+//  - Reaches Server's Echoer, the Tap only sees encrypted payloads
+#[ockam_macros::test]
+async fn test5(ctx: &mut Context) -> Result<()> {
+    // Cloud
+    let cloud_tcp_listener_options = TcpListenerOptions::new();
+    let options = RelayServiceOptions::new()
+        .service_as_consumer(&cloud_tcp_listener_options.spawner_flow_control_id())
+        .relay_as_consumer(&cloud_tcp_listener_options.spawner_flow_control_id());
+    RelayService::create(ctx, "forwarding_service", options).await?;
+
+    let cloud_tcp = TcpTransport::create(ctx).await?;
+    let cloud_listener = cloud_tcp
+        .listen("127.0.0.1:0", cloud_tcp_listener_options)
+        .await?;
+
+    // Server
+    let server_tcp_options = TcpConnectionOptions::new();
+    let server_secure_channel_listener_options =
+        SecureChannelListenerOptions::new().as_consumer(&server_tcp_options.flow_control_id());
+
+    ctx.start_worker("echoer", Echoer).await?;
+    ctx.flow_controls().add_consumer(
+        "echoer",
+        &server_secure_channel_listener_options.spawner_flow_control_id(),
+    );
+
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let server_identity = identities_creation.create_identity().await?;
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            server_identity.identifier(),
+            "server_listener",
+            server_secure_channel_listener_options,
+        )
+        .await?;
+
+    let server_tcp = TcpTransport::create(ctx).await?;
+    let cloud_server_connection = server_tcp
+        .connect(cloud_listener.socket_string(), server_tcp_options)
+        .await?;
+    let second_hop =
+        RemoteRelay::create(ctx, cloud_server_connection, RemoteRelayOptions::new()).await?;
+
+    // Middle
+    let middle_tcp_options = TcpConnectionOptions::new();
+    let payloads = Arc::new(Mutex::new(vec![]));
+    ctx.start_worker(
+        "tap",
+        Tap {
+            payloads: payloads.clone(),
+        },
+    )
+    .await?;
+    ctx.flow_controls()
+        .add_consumer("tap", &middle_tcp_options.flow_control_id());
+
+    let middle_tcp = TcpTransport::create(ctx).await?;
+    let cloud_middle_connection = middle_tcp
+        .connect(cloud_listener.socket_string(), middle_tcp_options)
+        .await?;
+    let first_hop = RemoteRelay::create(
+        ctx,
+        route!["tap", cloud_middle_connection],
+        RemoteRelayOptions::new(),
+    )
+    .await?;
+
+    // Client
+    let client_tcp = TcpTransport::create(ctx).await?;
+    let cloud_client_connection = client_tcp
+        .connect(cloud_listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let client_identity = identities_creation.create_identity().await?;
+    let channel_to_server = secure_channels
+        .create_secure_channel(
+            ctx,
+            client_identity.identifier(),
+            route![
+                cloud_client_connection,
+                first_hop.remote_address(),
+                second_hop.remote_address(),
+                "server_listener"
+            ],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let message = "Hello through two relays".to_string();
+    let resp = ctx
+        .send_and_receive::<String>(route![channel_to_server, "echoer"], message.clone())
+        .await?;
+    assert_eq!(resp, message);
+
+    let payloads = payloads.lock().unwrap();
+    assert!(
+        payloads.len() > 1,
+        "The handshake and the messages went through both relays"
+    );
+    assert!(
+        payloads.iter().all(|payload| !payload
+            .windows(message.len())
+            .any(|w| w == message.as_bytes())),
+        "The relays can only see encrypted payloads"
+    );
+    drop(payloads);
+
+    ctx.stop().await
+}
+
+// A Forwarder gives a stable address to a service reachable through a changing route
+#[ockam_macros::test]
+async fn test_forwarder_update_route(ctx: &mut Context) -> Result<()> {
+    let mut mobile1 = ctx.new_detached("mobile1", AllowAll, AllowAll).await?;
+    let mut mobile2 = ctx.new_detached("mobile2", AllowAll, AllowAll).await?;
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let forwarder = Forwarder::create(
+        ctx,
+        "rendezvous",
+        route!["mobile1"],
+        ForwarderOptions::new()
+            .with_control_incoming_access_control(Arc::new(AllowSourceAddress(ctx.address()))),
+    )
+    .await?;
+
+    // Only the allowed workers can update the route
+    let err = Forwarder::update_route(
+        &mobile2,
+        route![forwarder.control_address().clone()],
+        route!["mobile2"],
+    )
+    .await
+    .unwrap_err();
+    let expected: ockam_core::Error = OckamError::ForwarderRouteUpdateDenied.into();
+    assert_eq!(err.code(), expected.code());
+    assert!(err
+        .to_string()
+        .contains(&OckamError::ForwarderRouteUpdateDenied.to_string()));
+
+    ctx.send(route!["rendezvous"], "Hello".to_string()).await?;
+    let msg = mobile1.receive::<String>().await?;
+    assert_eq!(msg.as_body(), "Hello");
+
+    // Replies flow back to the original sender
+    mobile1
+        .send(msg.return_route(), "Hello back".to_string())
+        .await?;
+    assert_eq!(ctx.receive::<String>().await?.body(), "Hello back");
+
+    Forwarder::update_route(
+        ctx,
+        route![forwarder.control_address().clone()],
+        route!["mobile2"],
+    )
+    .await?;
+
+    ctx.send(route!["rendezvous"], "Hello again".to_string())
+        .await?;
+    assert_eq!(mobile2.receive::<String>().await?.body(), "Hello again");
+    let res = mobile1
+        .receive_extended::<String>(
+            MessageReceiveOptions::new().with_timeout(Duration::from_millis(100)),
+        )
+        .await;
+    assert!(res.is_err());
+
+    // The rest of the onward route is kept after the configured route
+    Forwarder::update_route(ctx, route![forwarder.control_address().clone()], route![]).await?;
+    let resp = ctx
+        .send_and_receive::<String>(route!["rendezvous", "echoer"], "Echo".to_string())
+        .await?;
+    assert_eq!(resp, "Echo");
+
+    ctx.stop().await
+}
@@ -0,0 +1,83 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+use ockam::identity::{secure_channels, SecureChannelListenerOptions, SecureChannelOptions};
+use ockam::workers::Echoer;
+use ockam::NodeBuilder;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{route, Result};
+use ockam_node::Context;
+use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions, TcpTransport};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context as LayerContext, Layer, SubscriberExt};
+
+/// Count the events logged with the `ERROR` level
+struct ErrorCounter(Arc<AtomicUsize>);
+
+impl<S: Subscriber> Layer<S> for ErrorCounter {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        if *event.metadata().level() == Level::ERROR {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Exchange a message with an echoer through a secure channel over TCP
+async fn run_flow(ctx: &Context) -> Result<()> {
+    let tcp = TcpTransport::create(ctx).await?;
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let tcp_listener = tcp.listen("127.0.0.1:0", TcpListenerOptions::new()).await?;
+    let secure_channel_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().as_consumer(tcp_listener.flow_control_id()),
+        )
+        .await?;
+    ctx.start_worker("echoer", Echoer).await?;
+    ctx.flow_controls()
+        .add_consumer("echoer", secure_channel_listener.flow_control_id());
+
+    let connection = tcp
+        .connect(tcp_listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![connection, "bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let reply: String = ctx
+        .send_and_receive(route![channel, "echoer"], "Hello".to_string())
+        .await?;
+    assert_eq!(reply, "Hello");
+    Ok(())
+}
+
+// The errors are counted by a global subscriber, which is why this test doesn't use
+// `ockam_macros::test`, installing its own subscriber
+#[allow(non_snake_case)]
+#[test]
+fn shutdown_graceful__secure_channel_over_tcp__should_not_log_errors() {
+    let errors = Arc::new(AtomicUsize::new(0));
+    let subscriber = tracing_subscriber::registry().with(ErrorCounter(errors.clone()));
+    tracing::subscriber::set_global_default(subscriber).unwrap();
+
+    let (ctx, mut executor) = NodeBuilder::new().no_logging().build();
+    executor
+        .execute(async move {
+            let res = run_flow(&ctx).await;
+            ctx.shutdown_graceful(Duration::from_secs(5)).await.unwrap();
+            res.unwrap();
+        })
+        .unwrap();
+
+    assert_eq!(errors.load(Ordering::Relaxed), 0);
+}
@@ -1,6 +1,10 @@
 use crate::compat::boxed::Box;
+use crate::compat::collections::HashSet;
+use crate::compat::sync::Arc;
 use crate::compat::vec::Vec;
-use crate::{async_trait, Address, IncomingAccessControl, RelayMessage, Result};
+use crate::{
+    async_trait, Address, AllIncomingAccessControl, IncomingAccessControl, RelayMessage, Result,
+};
 
 /// An Access Control type that allows messages from the given source address to go through
 /// Note that it's based on source address, not a first hop of return_route, which may be different
@@ -29,15 +33,96 @@ impl IncomingAccessControl for AllowSourceAddress {
 /// An Access Control type that allows messages from the given source addresses to go through
 /// Note that it's based on source address, not a first hop of return_route, which may be different
 /// in some scenarios
-#[derive(Debug)]
+///
+/// The rejected messages are logged at the debug level. This policy can be combined with
+/// session-based authorization, for example an identity check on a secure channel, with
+/// [`AllowSourceAddresses::and`].
+#[derive(Debug, Default)]
 pub struct AllowSourceAddresses(pub Vec<Address>);
 
+impl AllowSourceAddresses {
+    /// Convenience constructor
+    pub fn new(addresses: impl IntoIterator<Item = impl Into<Address>>) -> Self {
+        Self(addresses.into_iter().map(|a| a.into()).collect())
+    }
+
+    /// Allow messages from another source address
+    pub fn allow(mut self, address: impl Into<Address>) -> Self {
+        let address = address.into();
+        if !self.0.contains(&address) {
+            self.0.push(address);
+        }
+        self
+    }
+
+    /// Only allow messages which are allowed by both this policy and another
+    /// [`IncomingAccessControl`]
+    pub fn and(self, other: impl IncomingAccessControl) -> AllIncomingAccessControl {
+        let access_controls: Vec<Arc<dyn IncomingAccessControl>> =
+            vec![Arc::new(self), Arc::new(other)];
+        AllIncomingAccessControl::new(access_controls)
+    }
+}
+
 #[async_trait]
 impl IncomingAccessControl for AllowSourceAddresses {
     async fn is_authorized(&self, relay_msg: &RelayMessage) -> Result<bool> {
         if self.0.contains(relay_msg.source()) {
             crate::allow()
         } else {
+            debug!(
+                "AllowSourceAddresses rejected a message from {} for {}",
+                relay_msg.source(),
+                relay_msg.destination()
+            );
+            crate::deny()
+        }
+    }
+}
+
+/// An Access Control type that only allows messages from a set of source addresses, for
+/// example for a detached context or a worker which must deny everything else by default
+/// Note that it's based on source address, not a first hop of return_route, which may be different
+/// in some scenarios
+///
+/// Unlike [`AllowSourceAddresses`], the lookup doesn't depend on the number of allowed
+/// addresses. The rejected messages are logged at the debug level. This policy can be
+/// combined with session-based authorization with [`AllowList::and`].
+#[derive(Debug, Default)]
+pub struct AllowList(pub HashSet<Address>);
+
+impl AllowList {
+    /// Convenience constructor
+    pub fn new(addresses: impl IntoIterator<Item = impl Into<Address>>) -> Self {
+        Self(addresses.into_iter().map(|a| a.into()).collect())
+    }
+
+    /// Allow messages from another source address
+    pub fn allow(mut self, address: impl Into<Address>) -> Self {
+        self.0.insert(address.into());
+        self
+    }
+
+    /// Only allow messages which are allowed by both this policy and another
+    /// [`IncomingAccessControl`]
+    pub fn and(self, other: impl IncomingAccessControl) -> AllIncomingAccessControl {
+        let access_controls: Vec<Arc<dyn IncomingAccessControl>> =
+            vec![Arc::new(self), Arc::new(other)];
+        AllIncomingAccessControl::new(access_controls)
+    }
+}
+
+#[async_trait]
+impl IncomingAccessControl for AllowList {
+    async fn is_authorized(&self, relay_msg: &RelayMessage) -> Result<bool> {
+        if self.0.contains(relay_msg.source()) {
+            crate::allow()
+        } else {
+            debug!(
+                "AllowList rejected a message from {} for {}",
+                relay_msg.source(),
+                relay_msg.destination()
+            );
             crate::deny()
         }
     }
@@ -47,8 +132,8 @@ impl IncomingAccessControl for AllowSourceAddresses {
 mod tests {
     use crate::compat::future::poll_once;
     use crate::{
-        route, Address, AllowSourceAddress, AllowSourceAddresses, IncomingAccessControl,
-        LocalMessage, RelayMessage, Result, TransportMessage,
+        route, Address, AllowAll, AllowList, AllowSourceAddress, AllowSourceAddresses, DenyAll,
+        IncomingAccessControl, LocalMessage, RelayMessage, Result, TransportMessage,
     };
 
     #[test]
@@ -127,4 +212,59 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_addresses_composition() -> Result<()> {
+        let source_address = Address::random_local();
+        let other_address = Address::random_local();
+        let onward_address = Address::random_local();
+
+        let msg = LocalMessage::new(
+            TransportMessage::v1(onward_address.clone(), route![], vec![]),
+            vec![],
+        );
+        let msg = RelayMessage::new(source_address.clone(), onward_address, msg);
+
+        let ac = AllowSourceAddresses::new([other_address.clone()]);
+        assert!(!poll_once(async { ac.is_authorized(&msg).await })?);
+
+        let ac = ac.allow(source_address.clone()).and(AllowAll);
+        assert!(poll_once(async { ac.is_authorized(&msg).await })?);
+
+        let ac = AllowSourceAddresses::new([source_address]).and(DenyAll);
+        assert!(!poll_once(async { ac.is_authorized(&msg).await })?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_allow_list() -> Result<()> {
+        let source_address = Address::random_local();
+        let other_address = Address::random_local();
+        let onward_address = Address::random_local();
+
+        let msg = LocalMessage::new(
+            TransportMessage::v1(onward_address.clone(), route![], vec![]),
+            vec![],
+        );
+        let msg = RelayMessage::new(source_address.clone(), onward_address, msg);
+
+        // Deny by default
+        let ac = AllowList::default();
+        assert!(!poll_once(async { ac.is_authorized(&msg).await })?);
+
+        let ac = AllowList::new([other_address]);
+        assert!(!poll_once(async { ac.is_authorized(&msg).await })?);
+
+        let ac = ac.allow(source_address.clone());
+        assert!(poll_once(async { ac.is_authorized(&msg).await })?);
+
+        let ac = AllowList::new([source_address.clone()]).and(AllowAll);
+        assert!(poll_once(async { ac.is_authorized(&msg).await })?);
+
+        let ac = AllowList::new([source_address]).and(DenyAll);
+        assert!(!poll_once(async { ac.is_authorized(&msg).await })?);
+
+        Ok(())
+    }
 }
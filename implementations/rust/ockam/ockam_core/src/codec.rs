@@ -0,0 +1,146 @@
+use crate::compat::vec::Vec;
+use crate::errcode::{Kind, Origin};
+use crate::{Error, LocalInfo, LocalMessage, Message, Result, Routed};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Codec LocalInfo unique Identifier
+pub const CODEC_LOCAL_INFO_IDENTIFIER: &str = "CODEC_LOCAL_INFO_IDENTIFIER";
+
+/// Encoding used to serialize a message payload
+///
+/// Messages are encoded with [BARE](https://baremessages.org) by default. JSON and CBOR
+/// can be used to exchange messages with services which are not written in Rust, they
+/// require the `json_codec` and `cbor_codec` features. Without them, encoding or decoding
+/// a message with that codec fails with [`Kind::Unsupported`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    /// Default encoding, used by [`crate::Encodable`] and [`crate::Decodable`]
+    #[default]
+    Bare,
+    /// JSON encoding
+    Json,
+    /// CBOR encoding
+    Cbor,
+}
+
+impl Codec {
+    /// Encode a message with this codec
+    pub fn encode<T: Serialize>(&self, msg: &T) -> Result<Vec<u8>> {
+        match self {
+            Codec::Bare => Ok(serde_bare::to_vec(msg)?),
+            #[cfg(feature = "json_codec")]
+            Codec::Json => serde_json::to_vec(msg).map_err(codec_error),
+            #[cfg(feature = "cbor_codec")]
+            Codec::Cbor => serde_cbor::to_vec(msg).map_err(codec_error),
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// Decode a message encoded with this codec
+    pub fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T> {
+        match self {
+            Codec::Bare => Ok(serde_bare::from_slice(data)?),
+            #[cfg(feature = "json_codec")]
+            Codec::Json => serde_json::from_slice(data).map_err(codec_error),
+            #[cfg(feature = "cbor_codec")]
+            Codec::Cbor => serde_cbor::from_slice(data).map_err(codec_error),
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    #[allow(dead_code)]
+    fn unsupported(&self) -> Error {
+        Error::new(
+            Origin::Core,
+            Kind::Unsupported,
+            "this codec is disabled, see the json_codec and cbor_codec features",
+        )
+    }
+
+    /// Encode this codec to a general `LocalInfo`
+    pub fn to_local_info(&self) -> Result<LocalInfo> {
+        Ok(LocalInfo::new(
+            CODEC_LOCAL_INFO_IDENTIFIER.into(),
+            serde_bare::to_vec(self)?,
+        ))
+    }
+
+    /// Find the codec used to encode the payload of that `LocalMessage`.
+    /// Messages which don't specify a codec use the default one
+    pub fn find_info(local_msg: &LocalMessage) -> Result<Self> {
+        match local_msg
+            .local_info()
+            .iter()
+            .find(|x| x.type_identifier() == CODEC_LOCAL_INFO_IDENTIFIER)
+        {
+            Some(local_info) => Ok(serde_bare::from_slice(local_info.data())?),
+            None => Ok(Codec::default()),
+        }
+    }
+}
+
+#[cfg(any(feature = "json_codec", feature = "cbor_codec"))]
+fn codec_error(e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Error {
+    Error::new(Origin::Core, Kind::Serialization, e)
+}
+
+impl<M: Message> Routed<M> {
+    /// Codec used to encode the payload of this message
+    pub fn codec(&self) -> Result<Codec> {
+        Codec::find_info(self.local_message())
+    }
+
+    /// Decode the payload of this message with the codec it was sent with
+    ///
+    /// The codec is sent to other nodes as a message extension, see
+    /// [`crate::MESSAGE_EXTENSIONS_IDENTIFIERS`]. Messages received from a peer which doesn't
+    /// send extensions are decoded with the default codec.
+    pub fn decode_with_codec<T: DeserializeOwned>(&self) -> Result<T> {
+        self.codec()?.decode(self.payload())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::string::{String, ToString};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Greeting {
+        text: String,
+        count: u8,
+    }
+
+    fn greeting() -> Greeting {
+        Greeting {
+            text: "hello".to_string(),
+            count: 3,
+        }
+    }
+
+    #[test]
+    fn bare_roundtrip() {
+        let encoded = Codec::Bare.encode(&greeting()).unwrap();
+        let decoded: Greeting = Codec::Bare.decode(&encoded).unwrap();
+        assert_eq!(decoded, greeting());
+    }
+
+    #[cfg(feature = "json_codec")]
+    #[test]
+    fn json_roundtrip() {
+        let json = Codec::Json.encode(&greeting()).unwrap();
+        assert_eq!(json, br#"{"text":"hello","count":3}"#);
+        let decoded: Greeting = Codec::Json.decode(&json).unwrap();
+        assert_eq!(decoded, greeting());
+    }
+
+    #[cfg(feature = "cbor_codec")]
+    #[test]
+    fn cbor_roundtrip() {
+        let encoded = Codec::Cbor.encode(&greeting()).unwrap();
+        let decoded: Greeting = Codec::Cbor.decode(&encoded).unwrap();
+        assert_eq!(decoded, greeting());
+    }
+}
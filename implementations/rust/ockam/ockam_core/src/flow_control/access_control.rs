@@ -8,6 +8,7 @@ use core::fmt::{Debug, Formatter};
 ///
 /// Allows to send messages only to members of the given [`FlowControlId`] or message a Spawner
 /// with given [`FlowControlId`]. Optionally, only 1 message can be passed to the Spawner.
+/// Nothing is allowed once a [`FlowControlId`] is revoked, see [`FlowControls::revoke`].
 pub struct FlowControlOutgoingAccessControl {
     flow_controls: FlowControls,
     flow_control_id: FlowControlId,
@@ -40,6 +41,10 @@ impl FlowControlOutgoingAccessControl {
 
 impl FlowControlOutgoingAccessControl {
     fn is_consumer(&self, next: &Address, flow_control_id: &FlowControlId) -> bool {
+        if self.flow_controls.is_revoked(flow_control_id) {
+            return false;
+        }
+
         let consumers_info = self.flow_controls.get_consumers_info(flow_control_id);
 
         consumers_info.contains(next)
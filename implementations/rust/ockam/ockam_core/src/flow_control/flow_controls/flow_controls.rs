@@ -1,4 +1,4 @@
-use crate::compat::collections::BTreeMap;
+use crate::compat::collections::{BTreeMap, BTreeSet};
 use crate::compat::sync::{Arc, RwLock};
 use crate::flow_control::{ConsumersInfo, FlowControlId, ProducerInfo};
 use crate::Address;
@@ -15,4 +15,8 @@ pub struct FlowControls {
     pub(super) producers_additional_addresses: Arc<RwLock<BTreeMap<Address, Address>>>,
     // All known spawners
     pub(super) spawners: Arc<RwLock<BTreeMap<Address, FlowControlId>>>,
+    // Parent of each child FlowControlId
+    pub(super) parents: Arc<RwLock<BTreeMap<FlowControlId, FlowControlId>>>,
+    // Revoked FlowControlIds, their descendants are revoked as well
+    pub(super) revoked: Arc<RwLock<BTreeSet<FlowControlId>>>,
 }
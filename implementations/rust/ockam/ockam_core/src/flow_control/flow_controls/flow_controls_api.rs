@@ -12,6 +12,8 @@ impl FlowControls {
             producers: Default::default(),
             producers_additional_addresses: Default::default(),
             spawners: Default::default(),
+            parents: Default::default(),
+            revoked: Default::default(),
         }
     }
 }
@@ -21,6 +21,8 @@ impl FlowControls {
             return;
         }
 
+        self.cleanup_flow_control_id(&spawner_flow_control_id);
+
         // Check if Producers spawned by this Spawner still exist
         let producers_exist =
             self.producers.read().unwrap().iter().any(|(_addr, info)| {
@@ -107,6 +109,8 @@ impl FlowControls {
             return;
         }
 
+        self.cleanup_flow_control_id(&flow_control_id);
+
         // We can clean Consumers for that FlowControlId
         self.consumers.write().unwrap().remove(&flow_control_id);
     }
@@ -0,0 +1,72 @@
+use crate::compat::rand::random;
+use crate::flow_control::{FlowControlId, FlowControls};
+
+impl FlowControls {
+    /// Generate a fresh random [`FlowControlId`] which stays valid only while `parent` is
+    /// valid, see [`revoke`](Self::revoke)
+    ///
+    /// A child can itself be the parent of other [`FlowControlId`]s, e.g. a [`FlowControlId`]
+    /// per connection derived from a [`FlowControlId`] per tenant.
+    pub fn generate_child_flow_control_id(&self, parent: &FlowControlId) -> FlowControlId {
+        let child: FlowControlId = random();
+        debug!("Generate child {child} of {parent}");
+        self.parents
+            .write()
+            .unwrap()
+            .insert(child.clone(), parent.clone());
+
+        child
+    }
+
+    /// Parent of a [`FlowControlId`] generated with
+    /// [`generate_child_flow_control_id`](Self::generate_child_flow_control_id)
+    pub fn get_parent(&self, flow_control_id: &FlowControlId) -> Option<FlowControlId> {
+        self.parents.read().unwrap().get(flow_control_id).cloned()
+    }
+
+    /// Revoke a [`FlowControlId`] and all its descendants
+    ///
+    /// Producers can't send messages to the Consumers of a revoked [`FlowControlId`] anymore,
+    /// starting with the next message. A revocation can't be undone.
+    pub fn revoke(&self, flow_control_id: &FlowControlId) {
+        info!("Revoke {flow_control_id}");
+        self.revoked
+            .write()
+            .unwrap()
+            .insert(flow_control_id.clone());
+    }
+
+    /// Check if a [`FlowControlId`] or one of its ancestors was revoked
+    pub fn is_revoked(&self, flow_control_id: &FlowControlId) -> bool {
+        let revoked = self.revoked.read().unwrap();
+        if revoked.is_empty() {
+            return false;
+        }
+        let parents = self.parents.read().unwrap();
+
+        let mut current = Some(flow_control_id);
+        while let Some(id) = current {
+            if revoked.contains(id) {
+                return true;
+            }
+            current = parents.get(id);
+        }
+
+        false
+    }
+
+    /// Forget a [`FlowControlId`] which is no longer used by any Producer or Spawner, unless
+    /// it is the parent of another [`FlowControlId`].
+    ///
+    /// Its ancestors are kept, since new children can still be generated from them.
+    pub(super) fn cleanup_flow_control_id(&self, flow_control_id: &FlowControlId) {
+        let mut parents = self.parents.write().unwrap();
+        if parents.values().any(|parent| parent == flow_control_id) {
+            return;
+        }
+        parents.remove(flow_control_id);
+        drop(parents);
+
+        self.revoked.write().unwrap().remove(flow_control_id);
+    }
+}
@@ -4,6 +4,7 @@ mod flow_controls;
 mod flow_controls_api;
 mod flow_controls_cleanup;
 mod flow_controls_debug;
+mod flow_controls_revocation;
 mod producer_info;
 
 pub use consumers_info::*;
@@ -11,6 +12,7 @@ pub use flow_controls::*;
 pub use flow_controls_api::*;
 pub use flow_controls_cleanup::*;
 pub use flow_controls_debug::*;
+pub use flow_controls_revocation::*;
 pub use producer_info::*;
 
 #[cfg(test)]
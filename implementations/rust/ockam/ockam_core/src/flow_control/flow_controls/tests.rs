@@ -195,3 +195,46 @@ fn test_cleanup() {
         .is_empty());
     assert!(flow_controls.spawners.read().unwrap().is_empty());
 }
+
+#[test]
+fn test_revoke_parent() {
+    let flow_controls = FlowControls::new();
+
+    let tenant = FlowControls::generate_flow_control_id();
+    let other_tenant = FlowControls::generate_flow_control_id();
+    let session = flow_controls.generate_child_flow_control_id(&tenant);
+    let connection = flow_controls.generate_child_flow_control_id(&session);
+    let other_session = flow_controls.generate_child_flow_control_id(&other_tenant);
+    assert_eq!(flow_controls.get_parent(&connection), Some(session.clone()));
+    assert!(!flow_controls.is_revoked(&connection));
+
+    flow_controls.revoke(&tenant);
+    assert!(flow_controls.is_revoked(&tenant));
+    assert!(flow_controls.is_revoked(&session));
+    assert!(flow_controls.is_revoked(&connection));
+    assert!(!flow_controls.is_revoked(&other_tenant));
+    assert!(!flow_controls.is_revoked(&other_session));
+}
+
+#[test]
+fn test_cleanup_child() {
+    let flow_controls = FlowControls::new();
+
+    let tenant = FlowControls::generate_flow_control_id();
+    let session = flow_controls.generate_child_flow_control_id(&tenant);
+    let connection = flow_controls.generate_child_flow_control_id(&session);
+    flow_controls.add_producer("session", &session, None, vec![]);
+    flow_controls.add_producer("connection", &connection, None, vec![]);
+    flow_controls.revoke(&connection);
+
+    // The session is still the parent of the connection
+    flow_controls.cleanup_address(&"session".into());
+    assert_eq!(flow_controls.get_parent(&session), Some(tenant.clone()));
+
+    flow_controls.cleanup_address(&"connection".into());
+    assert_eq!(flow_controls.get_parent(&connection), None);
+    assert!(!flow_controls.is_revoked(&connection));
+
+    // The session is kept, since new children can still be generated from it
+    assert_eq!(flow_controls.get_parent(&session), Some(tenant));
+}
@@ -76,6 +76,7 @@ pub mod hex_encoding;
 pub mod env;
 
 mod cbor;
+mod codec;
 mod error;
 mod message;
 mod processor;
@@ -85,6 +86,7 @@ mod worker;
 
 pub use access_control::*;
 pub use cbor::*;
+pub use codec::*;
 pub use error::*;
 pub use message::*;
 pub use processor::*;
@@ -4,7 +4,7 @@ use crate::{
         vec::Vec,
     },
     errcode::{Kind, Origin},
-    Address, Error, LocalMessage, Result, Route, TransportMessage,
+    Address, Error, LocalMessage, MessageHeaders, Result, Route, TransportMessage,
 };
 use core::{
     fmt::{self, Debug, Display, Formatter},
@@ -203,6 +203,8 @@ impl<M: Message> Routed<M> {
     }
 
     /// Return a copy of the message address.
+    ///
+    /// For a worker with several addresses, this is the address the message was sent to.
     #[inline]
     pub fn msg_addr(&self) -> Address {
         self.msg_addr.clone()
@@ -261,6 +263,12 @@ impl<M: Message> Routed<M> {
         &self.local_msg
     }
 
+    /// Return the headers sent along with the message, which are empty if the message
+    /// was sent without headers
+    pub fn headers(&self) -> Result<MessageHeaders> {
+        Ok(MessageHeaders::find_info(&self.local_msg)?.unwrap_or_default())
+    }
+
     /// Return a reference to the underlying transport message's binary payload.
     #[inline]
     pub fn payload(&self) -> &[u8] {
@@ -274,6 +282,14 @@ impl<M: Message> Routed<M> {
     }
 }
 
+fn invalid_payload_bytes() -> Error {
+    Error::new(
+        Origin::Core,
+        Kind::Serialization,
+        "the message payload is not an encoded Vec<u8>",
+    )
+}
+
 impl Routed<Any> {
     /// Try to cast an `Any` message into another valid message type
     pub fn cast<M: Message>(self) -> Result<Routed<M>> {
@@ -285,6 +301,77 @@ impl Routed<Any> {
             src_addr: self.src_addr,
         })
     }
+
+    /// Return a reference to the bytes of a `Vec<u8>` message, without copying them.
+    ///
+    /// Receiving a large binary message as `Vec<u8>` copies it out of the message payload.
+    /// Receive it as [`crate::Any`] instead, and read its bytes with this method: they stay valid
+    /// as long as this wrapper is borrowed.
+    pub fn payload_bytes(&self) -> Result<&[u8]> {
+        let payload = self.payload();
+
+        // A `Vec<u8>` is encoded as its length followed by its bytes
+        let length: serde_bare::Uint = serde_bare::from_slice(payload)?;
+        let offset = serde_bare::to_vec(&length)?.len();
+        match payload.get(offset..) {
+            Some(bytes) if bytes.len() as u64 == length.0 => Ok(bytes),
+            _ => Err(invalid_payload_bytes()),
+        }
+    }
+}
+
+/// A token matching a response with the request it answers
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct CorrelationId([u8; 16]);
+
+impl CorrelationId {
+    /// Generate a random `CorrelationId`
+    pub fn random() -> Self {
+        Self(crate::compat::rand::random())
+    }
+}
+
+/// A message tagged with a [`CorrelationId`]
+///
+/// Requests sent with `ockam_node::Context::send_and_receive_correlated` are wrapped into
+/// this type. The receiving worker can answer with [`Routed::correlated_response`], so that
+/// the response is matched with its request.
+#[derive(Serialize, Deserialize, Clone, Debug, crate::Message)]
+pub struct Correlated {
+    correlation_id: CorrelationId,
+    payload: Vec<u8>,
+}
+
+impl Correlated {
+    /// Wrap a message with the given [`CorrelationId`]
+    pub fn new(correlation_id: CorrelationId, body: &impl Message) -> Result<Self> {
+        Ok(Self {
+            correlation_id,
+            payload: body.encode()?,
+        })
+    }
+
+    /// [`CorrelationId`] of this message
+    pub fn correlation_id(&self) -> CorrelationId {
+        self.correlation_id
+    }
+
+    /// Decode the wrapped message
+    pub fn body<M: Message>(&self) -> Result<M> {
+        M::decode(&self.payload)
+    }
+}
+
+impl Routed<Correlated> {
+    /// Decode the wrapped request
+    pub fn correlated_body<M: Message>(&self) -> Result<M> {
+        self.inner.body()
+    }
+
+    /// Create a response carrying the [`CorrelationId`] of this request
+    pub fn correlated_response(&self, response: &impl Message) -> Result<Correlated> {
+        Correlated::new(self.inner.correlation_id(), response)
+    }
 }
 
 impl<M: Message> Deref for Routed<M> {
@@ -15,6 +15,9 @@ pub trait Processor: Send + 'static {
     }
 
     /// Define the Processor Worker shutdown behaviour.
+    ///
+    /// Like [`crate::Worker::shutdown`], this hook is called exactly once when the
+    /// processor is stopped, including when its node is force-stopped.
     async fn shutdown(&mut self, _context: &mut Self::Context) -> Result<()> {
         Ok(())
     }
@@ -9,6 +9,8 @@ use core::fmt::{self, Debug, Display};
 pub enum RouteError {
     /// Message had an incomplete route
     IncompleteRoute,
+    /// Message headers exceed their maximum size
+    HeadersTooLarge,
 }
 
 impl From<RouteError> for Error {
@@ -16,6 +18,7 @@ impl From<RouteError> for Error {
     fn from(err: RouteError) -> Self {
         let kind = match err {
             RouteError::IncompleteRoute => Kind::Misuse,
+            RouteError::HeadersTooLarge => Kind::ResourceExhausted,
         };
         Error::new(Origin::Core, kind, err)
     }
@@ -26,6 +29,7 @@ impl Display for RouteError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             RouteError::IncompleteRoute => write!(f, "incomplete route"),
+            RouteError::HeadersTooLarge => write!(f, "message headers too large"),
         }
     }
 }
@@ -8,3 +8,12 @@ pub use relay_message::*;
 
 mod transport_message;
 pub use transport_message::*;
+
+mod route_trace;
+pub use route_trace::*;
+
+mod message_headers;
+pub use message_headers::*;
+
+mod message_extensions;
+pub use message_extensions::*;
@@ -0,0 +1,144 @@
+use crate::{
+    compat::vec::Vec, LocalInfo, LocalMessage, Result, TransportMessage,
+    CODEC_LOCAL_INFO_IDENTIFIER, MESSAGE_HEADERS_IDENTIFIER, ROUTE_TRACE_IDENTIFIER,
+};
+use serde::{Deserialize, Serialize};
+
+/// Version of a [`TransportMessage`] which carries extensions along with its payload
+///
+/// The extensions are the [`LocalInfo`] which are still meaningful on another node, like a
+/// [`crate::RouteTrace`], [`crate::MessageHeaders`] or the [`crate::Codec`] of the payload. Older nodes can't decode such messages,
+/// so transports and secure channels only send extensions to peers which support them.
+/// Messages without extensions keep the version 1.
+pub const EXTENDED_TRANSPORT_MESSAGE_VERSION: u8 = 2;
+
+/// Identifiers of the [`LocalInfo`] which are sent to other nodes as extensions
+///
+/// The other extensions received from another node are dropped: new extensions can be added
+/// without breaking older peers, and a peer can't set a [`LocalInfo`] which must only be set
+/// locally, like the identity of a secure channel.
+pub const MESSAGE_EXTENSIONS_IDENTIFIERS: &[&str] = &[
+    ROUTE_TRACE_IDENTIFIER,
+    MESSAGE_HEADERS_IDENTIFIER,
+    CODEC_LOCAL_INFO_IDENTIFIER,
+];
+
+/// Payload of a [`TransportMessage`] carrying extensions
+#[derive(Serialize, Deserialize)]
+struct ExtendedPayload {
+    extensions: Vec<LocalInfo>,
+    payload: Vec<u8>,
+}
+
+fn is_extension(local_info: &LocalInfo) -> bool {
+    MESSAGE_EXTENSIONS_IDENTIFIERS.contains(&local_info.type_identifier())
+}
+
+impl LocalMessage {
+    /// The [`LocalInfo`] of this message which can be sent to other nodes,
+    /// see [`MESSAGE_EXTENSIONS_IDENTIFIERS`]
+    pub fn extensions(&self) -> Vec<LocalInfo> {
+        self.local_info()
+            .iter()
+            .filter(|local_info| is_extension(local_info))
+            .cloned()
+            .collect()
+    }
+}
+
+impl TransportMessage {
+    /// Embed extensions into this message before sending it to another node.
+    /// The message is left unchanged if there are no extensions
+    pub fn with_extensions(self, extensions: Vec<LocalInfo>) -> Result<Self> {
+        if extensions.is_empty() {
+            return Ok(self);
+        }
+        let payload = serde_bare::to_vec(&ExtendedPayload {
+            extensions,
+            payload: self.payload,
+        })?;
+        Ok(Self {
+            version: EXTENDED_TRANSPORT_MESSAGE_VERSION,
+            onward_route: self.onward_route,
+            return_route: self.return_route,
+            payload,
+        })
+    }
+
+    /// Extract the extensions embedded by [`TransportMessage::with_extensions`]
+    /// from a message received from another node. Unknown extensions are dropped
+    pub fn take_extensions(self) -> Result<(Self, Vec<LocalInfo>)> {
+        if self.version != EXTENDED_TRANSPORT_MESSAGE_VERSION {
+            return Ok((self, Vec::new()));
+        }
+        let extended: ExtendedPayload = serde_bare::from_slice(&self.payload)?;
+        let extensions = extended
+            .extensions
+            .into_iter()
+            .filter(is_extension)
+            .collect();
+        let msg = TransportMessage::v1(self.onward_route, self.return_route, extended.payload);
+        Ok((msg, extensions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{route, Codec, MessageHeaders, RouteTrace};
+
+    #[test]
+    fn extensions_survive_transport() {
+        let mut trace = RouteTrace::new("collector");
+        trace.add_hop("sender");
+        let headers = MessageHeaders::new().with("tenant", "acme").unwrap();
+        let extensions = vec![
+            trace.to_local_info().unwrap(),
+            headers.to_local_info().unwrap(),
+            Codec::Cbor.to_local_info().unwrap(),
+        ];
+
+        let msg = TransportMessage::v1(route!["a", "b"], route!["c"], vec![1, 2, 3]);
+        let extended = msg.clone().with_extensions(extensions.clone()).unwrap();
+        assert_eq!(extended.version, EXTENDED_TRANSPORT_MESSAGE_VERSION);
+
+        let (received, received_extensions) = extended.take_extensions().unwrap();
+        assert_eq!(received, msg);
+        assert_eq!(received_extensions, extensions);
+
+        let local_msg = LocalMessage::new(received, received_extensions);
+        assert_eq!(RouteTrace::find_info(&local_msg).unwrap(), Some(trace));
+        assert_eq!(
+            MessageHeaders::find_info(&local_msg).unwrap(),
+            Some(headers)
+        );
+        assert_eq!(Codec::find_info(&local_msg).unwrap(), Codec::Cbor);
+
+        // Messages without extensions are unchanged
+        let unchanged = msg.clone().with_extensions(vec![]).unwrap();
+        assert_eq!(unchanged, msg);
+        let (received, no_extensions) = unchanged.take_extensions().unwrap();
+        assert_eq!(received, msg);
+        assert!(no_extensions.is_empty());
+    }
+
+    #[test]
+    fn unknown_extensions_are_dropped() {
+        let headers = MessageHeaders::new().with("tenant", "acme").unwrap();
+        let unknown = LocalInfo::new("IDENTITY_SECURE_CHANNEL_IDENTIFIER".into(), vec![1]);
+
+        let msg = TransportMessage::v1(route!["a"], route![], vec![]);
+        let (_, extensions) = msg
+            .with_extensions(vec![unknown.clone(), headers.to_local_info().unwrap()])
+            .unwrap()
+            .take_extensions()
+            .unwrap();
+        assert_eq!(extensions, vec![headers.to_local_info().unwrap()]);
+
+        let local_msg = LocalMessage::new(
+            TransportMessage::v1(route![], route![], vec![]),
+            vec![unknown],
+        );
+        assert!(local_msg.extensions().is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    compat::{collections::BTreeMap, string::String, vec::Vec},
+    Decodable, Encodable, LocalInfo, LocalMessage, Message, Result, RouteError,
+};
+use serde::{Deserialize, Serialize};
+
+/// MessageHeaders LocalInfo unique Identifier
+pub const MESSAGE_HEADERS_IDENTIFIER: &str = "MESSAGE_HEADERS_IDENTIFIER";
+
+/// Maximum total size of the keys and values of [`MessageHeaders`], in bytes
+pub const MAX_HEADERS_SIZE: usize = 4 * 1024;
+
+/// Key/value metadata sent along with a message with `Context::send_with_headers`,
+/// such as a trace id or a tenant tag, and read with [`crate::Routed::headers`]
+///
+/// The headers are carried as a [`LocalInfo`] within a node and sent to other nodes as a
+/// message extension, see [`crate::EXTENDED_TRANSPORT_MESSAGE_VERSION`]. Secure channels
+/// encrypt them along with the message. Their total size is limited to [`MAX_HEADERS_SIZE`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Message)]
+pub struct MessageHeaders {
+    headers: BTreeMap<String, String>,
+}
+
+impl MessageHeaders {
+    /// Create empty headers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a header, replacing the previous value of the same key
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> Result<Self> {
+        self.insert(key, value)?;
+        Ok(self)
+    }
+
+    /// Add a header, replacing the previous value of the same key.
+    /// Fails with [`RouteError::HeadersTooLarge`] if the headers would exceed
+    /// [`MAX_HEADERS_SIZE`], in which case they are left unchanged
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Result<()> {
+        let key = key.into();
+        let value = value.into();
+        let previous_size = self.headers.get(&key).map_or(0, |v| key.len() + v.len());
+        if self.size() - previous_size + key.len() + value.len() > MAX_HEADERS_SIZE {
+            return Err(RouteError::HeadersTooLarge.into());
+        }
+        self.headers.insert(key, value);
+        Ok(())
+    }
+
+    /// Value of a header
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.headers.get(key).map(|v| v.as_str())
+    }
+
+    /// Headers sorted by key
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Number of headers
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// True if there are no headers
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Total size of the keys and values
+    fn size(&self) -> usize {
+        self.headers.iter().map(|(k, v)| k.len() + v.len()).sum()
+    }
+
+    /// Headers received from another node may have been built by a different implementation
+    fn check_size(self) -> Result<Self> {
+        if self.size() > MAX_HEADERS_SIZE {
+            return Err(RouteError::HeadersTooLarge.into());
+        }
+        Ok(self)
+    }
+
+    /// Encode these headers to a general `LocalInfo`
+    pub fn to_local_info(&self) -> Result<LocalInfo> {
+        Ok(LocalInfo::new(
+            MESSAGE_HEADERS_IDENTIFIER.into(),
+            self.encode()?,
+        ))
+    }
+
+    /// Find the headers of that `LocalMessage`, if any
+    pub fn find_info(local_msg: &LocalMessage) -> Result<Option<Self>> {
+        match local_msg
+            .local_info()
+            .iter()
+            .find(|x| x.type_identifier() == MESSAGE_HEADERS_IDENTIFIER)
+        {
+            // The headers may have been received from another node
+            Some(local_info) => Ok(Some(
+                MessageHeaders::decode(local_info.data())?.check_size()?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_size_is_bounded() {
+        let mut headers = MessageHeaders::new();
+        let value = "x".repeat(MAX_HEADERS_SIZE - 3);
+        headers.insert("key", value.as_str()).unwrap();
+        assert!(headers.insert("other", "value").is_err());
+        assert_eq!(headers.len(), 1);
+
+        // Replacing a value only counts the new one
+        headers.insert("key", "small").unwrap();
+        headers.insert("other", "value").unwrap();
+        assert_eq!(headers.get("key"), Some("small"));
+        assert_eq!(headers.len(), 2);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{
+    compat::vec::Vec, Address, Decodable, Encodable, LocalInfo, LocalMessage, Message, Result,
+};
+use serde::{Deserialize, Serialize};
+
+/// RouteTrace LocalInfo unique Identifier
+pub const ROUTE_TRACE_IDENTIFIER: &str = "ROUTE_TRACE_IDENTIFIER";
+
+/// A worker which handled a traced message
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Message)]
+pub struct TraceHop {
+    address: Address,
+    timestamp: u64,
+}
+
+impl TraceHop {
+    /// Address of the worker
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Time at which the worker handled the message, in milliseconds since the UNIX epoch.
+    /// Hops recorded on different nodes are subject to clock drift between these nodes
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+/// Trail of the workers which handled a message sent with `Context::send_traced`
+///
+/// The trace is carried as a [`LocalInfo`] within a node and sent to other nodes as a message
+/// extension, see [`crate::EXTENDED_TRANSPORT_MESSAGE_VERSION`]. Once the message is delivered,
+/// the trace is sent back to its collector.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Message)]
+pub struct RouteTrace {
+    collector: Address,
+    hops: Vec<TraceHop>,
+}
+
+impl RouteTrace {
+    /// Create an empty trace which will be sent to the `collector` address
+    pub fn new(collector: impl Into<Address>) -> Self {
+        Self {
+            collector: collector.into(),
+            hops: Vec::new(),
+        }
+    }
+
+    /// Address receiving the trace once the message is delivered
+    pub fn collector(&self) -> &Address {
+        &self.collector
+    }
+
+    /// Hops in the order in which they handled the message
+    pub fn hops(&self) -> &[TraceHop] {
+        &self.hops
+    }
+
+    /// Addresses of the hops in the order in which they handled the message
+    pub fn addresses(&self) -> Vec<Address> {
+        self.hops.iter().map(|h| h.address.clone()).collect()
+    }
+
+    /// Record that the worker with the given address handled the message
+    pub fn add_hop(&mut self, address: impl Into<Address>) {
+        self.hops.push(TraceHop {
+            address: address.into(),
+            timestamp: now(),
+        })
+    }
+
+    /// Encode this trace to a general `LocalInfo`
+    pub fn to_local_info(&self) -> Result<LocalInfo> {
+        Ok(LocalInfo::new(
+            ROUTE_TRACE_IDENTIFIER.into(),
+            self.encode()?,
+        ))
+    }
+
+    /// Find the trace of that `LocalMessage`, if it is traced
+    pub fn find_info(local_msg: &LocalMessage) -> Result<Option<Self>> {
+        match local_msg
+            .local_info()
+            .iter()
+            .find(|x| x.type_identifier() == ROUTE_TRACE_IDENTIFIER)
+        {
+            Some(local_info) => Ok(Some(RouteTrace::decode(local_info.data())?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn now() -> u64 {
+    use crate::compat::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(not(feature = "std"))]
+fn now() -> u64 {
+    0
+}
+
+impl LocalMessage {
+    /// Record a hop if this message is traced
+    pub fn trace_hop(&mut self, address: &Address) -> Result<()> {
+        if let Some(mut trace) = RouteTrace::find_info(self)? {
+            trace.add_hop(address.clone());
+            self.replace_local_info(trace.to_local_info()?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{route, TransportMessage};
+
+    #[test]
+    fn trace_hop_only_applies_to_traced_messages() {
+        let msg = TransportMessage::v1(route!["a"], route![], vec![]);
+        let mut local_msg = LocalMessage::new(msg.clone(), vec![]);
+        local_msg.trace_hop(&"a".into()).unwrap();
+        assert!(RouteTrace::find_info(&local_msg).unwrap().is_none());
+
+        let trace = RouteTrace::new("collector");
+        let mut local_msg = LocalMessage::new(msg, vec![trace.to_local_info().unwrap()]);
+        local_msg.trace_hop(&"a".into()).unwrap();
+        local_msg.trace_hop(&"b".into()).unwrap();
+        let trace = RouteTrace::find_info(&local_msg).unwrap().unwrap();
+        assert_eq!(trace.addresses(), vec!["a".into(), "b".into()]);
+        assert_eq!(local_msg.local_info().len(), 1);
+    }
+}
@@ -44,6 +44,34 @@ impl TransportMessage {
             payload,
         }
     }
+
+    /// Length of the encoded message, computed without encoding it
+    pub fn encoded_len(&self) -> usize {
+        // The version, then the routes and the payload as BARE lists
+        1 + route_encoded_len(&self.onward_route)
+            + route_encoded_len(&self.return_route)
+            + uint_encoded_len(self.payload.len())
+            + self.payload.len()
+    }
+}
+
+/// Length of a BARE `uint`, encoded with 7 bits per byte
+fn uint_encoded_len(mut value: usize) -> usize {
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+/// Length of an encoded route: its addresses are a transport type and a list of bytes
+fn route_encoded_len(route: &Route) -> usize {
+    uint_encoded_len(route.len())
+        + route
+            .iter()
+            .map(|address| 1 + uint_encoded_len(address.len()) + address.len())
+            .sum::<usize>()
 }
 
 impl Display for TransportMessage {
@@ -55,3 +83,28 @@ impl Display for TransportMessage {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::format;
+    use crate::{route, Address, Encodable};
+
+    #[test]
+    fn encoded_len_matches_encode() {
+        let binary = Address::from(&[0xff; 200][..]);
+        let mut long_route = Route::new();
+        for i in 0..130 {
+            long_route = long_route.append(format!("hop{i}"));
+        }
+        let messages = vec![
+            TransportMessage::v1(route![], route![], vec![]),
+            TransportMessage::v1(route!["a", "b"], route!["c"], vec![1, 2, 3]),
+            TransportMessage::v1(route![binary], long_route, vec![7; 20_000]),
+        ];
+
+        for msg in messages {
+            assert_eq!(msg.encoded_len(), msg.encode().unwrap().len());
+        }
+    }
+}
@@ -25,6 +25,14 @@ pub trait Worker: Send + 'static {
     }
 
     /// Override shutdown behaviour.
+    ///
+    /// This hook is called exactly once, after the worker stopped handling messages and
+    /// before its mailbox is dropped, whether the worker is stopped on its own or when its
+    /// node is stopped. A worker force-stopped because the node shutdown timed out is
+    /// interrupted in the middle of a message, but its hook still runs. With the `std`
+    /// feature, the node then gives the hooks a limited time to complete, configured when the
+    /// node is built, before its runtime is dropped. Without it, the hooks still running when
+    /// the node stops may not complete.
     async fn shutdown(&mut self, _context: &mut Self::Context) -> Result<()> {
         Ok(())
     }
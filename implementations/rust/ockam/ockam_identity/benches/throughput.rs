@@ -0,0 +1,160 @@
+//! Steady-state throughput of a node: local messages between workers, messages sent over a
+//! TCP loopback connection and messages encrypted and decrypted by a secure channel.
+//!
+//! Run with `cargo bench -p ockam_identity --bench throughput`. Each iteration sends a batch
+//! of messages to a sink worker and waits until the sink received all of them.
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+use ockam_core::{route, AllowAll, Result, Route, Routed, Worker};
+use ockam_identity::secure_channels::secure_channels;
+use ockam_identity::{SecureChannelListenerOptions, SecureChannelOptions};
+use ockam_node::{Context, NodeBuilder};
+use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions, TcpTransport};
+use tokio::runtime::Handle;
+
+/// Number of messages sent during each benchmark iteration
+const MESSAGES_PER_ITERATION: usize = 100;
+
+/// Sizes of the payloads of the messages, in bytes
+const PAYLOAD_SIZES: [usize; 3] = [64, 1024, 16 * 1024];
+
+/// Address of the context receiving the notifications of the sinks
+const BENCH_ADDRESS: &str = "bench";
+
+/// Worker dropping the messages it receives, which notifies the benchmark once it
+/// received all the messages of an iteration
+struct NullSink {
+    received: usize,
+}
+
+#[ockam_core::worker]
+impl Worker for NullSink {
+    type Message = Vec<u8>;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, _msg: Routed<Vec<u8>>) -> Result<()> {
+        self.received += 1;
+        if self.received == MESSAGES_PER_ITERATION {
+            self.received = 0;
+            ctx.send(route![BENCH_ADDRESS], ()).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Start a [`NullSink`] and wait until it's ready to handle messages
+///
+/// The node has no way to subscribe to the changes of its registry, so we ask the router
+/// with `wait_for`, which answers once the sink has been initialized. The first iteration
+/// then doesn't measure the start of the sink.
+async fn start_sink(ctx: &Context, address: &str) -> Result<()> {
+    ctx.start_worker(address, NullSink { received: 0 }).await?;
+    ctx.wait_for(address).await
+}
+
+/// Routes to the sinks of each benchmark
+struct Routes {
+    local: Route,
+    tcp: Route,
+    secure_channel: Route,
+}
+
+/// Start the workers of all the benchmarks. Every step returns once the worker, connection
+/// or channel it creates can be used, so the benchmarks start right after the setup
+async fn setup(ctx: &Context) -> Result<Routes> {
+    start_sink(ctx, "local_sink").await?;
+
+    let tcp = TcpTransport::create(ctx).await?;
+    let tcp_listener_options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("tcp_sink", &tcp_listener_options.spawner_flow_control_id());
+    start_sink(ctx, "tcp_sink").await?;
+    let tcp_listener = tcp.listen("127.0.0.1:0", tcp_listener_options).await?;
+    let connection = tcp
+        .connect(tcp_listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+    let listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+    ctx.flow_controls()
+        .add_consumer("secure_channel_sink", listener.flow_control_id());
+    start_sink(ctx, "secure_channel_sink").await?;
+    // The channel is returned once the handshake completed
+    let channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    Ok(Routes {
+        local: route!["local_sink"],
+        tcp: route![connection.sender_address().clone(), "tcp_sink"],
+        secure_channel: route![channel.encryptor_address().clone(), "secure_channel_sink"],
+    })
+}
+
+/// Send a batch of messages, then wait for the sink to receive all of them
+async fn send_batch(ctx: &mut Context, route: &Route, payload: &[u8]) -> Result<()> {
+    for _ in 0..MESSAGES_PER_ITERATION {
+        ctx.send(route.clone(), payload.to_vec()).await?;
+    }
+    ctx.receive::<()>().await?;
+    Ok(())
+}
+
+fn bench_throughput(c: &mut Criterion, name: &str, rt: &Handle, ctx: &mut Context, route: &Route) {
+    let mut group = c.benchmark_group(name);
+    for size in PAYLOAD_SIZES {
+        let payload = vec![0u8; size];
+        group.throughput(Throughput::Bytes((size * MESSAGES_PER_ITERATION) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &payload, |b, payload| {
+            b.iter(|| rt.block_on(send_batch(ctx, route, payload)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn main() {
+    let (ctx, mut executor) = NodeBuilder::new().no_logging().build();
+    executor
+        .execute(async move {
+            let routes = setup(&ctx).await?;
+            let mut bench_ctx = ctx.new_detached(BENCH_ADDRESS, AllowAll, AllowAll).await?;
+            let rt = ctx.runtime().clone();
+
+            // Criterion runs synchronously, it drives the node from a blocking thread
+            let res = tokio::task::spawn_blocking(move || {
+                let mut criterion = Criterion::default().configure_from_args();
+                bench_throughput(&mut criterion, "local", &rt, &mut bench_ctx, &routes.local);
+                bench_throughput(&mut criterion, "tcp", &rt, &mut bench_ctx, &routes.tcp);
+                bench_throughput(
+                    &mut criterion,
+                    "secure_channel",
+                    &rt,
+                    &mut bench_ctx,
+                    &routes.secure_channel,
+                );
+                criterion.final_summary();
+            })
+            .await;
+
+            ctx.stop().await?;
+            res.expect("the benchmarks panicked");
+            Result::<()>::Ok(())
+        })
+        .unwrap()
+        .unwrap();
+}
@@ -1,5 +1,8 @@
 use crate::models::{CredentialData, PurposeKeyAttestationData};
-use crate::{CredentialsCreation, CredentialsVerification, IdentitiesRepository, PurposeKeys};
+use crate::{
+    CredentialsCache, CredentialsCreation, CredentialsVerification, IdentitiesRepository,
+    PurposeKeys,
+};
 
 use ockam_core::compat::sync::Arc;
 use ockam_vault::{VaultForSigning, VaultForVerifyingSignatures};
@@ -20,6 +23,7 @@ pub struct Credentials {
     verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
     purpose_keys: Arc<PurposeKeys>,
     identities_repository: Arc<dyn IdentitiesRepository>,
+    cache: CredentialsCache,
 }
 
 impl Credentials {
@@ -29,12 +33,14 @@ impl Credentials {
         verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
         purpose_keys: Arc<PurposeKeys>,
         identities_repository: Arc<dyn IdentitiesRepository>,
+        cache: CredentialsCache,
     ) -> Self {
         Self {
             credential_vault,
             verifying_vault,
             purpose_keys,
             identities_repository,
+            cache,
         }
     }
 
@@ -64,6 +70,7 @@ impl Credentials {
             self.purpose_keys.purpose_keys_verification(),
             self.verifying_vault.clone(),
             self.identities_repository.clone(),
+            self.cache.clone(),
         ))
     }
 }
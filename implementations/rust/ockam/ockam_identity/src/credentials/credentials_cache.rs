@@ -0,0 +1,236 @@
+use core::time::Duration;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::compat::vec::Vec;
+use std::time::Instant;
+
+use crate::CredentialAndPurposeKeyData;
+
+/// Default number of verified credentials kept by a [`CredentialsCache`]
+pub const DEFAULT_CREDENTIALS_CACHE_CAPACITY: usize = 1024;
+
+/// Default duration during which a verified credential is served by a [`CredentialsCache`]
+pub const DEFAULT_CREDENTIALS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Least recently used cache of the credentials whose signatures were verified, shared by
+/// all the [`crate::CredentialsVerification`] of an [`crate::Identities`] instance
+///
+/// A credential presented again, for instance a group credential shared by many peers, is
+/// verified without checking its signatures while it's in the cache. Its validity period,
+/// its authority and its subject are still checked on every verification.
+///
+/// An entry is served for at most the configured TTL after the credential was verified, which
+/// bounds how long a revoked credential can still be accepted. The TTL is measured with a
+/// monotonic clock, so that setting the system time back doesn't extend it, while the
+/// expiration of the credential is checked against the system time. Use [`Self::clear`] to
+/// drop all the entries at once.
+#[derive(Clone)]
+pub struct CredentialsCache {
+    capacity: usize,
+    ttl: Duration,
+    inner: Arc<Mutex<CacheEntries>>,
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    // Credentials indexed by their encoded bytes
+    entries: BTreeMap<Vec<u8>, CacheEntry>,
+    // Keys of the entries, from the least to the most recently used
+    recency: BTreeMap<u64, Vec<u8>>,
+    last_use: u64,
+}
+
+struct CacheEntry {
+    data: CredentialAndPurposeKeyData,
+    verified_at: Instant,
+    last_use: u64,
+}
+
+impl Default for CredentialsCache {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_CREDENTIALS_CACHE_CAPACITY,
+            DEFAULT_CREDENTIALS_CACHE_TTL,
+        )
+    }
+}
+
+impl CredentialsCache {
+    /// Create a cache keeping up to `capacity` credentials for at most `ttl`.
+    /// A capacity of 0 disables the cache
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            inner: Default::default(),
+        }
+    }
+
+    /// A cache which never keeps any credential
+    pub fn disabled() -> Self {
+        Self::new(0, Duration::ZERO)
+    }
+
+    /// Number of credentials currently in the cache, including the expired ones which
+    /// were not evicted yet
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// True if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all the credentials from the cache
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.recency.clear();
+    }
+
+    /// Return the data of a credential verified less than a TTL before `now`
+    pub(crate) fn get(&self, key: &[u8], now: Instant) -> Option<CredentialAndPurposeKeyData> {
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+
+        let entry = inner.entries.get_mut(key)?;
+        if now.saturating_duration_since(entry.verified_at) >= self.ttl {
+            let last_use = entry.last_use;
+            inner.entries.remove(key);
+            inner.recency.remove(&last_use);
+            return None;
+        }
+
+        inner.last_use += 1;
+        let key = inner
+            .recency
+            .remove(&entry.last_use)
+            .unwrap_or_else(|| key.to_vec());
+        inner.recency.insert(inner.last_use, key);
+        entry.last_use = inner.last_use;
+
+        Some(entry.data.clone())
+    }
+
+    /// Add a verified credential, evicting the least recently used one if the cache is full
+    pub(crate) fn insert(&self, key: Vec<u8>, data: CredentialAndPurposeKeyData, now: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.last_use += 1;
+        let last_use = inner.last_use;
+
+        let entry = CacheEntry {
+            data,
+            verified_at: now,
+            last_use,
+        };
+        if let Some(previous) = inner.entries.insert(key.clone(), entry) {
+            inner.recency.remove(&previous.last_use);
+        }
+        inner.recency.insert(last_use, key);
+
+        while inner.entries.len() > self.capacity {
+            match inner.recency.pop_first() {
+                Some((_, oldest)) => {
+                    inner.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identities::identities;
+    use crate::models::CredentialSchemaIdentifier;
+    use crate::Attributes;
+    use ockam_core::Result;
+
+    async fn credential_data() -> Result<CredentialAndPurposeKeyData> {
+        let identities = identities();
+        let issuer = identities.identities_creation().create_identity().await?;
+        let credentials = identities.credentials();
+        let credential = credentials
+            .credentials_creation()
+            .issue_credential(
+                issuer.identifier(),
+                issuer.identifier(),
+                Attributes {
+                    schema: CredentialSchemaIdentifier(1),
+                    map: Default::default(),
+                },
+                Duration::from_secs(60),
+            )
+            .await?;
+
+        credentials
+            .credentials_verification()
+            .verify_credential(
+                Some(issuer.identifier()),
+                &[issuer.identifier().clone()],
+                &credential,
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction_and_ttl() -> Result<()> {
+        let data = credential_data().await?;
+        let cache = CredentialsCache::new(2, Duration::from_secs(10));
+        let now = Instant::now();
+
+        cache.insert(b"a".to_vec(), data.clone(), now);
+        cache.insert(b"b".to_vec(), data.clone(), now);
+        // "a" becomes the most recently used entry, "b" is evicted
+        assert!(cache.get(b"a", now).is_some());
+        cache.insert(b"c".to_vec(), data.clone(), now);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(b"b", now).is_none());
+        assert!(cache.get(b"a", now).is_some());
+        assert!(cache.get(b"c", now).is_some());
+
+        // Entries are not served once their TTL elapsed
+        assert!(cache.get(b"a", now + Duration::from_secs(9)).is_some());
+        assert!(cache.get(b"a", now + Duration::from_secs(10)).is_none());
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+
+        let disabled = CredentialsCache::disabled();
+        disabled.insert(b"a".to_vec(), data, now);
+        assert!(disabled.get(b"a", now).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ttl_follows_the_monotonic_clock() -> Result<()> {
+        let data = credential_data().await?;
+        let cache = CredentialsCache::new(2, Duration::from_secs(10));
+        let verified_at = Instant::now() + Duration::from_secs(100);
+        cache.insert(b"a".to_vec(), data, verified_at);
+
+        // A clock reading older than the verification doesn't expire, nor extend, the entry
+        assert!(cache
+            .get(b"a", verified_at - Duration::from_secs(50))
+            .is_some());
+        assert!(cache
+            .get(b"a", verified_at + Duration::from_secs(9))
+            .is_some());
+
+        // Moving the clock past the TTL expires it
+        assert!(cache
+            .get(b"a", verified_at + Duration::from_secs(10))
+            .is_none());
+        assert!(cache.is_empty());
+
+        Ok(())
+    }
+}
@@ -2,8 +2,8 @@ use crate::identities::AttributesEntry;
 use crate::models::{CredentialAndPurposeKey, CredentialData, Identifier, PurposePublicKey};
 use crate::utils::now;
 use crate::{
-    CredentialAndPurposeKeyData, IdentitiesRepository, IdentityError, PurposeKeyVerification,
-    TimestampInSeconds,
+    CredentialAndPurposeKeyData, CredentialsCache, IdentitiesRepository, IdentityError,
+    PurposeKeyVerification, TimestampInSeconds,
 };
 
 use ockam_core::compat::collections::BTreeMap;
@@ -11,6 +11,7 @@ use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
 use ockam_vault::VaultForVerifyingSignatures;
+use std::time::Instant;
 
 /// We allow Credentials to be created in the future related to this machine's time due to
 /// possible time dyssynchronization
@@ -21,6 +22,7 @@ pub struct CredentialsVerification {
     purpose_keys_verification: Arc<PurposeKeyVerification>,
     verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
     identities_repository: Arc<dyn IdentitiesRepository>,
+    cache: CredentialsCache,
 }
 
 impl CredentialsVerification {
@@ -29,14 +31,21 @@ impl CredentialsVerification {
         purpose_keys_verification: Arc<PurposeKeyVerification>,
         verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
         identities_repository: Arc<dyn IdentitiesRepository>,
+        cache: CredentialsCache,
     ) -> Self {
         Self {
             purpose_keys_verification,
             verifying_vault,
             identities_repository,
+            cache,
         }
     }
 
+    /// [`CredentialsCache`] of the credentials whose signatures were already verified
+    pub fn cache(&self) -> &CredentialsCache {
+        &self.cache
+    }
+
     /// [`IdentitiesRepository`]
     pub fn identities_repository(&self) -> Arc<dyn IdentitiesRepository> {
         self.identities_repository.clone()
@@ -45,12 +54,36 @@ impl CredentialsVerification {
 
 impl CredentialsVerification {
     /// Verify a [`Credential`]
+    ///
+    /// The signatures of a credential found in the [`CredentialsCache`] are not checked again
     // TODO: Move to CredentialsVerification
     pub async fn verify_credential(
         &self,
         expected_subject: Option<&Identifier>,
         authorities: &[Identifier],
         credential_and_purpose_key: &CredentialAndPurposeKey,
+    ) -> Result<CredentialAndPurposeKeyData> {
+        let key = minicbor::to_vec(credential_and_purpose_key)?;
+        let data = match self.cache.get(&key, Instant::now()) {
+            Some(data) => data,
+            None => {
+                let data = self
+                    .verify_signatures(authorities, credential_and_purpose_key)
+                    .await?;
+                self.cache.insert(key, data.clone(), Instant::now());
+                data
+            }
+        };
+
+        Self::check_credential_data(expected_subject, authorities, data)
+    }
+
+    /// Verify the signatures of the purpose key attestation and of the credential, and
+    /// parse the credential data
+    async fn verify_signatures(
+        &self,
+        authorities: &[Identifier],
+        credential_and_purpose_key: &CredentialAndPurposeKey,
     ) -> Result<CredentialAndPurposeKeyData> {
         let purpose_key_data = self
             .purpose_keys_verification
@@ -60,6 +93,7 @@ impl CredentialsVerification {
             )
             .await?;
 
+        // Don't spend time on the signature of a credential which is rejected anyway
         if !authorities.contains(&purpose_key_data.subject) {
             return Err(IdentityError::UnknownAuthority.into());
         }
@@ -100,6 +134,28 @@ impl CredentialsVerification {
 
         let credential_data = CredentialData::get_data(&versioned_data)?;
 
+        Ok(CredentialAndPurposeKeyData {
+            credential_data,
+            purpose_key_data,
+        })
+    }
+
+    /// Check the authority, the subject and the validity period of a credential whose
+    /// signatures were verified
+    fn check_credential_data(
+        expected_subject: Option<&Identifier>,
+        authorities: &[Identifier],
+        data: CredentialAndPurposeKeyData,
+    ) -> Result<CredentialAndPurposeKeyData> {
+        let CredentialAndPurposeKeyData {
+            credential_data,
+            purpose_key_data,
+        } = data;
+
+        if !authorities.contains(&purpose_key_data.subject) {
+            return Err(IdentityError::UnknownAuthority.into());
+        }
+
         if credential_data.subject.is_none() {
             // Currently unsupported
             return Err(IdentityError::CredentialVerificationFailed.into());
@@ -178,7 +234,21 @@ impl CredentialsVerification {
             )
             .await?;
 
-        let map = credential_data.credential_data.subject_attributes.map;
+        self.persist_credential_attributes(subject, &credential_data)
+            .await
+    }
+
+    /// Put attributes from an already verified [`Credential`] to the storage
+    pub async fn persist_credential_attributes(
+        &self,
+        subject: &Identifier,
+        credential_data: &CredentialAndPurposeKeyData,
+    ) -> Result<()> {
+        let map = credential_data
+            .credential_data
+            .subject_attributes
+            .map
+            .clone();
         let map: BTreeMap<_, _> = map
             .into_iter()
             .map(|(k, v)| (Vec::<u8>::from(k), Vec::<u8>::from(v)))
@@ -191,11 +261,9 @@ impl CredentialsVerification {
                     map,
                     now()?,
                     Some(credential_data.credential_data.expires_at),
-                    Some(credential_data.purpose_key_data.subject),
+                    Some(credential_data.purpose_key_data.subject.clone()),
                 ),
             )
-            .await?;
-
-        Ok(())
+            .await
     }
 }
@@ -1,6 +1,7 @@
 mod authority_service;
 #[allow(clippy::module_inception)]
 mod credentials;
+mod credentials_cache;
 mod credentials_creation;
 mod credentials_issuer;
 mod credentials_retriever;
@@ -12,6 +13,7 @@ mod trust_context;
 
 pub use authority_service::*;
 pub use credentials::*;
+pub use credentials_cache::*;
 pub use credentials_creation::*;
 pub use credentials_issuer::*;
 pub use credentials_retriever::*;
@@ -50,6 +50,43 @@ pub enum IdentityError {
     InvalidHex,
     /// Secret Key doesn't correspond to the Identity
     WrongSecretKey,
+    /// The sender of a sealed message couldn't be verified
+    SealedMessageVerificationFailed,
+    /// Unknown version of a sealed message
+    UnknownSealedMessageVersion,
+    /// The identity is not a recipient of a sealed message
+    NotAGroupMember,
+    /// Secret Key referenced by an exported Identity is not present in the Vault
+    MissingSecretKey,
+    /// Attributes of a credential presented during a Secure Channel handshake couldn't be persisted
+    AttributesPersistenceFailed,
+    /// The other side of a Secure Channel doesn't have the same pre-shared key
+    SecureChannelPreSharedKeyMismatch,
+    /// A Secure Channel handshake didn't arrive through the transport required by the listener
+    SecureChannelUnauthorizedTransport,
+    /// A Secure Channel handshake was cancelled before completing
+    SecureChannelCancelled,
+    /// A Secure Channel handshake didn't complete before its timeout
+    SecureChannelHandshakeTimeout,
+    /// No established Secure Channel has the given address
+    SecureChannelNotFound,
+    /// A Secure Channel listener is already registered with the given address
+    DuplicateSecureChannelListener,
+    /// No cipher suite is allowed by both sides of a Secure Channel
+    SecureChannelNoCommonCipher,
+    /// Only one side of a Secure Channel enabled the hybrid post-quantum key exchange.
+    /// The handshake is rejected to prevent a downgrade to the classical key exchange
+    SecureChannelPqHybridMismatch,
+    /// An attribute value couldn't be converted to the requested type
+    InvalidAttributeValue,
+    /// A Secure Channel Listener received too many handshakes from the same source
+    SecureChannelRateLimited,
+    /// A compressed Secure Channel message couldn't be decompressed, or compression
+    /// wasn't negotiated for the channel
+    InvalidCompressedMessage,
+    /// A Secure Channel can't be stopped while other Secure Channels are carried over it,
+    /// unless it's force-stopped
+    SecureChannelHasInnerChannels,
 }
 
 impl ockam_core::compat::error::Error for IdentityError {}
@@ -62,8 +99,21 @@ impl core::fmt::Display for IdentityError {
 impl From<IdentityError> for Error {
     #[track_caller]
     fn from(err: IdentityError) -> Self {
-        let kind = Kind::Unknown; // FIXME: fill these in with more
-                                  // meaningful error kinds
+        let kind = match err {
+            IdentityError::SecureChannelCancelled => Kind::Cancelled,
+            IdentityError::SecureChannelHandshakeTimeout => Kind::Timeout,
+            IdentityError::SecureChannelNotFound => Kind::NotFound,
+            IdentityError::DuplicateSecureChannelListener => Kind::AlreadyExists,
+            IdentityError::SecureChannelNoCommonCipher => Kind::Unsupported,
+            IdentityError::SecureChannelPqHybridMismatch => Kind::Protocol,
+            IdentityError::InvalidAttributeValue => Kind::Serialization,
+            IdentityError::SecureChannelRateLimited => Kind::ResourceExhausted,
+            IdentityError::SecureChannelUnauthorizedTransport => Kind::Misuse,
+            IdentityError::InvalidCompressedMessage => Kind::Protocol,
+            IdentityError::SecureChannelHasInnerChannels => Kind::Conflict,
+            _ => Kind::Unknown, // FIXME: fill these in with more
+                                // meaningful error kinds
+        };
         Error::new(Origin::Identity, kind, err)
     }
 }
@@ -1,8 +1,9 @@
 use crate::identities::{IdentitiesKeys, IdentitiesRepository};
 use crate::purpose_keys::storage::{PurposeKeysRepository, PurposeKeysStorage};
 use crate::{
-    Credentials, CredentialsServer, CredentialsServerModule, Identifier, IdentitiesBuilder,
-    IdentitiesCreation, IdentitiesReader, IdentitiesStorage, Identity, PurposeKeys, Vault,
+    AttributeValue, AttributesEntry, Credentials, CredentialsCache, CredentialsServer,
+    CredentialsServerModule, Identifier, IdentitiesBuilder, IdentitiesCreation, IdentitiesReader,
+    IdentitiesStorage, Identity, IdentityAttributesReader, PurposeKeys, SealedMessages, Vault,
 };
 
 use ockam_core::compat::sync::Arc;
@@ -15,6 +16,7 @@ pub struct Identities {
     vault: Vault,
     identities_repository: Arc<dyn IdentitiesRepository>,
     purpose_keys_repository: Arc<dyn PurposeKeysRepository>,
+    credentials_cache: CredentialsCache,
 }
 
 impl Identities {
@@ -28,6 +30,32 @@ impl Identities {
         self.identities_repository.clone()
     }
 
+    /// Get the attributes of `subject` stored in the identities repository
+    ///
+    /// Returns `None` if no attributes were stored for `subject`, or if they expired
+    pub async fn get_attributes(&self, subject: &Identifier) -> Result<Option<AttributesEntry>> {
+        self.identities_repository.get_attributes(subject).await
+    }
+
+    /// Get the value of the attribute `name` of `subject`, converted to `T`
+    ///
+    /// Returns `None` if `subject` doesn't have that attribute, and an error if the value
+    /// can't be converted
+    pub async fn get_attribute<T: AttributeValue>(
+        &self,
+        subject: &Identifier,
+        name: &str,
+    ) -> Result<Option<T>> {
+        match self.get_attributes(subject).await? {
+            Some(entry) => entry
+                .attrs()
+                .get(name.as_bytes())
+                .map(|value| T::from_attribute_value(value))
+                .transpose(),
+            None => Ok(None),
+        }
+    }
+
     /// Return the purpose keys repository
     pub fn purpose_keys_repository(&self) -> Arc<dyn PurposeKeysRepository> {
         self.purpose_keys_repository.clone()
@@ -88,6 +116,23 @@ impl Identities {
             self.vault.verifying_vault.clone(),
             self.purpose_keys(),
             self.identities_repository.clone(),
+            self.credentials_cache.clone(),
+        ))
+    }
+
+    /// Return the cache of the verified credentials, shared by all the
+    /// [`crate::CredentialsVerification`] of these identities
+    pub fn credentials_cache(&self) -> &CredentialsCache {
+        &self.credentials_cache
+    }
+
+    /// Return the sealed messages service
+    pub fn sealed_messages(&self) -> Arc<SealedMessages> {
+        Arc::new(SealedMessages::new(
+            self.vault.clone(),
+            self.identities_keys(),
+            self.identities_repository.clone(),
+            self.purpose_keys(),
         ))
     }
 
@@ -103,11 +148,13 @@ impl Identities {
         vault: Vault,
         identities_repository: Arc<dyn IdentitiesRepository>,
         purpose_keys_repository: Arc<dyn PurposeKeysRepository>,
+        credentials_cache: CredentialsCache,
     ) -> Identities {
         Identities {
             vault,
             identities_repository,
             purpose_keys_repository,
+            credentials_cache,
         }
     }
 
@@ -117,6 +164,7 @@ impl Identities {
             vault: Vault::create(),
             repository: IdentitiesStorage::create(),
             purpose_keys_repository: PurposeKeysStorage::create(),
+            credentials_cache: CredentialsCache::default(),
         }
     }
 }
@@ -1,8 +1,9 @@
 use crate::identities::{Identities, IdentitiesRepository, IdentitiesStorage};
 use crate::purpose_keys::storage::{PurposeKeysRepository, PurposeKeysStorage};
 use crate::storage::Storage;
-use crate::{Vault, VaultStorage};
+use crate::{CredentialsCache, Vault, VaultStorage};
 
+use core::time::Duration;
 use ockam_core::compat::sync::Arc;
 
 /// Builder for Identities services
@@ -11,6 +12,7 @@ pub struct IdentitiesBuilder {
     pub(crate) vault: Vault,
     pub(crate) repository: Arc<dyn IdentitiesRepository>,
     pub(crate) purpose_keys_repository: Arc<dyn PurposeKeysRepository>,
+    pub(crate) credentials_cache: CredentialsCache,
 }
 
 /// Return a default identities
@@ -56,12 +58,21 @@ impl IdentitiesBuilder {
         self
     }
 
+    /// Keep up to `capacity` verified credentials for at most `ttl` in the
+    /// [`CredentialsCache`], so that their signatures are not verified again.
+    /// A capacity of 0 disables the cache
+    pub fn with_credentials_cache(mut self, capacity: usize, ttl: Duration) -> Self {
+        self.credentials_cache = CredentialsCache::new(capacity, ttl);
+        self
+    }
+
     /// Build identities
     pub fn build(self) -> Arc<Identities> {
         Arc::new(Identities::new(
             self.vault,
             self.repository,
             self.purpose_keys_repository,
+            self.credentials_cache,
         ))
     }
 }
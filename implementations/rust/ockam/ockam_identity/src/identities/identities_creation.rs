@@ -1,12 +1,26 @@
 use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
+#[cfg(feature = "test_utils")]
+use ockam_vault::{EdDSACurve25519SecretKey, SigningSecret, SoftwareVaultForSigning};
 use ockam_vault::{SigningSecretKeyHandle, VaultForSigning, VaultForVerifyingSignatures};
 
 use crate::identities::identity_builder::IdentityBuilder;
-use crate::models::{ChangeHistory, Identifier};
+#[cfg(feature = "test_utils")]
+use crate::models::TimestampInSeconds;
+use crate::models::{ChangeHistory, Identifier, PrivateIdentityExport};
 use crate::{IdentitiesKeys, IdentitiesRepository, Identity, IdentityError};
 use crate::{IdentityHistoryComparison, IdentityOptions};
 
+/// Creation timestamp of the identities created with [`IdentitiesCreation::create_identity_with_seed`]
+#[cfg(feature = "test_utils")]
+const SEEDED_IDENTITY_CREATED_AT: TimestampInSeconds = TimestampInSeconds(1_672_531_200); // 2023-01-01
+
+/// Expiration timestamp of the identities created with [`IdentitiesCreation::create_identity_with_seed`]
+#[cfg(feature = "test_utils")]
+const SEEDED_IDENTITY_EXPIRES_AT: TimestampInSeconds =
+    TimestampInSeconds(1_672_531_200 + 100 * 365 * 24 * 60 * 60); // A hundred years later
+
 /// This struct supports functions for the creation and import of identities using an IdentityVault
 pub struct IdentitiesCreation {
     pub(super) repository: Arc<dyn IdentitiesRepository>,
@@ -85,6 +99,46 @@ impl IdentitiesCreation {
         builder.build().await
     }
 
+    /// Create an `Identity` whose key is derived from `seed` and store it
+    ///
+    /// The key and the timestamps are fixed, so the same seed always produces the same
+    /// [`Identifier`]. This is only meant for tests and development fixtures: anyone knowing
+    /// the seed can impersonate the identity, use [`Self::create_identity`] otherwise.
+    /// `vault` must be the [`VaultForSigning`] of these identities, otherwise
+    /// [`IdentityError::MissingSecretKey`] is returned and the key is deleted from `vault`.
+    ///
+    /// This function is only available with the `test_utils` feature
+    #[cfg(feature = "test_utils")]
+    pub async fn create_identity_with_seed(
+        &self,
+        vault: &SoftwareVaultForSigning,
+        seed: [u8; 32],
+    ) -> Result<Identity> {
+        let signing_secret_key_handle = vault
+            .import_key(SigningSecret::EdDSACurve25519(
+                EdDSACurve25519SecretKey::new(seed),
+            ))
+            .await?;
+
+        if self
+            .identity_vault
+            .get_verifying_public_key(&signing_secret_key_handle)
+            .await
+            .is_err()
+        {
+            vault
+                .delete_signing_secret_key(signing_secret_key_handle)
+                .await?;
+            return Err(IdentityError::MissingSecretKey.into());
+        }
+
+        self.identity_builder()
+            .with_existing_key(signing_secret_key_handle)
+            .with_timestamps(SEEDED_IDENTITY_CREATED_AT, SEEDED_IDENTITY_EXPIRES_AT)
+            .build()
+            .await
+    }
+
     /// Create an `Identity` and store it
     pub async fn create_identity_with_options(&self, options: IdentityOptions) -> Result<Identity> {
         let identity = self.identities_keys().create_initial_key(options).await?;
@@ -153,6 +207,53 @@ impl IdentitiesCreation {
         Ok(identity)
     }
 
+    /// Export an existing Identity together with a handle to its current signing secret key,
+    /// so that it can be restored later with [`Self::import_exported_private_identity`]
+    /// using the same Vault. The secret key itself never leaves the Vault
+    pub async fn export_private_identity(&self, identifier: &Identifier) -> Result<Vec<u8>> {
+        let change_history = self.repository.get_identity(identifier).await?;
+        let identity = Identity::import_from_change_history(
+            Some(identifier),
+            change_history,
+            self.verifying_vault.clone(),
+        )
+        .await?;
+
+        let signing_secret_key_handle = self.identities_keys().get_secret_key(&identity).await?;
+
+        PrivateIdentityExport {
+            change_history: identity.change_history().clone(),
+            signing_key_handle: signing_secret_key_handle.into(),
+        }
+        .export()
+    }
+
+    /// Import an Identity exported with [`Self::export_private_identity`]
+    /// Its secret key is expected to exist in the Vault, otherwise
+    /// [`IdentityError::MissingSecretKey`] is returned
+    pub async fn import_exported_private_identity(&self, data: &[u8]) -> Result<Identity> {
+        let exported = PrivateIdentityExport::import(data)?;
+        let signing_secret_key_handle: SigningSecretKeyHandle = exported.signing_key_handle.into();
+
+        if self
+            .identity_vault
+            .get_verifying_public_key(&signing_secret_key_handle)
+            .await
+            .is_err()
+        {
+            vault
+                .delete_signing_secret_key(signing_secret_key_handle)
+                .await?;
+            return Err(IdentityError::MissingSecretKey.into());
+        }
+
+        self.import_private_identity(
+            &exported.change_history.export()?,
+            &signing_secret_key_handle,
+        )
+        .await
+    }
+
     /// [`SigningVault`]
     pub fn identity_vault(&self) -> Arc<dyn VaultForSigning> {
         self.identity_vault.clone()
@@ -0,0 +1,35 @@
+use ockam_core::compat::string::String;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+use crate::IdentityError;
+
+/// Type of a value which can be read from the attributes of an identity,
+/// see [`crate::Identities::get_attribute`]
+pub trait AttributeValue: Sized {
+    /// Convert the raw bytes of an attribute value
+    fn from_attribute_value(value: &[u8]) -> Result<Self>;
+}
+
+impl AttributeValue for Vec<u8> {
+    fn from_attribute_value(value: &[u8]) -> Result<Self> {
+        Ok(value.to_vec())
+    }
+}
+
+impl AttributeValue for String {
+    fn from_attribute_value(value: &[u8]) -> Result<Self> {
+        String::from_utf8(value.to_vec()).map_err(|_| IdentityError::InvalidAttributeValue.into())
+    }
+}
+
+/// Booleans are stored as `"true"` or `"false"`
+impl AttributeValue for bool {
+    fn from_attribute_value(value: &[u8]) -> Result<Self> {
+        match value {
+            b"true" => Ok(true),
+            b"false" => Ok(false),
+            _ => Err(IdentityError::InvalidAttributeValue.into()),
+        }
+    }
+}
@@ -1,7 +1,9 @@
+mod attribute_value;
 mod attributes_entry;
 mod identities_repository_impl;
 mod identities_repository_trait;
 
+pub use attribute_value::*;
 pub use attributes_entry::*;
 pub use identities_repository_impl::*;
 pub use identities_repository_trait::*;
@@ -14,6 +14,8 @@
 //!    between 2 identities
 //!
 //!  - the `secure_channels` module provides services to create a secure channel between 2 identities
+//!
+//!  - the `sealed_messages` module provides services to seal a message to a group of identities
 
 #![deny(unsafe_code)]
 #![warn(
@@ -62,6 +64,9 @@ pub mod secure_channel;
 /// Service supporting the creation of secure channel listener and connection to a listener
 pub mod secure_channels;
 
+/// Service sealing messages to a group of recipients
+pub mod sealed_messages;
+
 /// Storage functions
 pub mod storage;
 
@@ -77,6 +82,7 @@ pub use identities::*;
 pub use identity::*;
 pub use purpose_key::*;
 pub use purpose_keys::*;
+pub use sealed_messages::*;
 pub use secure_channel::*;
 pub use secure_channels::*;
 pub use vault::*;
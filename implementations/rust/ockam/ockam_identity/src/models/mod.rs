@@ -2,7 +2,9 @@ mod change_history;
 mod credential;
 mod credential_and_purpose_key;
 mod identifiers;
+mod private_identity;
 mod purpose_key_attestation;
+mod sealed_message;
 mod timestamp;
 mod utils;
 mod versioned_data;
@@ -11,6 +13,8 @@ pub use change_history::*;
 pub use credential::*;
 pub use credential_and_purpose_key::*;
 pub use identifiers::*;
+pub use private_identity::*;
 pub use purpose_key_attestation::*;
+pub use sealed_message::*;
 pub use timestamp::*;
 pub use versioned_data::*;
@@ -0,0 +1,25 @@
+use crate::models::ChangeHistory;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::vec::Vec;
+
+/// Exported [`ChangeHistory`] of an Identity together with a handle to its current
+/// signing secret key. The secret itself never leaves the Vault
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PrivateIdentityExport {
+    /// Public change history of the Identity
+    #[n(1)] pub change_history: ChangeHistory,
+    /// Handle to the signing secret key of the latest change, as stored in the Vault
+    #[n(2)] pub signing_key_handle: SigningKeyHandle,
+}
+
+/// Vault handle to a signing secret key
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+pub enum SigningKeyHandle {
+    /// Handle to a Curve25519 key used for EdDSA signatures
+    #[n(1)] EdDSACurve25519(#[cbor(with = "minicbor::bytes")] #[n(0)] Vec<u8>),
+    /// Handle to a Curve P-256 key used for ECDSA SHA256 signatures
+    #[n(2)] ECDSASHA256CurveP256(#[cbor(with = "minicbor::bytes")] #[n(0)] Vec<u8>),
+}
@@ -0,0 +1,59 @@
+use crate::models::Identifier;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::{string::String, vec::Vec};
+use ockam_vault::{ECDSASHA256CurveP256Signature, EdDSACurve25519Signature, X25519PublicKey};
+
+/// Message sealed to a group of recipients and signed by its sender
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct SealedMessage {
+    /// CBOR serialized [`super::VersionedData`]
+    /// where VersionedData::data is CBOR serialized [`SealedMessageData`]
+    #[cbor(with = "minicbor::bytes")]
+    #[n(1)] pub data: Vec<u8>,
+    /// Signature over data field using the primary key of the sender's
+    /// [`super::super::identity::Identity`]
+    #[n(2)] pub signature: SealedMessageSignature,
+}
+
+/// Signature over [`SealedMessageData`] using the sender's primary key
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+pub enum SealedMessageSignature {
+    /// An EdDSA signature using Curve 25519.
+    #[n(1)] EdDSACurve25519(#[n(0)] EdDSACurve25519Signature),
+    /// An ECDSA signature using SHA-256 and Curve P-256.
+    #[n(2)] ECDSASHA256CurveP256(#[n(0)] ECDSASHA256CurveP256Signature),
+}
+
+/// Data inside a [`SealedMessage`]
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct SealedMessageData {
+    /// Name of the group this message was sealed to
+    #[n(1)] pub group: String,
+    /// Exported [`super::ChangeHistory`] of the sender
+    #[cbor(with = "minicbor::bytes")]
+    #[n(2)] pub sender: Vec<u8>,
+    /// Ephemeral X25519 public key used to wrap the content key for every recipient
+    #[n(3)] pub ephemeral_public_key: X25519PublicKey,
+    /// Content key wrapped for every group member
+    #[n(4)] pub recipients: Vec<SealedMessageRecipient>,
+    /// Payload encrypted with the content key
+    #[cbor(with = "minicbor::bytes")]
+    #[n(5)] pub ciphertext: Vec<u8>,
+}
+
+/// Content key of a [`SealedMessage`] wrapped for a single group member
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct SealedMessageRecipient {
+    /// [`Identifier`] of the group member
+    #[n(1)] pub identifier: Identifier,
+    /// Content key encrypted with a key derived from the member's Secure Channel Purpose Key
+    #[cbor(with = "minicbor::bytes")]
+    #[n(2)] pub wrapped_key: Vec<u8>,
+}
@@ -8,5 +8,7 @@ fn get_versioned_data<'a, T: Decode<'a, ()>>(data: &'a [u8]) -> Result<T> {
 mod change_history;
 mod credentials;
 mod identifiers;
+mod private_identity;
 mod purpose_key_attestation;
+mod sealed_message;
 mod timestamp;
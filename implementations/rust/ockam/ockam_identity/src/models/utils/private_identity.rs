@@ -0,0 +1,42 @@
+use crate::models::{PrivateIdentityExport, SigningKeyHandle};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use ockam_vault::{HandleToSecret, SigningSecretKeyHandle};
+
+impl PrivateIdentityExport {
+    /// Export [`PrivateIdentityExport`] to a binary format using CBOR
+    pub fn export(&self) -> Result<Vec<u8>> {
+        Ok(minicbor::to_vec(self)?)
+    }
+
+    /// Import [`PrivateIdentityExport`] from a binary format using CBOR
+    pub fn import(data: &[u8]) -> Result<Self> {
+        Ok(minicbor::decode(data)?)
+    }
+}
+
+impl From<SigningSecretKeyHandle> for SigningKeyHandle {
+    fn from(value: SigningSecretKeyHandle) -> Self {
+        match value {
+            SigningSecretKeyHandle::EdDSACurve25519(handle) => {
+                Self::EdDSACurve25519(handle.value().clone())
+            }
+            SigningSecretKeyHandle::ECDSASHA256CurveP256(handle) => {
+                Self::ECDSASHA256CurveP256(handle.value().clone())
+            }
+        }
+    }
+}
+
+impl From<SigningKeyHandle> for SigningSecretKeyHandle {
+    fn from(value: SigningKeyHandle) -> Self {
+        match value {
+            SigningKeyHandle::EdDSACurve25519(handle) => {
+                Self::EdDSACurve25519(HandleToSecret::new(handle))
+            }
+            SigningKeyHandle::ECDSASHA256CurveP256(handle) => {
+                Self::ECDSASHA256CurveP256(HandleToSecret::new(handle))
+            }
+        }
+    }
+}
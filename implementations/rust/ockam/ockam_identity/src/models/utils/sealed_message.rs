@@ -0,0 +1,39 @@
+use crate::models::utils::get_versioned_data;
+use crate::models::{SealedMessage, SealedMessageData, SealedMessageSignature, VersionedData};
+
+use ockam_core::Result;
+use ockam_vault::Signature;
+
+impl SealedMessage {
+    /// Extract [`VersionedData`]
+    pub fn get_versioned_data(&self) -> Result<VersionedData> {
+        get_versioned_data(&self.data)
+    }
+}
+
+impl SealedMessageData {
+    /// Extract [`SealedMessageData`] from [`VersionedData`]
+    pub fn get_data(versioned_data: &VersionedData) -> Result<Self> {
+        Ok(minicbor::decode(&versioned_data.data)?)
+    }
+}
+
+impl From<SealedMessageSignature> for Signature {
+    fn from(value: SealedMessageSignature) -> Self {
+        match value {
+            SealedMessageSignature::EdDSACurve25519(value) => Self::EdDSACurve25519(value),
+            SealedMessageSignature::ECDSASHA256CurveP256(value) => {
+                Self::ECDSASHA256CurveP256(value)
+            }
+        }
+    }
+}
+
+impl From<Signature> for SealedMessageSignature {
+    fn from(value: Signature) -> Self {
+        match value {
+            Signature::EdDSACurve25519(value) => Self::EdDSACurve25519(value),
+            Signature::ECDSASHA256CurveP256(value) => Self::ECDSASHA256CurveP256(value),
+        }
+    }
+}
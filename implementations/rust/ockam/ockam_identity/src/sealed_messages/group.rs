@@ -0,0 +1,56 @@
+use crate::models::Identifier;
+
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::string::String;
+use ockam_vault::X25519PublicKey;
+
+/// Public key material of a named group of recipients.
+///
+/// Every member is represented by the public key of its Secure Channel Purpose Key,
+/// which is all a sender needs in order to seal a message to the group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SealingGroup {
+    name: String,
+    members: BTreeMap<Identifier, X25519PublicKey>,
+}
+
+impl SealingGroup {
+    /// Create an empty group
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            members: Default::default(),
+        }
+    }
+
+    /// Name of the group
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Add a member or replace the public key of an existing member
+    pub fn add_member(&mut self, identifier: Identifier, public_key: X25519PublicKey) {
+        self.members.insert(identifier, public_key);
+    }
+
+    /// Add a member
+    pub fn with_member(mut self, identifier: Identifier, public_key: X25519PublicKey) -> Self {
+        self.add_member(identifier, public_key);
+        self
+    }
+
+    /// Remove a member. Messages sealed after the removal can't be opened by that member
+    pub fn remove_member(&mut self, identifier: &Identifier) -> bool {
+        self.members.remove(identifier).is_some()
+    }
+
+    /// Return true if the given [`Identifier`] is a current member of the group
+    pub fn is_member(&self, identifier: &Identifier) -> bool {
+        self.members.contains_key(identifier)
+    }
+
+    /// Current members of the group
+    pub fn members(&self) -> &BTreeMap<Identifier, X25519PublicKey> {
+        &self.members
+    }
+}
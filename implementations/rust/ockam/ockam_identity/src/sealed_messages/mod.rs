@@ -0,0 +1,6 @@
+mod group;
+#[allow(clippy::module_inception)]
+mod sealed_messages;
+
+pub use group::*;
+pub use sealed_messages::*;
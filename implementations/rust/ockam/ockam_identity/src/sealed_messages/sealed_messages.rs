@@ -0,0 +1,289 @@
+use crate::models::{
+    Identifier, SealedMessage, SealedMessageData, SealedMessageRecipient, VersionedData,
+};
+use crate::sealed_messages::SealingGroup;
+use crate::{IdentitiesKeys, IdentitiesRepository, Identity, IdentityError, PurposeKeys, Vault};
+
+use ockam_core::compat::rand::{thread_rng, RngCore};
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use ockam_vault::{
+    AeadSecretKeyHandle, HKDFNumberOfOutputs, SecretBufferHandle, X25519PublicKey,
+    X25519SecretKeyHandle,
+};
+use zeroize::Zeroizing;
+
+/// Salt used to derive the key wrapping a content key for a given group member
+const KEY_WRAPPING_SALT: &[u8; 32] = b"OCKAM_SEALED_MESSAGE_KEY_WRAP_V1";
+/// Length of the random key used to encrypt the payload
+const CONTENT_KEY_LENGTH: usize = 32;
+/// Every key is used for exactly one encryption, hence the constant nonce
+const NONCE: [u8; 12] = [0u8; 12];
+
+/// Message opened by a group member
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpenedMessage {
+    /// Name of the group the message was sealed to
+    pub group: String,
+    /// Verified [`Identifier`] of the sender
+    pub sender: Identifier,
+    /// Decrypted payload
+    pub payload: Vec<u8>,
+}
+
+/// Service sealing messages to a [`SealingGroup`] so that any current member can open them
+/// and verify the identity of the sender
+pub struct SealedMessages {
+    vault: Vault,
+    identities_keys: Arc<IdentitiesKeys>,
+    identities_repository: Arc<dyn IdentitiesRepository>,
+    purpose_keys: Arc<PurposeKeys>,
+}
+
+impl SealedMessages {
+    /// Constructor
+    pub fn new(
+        vault: Vault,
+        identities_keys: Arc<IdentitiesKeys>,
+        identities_repository: Arc<dyn IdentitiesRepository>,
+        purpose_keys: Arc<PurposeKeys>,
+    ) -> Self {
+        Self {
+            vault,
+            identities_keys,
+            identities_repository,
+            purpose_keys,
+        }
+    }
+}
+
+impl SealedMessages {
+    /// Return the public key a member should share with the group administrator in order to
+    /// be added to a [`SealingGroup`]. That's the member's Secure Channel Purpose Key
+    pub async fn member_public_key(&self, member: &Identifier) -> Result<X25519PublicKey> {
+        let purpose_key = self
+            .purpose_keys
+            .purpose_keys_creation()
+            .get_or_create_secure_channel_purpose_key(member)
+            .await?;
+
+        Ok(purpose_key.public_key().clone())
+    }
+
+    /// Seal a payload to every current member of the group.
+    /// The resulting message is signed with the sender's primary key
+    pub async fn seal(
+        &self,
+        sender: &Identifier,
+        group: &SealingGroup,
+        payload: &[u8],
+    ) -> Result<SealedMessage> {
+        let sender_change_history = self.identities_repository.get_identity(sender).await?;
+        let sender_identity = Identity::import_from_change_history(
+            Some(sender),
+            sender_change_history,
+            self.vault.verifying_vault.clone(),
+        )
+        .await?;
+
+        let vault = &self.vault.secure_channel_vault;
+
+        let mut content_key = Zeroizing::new(vec![0u8; CONTENT_KEY_LENGTH]);
+        thread_rng().fill_bytes(&mut content_key);
+
+        let key = self.import_aead_key(content_key.to_vec()).await?;
+        let ciphertext = vault
+            .aead_encrypt(&key, payload, &NONCE, group.name().as_bytes())
+            .await;
+        vault.delete_aead_secret_key(key).await?;
+        let ciphertext = ciphertext?;
+
+        let ephemeral_key = vault.generate_ephemeral_x25519_secret_key().await?;
+        let recipients = self
+            .wrap_content_key(&ephemeral_key, group, &content_key)
+            .await;
+        let ephemeral_public_key = vault.get_x25519_public_key(&ephemeral_key).await;
+        vault
+            .delete_ephemeral_x25519_secret_key(ephemeral_key)
+            .await?;
+
+        let data = SealedMessageData {
+            group: group.name().to_string(),
+            sender: sender_identity.export()?,
+            ephemeral_public_key: ephemeral_public_key?,
+            recipients: recipients?,
+            ciphertext,
+        };
+        let data = minicbor::to_vec(data)?;
+
+        let versioned_data = VersionedData { version: 1, data };
+        let versioned_data = minicbor::to_vec(&versioned_data)?;
+
+        let versioned_data_hash = self.vault.verifying_vault.sha256(&versioned_data).await?;
+
+        let secret_key = self
+            .identities_keys
+            .get_secret_key(&sender_identity)
+            .await?;
+        let signature = self
+            .vault
+            .identity_vault
+            .sign(&secret_key, &versioned_data_hash.0)
+            .await?;
+
+        Ok(SealedMessage {
+            data: versioned_data,
+            signature: signature.into(),
+        })
+    }
+
+    /// Open a [`SealedMessage`] as the given group member.
+    /// Fails if the sender's signature is invalid or if `member` is not a recipient
+    pub async fn open(
+        &self,
+        member: &Identifier,
+        message: &SealedMessage,
+    ) -> Result<OpenedMessage> {
+        let versioned_data_hash = self.vault.verifying_vault.sha256(&message.data).await?;
+
+        let versioned_data = message.get_versioned_data()?;
+        if versioned_data.version != 1 {
+            return Err(IdentityError::UnknownSealedMessageVersion.into());
+        }
+
+        let data = SealedMessageData::get_data(&versioned_data)?;
+
+        let sender = Identity::import(None, &data.sender, self.vault.verifying_vault.clone())
+            .await
+            .map_err(|_| IdentityError::SealedMessageVerificationFailed)?;
+
+        let signature_is_valid = self
+            .vault
+            .verifying_vault
+            .verify_signature(
+                &sender.get_latest_public_key()?,
+                &versioned_data_hash.0,
+                &message.signature.clone().into(),
+            )
+            .await?;
+
+        if !signature_is_valid {
+            return Err(IdentityError::SealedMessageVerificationFailed.into());
+        }
+
+        let recipient = data
+            .recipients
+            .iter()
+            .find(|r| &r.identifier == member)
+            .ok_or(IdentityError::NotAGroupMember)?;
+
+        let purpose_key = self
+            .purpose_keys
+            .purpose_keys_creation()
+            .get_secure_channel_purpose_key(member)
+            .await
+            .map_err(|_| IdentityError::NotAGroupMember)?;
+
+        let vault = &self.vault.secure_channel_vault;
+
+        let wrapping_key = self
+            .derive_wrapping_key(purpose_key.key(), &data.ephemeral_public_key)
+            .await?;
+        let content_key = vault
+            .aead_decrypt(
+                &wrapping_key,
+                &recipient.wrapped_key,
+                &NONCE,
+                &Self::wrapping_aad(&data.group, member),
+            )
+            .await;
+        vault.delete_aead_secret_key(wrapping_key).await?;
+        // A wrapped key that can't be decrypted was not meant for that member's key
+        let content_key = Zeroizing::new(content_key.map_err(|_| IdentityError::NotAGroupMember)?);
+
+        let key = self.import_aead_key(content_key.to_vec()).await?;
+        let payload = vault
+            .aead_decrypt(&key, &data.ciphertext, &NONCE, data.group.as_bytes())
+            .await;
+        vault.delete_aead_secret_key(key).await?;
+
+        Ok(OpenedMessage {
+            group: data.group,
+            sender: sender.identifier().clone(),
+            payload: payload?,
+        })
+    }
+}
+
+impl SealedMessages {
+    async fn wrap_content_key(
+        &self,
+        ephemeral_key: &X25519SecretKeyHandle,
+        group: &SealingGroup,
+        content_key: &[u8],
+    ) -> Result<Vec<SealedMessageRecipient>> {
+        let vault = &self.vault.secure_channel_vault;
+
+        let mut recipients = Vec::with_capacity(group.members().len());
+        for (identifier, public_key) in group.members() {
+            let wrapping_key = self.derive_wrapping_key(ephemeral_key, public_key).await?;
+            let wrapped_key = vault
+                .aead_encrypt(
+                    &wrapping_key,
+                    content_key,
+                    &NONCE,
+                    &Self::wrapping_aad(group.name(), identifier),
+                )
+                .await;
+            vault.delete_aead_secret_key(wrapping_key).await?;
+
+            recipients.push(SealedMessageRecipient {
+                identifier: identifier.clone(),
+                wrapped_key: wrapped_key?,
+            });
+        }
+
+        Ok(recipients)
+    }
+
+    async fn derive_wrapping_key(
+        &self,
+        secret_key: &X25519SecretKeyHandle,
+        public_key: &X25519PublicKey,
+    ) -> Result<AeadSecretKeyHandle> {
+        let vault = &self.vault.secure_channel_vault;
+
+        let dh = vault.x25519_ecdh(secret_key, public_key).await?;
+        let salt = vault
+            .import_secret_buffer(KEY_WRAPPING_SALT.to_vec())
+            .await?;
+
+        let hkdf_output = vault.hkdf(&salt, Some(&dh), HKDFNumberOfOutputs::Two).await;
+        vault.delete_secret_buffer(salt).await?;
+        vault.delete_secret_buffer(dh).await?;
+
+        let [k1, k2]: [SecretBufferHandle; 2] = hkdf_output?
+            .0
+             .0
+            .try_into()
+            .map_err(|_| IdentityError::ConsistencyError)?;
+        vault.delete_secret_buffer(k2).await?;
+
+        vault.convert_secret_buffer_to_aead_key(k1).await
+    }
+
+    async fn import_aead_key(&self, key: Vec<u8>) -> Result<AeadSecretKeyHandle> {
+        let vault = &self.vault.secure_channel_vault;
+        let buffer = vault.import_secret_buffer(key).await?;
+        vault.convert_secret_buffer_to_aead_key(buffer).await
+    }
+
+    /// Bind a wrapped key to both the group and the member it was wrapped for
+    fn wrapping_aad(group: &str, member: &Identifier) -> Vec<u8> {
+        let mut aad = group.as_bytes().to_vec();
+        aad.extend_from_slice(&member.0);
+        aad
+    }
+}
@@ -0,0 +1,79 @@
+use cfg_if::cfg_if;
+use minicbor::{Decode, Encode};
+use ockam_core::Result;
+
+use crate::IdentityError;
+
+/// AEAD cipher suite encrypting the messages of a Secure Channel
+///
+/// A build of this crate uses a single suite, selected with the Noise protocol features and
+/// returned by [`CipherSuite::supported`]. Each side of a channel can restrict the suites it
+/// allows with `with_allowed_ciphers`: the initiator sends its allowed suites with the first
+/// handshake message, and the handshake fails with
+/// [`IdentityError::SecureChannelNoCommonCipher`] if no suite is allowed by both sides.
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+pub enum CipherSuite {
+    /// AES-256-GCM
+    #[n(1)] Aes256Gcm,
+    /// AES-128-GCM
+    #[n(2)] Aes128Gcm,
+    /// ChaCha20-Poly1305
+    #[n(3)] ChaCha20Poly1305,
+}
+
+cfg_if! {
+    if #[cfg(any(not(feature = "disable_default_noise_protocol"), feature = "OCKAM_XX_25519_AES256_GCM_SHA256"))] {
+        const SUPPORTED_CIPHER_SUITE: CipherSuite = CipherSuite::Aes256Gcm;
+    } else if #[cfg(feature = "OCKAM_XX_25519_AES128_GCM_SHA256")] {
+        const SUPPORTED_CIPHER_SUITE: CipherSuite = CipherSuite::Aes128Gcm;
+    } else if #[cfg(feature = "OCKAM_XX_25519_ChaChaPolyBLAKE2s")] {
+        const SUPPORTED_CIPHER_SUITE: CipherSuite = CipherSuite::ChaCha20Poly1305;
+    }
+}
+
+impl CipherSuite {
+    /// Cipher suite used by the Secure Channels of this build
+    pub const fn supported() -> Self {
+        SUPPORTED_CIPHER_SUITE
+    }
+
+    /// Select the suite of a channel among the suites allowed by both sides
+    pub(crate) fn negotiate(ours: &[CipherSuite], theirs: &[CipherSuite]) -> Result<Self> {
+        let supported = Self::supported();
+        if ours.contains(&supported) && theirs.contains(&supported) {
+            Ok(supported)
+        } else {
+            Err(IdentityError::SecureChannelNoCommonCipher.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::compat::vec::Vec;
+
+    #[test]
+    fn test_negotiate() {
+        let supported = CipherSuite::supported();
+        let other = if supported == CipherSuite::Aes256Gcm {
+            CipherSuite::ChaCha20Poly1305
+        } else {
+            CipherSuite::Aes256Gcm
+        };
+
+        assert_eq!(
+            CipherSuite::negotiate(&[other, supported], &[supported]).unwrap(),
+            supported
+        );
+        assert!(CipherSuite::negotiate(&[other], &[other]).is_err());
+        assert!(CipherSuite::negotiate(&[supported], &[other]).is_err());
+        assert!(CipherSuite::negotiate(&[], &[supported]).is_err());
+
+        let encoded = minicbor::to_vec([other, supported]).unwrap();
+        let decoded: Vec<CipherSuite> = minicbor::decode(&encoded).unwrap();
+        assert_eq!(decoded, vec![other, supported]);
+    }
+}
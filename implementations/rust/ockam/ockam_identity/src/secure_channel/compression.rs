@@ -0,0 +1,118 @@
+use minicbor::{Decode, Encode};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+use crate::IdentityError;
+
+/// Messages smaller than this number of bytes are never compressed
+pub const COMPRESSION_THRESHOLD: usize = 128;
+
+// Like the heartbeats, a compressed plaintext starts with a zero byte, so that it can't be
+// mistaken for an encoded `TransportMessage`
+const COMPRESSED_MESSAGE_PREFIX: &[u8] = &[0, 2];
+
+/// Compression level of DEFLATE, between 0 (no compression) and 10 (slowest)
+const DEFLATE_LEVEL: u8 = 6;
+
+/// Algorithm compressing the messages of a Secure Channel before they are encrypted
+///
+/// Each side of a channel can select an algorithm with `with_compression` and sends it along
+/// with its identity during the handshake. The messages are only compressed when both sides
+/// selected the same algorithm, see [`crate::SecureChannelStats::compression`].
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+#[non_exhaustive]
+pub enum CompressionAlgorithm {
+    /// DEFLATE, see RFC 1951
+    #[n(1)] Deflate,
+}
+
+impl CompressionAlgorithm {
+    /// Select the compression of a channel given the algorithms selected by both sides
+    pub(crate) fn negotiate(ours: Option<Self>, theirs: Option<Self>) -> Option<Self> {
+        if ours == theirs {
+            ours
+        } else {
+            None
+        }
+    }
+
+    /// Compress a plaintext and mark it as compressed.
+    /// Return None if the plaintext is too small or doesn't shrink, so that it's sent as is
+    pub(crate) fn compress(&self, plaintext: &[u8]) -> Option<Vec<u8>> {
+        if plaintext.len() < COMPRESSION_THRESHOLD {
+            return None;
+        }
+
+        let mut compressed = COMPRESSED_MESSAGE_PREFIX.to_vec();
+        match self {
+            CompressionAlgorithm::Deflate => compressed.extend(
+                miniz_oxide::deflate::compress_to_vec(plaintext, DEFLATE_LEVEL),
+            ),
+        }
+
+        if compressed.len() < plaintext.len() {
+            Some(compressed)
+        } else {
+            None
+        }
+    }
+
+    /// Return the original plaintext if it was compressed with [`Self::compress`].
+    /// Fail if the decompressed size would exceed `max_size`, the maximum message size of the node
+    pub(crate) fn decompress(&self, compressed: &[u8], max_size: usize) -> Result<Vec<u8>> {
+        match self {
+            CompressionAlgorithm::Deflate => {
+                miniz_oxide::inflate::decompress_to_vec_with_limit(compressed, max_size)
+                    .map_err(|_| IdentityError::InvalidCompressedMessage.into())
+            }
+        }
+    }
+
+    /// Return the compressed data if the plaintext was compressed
+    pub(crate) fn strip_prefix(plaintext: &[u8]) -> Option<&[u8]> {
+        plaintext.strip_prefix(COMPRESSED_MESSAGE_PREFIX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::compat::rand::RngCore;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_compress_decompress() {
+        let algorithm = CompressionAlgorithm::Deflate;
+
+        let plaintext = br#"{"name": "ockam", "value": 1}"#.repeat(20);
+        let compressed = algorithm.compress(&plaintext).unwrap();
+        assert!(compressed.len() < plaintext.len());
+        let data = CompressionAlgorithm::strip_prefix(&compressed).unwrap();
+        assert_eq!(algorithm.decompress(data, 1024 * 1024).unwrap(), plaintext);
+
+        // The decompressed size is limited
+        assert!(algorithm.decompress(data, plaintext.len() - 1).is_err());
+
+        // Small plaintexts are not compressed
+        assert!(algorithm.compress(&plaintext[..64]).is_none());
+
+        // Random data doesn't shrink
+        let mut random = vec![0u8; 1024];
+        thread_rng().fill_bytes(&mut random);
+        assert!(algorithm.compress(&random).is_none());
+
+        // A block with the reserved type is invalid
+        assert!(algorithm.decompress(&[0xff; 16], 1024 * 1024).is_err());
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let deflate = Some(CompressionAlgorithm::Deflate);
+        assert_eq!(CompressionAlgorithm::negotiate(deflate, deflate), deflate);
+        assert_eq!(CompressionAlgorithm::negotiate(deflate, None), None);
+        assert_eq!(CompressionAlgorithm::negotiate(None, deflate), None);
+        assert_eq!(CompressionAlgorithm::negotiate(None, None), None);
+    }
+}
@@ -1,15 +1,19 @@
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
-use ockam_core::{Any, Result, Routed, TransportMessage};
+use ockam_core::{Any, Result, RouteTrace, Routed, TransportMessage, ROUTE_TRACE_IDENTIFIER};
 use ockam_core::{Decodable, LocalMessage};
 use ockam_node::Context;
 
 use crate::models::Identifier;
 use crate::secure_channel::encryptor::{Encryptor, KEY_RENEWAL_INTERVAL};
+use crate::secure_channel::heartbeat::{HeartbeatSignal, HEARTBEAT_REQUEST, HEARTBEAT_RESPONSE};
 use crate::secure_channel::key_tracker::KeyTracker;
-use crate::secure_channel::nonce_tracker::NonceTracker;
+use crate::secure_channel::nonce_tracker::{NonceStatus, NonceTracker};
 use crate::secure_channel::Addresses;
-use crate::{DecryptionRequest, DecryptionResponse, IdentityError, IdentitySecureChannelLocalInfo};
+use crate::{
+    CompressionAlgorithm, DecryptionRequest, DecryptionResponse, IdentityError,
+    IdentitySecureChannelLocalInfo, ReplayProtection, ReplayProtectionCounters, SecureChannelStats,
+};
 
 use ockam_vault::{AeadSecretKeyHandle, VaultForSecureChannels};
 use tracing::{debug, warn};
@@ -20,6 +24,7 @@ pub(crate) struct DecryptorHandler {
     pub(crate) addresses: Addresses,
     pub(crate) their_identity_id: Identifier,
     pub(crate) decryptor: Decryptor,
+    pub(crate) compression: Option<CompressionAlgorithm>,
 }
 
 impl DecryptorHandler {
@@ -29,15 +34,33 @@ impl DecryptorHandler {
         key: AeadSecretKeyHandle,
         vault: Arc<dyn VaultForSecureChannels>,
         their_identity_id: Identifier,
+        replay_protection: ReplayProtection,
+        replay_window: u64,
+        replay_counters: ReplayProtectionCounters,
+        stats: SecureChannelStats,
     ) -> Self {
         Self {
             role,
             addresses,
             their_identity_id,
-            decryptor: Decryptor::new(key, vault),
+            decryptor: Decryptor::new(
+                key,
+                vault,
+                replay_protection,
+                replay_window,
+                replay_counters,
+                stats,
+            ),
+            compression: None,
         }
     }
 
+    /// Decompress the messages compressed by the other side, when compression was negotiated
+    pub fn with_compression(mut self, compression: Option<CompressionAlgorithm>) -> Self {
+        self.compression = compression;
+        self
+    }
+
     pub(crate) async fn handle_decrypt_api(
         &mut self,
         ctx: &mut Context,
@@ -78,14 +101,46 @@ impl DecryptorHandler {
             self.role, &self.addresses.decryptor_remote
         );
 
+        let trace = RouteTrace::find_info(msg.local_message())?;
+
         // Decode raw payload binary
         let payload = Vec::<u8>::decode(&msg.into_transport_message().payload)?;
 
         // Decrypt the binary
         let decrypted_payload = self.decryptor.decrypt(&payload).await?;
+        ctx.record_metrics(|m| m.message_decrypted(&self.addresses.encryptor, payload.len()));
 
-        // Encrypted data should be a TransportMessage
-        let mut transport_message = TransportMessage::decode(&decrypted_payload)?;
+        // Heartbeats are handled by the encryptor and never reach the application
+        let heartbeat_signal = if decrypted_payload == HEARTBEAT_REQUEST {
+            Some(HeartbeatSignal::SendResponse)
+        } else if decrypted_payload == HEARTBEAT_RESPONSE {
+            Some(HeartbeatSignal::ResponseReceived)
+        } else {
+            None
+        };
+
+        if let Some(heartbeat_signal) = heartbeat_signal {
+            return ctx
+                .send_from_address(
+                    self.addresses.encryptor_internal.clone(),
+                    heartbeat_signal,
+                    self.addresses.decryptor_api.clone(),
+                )
+                .await;
+        }
+
+        // Compressed messages are only accepted if compression was negotiated
+        let decrypted_payload = match CompressionAlgorithm::strip_prefix(&decrypted_payload) {
+            Some(compressed) => match self.compression {
+                Some(algorithm) => algorithm.decompress(compressed, ctx.max_message_size())?,
+                None => return Err(IdentityError::InvalidCompressedMessage.into()),
+            },
+            None => decrypted_payload,
+        };
+
+        // Encrypted data should be a TransportMessage, possibly carrying extensions
+        let (mut transport_message, extensions) =
+            TransportMessage::decode(&decrypted_payload)?.take_extensions()?;
 
         // Add encryptor hop in the return_route (instead of our address)
         transport_message
@@ -95,9 +150,20 @@ impl DecryptorHandler {
 
         // Mark message LocalInfo with IdentitySecureChannelLocalInfo,
         // replacing any pre-existing entries
-        let local_info =
+        let mut local_info =
             IdentitySecureChannelLocalInfo::mark(vec![], self.their_identity_id.clone())?;
 
+        // Keep tracing the message after it leaves the channel
+        if let Some(trace) = trace {
+            local_info.push(trace.to_local_info()?);
+        }
+        // The trace only travels outside the encrypted message
+        local_info.extend(
+            extensions
+                .into_iter()
+                .filter(|local_info| local_info.type_identifier() != ROUTE_TRACE_IDENTIFIER),
+        );
+
         let msg = LocalMessage::new(transport_message, local_info);
 
         match ctx
@@ -125,14 +191,25 @@ pub(crate) struct Decryptor {
     vault: Arc<dyn VaultForSecureChannels>,
     key_tracker: KeyTracker,
     nonce_tracker: NonceTracker,
+    replay_counters: ReplayProtectionCounters,
+    stats: SecureChannelStats,
 }
 
 impl Decryptor {
-    pub fn new(key: AeadSecretKeyHandle, vault: Arc<dyn VaultForSecureChannels>) -> Self {
+    pub fn new(
+        key: AeadSecretKeyHandle,
+        vault: Arc<dyn VaultForSecureChannels>,
+        replay_protection: ReplayProtection,
+        replay_window: u64,
+        replay_counters: ReplayProtectionCounters,
+        stats: SecureChannelStats,
+    ) -> Self {
         Self {
             vault,
             key_tracker: KeyTracker::new(key, KEY_RENEWAL_INTERVAL),
-            nonce_tracker: NonceTracker::new(),
+            nonce_tracker: NonceTracker::new(replay_protection, replay_window),
+            replay_counters,
+            stats,
         }
     }
 
@@ -151,14 +228,29 @@ impl Decryptor {
         }
 
         let (nonce, nonce_buffer) = Self::convert_nonce_from_small(&payload[..8])?;
-        let nonce_tracker = self.nonce_tracker.mark(nonce)?;
+        let nonce_status = self.nonce_tracker.status(nonce);
+        let nonce_tracker = match self.nonce_tracker.mark(nonce) {
+            Ok(nonce_tracker) => nonce_tracker,
+            Err(err) => {
+                match nonce_status {
+                    NonceStatus::OutOfOrder => self.replay_counters.record_out_of_order_dropped(),
+                    NonceStatus::Replayed => self.replay_counters.record_replay_dropped(),
+                    NonceStatus::OutOfWindow => self.replay_counters.record_out_of_window_dropped(),
+                    NonceStatus::InOrder => {}
+                }
+                return Err(err);
+            }
+        };
 
         // get the key corresponding to the current nonce and
         // rekey if necessary
-        let key = if let Some(key) = self.key_tracker.get_key(nonce)? {
-            key
+        let (key, rekeyed) = if let Some(key) = self.key_tracker.get_key(nonce)? {
+            (key, false)
         } else {
-            Encryptor::rekey(&self.vault, &self.key_tracker.current_key).await?
+            (
+                Encryptor::rekey(&self.vault, &self.key_tracker.current_key).await?,
+                true,
+            )
         };
 
         // to improve protection against connection disruption attacks, we want to validate the
@@ -168,7 +260,14 @@ impl Decryptor {
             .aead_decrypt(&key, &payload[8..], &nonce_buffer, &[])
             .await;
 
-        if result.is_ok() {
+        if let Ok(plaintext) = &result {
+            if nonce_status == NonceStatus::OutOfOrder {
+                self.replay_counters.record_out_of_order_accepted();
+            }
+            if rekeyed {
+                self.stats.record_rekey();
+            }
+            self.stats.record_decrypted(plaintext.len());
             self.nonce_tracker = nonce_tracker;
             if let Some(key_to_delete) = self.key_tracker.update_key(key)? {
                 self.vault.delete_aead_secret_key(key_to_delete).await?;
@@ -4,12 +4,13 @@ use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Result};
 use ockam_vault::{AeadSecretKeyHandle, VaultForSecureChannels};
 
-use crate::IdentityError;
+use crate::{IdentityError, SecureChannelStats};
 
 pub(crate) struct Encryptor {
     key: AeadSecretKeyHandle,
     nonce: u64,
     vault: Arc<dyn VaultForSecureChannels>,
+    stats: SecureChannelStats,
 }
 
 // To simplify the implementation we use the same constant for the size of the message
@@ -58,6 +59,7 @@ impl Encryptor {
             let new_key = Self::rekey(&self.vault, &self.key).await?;
             let old_key = core::mem::replace(&mut self.key, new_key);
             self.vault.delete_aead_secret_key(old_key).await?;
+            self.stats.record_rekey();
         }
 
         let (small_nonce, nonce) = Self::convert_nonce_from_u64(current_nonce);
@@ -70,6 +72,7 @@ impl Encryptor {
         let mut res = Vec::new();
         res.extend_from_slice(&small_nonce);
         res.append(&mut cipher_text);
+        self.stats.record_encrypted(payload.len());
 
         Ok(res)
     }
@@ -78,8 +81,14 @@ impl Encryptor {
         key: AeadSecretKeyHandle,
         nonce: u64,
         vault: Arc<dyn VaultForSecureChannels>,
+        stats: SecureChannelStats,
     ) -> Self {
-        Self { key, nonce, vault }
+        Self {
+            key,
+            nonce,
+            vault,
+            stats,
+        }
     }
 
     pub(crate) async fn shutdown(&self) -> Result<()> {
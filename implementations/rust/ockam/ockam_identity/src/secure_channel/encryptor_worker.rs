@@ -1,13 +1,31 @@
+use core::time::Duration;
 use ockam_core::compat::boxed::Box;
-use ockam_core::{async_trait, Decodable, Encodable, Route};
-use ockam_core::{Any, Result, Routed, TransportMessage, Worker};
-use ockam_node::Context;
-use tracing::debug;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, route, Decodable, Encodable, LocalMessage, Route};
+use ockam_core::{Any, Result, Routed, TransportMessage, Worker, ROUTE_TRACE_IDENTIFIER};
+use ockam_node::{Context, DelayedEvent};
+use tracing::{debug, info, warn};
 
+use crate::models::Identifier;
 use crate::secure_channel::addresses::Addresses;
 use crate::secure_channel::api::{EncryptionRequest, EncryptionResponse};
 use crate::secure_channel::encryptor::Encryptor;
-use crate::IdentityError;
+use crate::secure_channel::heartbeat::{
+    HeartbeatSignal, HeartbeatState, HEARTBEAT_REQUEST, HEARTBEAT_RESPONSE,
+};
+use crate::secure_channel::CLUSTER_NAME;
+use crate::{
+    CompressionAlgorithm, IdentityError, SecureChannelCloseReason, SecureChannelHeartbeat,
+    SecureChannelRegistry, SecureChannelTrustInfo, TrustPolicy,
+};
+
+/// Periodic check of the trust policy of an established channel
+struct TrustReevaluation {
+    policy: Arc<dyn TrustPolicy>,
+    trust_info: SecureChannelTrustInfo,
+    interval: Duration,
+    event: DelayedEvent<HeartbeatSignal>,
+}
 
 pub(crate) struct EncryptorWorker {
     //for debug purposes only
@@ -15,6 +33,11 @@ pub(crate) struct EncryptorWorker {
     addresses: Addresses,
     remote_route: Route,
     encryptor: Encryptor,
+    registry: SecureChannelRegistry,
+    heartbeat: Option<(HeartbeatState, DelayedEvent<HeartbeatSignal>)>,
+    trust_reevaluation: Option<TrustReevaluation>,
+    compression: Option<CompressionAlgorithm>,
+    message_extensions: bool,
 }
 
 impl EncryptorWorker {
@@ -23,12 +46,158 @@ impl EncryptorWorker {
         addresses: Addresses,
         remote_route: Route,
         encryptor: Encryptor,
+        registry: SecureChannelRegistry,
     ) -> Self {
         Self {
             role,
             addresses,
             remote_route,
             encryptor,
+            registry,
+            heartbeat: None,
+            trust_reevaluation: None,
+            compression: None,
+            message_extensions: false,
+        }
+    }
+
+    /// Compress the messages before encrypting them, when compression was negotiated
+    pub fn with_compression(mut self, compression: Option<CompressionAlgorithm>) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Encrypt the extensions of the messages along with them, when the other side
+    /// supports message extensions
+    pub fn with_message_extensions(mut self, message_extensions: bool) -> Self {
+        self.message_extensions = message_extensions;
+        self
+    }
+
+    /// Periodically check that the other side of the channel is still alive
+    pub fn with_heartbeat(
+        mut self,
+        heartbeat: SecureChannelHeartbeat,
+        event: DelayedEvent<HeartbeatSignal>,
+    ) -> Self {
+        self.heartbeat = Some((HeartbeatState::new(heartbeat), event));
+        self
+    }
+
+    /// Periodically check that the other side of the channel still passes the trust policy
+    pub fn with_trust_reevaluation(
+        mut self,
+        policy: Arc<dyn TrustPolicy>,
+        their_identifier: Identifier,
+        interval: Duration,
+        event: DelayedEvent<HeartbeatSignal>,
+    ) -> Self {
+        self.trust_reevaluation = Some(TrustReevaluation {
+            policy,
+            trust_info: SecureChannelTrustInfo::new(their_identifier),
+            interval,
+            event,
+        });
+        self
+    }
+
+    /// Shut the channel down if the other side doesn't pass the trust policy anymore
+    async fn reevaluate_trust(&mut self, ctx: &mut <Self as Worker>::Context) -> Result<()> {
+        let reevaluation = match self.trust_reevaluation.as_mut() {
+            Some(reevaluation) => reevaluation,
+            None => return Ok(()),
+        };
+
+        // As during the handshake, a failing check is not trusted
+        let trusted = match reevaluation.policy.check(&reevaluation.trust_info).await {
+            Ok(trusted) => trusted,
+            Err(e) => {
+                warn!(
+                    "SecureChannel {} at {} failed to check its trust policy: {}",
+                    self.role, &self.addresses.encryptor, e
+                );
+                false
+            }
+        };
+
+        if trusted {
+            return reevaluation.event.schedule(reevaluation.interval).await;
+        }
+
+        info!(
+            "SecureChannel {} at {}: {} is not trusted anymore, shutting down",
+            self.role,
+            &self.addresses.encryptor,
+            reevaluation.trust_info.their_identity_id()
+        );
+        self.registry.mark_channel_closed(
+            &self.addresses.encryptor,
+            SecureChannelCloseReason::AuthorizationRevoked,
+        );
+        ctx.stop_worker(self.addresses.encryptor.clone()).await
+    }
+
+    /// Encrypt a heartbeat payload and send it to the decryptor on the other side
+    async fn send_heartbeat_payload(
+        &mut self,
+        ctx: &mut <Self as Worker>::Context,
+        payload: &[u8],
+    ) -> Result<()> {
+        let encrypted_payload = self.encryptor.encrypt(payload).await?;
+
+        ctx.send_from_address(
+            self.remote_route.clone(),
+            encrypted_payload,
+            self.addresses.encryptor.clone(),
+        )
+        .await
+    }
+
+    async fn handle_heartbeat(
+        &mut self,
+        ctx: &mut <Self as Worker>::Context,
+        msg: Routed<<Self as Worker>::Message>,
+    ) -> Result<()> {
+        let signal = HeartbeatSignal::decode(&msg.into_transport_message().payload)?;
+
+        match signal {
+            HeartbeatSignal::Tick => {
+                let (state, event) = match self.heartbeat.as_mut() {
+                    Some(heartbeat) => heartbeat,
+                    None => return Ok(()),
+                };
+
+                if state.is_dead() {
+                    info!(
+                        "SecureChannel {} at {} missed {} heartbeats, shutting down",
+                        self.role, &self.addresses.encryptor, state.missed
+                    );
+                    self.registry.mark_channel_closed(
+                        &self.addresses.encryptor,
+                        SecureChannelCloseReason::HeartbeatTimeout,
+                    );
+                    return ctx.stop_worker(self.addresses.encryptor.clone()).await;
+                }
+
+                state.missed += 1;
+                event.schedule(state.options.interval).await?;
+
+                debug!(
+                    "SecureChannel {} sending heartbeat {}",
+                    self.role, &self.addresses.encryptor
+                );
+                self.send_heartbeat_payload(ctx, HEARTBEAT_REQUEST).await
+            }
+            HeartbeatSignal::SendResponse => {
+                self.send_heartbeat_payload(ctx, HEARTBEAT_RESPONSE).await
+            }
+            HeartbeatSignal::ResponseReceived => {
+                if let Some((state, _)) = self.heartbeat.as_mut() {
+                    state.missed = 0;
+                }
+                Ok(())
+            }
+            HeartbeatSignal::ReevaluateTrust => self.reevaluate_trust(ctx).await,
         }
     }
 
@@ -78,19 +247,49 @@ impl EncryptorWorker {
         // Remove our address
         let _ = onward_route.step();
 
+        // The trace is not encrypted, it travels next to the encrypted message so that
+        // transports and the decryptor on the other side can keep recording hops
+        let (trace, extensions): (Vec<_>, Vec<_>) = msg
+            .local_message()
+            .extensions()
+            .into_iter()
+            .partition(|local_info| local_info.type_identifier() == ROUTE_TRACE_IDENTIFIER);
+
+        // The other extensions are encrypted along with the message
+        let extensions = if self.message_extensions {
+            extensions
+        } else {
+            vec![]
+        };
         let msg = TransportMessage::v1(
             onward_route,
             return_route,
             msg.into_transport_message().payload,
-        );
+        )
+        .with_extensions(extensions)?;
+
+        // Compress the message before encrypting it, never after, since a ciphertext
+        // doesn't compress
+        let plaintext = msg.encode()?;
+        let plaintext = self
+            .compression
+            .and_then(|algorithm| algorithm.compress(&plaintext))
+            .unwrap_or(plaintext);
 
         // Encrypt the message
-        let encrypted_payload = self.encryptor.encrypt(&msg.encode()?).await?;
+        let encrypted_payload = self.encryptor.encrypt(&plaintext).await?;
+        ctx.record_metrics(|m| {
+            m.message_encrypted(&self.addresses.encryptor, encrypted_payload.len())
+        });
 
         // Send the message to the decryptor on the other side
-        ctx.send_from_address(
+        let msg = TransportMessage::v1(
             self.remote_route.clone(),
-            encrypted_payload,
+            route![self.addresses.encryptor.clone()],
+            encrypted_payload.encode()?,
+        );
+        ctx.forward_from_address(
+            LocalMessage::new(msg, trace),
             self.addresses.encryptor.clone(),
         )
         .await?;
@@ -104,6 +303,19 @@ impl Worker for EncryptorWorker {
     type Message = Any;
     type Context = Context;
 
+    async fn initialize(&mut self, context: &mut Self::Context) -> Result<()> {
+        context.set_cluster(CLUSTER_NAME).await?;
+
+        if let Some((state, event)) = self.heartbeat.as_mut() {
+            event.schedule(state.options.interval).await?;
+        }
+        if let Some(reevaluation) = self.trust_reevaluation.as_mut() {
+            reevaluation.event.schedule(reevaluation.interval).await?;
+        }
+
+        Ok(())
+    }
+
     async fn handle_message(
         &mut self,
         ctx: &mut Self::Context,
@@ -115,6 +327,8 @@ impl Worker for EncryptorWorker {
             self.handle_encrypt(ctx, msg).await?;
         } else if msg_addr == self.addresses.encryptor_api {
             self.handle_encrypt_api(ctx, msg).await?;
+        } else if msg_addr == self.addresses.encryptor_internal {
+            self.handle_heartbeat(ctx, msg).await?;
         } else {
             return Err(IdentityError::UnknownChannelMsgDestination.into());
         }
@@ -123,6 +337,18 @@ impl Worker for EncryptorWorker {
     }
 
     async fn shutdown(&mut self, context: &mut Self::Context) -> Result<()> {
+        if let Some((_, event)) = self.heartbeat.as_mut() {
+            event.cancel();
+        }
+        if let Some(reevaluation) = self.trust_reevaluation.as_mut() {
+            reevaluation.event.cancel();
+        }
+        // The channels carried over this one can't reach the other side anymore
+        for inner in self.registry.get_inner_channels(&self.addresses.encryptor) {
+            let _ = context
+                .stop_worker(inner.encryptor_messaging_address().clone())
+                .await;
+        }
         let _ = context
             .stop_worker(self.addresses.decryptor_internal.clone())
             .await;
@@ -12,6 +12,11 @@ use Status::*;
 
 use crate::secure_channel::handshake::error::XXError;
 use crate::secure_channel::handshake::handshake_state_machine::{HandshakeKeys, Status};
+use crate::secure_channel::handshake::hybrid_kem::HybridKem;
+#[cfg(feature = "pq_hybrid")]
+use crate::secure_channel::handshake::hybrid_kem::{
+    encapsulate, KemKeyPair, KEM_CIPHERTEXT_LENGTH,
+};
 use crate::secure_channel::Role;
 
 /// The number of bytes in a SHA256 digest
@@ -27,6 +32,8 @@ pub(super) struct Handshake {
     vault: Arc<dyn VaultForSecureChannels>,
     protocol_name: [u8; 32],
     pub(super) state: HandshakeState,
+    #[cfg_attr(not(feature = "pq_hybrid"), allow(dead_code))]
+    kem: HybridKem,
 }
 
 /// Top-level functions used in the initiator and responder state machines
@@ -45,6 +52,35 @@ impl Handshake {
         Ok(())
     }
 
+    /// Start a hybrid handshake on the initiator side and return the KEM public key to send
+    /// with message 1
+    pub(super) fn start_hybrid_kem(&mut self) -> Result<Vec<u8>> {
+        cfg_if! {
+            if #[cfg(feature = "pq_hybrid")] {
+                let key_pair = KemKeyPair::generate()?;
+                let public_key = key_pair.public_key().to_vec();
+                self.kem = HybridKem::Initiator(key_pair);
+                Ok(public_key)
+            } else {
+                Err(XXError::InvalidInternalState.into())
+            }
+        }
+    }
+
+    /// Accept a hybrid handshake on the responder side, with the KEM public key received
+    /// in message 1
+    pub(super) fn accept_hybrid_kem(&mut self, their_kem_public_key: Vec<u8>) -> Result<()> {
+        cfg_if! {
+            if #[cfg(feature = "pq_hybrid")] {
+                self.kem = HybridKem::Responder(their_kem_public_key);
+                Ok(())
+            } else {
+                _ = their_kem_public_key;
+                Err(XXError::InvalidInternalState.into())
+            }
+        }
+    }
+
     /// Encode the first message, sent from the initiator to the responder
     pub(super) async fn encode_message1(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
         let mut state = self.state.clone();
@@ -92,6 +128,16 @@ impl Handshake {
         let dh = self.dh(state.e()?, state.re()?).await?;
         self.hkdf(&mut state, dh).await?;
 
+        // output the KEM ciphertext and ck, k = HKDF(ck, KEM shared secret, 2)
+        #[cfg(feature = "pq_hybrid")]
+        if let HybridKem::Responder(their_kem_public_key) = &self.kem {
+            let (ciphertext, shared_secret) = encapsulate(their_kem_public_key)?;
+            state.mix_hash(&ciphertext);
+            message2.extend_from_slice(&ciphertext);
+            let shared_secret = self.vault.import_secret_buffer(shared_secret).await?;
+            self.hkdf(&mut state, shared_secret).await?;
+        }
+
         // encrypt and output s.pubKey
         let s_pub_key = self.get_public_key(state.s()?).await?;
         let c = self.encrypt_and_hash(&mut state, &s_pub_key.0).await?;
@@ -119,6 +165,23 @@ impl Handshake {
         // ck, k = HKDF(ck, DH(e, re), 2)
         let dh = self.dh(state.e()?, state.re()?).await?;
         self.hkdf(&mut state, dh).await?;
+        let message = Self::read_end::<X25519_PUBLIC_KEY_LENGTH>(message)?;
+
+        // decode the KEM ciphertext and ck, k = HKDF(ck, KEM shared secret, 2)
+        #[cfg(feature = "pq_hybrid")]
+        let message = if let HybridKem::Initiator(kem_key_pair) = &self.kem {
+            if message.len() < KEM_CIPHERTEXT_LENGTH {
+                return Err(XXError::MessageLenMismatch.into());
+            }
+            let (ciphertext, message) = message.split_at(KEM_CIPHERTEXT_LENGTH);
+            state.mix_hash(ciphertext);
+            let shared_secret = kem_key_pair.decapsulate(ciphertext)?;
+            let shared_secret = self.vault.import_secret_buffer(shared_secret).await?;
+            self.hkdf(&mut state, shared_secret).await?;
+            message
+        } else {
+            message
+        };
 
         // decrypt rs.pubKey
         let rs_pub_key = Self::read_message2_encrypted_key(message)?;
@@ -233,6 +296,7 @@ impl Handshake {
             vault,
             protocol_name: *PROTOCOL_NAME,
             state: HandshakeState::new(static_key, ephemeral_key),
+            kem: HybridKem::Disabled,
         })
     }
 
@@ -381,15 +445,16 @@ impl Handshake {
         Self::read_end::<X25519_PUBLIC_KEY_LENGTH>(message)
     }
 
-    /// Read the message 2 encrypted key, which is present after the public key
+    /// Read the message 2 encrypted key, at the beginning of the message once the public key
+    /// and the KEM ciphertext have been read
     fn read_message2_encrypted_key(message: &[u8]) -> Result<&[u8]> {
         const L: usize = X25519_PUBLIC_KEY_LENGTH + AES_GCM_TAGSIZE;
-        Self::read_middle::<X25519_PUBLIC_KEY_LENGTH, L>(message)
+        Ok(Self::read_start::<L>(message)?)
     }
 
     /// Read the message 2 encrypted payload, which is present after the encrypted key
     fn read_message2_payload(message: &[u8]) -> Result<&[u8]> {
-        const L: usize = 2 * X25519_PUBLIC_KEY_LENGTH + AES_GCM_TAGSIZE;
+        const L: usize = X25519_PUBLIC_KEY_LENGTH + AES_GCM_TAGSIZE;
         Self::read_end::<L>(message)
     }
 
@@ -419,13 +484,6 @@ impl Handshake {
             .map_err(|_| XXError::MessageLenMismatch.into())
     }
 
-    /// Read 'length' bytes of the message after the first 'drop_length' bytes
-    fn read_middle<const N: usize, const L: usize>(message: &[u8]) -> Result<&[u8]> {
-        message[N..(N + L)]
-            .try_into()
-            .map_err(|_| XXError::MessageLenMismatch.into())
-    }
-
     /// Read the bytes of a key at the beginning of a message
     fn read_key(message: &[u8]) -> Result<&[u8; X25519_PUBLIC_KEY_LENGTH]> {
         Self::read_start::<X25519_PUBLIC_KEY_LENGTH>(message)
@@ -737,6 +795,7 @@ mod tests {
                 vault,
                 protocol_name,
                 state: HandshakeState::new(static_key, ephemeral_key),
+                kem: HybridKem::Disabled,
             })
         }
 
@@ -755,6 +814,7 @@ mod tests {
                 vault,
                 protocol_name,
                 state: HandshakeState::new(static_key, ephemeral_key),
+                kem: HybridKem::Disabled,
             })
         }
     }
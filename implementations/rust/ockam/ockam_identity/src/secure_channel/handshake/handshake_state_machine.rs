@@ -10,7 +10,8 @@ use crate::models::{
     ChangeHistory, CredentialAndPurposeKey, Identifier, PurposeKeyAttestation, PurposePublicKey,
 };
 use crate::{
-    Identities, Identity, IdentityError, SecureChannelTrustInfo, TrustContext, TrustPolicy,
+    CipherSuite, CompressionAlgorithm, CredentialAndPurposeKeyData, Identities, Identity,
+    IdentityError, SecureChannelTrustInfo, StorageFailurePolicy, TrustContext, TrustPolicy,
 };
 
 /// Interface for a state machine in a key exchange protocol
@@ -58,6 +59,9 @@ pub(super) struct HandshakeKeys {
 pub(super) struct HandshakeResults {
     pub(super) handshake_keys: HandshakeKeys,
     pub(super) their_identifier: Identifier,
+    pub(super) attributes_persisted: bool,
+    pub(super) compression: Option<CompressionAlgorithm>,
+    pub(super) message_extensions: bool,
 }
 
 /// This struct implements functions common to both initiator and the responder state machines
@@ -68,7 +72,12 @@ pub(super) struct CommonStateMachine {
     pub(super) credentials: Vec<CredentialAndPurposeKey>,
     pub(super) trust_policy: Arc<dyn TrustPolicy>,
     pub(super) trust_context: Option<TrustContext>,
+    pub(super) storage_failure_policy: StorageFailurePolicy,
+    pub(super) compression: Option<CompressionAlgorithm>,
     their_identifier: Option<Identifier>,
+    attributes_persisted: bool,
+    negotiated_compression: Option<CompressionAlgorithm>,
+    negotiated_message_extensions: bool,
 }
 
 impl CommonStateMachine {
@@ -79,6 +88,8 @@ impl CommonStateMachine {
         credentials: Vec<CredentialAndPurposeKey>,
         trust_policy: Arc<dyn TrustPolicy>,
         trust_context: Option<TrustContext>,
+        storage_failure_policy: StorageFailurePolicy,
+        compression: Option<CompressionAlgorithm>,
     ) -> Self {
         Self {
             identities,
@@ -87,7 +98,12 @@ impl CommonStateMachine {
             credentials,
             trust_policy,
             trust_context,
+            storage_failure_policy,
+            compression,
             their_identifier: None,
+            attributes_persisted: true,
+            negotiated_compression: None,
+            negotiated_message_extensions: false,
         }
     }
 
@@ -97,6 +113,8 @@ impl CommonStateMachine {
     ///  - the current Identity Change History
     ///  - the current Secure Channel Purpose Key Attestation
     ///  - the Identity Credentials and corresponding Credentials Purpose Key Attestations
+    ///  - the compression algorithm selected by the current party, if any
+    ///  - the support of message extensions by the current party
     ///
     pub(super) async fn make_identity_payload(&self) -> Result<Vec<u8>> {
         // prepare the payload that will be sent either in message 2 or message 3
@@ -109,6 +127,8 @@ impl CommonStateMachine {
             change_history,
             purpose_key_attestation: self.purpose_key_attestation.clone(),
             credentials: self.credentials.clone(),
+            compression: self.compression,
+            message_extensions: Some(true),
         };
         Ok(minicbor::to_vec(payload)?)
     }
@@ -156,6 +176,10 @@ impl CommonStateMachine {
 
         self.verify_credentials(identity.identifier(), peer.credentials)
             .await?;
+        self.negotiated_compression =
+            CompressionAlgorithm::negotiate(self.compression, peer.compression);
+        // Older peers don't send that field and can't decode messages carrying extensions
+        self.negotiated_message_extensions = peer.message_extensions == Some(true);
         self.their_identifier = Some(identity.identifier().clone());
         Ok(())
     }
@@ -163,7 +187,7 @@ impl CommonStateMachine {
     /// Verify that the credentials sent by the other party are valid using a trust context
     /// and store them
     async fn verify_credentials(
-        &self,
+        &mut self,
         their_identifier: &Identifier,
         credentials: Vec<CredentialAndPurposeKey>,
     ) -> Result<()> {
@@ -184,25 +208,29 @@ impl CommonStateMachine {
                 "got a trust context to check the credentials. There are {} credentials to check",
                 credentials.len()
             );
+            let authority = trust_context.authority()?.identifier().clone();
             for credential in &credentials {
                 let result = self
                     .identities
                     .credentials()
                     .credentials_verification()
-                    .receive_presented_credential(
-                        their_identifier,
-                        &[trust_context.authority()?.identifier().clone()],
-                        credential,
-                    )
+                    .verify_credential(Some(their_identifier), &[authority.clone()], credential)
                     .await;
 
-                if let Some(err) = result.err() {
-                    warn!("a credential could not be validated {}", err.to_string());
-                    // TODO: consider the possibility of keep going when a credential validation fails
-                    return Err(
-                        IdentityError::SecureChannelVerificationFailedIncorrectCredential.into(),
-                    );
-                }
+                let credential_data = match result {
+                    Ok(credential_data) => credential_data,
+                    Err(err) => {
+                        warn!("a credential could not be validated {}", err.to_string());
+                        // TODO: consider the possibility of keep going when a credential validation fails
+                        return Err(
+                            IdentityError::SecureChannelVerificationFailedIncorrectCredential
+                                .into(),
+                        );
+                    }
+                };
+
+                self.persist_credential_attributes(their_identifier, &credential_data)
+                    .await?;
             }
         } else if !credentials.is_empty() {
             warn!("no credentials have been received");
@@ -213,9 +241,55 @@ impl CommonStateMachine {
         Ok(())
     }
 
+    /// Store the attributes of a verified credential according to the [`StorageFailurePolicy`]
+    async fn persist_credential_attributes(
+        &mut self,
+        their_identifier: &Identifier,
+        credential_data: &CredentialAndPurposeKeyData,
+    ) -> Result<()> {
+        let credentials_verification = self.identities.credentials().credentials_verification();
+        let mut retries = 0;
+        loop {
+            let err = match credentials_verification
+                .persist_credential_attributes(their_identifier, credential_data)
+                .await
+            {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+
+            match self.storage_failure_policy {
+                StorageFailurePolicy::Retry(max_retries) if retries < max_retries => {
+                    retries += 1;
+                    debug!(
+                        "retrying to persist the attributes of {} ({}/{}): {}",
+                        their_identifier, retries, max_retries, err
+                    );
+                }
+                StorageFailurePolicy::BestEffort => {
+                    warn!(
+                        "the attributes of {} could not be persisted, proceeding without them: {}",
+                        their_identifier, err
+                    );
+                    self.attributes_persisted = false;
+                    return Ok(());
+                }
+                StorageFailurePolicy::Fail | StorageFailurePolicy::Retry(_) => {
+                    warn!(
+                        "the attributes of {} could not be persisted: {}",
+                        their_identifier, err
+                    );
+                    return Err(IdentityError::AttributesPersistenceFailed.into());
+                }
+            }
+        }
+    }
+
     /// Return the results of the full handshake
     ///  - the other party identity
     ///  - the encryption and decryption keys to use on the next messages to exchange
+    ///  - the compression of the next messages, if both parties selected the same algorithm
+    ///  - whether the next messages can carry extensions, if both parties support them
     pub(super) fn make_handshake_results(
         &self,
         handshake_keys: Option<HandshakeKeys>,
@@ -224,6 +298,9 @@ impl CommonStateMachine {
             (Some(their_identifier), Some(handshake_keys)) => Some(HandshakeResults {
                 their_identifier,
                 handshake_keys,
+                attributes_persisted: self.attributes_persisted,
+                compression: self.negotiated_compression,
+                message_extensions: self.negotiated_message_extensions,
             }),
             _ => None,
         }
@@ -244,4 +321,24 @@ pub(super) struct IdentityAndCredentials {
     /// Credentials associated to the identity along with corresponding Credentials Purpose Keys
     /// to verify those Credentials
     #[n(3)] pub(super) credentials: Vec<CredentialAndPurposeKey>,
+    /// Compression algorithm selected by the sender. Messages are only compressed when both
+    /// parties selected the same one
+    #[n(4)] pub(super) compression: Option<CompressionAlgorithm>,
+    /// Set by the parties which can decode messages carrying extensions, see
+    /// [`ockam_core::EXTENDED_TRANSPORT_MESSAGE_VERSION`]
+    #[n(5)] pub(super) message_extensions: Option<bool>,
+}
+
+/// This internal structure is used as the payload of message 1 in the XX protocol
+///
+/// Initiators which send an empty payload instead are treated as allowing only
+/// [`CipherSuite::supported`], with the classical key exchange.
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub(super) struct KeyExchangeParameters {
+    /// Cipher suites allowed by the initiator
+    #[n(1)] pub(super) allowed_ciphers: Vec<CipherSuite>,
+    /// ML-KEM public key of the initiator when it requests a hybrid post-quantum handshake
+    #[b(2)] pub(super) kem_public_key: Option<Vec<u8>>,
 }
@@ -2,14 +2,13 @@ use alloc::sync::Arc;
 use core::time::Duration;
 use ockam_core::compat::{boxed::Box, vec::Vec};
 use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Address, AllowOnwardAddress, Result, Worker};
 use ockam_core::{
-    AllowAll, Any, Decodable, DenyAll, Error, Mailbox, Mailboxes, OutgoingAccessControl, Route,
-    Routed,
+    AllowAll, AllowSourceAddresses, Any, Decodable, DenyAll, Error, IncomingAccessControl, Mailbox,
+    Mailboxes, OutgoingAccessControl, Route, Routed,
 };
-use ockam_core::{AllowOnwardAddress, Result, Worker};
-use ockam_node::callback::CallbackSender;
-use ockam_node::{Context, WorkerBuilder};
-use tracing::{debug, info};
+use ockam_node::{Context, DelayedEvent, WorkerBuilder};
+use tracing::{debug, info, warn};
 
 use crate::models::{CredentialAndPurposeKey, Identifier};
 use crate::secure_channel::decryptor::DecryptorHandler;
@@ -23,24 +22,52 @@ use crate::secure_channel::handshake::handshake_state_machine::{
     Action, HandshakeResults, StateMachine,
 };
 use crate::secure_channel::handshake::initiator_state_machine::InitiatorStateMachine;
+use crate::secure_channel::handshake::pre_shared_key_state_machine::PreSharedKeyStateMachine;
+use crate::secure_channel::handshake::rejection::HandshakeRejection;
 use crate::secure_channel::handshake::responder_state_machine::ResponderStateMachine;
-use crate::secure_channel::{Addresses, Role};
+use crate::secure_channel::heartbeat::HeartbeatSignal;
+use crate::secure_channel::{
+    Addresses, Role, CLUSTER_NAME, SECURE_CHANNEL_DECRYPTOR_KIND, SECURE_CHANNEL_ENCRYPTOR_KIND,
+};
+use crate::secure_channels::HandshakeCompletion;
 use crate::{
-    IdentityError, SecureChannelPurposeKey, SecureChannelRegistryEntry, SecureChannels,
+    CipherSuite, CompressionAlgorithm, IdentityError, PreSharedKey, ReplayProtection,
+    ReplayProtectionCounters, SecureChannelHeartbeat, SecureChannelPurposeKey,
+    SecureChannelRegistryEntry, SecureChannelStats, SecureChannels, StorageFailurePolicy,
     TrustContext, TrustPolicy,
 };
 
+/// Key exchange used to establish the channel
+pub(crate) enum HandshakeMode {
+    /// Noise XX key exchange, authenticated with our identity's purpose key
+    PurposeKey(SecureChannelPurposeKey),
+    /// Key derivation from a key shared out of band by both sides
+    PreSharedKey(PreSharedKey),
+}
+
 /// This struct implements a Worker receiving and sending messages
 /// on one side of the secure channel creation as specified with its role: INITIATOR or REPSONDER
 pub(crate) struct HandshakeWorker {
     secure_channels: Arc<SecureChannels>,
-    callback_sender: Option<CallbackSender<()>>,
+    completion: Option<HandshakeCompletion>,
+    // Address of the listener which accepted the channel, on the responder side
+    listener: Option<Address>,
     state_machine: Box<dyn StateMachine>,
     identifier: Identifier,
     addresses: Addresses,
     role: Role,
     remote_route: Option<Route>,
+    heartbeat: Option<SecureChannelHeartbeat>,
+    // Checked again periodically once the channel is established, if it asks for it
+    trust_policy: Arc<dyn TrustPolicy>,
+    replay_protection: ReplayProtection,
+    replay_window: u64,
+    pq_hybrid: bool,
     decryptor_handler: Option<DecryptorHandler>,
+    // Timer aborting the handshake if it isn't completed in time, on the responder side
+    handshake_timeout: Option<(Duration, DelayedEvent<()>)>,
+    #[cfg(feature = "std")]
+    started_at: std::time::Instant,
 }
 
 #[ockam_core::worker]
@@ -51,23 +78,15 @@ impl Worker for HandshakeWorker {
     /// Initialize the state machine with an `Initialize` event
     /// Depending on the state machine role there might be a message to send to the other party
     async fn initialize(&mut self, context: &mut Self::Context) -> Result<()> {
-        match self.state_machine.on_event(Initialize).await? {
-            SendMessage(message) => {
-                debug!(
-                    "remote route {:?}, decryptor remote {:?}",
-                    self.remote_route.clone(),
-                    self.addresses.decryptor_remote.clone()
-                );
-                context
-                    .send_from_address(
-                        self.remote_route()?,
-                        message,
-                        self.addresses.decryptor_remote.clone(),
-                    )
-                    .await
-            }
-            Action::NoAction => Ok(()),
-        }
+        context.set_cluster(CLUSTER_NAME).await?;
+
+        #[cfg(feature = "tracing_spans")]
+        let span = self.handshake_span();
+
+        let started = self.start_handshake(context);
+        #[cfg(feature = "tracing_spans")]
+        let started = tracing::Instrument::instrument(started, span);
+        started.await
     }
 
     /// Handle a message coming from the other party
@@ -80,6 +99,20 @@ impl Worker for HandshakeWorker {
         context: &mut Self::Context,
         message: Routed<Self::Message>,
     ) -> Result<()> {
+        // The handshake timer fired
+        if message.msg_addr() == self.addresses.decryptor_internal {
+            if self.decryptor_handler.is_none() {
+                warn!(
+                    "SecureChannel {} handshake at {} timed out",
+                    self.role, self.addresses.decryptor_remote
+                );
+                context
+                    .stop_worker(self.addresses.decryptor_remote.clone())
+                    .await?;
+            }
+            return Ok(());
+        }
+
         // Once the decryptor has been initialized, let it handle messages
         // Some messages can come from other systems using the remote address
         // and some messages can come from the current node when the decryptor
@@ -97,39 +130,13 @@ impl Worker for HandshakeWorker {
             return result;
         };
 
-        let transport_message = message.into_transport_message();
-        if let SendMessage(message) = self
-            .state_machine
-            .on_event(ReceivedMessage(Vec::<u8>::decode(
-                &transport_message.payload,
-            )?))
-            .await?
-        {
-            // set the remote route by taking the most up to date message return route
-            // In the case of the initiator the first return route mentions the secure channel listener
-            // address so we need to wait for the return route corresponding to the remote handshake worker
-            // when it has been spawned
-            self.remote_route = Some(transport_message.return_route);
+        #[cfg(feature = "tracing_spans")]
+        let span = self.handshake_span();
 
-            context
-                .send_from_address(
-                    self.remote_route()?,
-                    message,
-                    self.addresses.decryptor_remote.clone(),
-                )
-                .await?
-        };
-
-        // if we reached the final state we can make a pair of encryptor/decryptor
-        if let Some(final_state) = self.state_machine.get_handshake_results() {
-            // start the encryptor worker and return the decryptor
-            self.decryptor_handler = Some(self.finalize(context, final_state).await?);
-            if let Some(callback_sender) = self.callback_sender.take() {
-                callback_sender.send(())?;
-            }
-        };
-
-        Ok(())
+        let handled = self.handle_handshake_message(context, message);
+        #[cfg(feature = "tracing_spans")]
+        let handled = tracing::Instrument::instrument(handled, span);
+        handled.await
     }
 
     async fn shutdown(&mut self, context: &mut Self::Context) -> Result<()> {
@@ -154,68 +161,104 @@ impl HandshakeWorker {
         secure_channels: Arc<SecureChannels>,
         addresses: Addresses,
         identifier: Identifier,
-        purpose_key: SecureChannelPurposeKey,
+        mode: HandshakeMode,
         trust_policy: Arc<dyn TrustPolicy>,
         decryptor_outgoing_access_control: Arc<dyn OutgoingAccessControl>,
         credentials: Vec<CredentialAndPurposeKey>,
+        allowed_ciphers: Vec<CipherSuite>,
+        pq_hybrid: bool,
+        compression: Option<CompressionAlgorithm>,
         trust_context: Option<TrustContext>,
         remote_route: Option<Route>,
-        timeout: Option<Duration>,
+        completion: Option<HandshakeCompletion>,
+        listener: Option<Address>,
+        heartbeat: Option<SecureChannelHeartbeat>,
+        storage_failure_policy: StorageFailurePolicy,
+        replay_protection: ReplayProtection,
+        replay_window: u64,
+        handshake_timeout: Option<Duration>,
         role: Role,
     ) -> Result<()> {
         let vault = secure_channels.identities.vault().secure_channel_vault;
         let identities = secure_channels.identities();
-        let state_machine: Box<dyn StateMachine> = if role.is_initiator() {
-            Box::new(
+        let state_machine: Box<dyn StateMachine> = match mode {
+            HandshakeMode::PreSharedKey(pre_shared_key) => Box::new(PreSharedKeyStateMachine::new(
+                vault,
+                role,
+                identifier.clone(),
+                pre_shared_key,
+                trust_policy.clone(),
+            )),
+            HandshakeMode::PurposeKey(purpose_key) if role.is_initiator() => Box::new(
                 InitiatorStateMachine::new(
                     vault,
                     identities,
                     identifier.clone(),
                     purpose_key,
                     credentials,
-                    trust_policy,
+                    allowed_ciphers,
+                    pq_hybrid,
+                    compression,
+                    trust_policy.clone(),
                     trust_context,
+                    storage_failure_policy,
                 )
                 .await?,
-            )
-        } else {
-            Box::new(
+            ),
+            HandshakeMode::PurposeKey(purpose_key) => Box::new(
                 ResponderStateMachine::new(
                     vault,
                     identities,
                     identifier.clone(),
                     purpose_key,
                     credentials,
-                    trust_policy,
+                    allowed_ciphers,
+                    pq_hybrid,
+                    compression,
+                    trust_policy.clone(),
                     trust_context,
+                    storage_failure_policy,
                 )
                 .await?,
-            )
+            ),
         };
 
-        let (callback_waiter, callback_sender) = if role.is_initiator() {
-            let callback = ockam_node::callback::new_callback();
-            (Some(callback.0), Some(callback.1))
-        } else {
-            (None, None)
+        let handshake_timeout = match handshake_timeout {
+            Some(timeout) => Some((
+                timeout,
+                DelayedEvent::create(context, addresses.decryptor_internal.clone(), ()).await?,
+            )),
+            None => None,
         };
+        let timer_address = handshake_timeout.as_ref().map(|(_, event)| event.address());
 
         let worker = Self {
             secure_channels,
-            callback_sender,
+            completion,
+            listener,
             state_machine,
             identifier,
             role,
             remote_route: remote_route.clone(),
+            heartbeat,
+            trust_policy,
+            replay_protection,
+            replay_window,
+            pq_hybrid,
             addresses: addresses.clone(),
             decryptor_handler: None,
+            handshake_timeout,
+            #[cfg(feature = "std")]
+            started_at: std::time::Instant::now(),
         };
 
         WorkerBuilder::new(worker)
             .with_mailboxes(Self::create_mailboxes(
                 &addresses,
                 decryptor_outgoing_access_control,
+                timer_address,
             ))
+            .with_kind(SECURE_CHANNEL_DECRYPTOR_KIND)
             .start(context)
             .await?;
 
@@ -225,22 +268,128 @@ impl HandshakeWorker {
             role, &decryptor_remote
         );
 
-        // before sending messages make sure that the handshake is finished and
-        // the encryptor worker is ready
-        if role.is_initiator() {
-            if let Some(callback_waiter) = callback_waiter {
-                // wait until the handshake is finished
-                if let Some(timeout) = timeout {
-                    callback_waiter.receive_timeout(timeout).await?;
-                } else {
-                    callback_waiter.receive().await?;
-                }
+        Ok(())
+    }
+
+    /// Start the handshake, sending the first message if we are the initiator
+    async fn start_handshake(&mut self, context: &Context) -> Result<()> {
+        if let Some((timeout, event)) = self.handshake_timeout.as_mut() {
+            event.schedule(*timeout).await?;
+        }
+
+        match self.state_machine.on_event(Initialize).await? {
+            SendMessage(message) => {
+                debug!(
+                    "remote route {:?}, decryptor remote {:?}",
+                    self.remote_route.clone(),
+                    self.addresses.decryptor_remote.clone()
+                );
+                context
+                    .send_from_address(
+                        self.remote_route()?,
+                        message,
+                        self.addresses.decryptor_remote.clone(),
+                    )
+                    .await
+            }
+            Action::NoAction => Ok(()),
+        }
+    }
+
+    /// Process a handshake message, then create the encryptor and the decryptor if the
+    /// handshake is complete
+    async fn handle_handshake_message(
+        &mut self,
+        context: &Context,
+        message: Routed<Any>,
+    ) -> Result<()> {
+        let transport_message = message.into_transport_message();
+        let payload = Vec::<u8>::decode(&transport_message.payload)?;
+
+        // Only the listener of the responder sends rejections, instead of its first message
+        if self.role.is_initiator() {
+            if let Some(rejection) = HandshakeRejection::decode(&payload) {
+                let err = IdentityError::from(rejection).into();
+                return self.fail(context, err).await;
             }
         }
 
+        let action = match self.state_machine.on_event(ReceivedMessage(payload)).await {
+            Ok(action) => action,
+            Err(err) => return self.fail(context, err).await,
+        };
+
+        if let SendMessage(message) = action {
+            // set the remote route by taking the most up to date message return route
+            // In the case of the initiator the first return route mentions the secure channel listener
+            // address so we need to wait for the return route corresponding to the remote handshake worker
+            // when it has been spawned
+            self.remote_route = Some(transport_message.return_route);
+
+            context
+                .send_from_address(
+                    self.remote_route()?,
+                    message,
+                    self.addresses.decryptor_remote.clone(),
+                )
+                .await?
+        };
+
+        // if we reached the final state we can make a pair of encryptor/decryptor
+        if let Some(final_state) = self.state_machine.get_handshake_results() {
+            // the handshake worker is being stopped
+            if let Some(completion) = &self.completion {
+                if completion.is_abandoned() {
+                    return Ok(());
+                }
+            }
+
+            let their_identifier = final_state.their_identifier.clone();
+
+            // start the encryptor worker and return the decryptor
+            match self.finalize(context, final_state).await {
+                Ok(decryptor_handler) => self.decryptor_handler = Some(decryptor_handler),
+                Err(err) => return self.fail(context, err).await,
+            }
+            self.handshake_timeout = None;
+            if let Some(completion) = self.completion.take() {
+                // if the handshake was cancelled meanwhile, the channel is going to be
+                // stopped and unregistered when this worker shuts down
+                completion.complete(their_identifier, self.pq_hybrid);
+            }
+        };
+
         Ok(())
     }
 
+    /// Span in which the steps of the handshake run
+    #[cfg(feature = "tracing_spans")]
+    fn handshake_span(&self) -> tracing::Span {
+        tracing::info_span!(
+            "secure_channel_handshake",
+            role = %self.role,
+            address = %self.addresses.decryptor_remote,
+        )
+    }
+
+    /// Report a handshake failure to the initiator and stop this worker.
+    /// On the responder side, the error is just returned
+    async fn fail(&mut self, context: &Context, err: Error) -> Result<()> {
+        match self.completion.take() {
+            Some(completion) => {
+                warn!(
+                    "SecureChannel {} handshake failed at {}: {}",
+                    self.role, self.addresses.decryptor_remote, err
+                );
+                completion.fail(err);
+                context
+                    .stop_worker(self.addresses.decryptor_remote.clone())
+                    .await
+            }
+            None => Err(err),
+        }
+    }
+
     /// Return the route for the other party's handshake worker
     fn remote_route(&self) -> Result<Route> {
         self.remote_route.clone().ok_or_else(|| {
@@ -252,10 +401,12 @@ impl HandshakeWorker {
         })
     }
 
-    /// Create mailboxes and access rights for the workers involved in the secure channel creation
+    /// Create mailboxes and access rights for the workers involved in the secure channel creation.
+    /// The internal address only accepts the messages of the handshake timer, if any
     pub(crate) fn create_mailboxes(
         addresses: &Addresses,
         decryptor_outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+        timer_address: Option<Address>,
     ) -> Mailboxes {
         let remote_mailbox = Mailbox::new(
             addresses.decryptor_remote.clone(),
@@ -266,9 +417,13 @@ impl HandshakeWorker {
             // Communicate to the other side of the channel during key exchange
             Arc::new(AllowAll),
         );
+        let internal_incoming_access_control: Arc<dyn IncomingAccessControl> = match timer_address {
+            Some(timer_address) => Arc::new(AllowSourceAddresses(vec![timer_address])),
+            None => Arc::new(DenyAll),
+        };
         let internal_mailbox = Mailbox::new(
             addresses.decryptor_internal.clone(),
-            Arc::new(DenyAll),
+            internal_incoming_access_control,
             decryptor_outgoing_access_control,
         );
         let api_mailbox = Mailbox::new(
@@ -289,15 +444,23 @@ impl HandshakeWorker {
         handshake_results: HandshakeResults,
     ) -> Result<DecryptorHandler> {
         // create a decryptor to delegate the processing of all messages after the handshake
+        let replay_counters = ReplayProtectionCounters::default();
+        let stats = SecureChannelStats::new(self.pq_hybrid, handshake_results.compression);
         let decryptor = DecryptorHandler::new(
             self.role.str(),
             self.addresses.clone(),
             handshake_results.handshake_keys.decryption_key,
             self.secure_channels.identities.vault().secure_channel_vault,
             handshake_results.their_identifier.clone(),
-        );
+            self.replay_protection,
+            self.replay_window,
+            replay_counters.clone(),
+            stats.clone(),
+        )
+        .with_compression(handshake_results.compression);
 
         // create a separate encryptor worker which will be started independently
+        let mut trust_reevaluation_trigger = None;
         {
             let encryptor = EncryptorWorker::new(
                 self.role.str(),
@@ -307,8 +470,58 @@ impl HandshakeWorker {
                     handshake_results.handshake_keys.encryption_key,
                     0,
                     self.secure_channels.identities.vault().secure_channel_vault,
+                    stats.clone(),
                 ),
-            );
+                self.secure_channels.secure_channel_registry(),
+            )
+            .with_compression(handshake_results.compression)
+            .with_message_extensions(handshake_results.message_extensions);
+
+            // Heartbeat notifications come from the decryptor and from the timers
+            let mut heartbeat_sources = vec![self.addresses.decryptor_api.clone()];
+            let encryptor = if let Some(heartbeat) = self.heartbeat {
+                let event = DelayedEvent::create(
+                    context,
+                    self.addresses.encryptor_internal.clone(),
+                    HeartbeatSignal::Tick,
+                )
+                .await?;
+                heartbeat_sources.push(event.address());
+                encryptor.with_heartbeat(heartbeat, event)
+            } else {
+                encryptor
+            };
+            let encryptor = if let Some(interval) = self.trust_policy.reevaluation_interval() {
+                let event = DelayedEvent::create(
+                    context,
+                    self.addresses.encryptor_internal.clone(),
+                    HeartbeatSignal::ReevaluateTrust,
+                )
+                .await?;
+                heartbeat_sources.push(event.address());
+
+                // Used by `SecureChannels::reevaluate_trust` to check the policy on demand
+                let trigger = context
+                    .new_detached_with_mailboxes(Mailboxes::main(
+                        Address::random_tagged("SecureChannel.trust_reevaluation"),
+                        Arc::new(DenyAll),
+                        Arc::new(AllowOnwardAddress(
+                            self.addresses.encryptor_internal.clone(),
+                        )),
+                    ))
+                    .await?;
+                heartbeat_sources.push(trigger.address());
+                trust_reevaluation_trigger = Some(trigger);
+
+                encryptor.with_trust_reevaluation(
+                    self.trust_policy.clone(),
+                    handshake_results.their_identifier.clone(),
+                    interval,
+                    event,
+                )
+            } else {
+                encryptor
+            };
 
             let next_hop = self.remote_route()?.next()?.clone();
             let main_mailbox = Mailbox::new(
@@ -322,8 +535,18 @@ impl HandshakeWorker {
                 Arc::new(AllowAll),
             );
 
+            let internal_mailbox = Mailbox::new(
+                self.addresses.encryptor_internal.clone(),
+                Arc::new(AllowSourceAddresses(heartbeat_sources)),
+                Arc::new(DenyAll),
+            );
+
             WorkerBuilder::new(encryptor)
-                .with_mailboxes(Mailboxes::new(main_mailbox, vec![api_mailbox]))
+                .with_mailboxes(Mailboxes::new(
+                    main_mailbox,
+                    vec![api_mailbox, internal_mailbox],
+                ))
+                .with_kind(SECURE_CHANNEL_ENCRYPTOR_KIND)
                 .start(context)
                 .await?;
         }
@@ -335,8 +558,8 @@ impl HandshakeWorker {
             &self.addresses.decryptor_remote
         );
 
-        let their_decryptor_address = self
-            .remote_route()?
+        let route = self.remote_route()?;
+        let their_decryptor_address = route
             .iter()
             .last()
             .expect("the remote route should not be empty")
@@ -351,11 +574,27 @@ impl HandshakeWorker {
             self.identifier.clone(),
             handshake_results.their_identifier,
             their_decryptor_address,
+            route,
+            handshake_results.attributes_persisted,
+            replay_counters,
+            stats,
+            self.listener.clone(),
         );
 
-        self.secure_channels
-            .secure_channel_registry()
-            .register_channel(info)?;
+        let registry = self.secure_channels.secure_channel_registry();
+        registry.register_channel(info)?;
+        if let Some(trigger) = trust_reevaluation_trigger {
+            registry.register_trust_reevaluation_trigger(
+                &self.addresses.encryptor,
+                trigger,
+                self.addresses.encryptor_internal.clone(),
+            );
+        }
+
+        #[cfg(feature = "std")]
+        context.record_metrics(|m| {
+            m.handshake_completed(&self.addresses.encryptor, self.started_at.elapsed())
+        });
 
         Ok(decryptor)
     }
@@ -0,0 +1,87 @@
+#[cfg(feature = "pq_hybrid")]
+use crate::secure_channel::handshake::error::XXError;
+#[cfg(feature = "pq_hybrid")]
+use ml_kem::kem::{Decapsulate, Encapsulate};
+#[cfg(feature = "pq_hybrid")]
+use ml_kem::{Ciphertext, Encoded, EncodedSizeUser, KemCore, MlKem768};
+#[cfg(feature = "pq_hybrid")]
+use ockam_core::{compat::vec::Vec, Result};
+
+#[cfg(feature = "pq_hybrid")]
+type DecapsulationKey = <MlKem768 as KemCore>::DecapsulationKey;
+#[cfg(feature = "pq_hybrid")]
+type EncapsulationKey = <MlKem768 as KemCore>::EncapsulationKey;
+
+/// State of the post-quantum part of a hybrid handshake.
+///
+/// In a hybrid handshake the initiator sends an ML-KEM-768 (FIPS 203) public key with
+/// message 1, the
+/// responder encapsulates a shared secret to that key and sends the ciphertext with message 2.
+/// Both sides then mix the shared secret into the chaining key, right after the `ee`
+/// Diffie-Hellman key, so that the channel keys stay safe as long as either the X25519 or the
+/// ML-KEM key exchange is not broken.
+pub(super) enum HybridKem {
+    /// Classical handshake, no KEM is used
+    Disabled,
+    /// Initiator of a hybrid handshake, waiting for the KEM ciphertext of message 2
+    #[cfg(feature = "pq_hybrid")]
+    Initiator(KemKeyPair),
+    /// Responder of a hybrid handshake, with the initiator KEM public key sent in message 1
+    #[cfg(feature = "pq_hybrid")]
+    Responder(Vec<u8>),
+}
+
+/// ML-KEM key pair generated by the initiator for one handshake
+#[cfg(feature = "pq_hybrid")]
+pub(super) struct KemKeyPair {
+    public_key: Vec<u8>,
+    secret_key: zeroize::Zeroizing<Vec<u8>>,
+}
+
+#[cfg(feature = "pq_hybrid")]
+impl KemKeyPair {
+    /// Generate a new ML-KEM key pair
+    pub(super) fn generate() -> Result<Self> {
+        let (secret_key, public_key) =
+            MlKem768::generate(&mut ockam_core::compat::rand::thread_rng());
+        Ok(Self {
+            public_key: public_key.as_bytes().to_vec(),
+            secret_key: zeroize::Zeroizing::new(secret_key.as_bytes().to_vec()),
+        })
+    }
+
+    /// Public key sent to the responder
+    pub(super) fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Decapsulate the shared secret of a ciphertext sent by the responder
+    pub(super) fn decapsulate(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let secret_key = Encoded::<DecapsulationKey>::try_from(self.secret_key.as_slice())
+            .map_err(|_| XXError::InternalVaultError)?;
+        let secret_key = DecapsulationKey::from_bytes(&secret_key);
+        let ciphertext = Ciphertext::<MlKem768>::try_from(ciphertext)
+            .map_err(|_| XXError::MessageLenMismatch)?;
+        let shared_secret = secret_key
+            .decapsulate(&ciphertext)
+            .map_err(|_| XXError::MessageLenMismatch)?;
+        Ok(shared_secret.to_vec())
+    }
+}
+
+/// Length of an ML-KEM-768 ciphertext
+#[cfg(feature = "pq_hybrid")]
+pub(super) const KEM_CIPHERTEXT_LENGTH: usize = 1088;
+
+/// Encapsulate a new shared secret to the initiator public key.
+/// Return the ciphertext and the shared secret
+#[cfg(feature = "pq_hybrid")]
+pub(super) fn encapsulate(public_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let public_key = Encoded::<EncapsulationKey>::try_from(public_key)
+        .map_err(|_| XXError::MessageLenMismatch)?;
+    let public_key = EncapsulationKey::from_bytes(&public_key);
+    let (ciphertext, shared_secret) = public_key
+        .encapsulate(&mut ockam_core::compat::rand::thread_rng())
+        .map_err(|_| XXError::InternalVaultError)?;
+    Ok((ciphertext.to_vec(), shared_secret.to_vec()))
+}
@@ -15,9 +15,12 @@ use crate::secure_channel::handshake::error::XXError;
 use crate::secure_channel::handshake::handshake::Handshake;
 use crate::secure_channel::handshake::handshake_state_machine::{
     Action, CommonStateMachine, Event, HandshakeKeys, HandshakeResults, IdentityAndCredentials,
-    StateMachine, Status,
+    KeyExchangeParameters, StateMachine, Status,
+};
+use crate::{
+    CipherSuite, CompressionAlgorithm, Identities, Role, SecureChannelPurposeKey,
+    StorageFailurePolicy, TrustContext, TrustPolicy,
 };
-use crate::{Identities, Role, SecureChannelPurposeKey, TrustContext, TrustPolicy};
 
 /// Implementation of a state machine for the key exchange on the initiator side
 #[async_trait]
@@ -28,7 +31,16 @@ impl StateMachine for InitiatorStateMachine {
             // Initialize the handshake and send message 1
             (Initial, Initialize) => {
                 self.initialize_handshake().await?;
-                let message1 = self.encode_message1(&[]).await?;
+                let kem_public_key = if self.pq_hybrid {
+                    Some(self.handshake.start_hybrid_kem()?)
+                } else {
+                    None
+                };
+                let parameters = minicbor::to_vec(KeyExchangeParameters {
+                    allowed_ciphers: self.allowed_ciphers.clone(),
+                    kem_public_key,
+                })?;
+                let message1 = self.encode_message1(&parameters).await?;
 
                 // Send message 1 and wait for message 2
                 self.handshake.state.status = WaitingForMessage2;
@@ -72,6 +84,10 @@ pub(super) struct InitiatorStateMachine {
     pub(super) handshake: Handshake,
     /// this serialized payload contains an identity, its credentials and a signature of its static key
     pub(super) identity_payload: Option<Vec<u8>>,
+    /// cipher suites sent to the responder with message 1
+    pub(super) allowed_ciphers: Vec<CipherSuite>,
+    /// request a hybrid post-quantum handshake by sending a KEM public key with message 1
+    pub(super) pq_hybrid: bool,
 }
 
 impl InitiatorStateMachine {
@@ -95,14 +111,19 @@ impl InitiatorStateMachine {
 }
 
 impl InitiatorStateMachine {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         vault: Arc<dyn VaultForSecureChannels>,
         identities: Arc<Identities>,
         identifier: Identifier,
         purpose_key: SecureChannelPurposeKey,
         credentials: Vec<CredentialAndPurposeKey>,
+        allowed_ciphers: Vec<CipherSuite>,
+        pq_hybrid: bool,
+        compression: Option<CompressionAlgorithm>,
         trust_policy: Arc<dyn TrustPolicy>,
         trust_context: Option<TrustContext>,
+        storage_failure_policy: StorageFailurePolicy,
     ) -> Result<InitiatorStateMachine> {
         let common = CommonStateMachine::new(
             identities,
@@ -111,6 +132,8 @@ impl InitiatorStateMachine {
             credentials,
             trust_policy,
             trust_context,
+            storage_failure_policy,
+            compression,
         );
         let identity_payload = common.make_identity_payload().await?;
 
@@ -118,6 +141,8 @@ impl InitiatorStateMachine {
             common,
             handshake: Handshake::new(vault, purpose_key.key().clone()).await?,
             identity_payload: Some(identity_payload),
+            allowed_ciphers,
+            pq_hybrid,
         })
     }
 }
@@ -8,5 +8,8 @@ compile_error!("Key Exchange is only supported on little-endian machines");
 mod handshake;
 mod handshake_state_machine;
 pub(crate) mod handshake_worker;
+mod hybrid_kem;
 mod initiator_state_machine;
+mod pre_shared_key_state_machine;
+pub(crate) mod rejection;
 mod responder_state_machine;
@@ -0,0 +1,338 @@
+use minicbor::{Decode, Encode};
+use ockam_core::compat::rand::{thread_rng, RngCore};
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::{boxed::Box, vec::Vec};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Result};
+use ockam_vault::{
+    AeadSecretKeyHandle, HKDFNumberOfOutputs, SecretBufferHandle, VaultForSecureChannels,
+};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+use Action::*;
+use Event::*;
+use Status::*;
+
+use crate::models::Identifier;
+use crate::secure_channel::handshake::error::XXError;
+use crate::secure_channel::handshake::handshake::{AES_GCM_TAGSIZE, SHA256_SIZE};
+use crate::secure_channel::handshake::handshake_state_machine::{
+    Action, Event, HandshakeKeys, HandshakeResults, StateMachine, Status,
+};
+use crate::{IdentityError, PreSharedKey, Role, SecureChannelTrustInfo, TrustPolicy};
+
+/// Protocol name, mixed in the transcript hash, padded to 32 bytes
+const PROTOCOL_NAME: &[u8; 32] = b"OCKAM_PSK_HKDF_AESGCM_SHA256\0\0\0\0";
+
+/// Number of random bytes contributed by each side to the channel keys
+const NONCE_LENGTH: usize = 32;
+
+/// Implementation of a key exchange based on a key shared out of band by both parties:
+///
+///  1. initiator -> responder: initiator nonce + initiator identifier
+///  2. responder -> initiator: responder nonce + responder identifier + confirmation tag
+///  3. initiator -> responder: confirmation tag
+///
+/// The channel keys and a confirmation key are derived with HKDF from the pre-shared key,
+/// using the hash of messages 1 and 2 as a salt. Each side proves that it has the same
+/// pre-shared key by authenticating that hash with the confirmation key, so that a channel
+/// is only established if the keys match. No asymmetric cryptography is involved.
+pub(super) struct PreSharedKeyStateMachine {
+    vault: Arc<dyn VaultForSecureChannels>,
+    role: Role,
+    identifier: Identifier,
+    pre_shared_key: PreSharedKey,
+    trust_policy: Arc<dyn TrustPolicy>,
+    status: Status,
+    message1: Option<Vec<u8>>,
+    transcript_hash: Option<[u8; SHA256_SIZE]>,
+    confirmation_key: Option<AeadSecretKeyHandle>,
+    pending_keys: Option<HandshakeKeys>,
+    their_identifier: Option<Identifier>,
+}
+
+#[async_trait]
+impl StateMachine for PreSharedKeyStateMachine {
+    async fn on_event(&mut self, event: Event) -> Result<Action> {
+        match (self.status.clone(), event) {
+            // The initiator sends message 1
+            (Initial, Initialize) if self.role.is_initiator() => {
+                let message1 = Self::encode_hello(&self.identifier)?;
+                self.message1 = Some(message1.clone());
+                self.status = WaitingForMessage2;
+                Ok(SendMessage(message1))
+            }
+            // The responder waits for message 1
+            (Initial, Initialize) => {
+                self.status = WaitingForMessage1;
+                Ok(NoAction)
+            }
+            // The responder processes message 1 and sends message 2
+            (WaitingForMessage1, ReceivedMessage(message1)) => {
+                let their_hello: Hello = minicbor::decode(&message1)?;
+                self.check_trust_policy(&their_hello.identifier).await?;
+
+                let hello = Self::encode_hello(&self.identifier)?;
+                self.derive_keys(&message1, &hello).await?;
+                let confirmation = self.make_confirmation(Role::Responder).await?;
+
+                self.their_identifier = Some(their_hello.identifier);
+                self.status = WaitingForMessage3;
+
+                let mut message2 = hello;
+                message2.extend(confirmation);
+                Ok(SendMessage(message2))
+            }
+            // The initiator processes message 2 and sends message 3
+            (WaitingForMessage2, ReceivedMessage(message2)) => {
+                if message2.len() < AES_GCM_TAGSIZE {
+                    return Err(XXError::MessageLenMismatch.into());
+                }
+                let (hello, confirmation) = message2.split_at(message2.len() - AES_GCM_TAGSIZE);
+                let their_hello: Hello = minicbor::decode(hello)?;
+                self.check_trust_policy(&their_hello.identifier).await?;
+
+                let message1 = self.message1.take().ok_or(XXError::InvalidInternalState)?;
+                self.derive_keys(&message1, hello).await?;
+                if let Err(err) = self
+                    .verify_confirmation(Role::Responder, confirmation)
+                    .await
+                {
+                    self.delete_keys().await;
+                    return Err(err);
+                }
+                let message3 = self.make_confirmation(Role::Initiator).await?;
+
+                self.their_identifier = Some(their_hello.identifier);
+                self.set_final_state().await?;
+                Ok(SendMessage(message3))
+            }
+            // The responder processes message 3
+            (WaitingForMessage3, ReceivedMessage(message3)) => {
+                if let Err(err) = self.verify_confirmation(Role::Initiator, &message3).await {
+                    self.delete_keys().await;
+                    return Err(err);
+                }
+                self.set_final_state().await?;
+                Ok(NoAction)
+            }
+            // incorrect state / event
+            (s, e) => Err(Error::new(
+                Origin::Channel,
+                Kind::Invalid,
+                format!(
+                    "Unexpected combination of {} state and event {:?}/{:?}",
+                    self.role, s, e
+                ),
+            )),
+        }
+    }
+
+    fn get_handshake_results(&self) -> Option<HandshakeResults> {
+        match (&self.status, &self.their_identifier) {
+            (Ready(handshake_keys), Some(their_identifier)) => Some(HandshakeResults {
+                handshake_keys: handshake_keys.clone(),
+                their_identifier: their_identifier.clone(),
+                attributes_persisted: true,
+                compression: None,
+                // Both parties of a pre-shared key channel support message extensions
+                message_extensions: true,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl PreSharedKeyStateMachine {
+    pub(super) fn new(
+        vault: Arc<dyn VaultForSecureChannels>,
+        role: Role,
+        identifier: Identifier,
+        pre_shared_key: PreSharedKey,
+        trust_policy: Arc<dyn TrustPolicy>,
+    ) -> Self {
+        Self {
+            vault,
+            role,
+            identifier,
+            pre_shared_key,
+            trust_policy,
+            status: Initial,
+            message1: None,
+            transcript_hash: None,
+            confirmation_key: None,
+            pending_keys: None,
+            their_identifier: None,
+        }
+    }
+
+    /// Encode our identifier along with a fresh nonce
+    fn encode_hello(identifier: &Identifier) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        thread_rng().fill_bytes(&mut nonce);
+        Ok(minicbor::to_vec(Hello {
+            nonce,
+            identifier: identifier.clone(),
+        })?)
+    }
+
+    /// The other side's identifier is only authenticated by the pre-shared key,
+    /// it still needs to be accepted by our trust policy
+    async fn check_trust_policy(&self, their_identifier: &Identifier) -> Result<()> {
+        let trust_info = SecureChannelTrustInfo::new(their_identifier.clone());
+        if !self.trust_policy.check(&trust_info).await? {
+            return Err(IdentityError::SecureChannelTrustCheckFailed.into());
+        }
+        debug!(
+            "{} checked trust policy for SecureChannel with: {}",
+            self.role, their_identifier
+        );
+        Ok(())
+    }
+
+    /// Derive the encryption, decryption and confirmation keys from the pre-shared key
+    async fn derive_keys(&mut self, message1: &[u8], hello2: &[u8]) -> Result<()> {
+        let transcript_hash: [u8; SHA256_SIZE] = Sha256::new()
+            .chain_update(PROTOCOL_NAME)
+            .chain_update(message1)
+            .chain_update(hello2)
+            .finalize()
+            .into();
+
+        let salt = self
+            .vault
+            .import_secret_buffer(transcript_hash.to_vec())
+            .await?;
+        // The copy of the key is moved into the vault, which zeroizes its secret buffers
+        // when they are deleted
+        let pre_shared_key = self
+            .vault
+            .import_secret_buffer(self.pre_shared_key.value().to_vec())
+            .await?;
+        let hkdf_output = self
+            .vault
+            .hkdf(&salt, Some(&pre_shared_key), HKDFNumberOfOutputs::Three)
+            .await;
+        self.vault.delete_secret_buffer(salt).await?;
+        self.vault.delete_secret_buffer(pre_shared_key).await?;
+
+        let [k1, k2, kc]: [SecretBufferHandle; 3] = hkdf_output?
+            .0
+             .0
+            .try_into()
+            .map_err(|_| XXError::InternalVaultError)?;
+        let k1 = self.vault.convert_secret_buffer_to_aead_key(k1).await?;
+        let k2 = self.vault.convert_secret_buffer_to_aead_key(k2).await?;
+        let kc = self.vault.convert_secret_buffer_to_aead_key(kc).await?;
+
+        let (encryption_key, decryption_key) = if self.role.is_initiator() {
+            (k1, k2)
+        } else {
+            (k2, k1)
+        };
+        self.pending_keys = Some(HandshakeKeys {
+            encryption_key,
+            decryption_key,
+        });
+        self.confirmation_key = Some(kc);
+        self.transcript_hash = Some(transcript_hash);
+        Ok(())
+    }
+
+    /// Nonces used with the confirmation key, one per side
+    fn confirmation_nonce(role: Role) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        if role.is_initiator() {
+            nonce[11] = 1;
+        }
+        nonce
+    }
+
+    /// Authenticate the transcript hash with the confirmation key
+    async fn make_confirmation(&self, role: Role) -> Result<Vec<u8>> {
+        let confirmation_key = self
+            .confirmation_key
+            .as_ref()
+            .ok_or(XXError::InvalidInternalState)?;
+        let transcript_hash = self
+            .transcript_hash
+            .as_ref()
+            .ok_or(XXError::InvalidInternalState)?;
+        self.vault
+            .aead_encrypt(
+                confirmation_key,
+                &[],
+                &Self::confirmation_nonce(role),
+                transcript_hash,
+            )
+            .await
+    }
+
+    /// Check that the other side authenticated the same transcript hash with
+    /// the same confirmation key, meaning that it has the same pre-shared key
+    async fn verify_confirmation(&self, their_role: Role, confirmation: &[u8]) -> Result<()> {
+        let confirmation_key = self
+            .confirmation_key
+            .as_ref()
+            .ok_or(XXError::InvalidInternalState)?;
+        let transcript_hash = self
+            .transcript_hash
+            .as_ref()
+            .ok_or(XXError::InvalidInternalState)?;
+        if let Err(err) = self
+            .vault
+            .aead_decrypt(
+                confirmation_key,
+                confirmation,
+                &Self::confirmation_nonce(their_role),
+                transcript_hash,
+            )
+            .await
+        {
+            warn!(
+                "the other side doesn't have the same pre-shared key: {}",
+                err
+            );
+            return Err(IdentityError::SecureChannelPreSharedKeyMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Discard all the derived keys, after the other side failed to prove that it has the same
+    /// pre-shared key
+    async fn delete_keys(&mut self) {
+        let keys = self
+            .pending_keys
+            .take()
+            .into_iter()
+            .flat_map(|keys| [keys.encryption_key, keys.decryption_key]);
+        for key in keys.chain(self.confirmation_key.take()) {
+            if let Err(err) = self.vault.delete_aead_secret_key(key).await {
+                warn!("failed to delete a pre-shared key channel key: {}", err);
+            }
+        }
+        self.transcript_hash = None;
+    }
+
+    /// Discard the confirmation key and make the channel keys available
+    async fn set_final_state(&mut self) -> Result<()> {
+        if let Some(confirmation_key) = self.confirmation_key.take() {
+            self.vault.delete_aead_secret_key(confirmation_key).await?;
+        }
+        let keys = self
+            .pending_keys
+            .take()
+            .ok_or(XXError::InvalidInternalState)?;
+        self.status = Ready(keys);
+        Ok(())
+    }
+}
+
+/// First part of the messages 1 and 2
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+struct Hello {
+    #[cbor(n(1), with = "minicbor::bytes")] nonce: [u8; NONCE_LENGTH],
+    #[n(2)] identifier: Identifier,
+}
@@ -0,0 +1,46 @@
+use ockam_core::compat::vec::Vec;
+
+use crate::IdentityError;
+
+/// First byte of a [`HandshakeRejection`]. No handshake message is as short as a rejection,
+/// so the two can't be mistaken for each other
+const REJECTION_MARKER: u8 = 0xff;
+
+/// Reason for which a Secure Channel Listener rejects a handshake
+///
+/// The rejection is sent to the initiator instead of the second handshake message, so that
+/// it fails right away instead of timing out. It is not authenticated: anyone on the route
+/// could forge it, but they could just as well drop the handshake messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HandshakeRejection {
+    /// See [`IdentityError::SecureChannelUnauthorizedTransport`]
+    UnauthorizedTransport,
+}
+
+impl HandshakeRejection {
+    /// Encode the rejection as the payload of a handshake message
+    pub(crate) fn encode(self) -> Vec<u8> {
+        let reason = match self {
+            Self::UnauthorizedTransport => 1,
+        };
+        vec![REJECTION_MARKER, reason]
+    }
+
+    /// Decode a rejection from the payload of a handshake message, if it is one
+    pub(crate) fn decode(payload: &[u8]) -> Option<Self> {
+        match payload {
+            [REJECTION_MARKER, 1] => Some(Self::UnauthorizedTransport),
+            _ => None,
+        }
+    }
+}
+
+impl From<HandshakeRejection> for IdentityError {
+    fn from(rejection: HandshakeRejection) -> Self {
+        match rejection {
+            HandshakeRejection::UnauthorizedTransport => {
+                IdentityError::SecureChannelUnauthorizedTransport
+            }
+        }
+    }
+}
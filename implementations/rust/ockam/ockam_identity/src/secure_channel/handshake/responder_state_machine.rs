@@ -15,9 +15,12 @@ use crate::secure_channel::handshake::error::XXError;
 use crate::secure_channel::handshake::handshake::Handshake;
 use crate::secure_channel::handshake::handshake_state_machine::{
     Action, CommonStateMachine, Event, HandshakeKeys, HandshakeResults, IdentityAndCredentials,
-    StateMachine, Status,
+    KeyExchangeParameters, StateMachine, Status,
+};
+use crate::{
+    CipherSuite, CompressionAlgorithm, Identities, IdentityError, Role, SecureChannelPurposeKey,
+    StorageFailurePolicy, TrustContext, TrustPolicy,
 };
-use crate::{Identities, Role, SecureChannelPurposeKey, TrustContext, TrustPolicy};
 
 /// Implementation of a state machine for the key exchange on the responder side
 #[async_trait]
@@ -33,7 +36,27 @@ impl StateMachine for ResponderStateMachine {
             }
             // Process message 1 and send message 2
             (WaitingForMessage1, ReceivedMessage(message)) => {
-                self.decode_message1(&message).await?;
+                let message1_payload = self.decode_message1(&message).await?;
+                // Initiators which don't send any parameters use the default cipher suite
+                // and the classical key exchange
+                let parameters = if message1_payload.is_empty() {
+                    KeyExchangeParameters {
+                        allowed_ciphers: vec![CipherSuite::supported()],
+                        kem_public_key: None,
+                    }
+                } else {
+                    minicbor::decode(&message1_payload)?
+                };
+                CipherSuite::negotiate(&self.allowed_ciphers, &parameters.allowed_ciphers)?;
+                // Both sides must agree on the hybrid mode, otherwise an attacker could
+                // silently strip the KEM public key
+                match (self.pq_hybrid, parameters.kem_public_key) {
+                    (true, Some(kem_public_key)) => {
+                        self.handshake.accept_hybrid_kem(kem_public_key)?
+                    }
+                    (false, None) => (),
+                    _ => return Err(IdentityError::SecureChannelPqHybridMismatch.into()),
+                }
                 let identity_payload = self
                     .identity_payload
                     .take()
@@ -75,6 +98,8 @@ pub struct ResponderStateMachine {
     handshake: Handshake,
     /// this serialized payload contains an identity, its credentials and a signature of its static key
     identity_payload: Option<Vec<u8>>,
+    allowed_ciphers: Vec<CipherSuite>,
+    pq_hybrid: bool,
 }
 
 impl ResponderStateMachine {
@@ -98,14 +123,19 @@ impl ResponderStateMachine {
 }
 
 impl ResponderStateMachine {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         vault: Arc<dyn VaultForSecureChannels>,
         identities: Arc<Identities>,
         identifier: Identifier,
         purpose_key: SecureChannelPurposeKey,
         credentials: Vec<CredentialAndPurposeKey>,
+        allowed_ciphers: Vec<CipherSuite>,
+        pq_hybrid: bool,
+        compression: Option<CompressionAlgorithm>,
         trust_policy: Arc<dyn TrustPolicy>,
         trust_context: Option<TrustContext>,
+        storage_failure_policy: StorageFailurePolicy,
     ) -> Result<ResponderStateMachine> {
         let common = CommonStateMachine::new(
             identities,
@@ -114,6 +144,8 @@ impl ResponderStateMachine {
             credentials,
             trust_policy,
             trust_context,
+            storage_failure_policy,
+            compression,
         );
         let identity_payload = common.make_identity_payload().await?;
 
@@ -121,6 +153,8 @@ impl ResponderStateMachine {
             common,
             handshake: Handshake::new(vault, purpose_key.key().clone()).await?,
             identity_payload: Some(identity_payload),
+            allowed_ciphers,
+            pq_hybrid,
         })
     }
 }
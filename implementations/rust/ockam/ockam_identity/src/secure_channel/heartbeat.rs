@@ -0,0 +1,88 @@
+use core::time::Duration;
+use ockam_core::Message;
+use serde::{Deserialize, Serialize};
+
+// Heartbeats are encrypted and sent like any other message, which means they consume nonces
+// from the same sequence and keys are renewed on the usual schedule.
+// Every encrypted application message is an encoded `TransportMessage`, which starts with a
+// non-zero protocol version, so the following plaintexts can never be mistaken for one.
+pub(crate) const HEARTBEAT_REQUEST: &[u8] = &[0, 0];
+pub(crate) const HEARTBEAT_RESPONSE: &[u8] = &[0, 1];
+
+/// Shortest interval between two heartbeats, shorter intervals are raised to it
+pub const MIN_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Heartbeat settings of a Secure Channel
+#[derive(Clone, Copy, Debug)]
+pub struct SecureChannelHeartbeat {
+    pub(crate) interval: Duration,
+    pub(crate) max_missed: u32,
+}
+
+impl SecureChannelHeartbeat {
+    /// Constructor. The interval is raised to [`MIN_HEARTBEAT_INTERVAL`] and `max_missed`
+    /// to 1, so that a zero value can't make the channel flood the other side with heartbeats
+    /// or be declared dead before a single heartbeat could be acknowledged
+    pub fn new(interval: Duration, max_missed: u32) -> Self {
+        Self {
+            interval: interval.max(MIN_HEARTBEAT_INTERVAL),
+            max_missed: max_missed.max(1),
+        }
+    }
+
+    /// Interval between two heartbeats
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Number of consecutive unacknowledged heartbeats after which the channel is considered dead
+    pub fn max_missed(&self) -> u32 {
+        self.max_missed
+    }
+}
+
+/// Messages received by the `EncryptorWorker` internal address
+#[derive(Serialize, Deserialize, Message, Clone)]
+pub(crate) enum HeartbeatSignal {
+    /// It's time to send the next heartbeat
+    Tick,
+    /// The other side sent us a heartbeat, we need to acknowledge it
+    SendResponse,
+    /// The other side acknowledged our heartbeat
+    ResponseReceived,
+    /// It's time to check the trust policy again, see [`crate::ReevaluatingTrustPolicy`]
+    ReevaluateTrust,
+}
+
+/// Heartbeat state of one side of a Secure Channel
+pub(crate) struct HeartbeatState {
+    pub(crate) options: SecureChannelHeartbeat,
+    pub(crate) missed: u32,
+}
+
+impl HeartbeatState {
+    pub(crate) fn new(options: SecureChannelHeartbeat) -> Self {
+        Self { options, missed: 0 }
+    }
+
+    /// Return true if the other side didn't answer too many heartbeats in a row
+    pub(crate) fn is_dead(&self) -> bool {
+        self.missed >= self.options.max_missed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_zero_values_are_clamped() {
+        let heartbeat = SecureChannelHeartbeat::new(Duration::ZERO, 0);
+        assert_eq!(heartbeat.interval(), MIN_HEARTBEAT_INTERVAL);
+        assert_eq!(heartbeat.max_missed(), 1);
+
+        let heartbeat = SecureChannelHeartbeat::new(Duration::from_secs(5), 3);
+        assert_eq!(heartbeat.interval(), Duration::from_secs(5));
+        assert_eq!(heartbeat.max_missed(), 3);
+    }
+}
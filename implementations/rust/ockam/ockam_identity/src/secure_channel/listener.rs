@@ -2,19 +2,27 @@ use ockam_core::compat::boxed::Box;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
 use ockam_core::{Address, Any, Result, Routed, Worker};
-use ockam_node::Context;
+use ockam_node::{Context, WorkerBuilder};
+use tracing::warn;
 
 use crate::models::{CredentialAndPurposeKey, Identifier};
 use crate::secure_channel::addresses::Addresses;
-use crate::secure_channel::handshake_worker::HandshakeWorker;
+use crate::secure_channel::handshake::rejection::HandshakeRejection;
+use crate::secure_channel::handshake_worker::{HandshakeMode, HandshakeWorker};
 use crate::secure_channel::options::SecureChannelListenerOptions;
+#[cfg(feature = "std")]
+use crate::secure_channel::rate_limiter::HandshakeRateLimiter;
 use crate::secure_channel::role::Role;
+use crate::secure_channel::SECURE_CHANNEL_LISTENER_KIND;
 use crate::secure_channels::secure_channels::SecureChannels;
+use crate::CipherSuite;
 
 pub(crate) struct IdentityChannelListener {
     secure_channels: Arc<SecureChannels>,
     identifier: Identifier,
     options: SecureChannelListenerOptions,
+    #[cfg(feature = "std")]
+    rate_limiter: Option<HandshakeRateLimiter>,
 }
 
 impl IdentityChannelListener {
@@ -26,6 +34,10 @@ impl IdentityChannelListener {
         Self {
             secure_channels,
             identifier,
+            #[cfg(feature = "std")]
+            rate_limiter: options
+                .rate_limit
+                .map(|(max_handshakes, per)| HandshakeRateLimiter::new(max_handshakes, per)),
             options,
         }
     }
@@ -37,11 +49,27 @@ impl IdentityChannelListener {
         address: Address,
         options: SecureChannelListenerOptions,
     ) -> Result<()> {
+        // A listener which can't use the cipher suite of this build would reject all handshakes
+        CipherSuite::negotiate(&options.allowed_ciphers, &[CipherSuite::supported()])?;
+        options.check_key_exchange()?;
+
+        // Checked before the flow controls of an existing listener can be overwritten
+        let registry = secure_channels.secure_channel_registry();
+        registry.register_listener(&address)?;
+
         options.setup_flow_control_for_listener(ctx.flow_controls(), &address);
 
         let listener = Self::new(secure_channels.clone(), identifier.clone(), options);
 
-        ctx.start_worker(address, listener).await?;
+        let started = WorkerBuilder::new(listener)
+            .with_address(address.clone())
+            .with_kind(SECURE_CHANNEL_LISTENER_KIND)
+            .start(ctx)
+            .await;
+        if let Err(err) = started {
+            registry.unregister_listener(&address);
+            return Err(err);
+        }
 
         Ok(())
     }
@@ -72,11 +100,49 @@ impl Worker for IdentityChannelListener {
     type Message = Any;
     type Context = Context;
 
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        self.secure_channels
+            .secure_channel_registry()
+            .unregister_listener(&ctx.address());
+
+        Ok(())
+    }
+
     async fn handle_message(
         &mut self,
         ctx: &mut Self::Context,
         message: Routed<Self::Message>,
     ) -> Result<()> {
+        if let Err(err) = self
+            .options
+            .check_transport(ctx.flow_controls(), &message.src_addr())
+        {
+            warn!(
+                "Rejecting a Secure Channel handshake received from {}: {}",
+                message.src_addr(),
+                err
+            );
+            // Let the initiator fail right away
+            ctx.send(
+                message.return_route(),
+                HandshakeRejection::UnauthorizedTransport.encode(),
+            )
+            .await?;
+            return Err(err);
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(rate_limiter) = &mut self.rate_limiter {
+            if let Err(err) = rate_limiter.check(&message.src_addr()) {
+                warn!(
+                    "Rejecting a Secure Channel handshake received from {}: {}",
+                    message.src_addr(),
+                    err
+                );
+                return Err(err);
+            }
+        }
+
         let addresses = Addresses::generate(Role::Responder);
         let flow_control_id = self.options.setup_flow_control_for_channel(
             ctx.flow_controls(),
@@ -89,27 +155,40 @@ impl Worker for IdentityChannelListener {
 
         let credentials = self.get_credentials(ctx).await?;
 
-        // TODO: Allow manual PurposeKey management
-        let purpose_key = self
-            .secure_channels
-            .identities
-            .purpose_keys()
-            .purpose_keys_creation()
-            .get_or_create_secure_channel_purpose_key(&self.identifier)
-            .await?;
+        let mode = match &self.options.pre_shared_key {
+            Some(pre_shared_key) => HandshakeMode::PreSharedKey(pre_shared_key.clone()),
+            // TODO: Allow manual PurposeKey management
+            None => HandshakeMode::PurposeKey(
+                self.secure_channels
+                    .identities
+                    .purpose_keys()
+                    .purpose_keys_creation()
+                    .get_or_create_secure_channel_purpose_key(&self.identifier)
+                    .await?,
+            ),
+        };
 
         HandshakeWorker::create(
             ctx,
             self.secure_channels.clone(),
             addresses.clone(),
             self.identifier.clone(),
-            purpose_key,
+            mode,
             self.options.trust_policy.clone(),
             access_control.decryptor_outgoing_access_control,
             credentials,
+            self.options.allowed_ciphers.clone(),
+            self.options.pq_hybrid,
+            self.options.compression,
             self.options.trust_context.clone(),
             None,
             None,
+            Some(ctx.address()),
+            self.options.heartbeat,
+            self.options.storage_failure_policy,
+            self.options.replay_protection,
+            self.options.replay_window,
+            self.options.handshake_timeout,
             Role::Responder,
         )
         .await?;
@@ -2,34 +2,68 @@
 pub mod access_control;
 mod addresses;
 mod api;
+mod cipher_suite;
+mod compression;
 mod decryptor;
 mod encryptor;
 mod encryptor_worker;
 mod handshake;
+mod heartbeat;
 mod key_tracker;
 mod listener;
 mod local_info;
 mod nonce_tracker;
 mod options;
+mod pre_shared_key;
+#[cfg(feature = "std")]
+mod rate_limiter;
 mod registry;
+mod replay_protection;
 mod role;
+mod stats;
 /// List of trust policies to setup ABAC controls
 pub mod trust_policy;
 
 pub use access_control::*;
 pub(crate) use addresses::*;
 pub use api::*;
+pub use cipher_suite::*;
+pub use compression::*;
 pub(crate) use handshake::*;
+pub use heartbeat::{SecureChannelHeartbeat, MIN_HEARTBEAT_INTERVAL};
 pub(crate) use listener::*;
 pub use local_info::*;
 pub use options::*;
+pub use pre_shared_key::*;
 pub use registry::*;
+pub use replay_protection::*;
 pub(crate) use role::*;
+pub use stats::*;
 pub use trust_policy::*;
 
+use ockam_node::WorkerKind;
+
+/// Cluster of the workers of secure channels, stopped after the application workers and
+/// before the transports by `Context::shutdown_graceful`
+pub(crate) const CLUSTER_NAME: &str = "_internals.secure_channel";
+
+/// [`WorkerKind`] of the encryptors of secure channels
+pub const SECURE_CHANNEL_ENCRYPTOR_KIND: WorkerKind =
+    WorkerKind::from_static("secure-channel-encryptor");
+/// [`WorkerKind`] of the decryptors of secure channels, which perform the handshake as well
+pub const SECURE_CHANNEL_DECRYPTOR_KIND: WorkerKind =
+    WorkerKind::from_static("secure-channel-decryptor");
+/// [`WorkerKind`] of the listeners accepting secure channels
+pub const SECURE_CHANNEL_LISTENER_KIND: WorkerKind =
+    WorkerKind::from_static("secure-channel-listener");
+
 #[cfg(test)]
 mod tests {
+    use crate::secure_channel::encryptor::KEY_RENEWAL_INTERVAL;
     use crate::secure_channel::{decryptor::Decryptor, encryptor::Encryptor};
+    use crate::{
+        ReplayProtection, ReplayProtectionCounters, SecureChannelStats, MAX_REPLAY_WINDOW,
+    };
     use ockam_core::compat::rand::RngCore;
     use ockam_core::Result;
     use ockam_vault::{SoftwareVaultForSecureChannels, VaultForSecureChannels};
@@ -105,6 +139,56 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_replay_protection_counters() {
+        for replay_protection in [ReplayProtection::Tolerant, ReplayProtection::Strict] {
+            let counters = ReplayProtectionCounters::default();
+            let (mut encryptor, mut decryptor) =
+                create_encryptor_decryptor_with_replay_protection(replay_protection, &counters)
+                    .await
+                    .unwrap();
+
+            let mut frames = vec![];
+            for n in 0..6 {
+                frames.push(encryptor.encrypt(&[n]).await.unwrap());
+            }
+
+            // 0, 2, 1 (out of order), 1 (replay), 4, 3 (out of order), 4 (replay), 5, 0 (replay)
+            let mut delivered = vec![];
+            for index in [0, 2, 1, 1, 4, 3, 4, 5, 0] {
+                if let Ok(plaintext) = decryptor.decrypt(&frames[index]).await {
+                    delivered.push(plaintext[0]);
+                }
+            }
+
+            match replay_protection {
+                ReplayProtection::Tolerant => {
+                    assert_eq!(delivered, vec![0, 2, 1, 4, 3, 5]);
+                    assert_eq!(counters.out_of_order_accepted(), 2);
+                    assert_eq!(counters.out_of_order_dropped(), 0);
+                    assert_eq!(counters.replays_dropped(), 3);
+                }
+                ReplayProtection::Strict => {
+                    assert_eq!(delivered, vec![0, 2, 4, 5]);
+                    assert_eq!(counters.out_of_order_accepted(), 0);
+                    assert_eq!(counters.out_of_order_dropped(), 3);
+                    assert_eq!(counters.replays_dropped(), 2);
+                }
+            }
+            assert_eq!(counters.out_of_window_dropped(), 0);
+
+            // A frame too far behind the last one is dropped in both modes
+            for n in 6..6 + KEY_RENEWAL_INTERVAL {
+                decryptor
+                    .decrypt(&encryptor.encrypt(&[n as u8]).await.unwrap())
+                    .await
+                    .unwrap();
+            }
+            assert!(decryptor.decrypt(&frames[4]).await.is_err());
+            assert_eq!(counters.out_of_window_dropped(), 1);
+        }
+    }
+
     #[tokio::test]
     async fn test_attack_nonce() {
         let (mut encryptor, mut decryptor) = create_encryptor_decryptor().await.unwrap();
@@ -132,6 +216,17 @@ mod tests {
     }
 
     async fn create_encryptor_decryptor() -> Result<(Encryptor, Decryptor)> {
+        create_encryptor_decryptor_with_replay_protection(
+            ReplayProtection::Tolerant,
+            &ReplayProtectionCounters::default(),
+        )
+        .await
+    }
+
+    async fn create_encryptor_decryptor_with_replay_protection(
+        replay_protection: ReplayProtection,
+        replay_counters: &ReplayProtectionCounters,
+    ) -> Result<(Encryptor, Decryptor)> {
         let vault1 = SoftwareVaultForSecureChannels::create();
         let vault2 = SoftwareVaultForSecureChannels::create();
 
@@ -146,8 +241,15 @@ mod tests {
         let key_on_v2 = vault2.convert_secret_buffer_to_aead_key(key_on_v2).await?;
 
         Ok((
-            Encryptor::new(key_on_v1, 0, vault1),
-            Decryptor::new(key_on_v2, vault2),
+            Encryptor::new(key_on_v1, 0, vault1, SecureChannelStats::default()),
+            Decryptor::new(
+                key_on_v2,
+                vault2,
+                replay_protection,
+                MAX_REPLAY_WINDOW,
+                replay_counters.clone(),
+                SecureChannelStats::default(),
+            ),
         ))
     }
 }
@@ -1,5 +1,5 @@
 use crate::secure_channel::encryptor::KEY_RENEWAL_INTERVAL;
-use crate::IdentityError;
+use crate::{IdentityError, ReplayProtection, MAX_REPLAY_WINDOW};
 
 /// fails compilation if [`KEY_RENEWAL_INTERVAL`] + 1 is bigger than [`BitmapType::BITS`].
 ///
@@ -8,48 +8,98 @@ use crate::IdentityError;
 const _: [(); (KEY_RENEWAL_INTERVAL + 1 > BitmapType::BITS as u64) as usize] = [];
 type BitmapType = u64;
 
+/// Position of a received nonce relatively to the nonces received so far
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NonceStatus {
+    /// The nonce is greater than all the previous ones
+    InOrder,
+    /// The nonce is smaller than the last one but was not received yet
+    OutOfOrder,
+    /// The nonce was already received
+    Replayed,
+    /// The nonce is too far behind or ahead of the last one
+    OutOfWindow,
+}
+
 #[derive(Debug)]
 pub(crate) struct NonceTracker {
     nonce_bitmap: BitmapType,
     current_nonce: u64,
+    replay_protection: ReplayProtection,
+    replay_window: u64,
 }
 
 impl NonceTracker {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(replay_protection: ReplayProtection, replay_window: u64) -> Self {
         Self {
             nonce_bitmap: 0,
             current_nonce: 0,
+            replay_protection,
+            replay_window: replay_window.min(MAX_REPLAY_WINDOW),
         }
     }
 
-    /// Mark a nonce as received, reject all invalid nonce values
-    pub(crate) fn mark(&self, nonce: u64) -> ockam_core::Result<NonceTracker> {
-        let new_tracker = if nonce > self.current_nonce {
-            // normal case, we increase the nonce and move the window
-            let relative_shift: u64 = nonce - self.current_nonce;
-            if relative_shift > KEY_RENEWAL_INTERVAL {
-                return Err(IdentityError::InvalidNonce.into());
-            }
-            NonceTracker {
-                nonce_bitmap: self.nonce_bitmap.overflowing_shl(relative_shift as u32).0 | 1,
-                current_nonce: nonce,
+    /// Classify a nonce without marking it as received
+    pub(crate) fn status(&self, nonce: u64) -> NonceStatus {
+        if nonce > self.current_nonce {
+            if nonce - self.current_nonce > KEY_RENEWAL_INTERVAL {
+                NonceStatus::OutOfWindow
+            } else {
+                NonceStatus::InOrder
             }
         } else {
-            // first message or an out of order message
             let relative: u64 = self.current_nonce - nonce;
-            if relative > KEY_RENEWAL_INTERVAL {
-                return Err(IdentityError::InvalidNonce.into());
+            if relative > self.replay_window {
+                return NonceStatus::OutOfWindow;
             }
 
             #[allow(trivial_numeric_casts)]
             let bit = (1 as BitmapType).overflowing_shl(relative as u32).0;
             if self.nonce_bitmap & bit != 0 {
-                // we already processed this nonce
-                return Err(IdentityError::InvalidNonce.into());
+                NonceStatus::Replayed
+            } else if relative == 0 {
+                // first message
+                NonceStatus::InOrder
+            } else {
+                NonceStatus::OutOfOrder
+            }
+        }
+    }
+
+    /// Mark a nonce as received, reject all invalid nonce values
+    pub(crate) fn mark(&self, nonce: u64) -> ockam_core::Result<NonceTracker> {
+        let new_tracker = match self.status(nonce) {
+            // normal case, we increase the nonce and move the window
+            NonceStatus::InOrder if nonce > self.current_nonce => {
+                let relative_shift: u64 = nonce - self.current_nonce;
+                NonceTracker {
+                    nonce_bitmap: self.nonce_bitmap.overflowing_shl(relative_shift as u32).0 | 1,
+                    current_nonce: nonce,
+                    replay_protection: self.replay_protection,
+                    replay_window: self.replay_window,
+                }
             }
-            NonceTracker {
-                nonce_bitmap: self.nonce_bitmap | bit,
+            // first message
+            NonceStatus::InOrder => NonceTracker {
+                nonce_bitmap: self.nonce_bitmap | 1,
                 current_nonce: self.current_nonce,
+                replay_protection: self.replay_protection,
+                replay_window: self.replay_window,
+            },
+            // out of order message
+            NonceStatus::OutOfOrder if self.replay_protection == ReplayProtection::Tolerant => {
+                let relative: u64 = self.current_nonce - nonce;
+                #[allow(trivial_numeric_casts)]
+                let bit = (1 as BitmapType).overflowing_shl(relative as u32).0;
+                NonceTracker {
+                    nonce_bitmap: self.nonce_bitmap | bit,
+                    current_nonce: self.current_nonce,
+                    replay_protection: self.replay_protection,
+                    replay_window: self.replay_window,
+                }
+            }
+            NonceStatus::OutOfOrder | NonceStatus::Replayed | NonceStatus::OutOfWindow => {
+                return Err(IdentityError::InvalidNonce.into());
             }
         };
 
@@ -59,7 +109,7 @@ impl NonceTracker {
 
 #[test]
 pub fn check_nonce_tracker() {
-    let mut tracker = NonceTracker::new();
+    let mut tracker = NonceTracker::new(ReplayProtection::Tolerant, MAX_REPLAY_WINDOW);
     tracker = tracker.mark(0).unwrap();
     tracker = tracker.mark(1).unwrap();
     tracker.mark(0).unwrap_err();
@@ -81,3 +131,43 @@ pub fn check_nonce_tracker() {
         tracker = tracker.mark(n).unwrap();
     }
 }
+
+#[test]
+pub fn check_strict_nonce_tracker() {
+    let mut tracker = NonceTracker::new(ReplayProtection::Strict, MAX_REPLAY_WINDOW);
+    tracker = tracker.mark(0).unwrap();
+    tracker = tracker.mark(2).unwrap();
+    assert_eq!(tracker.status(1), NonceStatus::OutOfOrder);
+    tracker.mark(1).unwrap_err();
+    assert_eq!(tracker.status(2), NonceStatus::Replayed);
+    tracker.mark(2).unwrap_err();
+    assert_eq!(
+        tracker.status(KEY_RENEWAL_INTERVAL + 3),
+        NonceStatus::OutOfWindow
+    );
+    tracker = tracker.mark(3).unwrap();
+    assert_eq!(tracker.status(4), NonceStatus::InOrder);
+}
+
+#[test]
+pub fn check_nonce_tracker_replay_window() {
+    let mut tracker = NonceTracker::new(ReplayProtection::Tolerant, 2);
+    for n in 0..5 {
+        tracker = tracker.mark(n).unwrap();
+    }
+    assert_eq!(tracker.status(4), NonceStatus::Replayed);
+    assert_eq!(tracker.status(2), NonceStatus::Replayed);
+    assert_eq!(tracker.status(1), NonceStatus::OutOfWindow);
+
+    tracker = tracker.mark(7).unwrap();
+    assert_eq!(tracker.status(5), NonceStatus::OutOfOrder);
+    tracker = tracker.mark(5).unwrap();
+    assert_eq!(tracker.status(5), NonceStatus::Replayed);
+    assert_eq!(tracker.status(4), NonceStatus::OutOfWindow);
+
+    // Frames ahead of the last one are only limited by the key renewal interval
+    assert_eq!(
+        tracker.status(7 + KEY_RENEWAL_INTERVAL),
+        NonceStatus::InOrder
+    );
+}
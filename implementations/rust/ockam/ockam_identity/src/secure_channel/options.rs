@@ -1,11 +1,16 @@
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
 use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
-use ockam_core::{Address, OutgoingAccessControl, Result};
+use ockam_core::{Address, Error, OutgoingAccessControl, Result};
 
 use crate::models::CredentialAndPurposeKey;
 use crate::secure_channel::Addresses;
-use crate::{TrustContext, TrustEveryonePolicy, TrustPolicy};
+use crate::{
+    CipherSuite, CompressionAlgorithm, IdentityError, PreSharedKey, ReplayProtection,
+    SecureChannelHeartbeat, TrustContext, TrustEveryonePolicy, TrustPolicy, COMPRESSION_THRESHOLD,
+    MAX_REPLAY_WINDOW,
+};
 
 use core::fmt;
 use core::fmt::Formatter;
@@ -14,6 +19,20 @@ use core::time::Duration;
 /// This is the default timeout for creating a secure channel
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
 
+/// Behavior when the attributes of credentials presented during a Secure Channel handshake
+/// can't be persisted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StorageFailurePolicy {
+    /// Fail the handshake
+    #[default]
+    Fail,
+    /// Establish the Secure Channel without persisting the attributes.
+    /// See [`crate::SecureChannelRegistryEntry::attributes_persisted`]
+    BestEffort,
+    /// Retry the write the given number of times, then fail the handshake
+    Retry(u32),
+}
+
 /// Trust options for a Secure Channel
 pub struct SecureChannelOptions {
     pub(crate) flow_control_id: FlowControlId,
@@ -21,6 +40,14 @@ pub struct SecureChannelOptions {
     pub(crate) trust_context: Option<TrustContext>,
     pub(crate) credentials: Vec<CredentialAndPurposeKey>,
     pub(crate) timeout: Duration,
+    pub(crate) heartbeat: Option<SecureChannelHeartbeat>,
+    pub(crate) storage_failure_policy: StorageFailurePolicy,
+    pub(crate) pre_shared_key: Option<PreSharedKey>,
+    pub(crate) replay_protection: ReplayProtection,
+    pub(crate) replay_window: u64,
+    pub(crate) allowed_ciphers: Vec<CipherSuite>,
+    pub(crate) pq_hybrid: bool,
+    pub(crate) compression: Option<CompressionAlgorithm>,
 }
 
 impl fmt::Debug for SecureChannelOptions {
@@ -43,6 +70,14 @@ impl SecureChannelOptions {
             trust_context: None,
             credentials: vec![],
             timeout: DEFAULT_TIMEOUT,
+            heartbeat: None,
+            storage_failure_policy: StorageFailurePolicy::default(),
+            pre_shared_key: None,
+            replay_protection: ReplayProtection::default(),
+            replay_window: MAX_REPLAY_WINDOW,
+            allowed_ciphers: vec![CipherSuite::supported()],
+            pq_hybrid: false,
+            compression: None,
         }
     }
 
@@ -76,13 +111,110 @@ impl SecureChannelOptions {
         self
     }
 
-    /// Freshly generated [`FlowControlId`]
+    /// Periodically send a heartbeat to the other side of the channel.
+    /// The channel is stopped and marked as dead in the [`crate::SecureChannelRegistry`]
+    /// after `max_missed` consecutive heartbeats were not acknowledged.
+    /// See [`SecureChannelHeartbeat::new`] for how zero values are handled
+    pub fn with_heartbeat(mut self, interval: Duration, max_missed: u32) -> Self {
+        self.heartbeat = Some(SecureChannelHeartbeat::new(interval, max_missed));
+        self
+    }
+
+    /// Set the behavior when the attributes of the credentials presented by the other side
+    /// can't be persisted. Defaults to [`StorageFailurePolicy::Fail`]
+    pub fn with_storage_failure_policy(mut self, policy: StorageFailurePolicy) -> Self {
+        self.storage_failure_policy = policy;
+        self
+    }
+
+    /// Derive the channel keys from a key shared out of band with the listener, instead of
+    /// running the default key exchange.
+    ///
+    /// This mode is meant for devices which can't perform asymmetric cryptography and is less
+    /// flexible than the default one: the other side's [`crate::Identifier`] is only
+    /// authenticated by the possession of the same key, no credentials are exchanged, and the
+    /// channel keys are only as safe as the pre-shared key. Both sides must use this mode.
+    pub fn with_pre_shared_key(mut self, pre_shared_key: PreSharedKey) -> Self {
+        self.pre_shared_key = Some(pre_shared_key);
+        self
+    }
+
+    /// Set how the frames arriving out of order are treated. Defaults to
+    /// [`ReplayProtection::Tolerant`]. The number of frames which arrived out of order
+    /// or were dropped as replays is reported by
+    /// [`crate::SecureChannelRegistryEntry::replay_counters`]
+    pub fn with_replay_protection(mut self, replay_protection: ReplayProtection) -> Self {
+        self.replay_protection = replay_protection;
+        self
+    }
+
+    /// Set how many nonces behind the last received one are still accepted, see
+    /// [`ReplayProtection`]. Frames older than the window are dropped, even if they were
+    /// never received. Defaults to, and is capped at, [`MAX_REPLAY_WINDOW`]
+    pub fn with_replay_window(mut self, size: u64) -> Self {
+        self.replay_window = size.min(MAX_REPLAY_WINDOW);
+        self
+    }
+
+    /// Only allow the given AEAD cipher suites, e.g. to comply with a FIPS requirement.
+    /// The handshake fails with [`IdentityError::SecureChannelNoCommonCipher`] if no suite
+    /// is allowed by both sides. Defaults to [`CipherSuite::supported`]
+    pub fn with_allowed_ciphers(mut self, ciphers: &[CipherSuite]) -> Self {
+        self.allowed_ciphers = ciphers.to_vec();
+        self
+    }
+
+    /// Mix an ML-KEM shared secret in the channel keys, in addition to the X25519 ones, so that
+    /// recorded traffic can't be decrypted by an attacker who later breaks X25519.
+    ///
+    /// The listener must enable this mode as well: when only one side enables it, the
+    /// listener rejects the handshake with [`IdentityError::SecureChannelPqHybridMismatch`]
+    /// and the channel creation fails after its timeout. This mode can't be combined with
+    /// [`SecureChannelOptions::with_pre_shared_key`]. See [`crate::SecureChannelStats::pq_hybrid`]
+    #[cfg(feature = "pq_hybrid")]
+    pub fn with_pq_hybrid(mut self) -> Self {
+        self.pq_hybrid = true;
+        self
+    }
+
+    /// Compress the messages sent on the channel with the given algorithm before they are
+    /// encrypted, and decompress the received ones. The messages are only compressed if the
+    /// listener selected the same algorithm, see [`crate::SecureChannelStats::compression`].
+    /// Messages smaller than [`COMPRESSION_THRESHOLD`] or which don't shrink are sent as is.
+    ///
+    /// Since the size of a compressed message depends on its content, compression makes the
+    /// channel vulnerable to CRIME/BREACH-style attacks: an attacker who can observe the
+    /// traffic and inject data in the messages may guess the secrets sent along with it,
+    /// for example tokens or credentials, from the sizes of the encrypted messages.
+    /// Only enable compression when the messages never mix secrets with data which can be
+    /// influenced by someone else.
+    /// This has no effect with [`SecureChannelOptions::with_pre_shared_key`]
+    pub fn with_compression(mut self, algorithm: CompressionAlgorithm) -> Self {
+        self.compression = Some(algorithm);
+        self
+    }
+
+    /// Mark this Secure Channel Decryptor as a Producer with the given [`FlowControlId`]
+    /// instead of a random one, e.g. a child generated with
+    /// [`FlowControls::generate_child_flow_control_id`] so that the channel is revoked along
+    /// with its parent
+    pub fn with_flow_control_id(mut self, flow_control_id: FlowControlId) -> Self {
+        self.flow_control_id = flow_control_id;
+        self
+    }
+
+    /// Freshly generated [`FlowControlId`], or the one given to
+    /// [`SecureChannelOptions::with_flow_control_id`]
     pub fn producer_flow_control_id(&self) -> FlowControlId {
         self.flow_control_id.clone()
     }
 }
 
 impl SecureChannelOptions {
+    pub(crate) fn check_key_exchange(&self) -> Result<()> {
+        check_key_exchange(self.pq_hybrid, &self.pre_shared_key)
+    }
+
     pub(crate) fn setup_flow_control(
         &self,
         flow_controls: &FlowControls,
@@ -126,10 +258,22 @@ impl SecureChannelOptions {
 /// Trust options for a Secure Channel Listener
 pub struct SecureChannelListenerOptions {
     pub(crate) consumer: Vec<FlowControlId>,
+    pub(crate) required_transport: Option<FlowControlId>,
     pub(crate) flow_control_id: FlowControlId,
     pub(crate) trust_policy: Arc<dyn TrustPolicy>,
     pub(crate) trust_context: Option<TrustContext>,
     pub(crate) credentials: Vec<CredentialAndPurposeKey>,
+    pub(crate) heartbeat: Option<SecureChannelHeartbeat>,
+    pub(crate) storage_failure_policy: StorageFailurePolicy,
+    pub(crate) pre_shared_key: Option<PreSharedKey>,
+    pub(crate) replay_protection: ReplayProtection,
+    pub(crate) replay_window: u64,
+    pub(crate) allowed_ciphers: Vec<CipherSuite>,
+    pub(crate) pq_hybrid: bool,
+    pub(crate) compression: Option<CompressionAlgorithm>,
+    #[cfg(feature = "std")]
+    pub(crate) rate_limit: Option<(u32, Duration)>,
+    pub(crate) handshake_timeout: Option<Duration>,
 }
 
 impl fmt::Debug for SecureChannelListenerOptions {
@@ -146,10 +290,22 @@ impl SecureChannelListenerOptions {
     pub fn new() -> Self {
         Self {
             consumer: vec![],
+            required_transport: None,
             flow_control_id: FlowControls::generate_flow_control_id(),
             trust_policy: Arc::new(TrustEveryonePolicy),
             trust_context: None,
             credentials: vec![],
+            heartbeat: None,
+            storage_failure_policy: StorageFailurePolicy::default(),
+            pre_shared_key: None,
+            replay_protection: ReplayProtection::default(),
+            replay_window: MAX_REPLAY_WINDOW,
+            allowed_ciphers: vec![CipherSuite::supported()],
+            pq_hybrid: false,
+            compression: None,
+            #[cfg(feature = "std")]
+            rate_limit: None,
+            handshake_timeout: None,
         }
     }
 
@@ -162,6 +318,22 @@ impl SecureChannelListenerOptions {
         self
     }
 
+    /// Only accept handshakes arriving through a transport connection marked with the given
+    /// [`FlowControlId`], for example a TCP connection, or any connection accepted by a TCP
+    /// listener when using the listener's [`FlowControlId`].
+    /// Other handshakes, including the ones sent by local workers, are rejected with
+    /// [`IdentityError::SecureChannelUnauthorizedTransport`], which is sent back to their
+    /// initiator.
+    /// The Secure Channel Listener is also marked as a Consumer for that [`FlowControlId`]
+    pub fn require_transport(mut self, id: &FlowControlId) -> Self {
+        if !self.consumer.contains(id) {
+            self.consumer.push(id.clone());
+        }
+        self.required_transport = Some(id.clone());
+
+        self
+    }
+
     /// Adds provided credentials
     pub fn with_credentials(mut self, credentials: Vec<CredentialAndPurposeKey>) -> Self {
         self.credentials.extend(credentials);
@@ -186,6 +358,110 @@ impl SecureChannelListenerOptions {
         self
     }
 
+    /// Periodically send a heartbeat to the other side of spawned channels.
+    /// A channel is stopped and marked as dead in the [`crate::SecureChannelRegistry`]
+    /// after `max_missed` consecutive heartbeats were not acknowledged.
+    /// See [`SecureChannelHeartbeat::new`] for how zero values are handled
+    pub fn with_heartbeat(mut self, interval: Duration, max_missed: u32) -> Self {
+        self.heartbeat = Some(SecureChannelHeartbeat::new(interval, max_missed));
+        self
+    }
+
+    /// Set the behavior when the attributes of the credentials presented by the other side
+    /// can't be persisted. Defaults to [`StorageFailurePolicy::Fail`]
+    pub fn with_storage_failure_policy(mut self, policy: StorageFailurePolicy) -> Self {
+        self.storage_failure_policy = policy;
+        self
+    }
+
+    /// Derive the channel keys from a key shared out of band with the initiator, instead of
+    /// running the default key exchange.
+    ///
+    /// This mode is meant for devices which can't perform asymmetric cryptography and is less
+    /// flexible than the default one: the other side's [`crate::Identifier`] is only
+    /// authenticated by the possession of the same key, no credentials are exchanged, and the
+    /// channel keys are only as safe as the pre-shared key. Both sides must use this mode.
+    pub fn with_pre_shared_key(mut self, pre_shared_key: PreSharedKey) -> Self {
+        self.pre_shared_key = Some(pre_shared_key);
+        self
+    }
+
+    /// Set how the frames arriving out of order are treated. Defaults to
+    /// [`ReplayProtection::Tolerant`]. The number of frames which arrived out of order
+    /// or were dropped as replays is reported by
+    /// [`crate::SecureChannelRegistryEntry::replay_counters`]
+    pub fn with_replay_protection(mut self, replay_protection: ReplayProtection) -> Self {
+        self.replay_protection = replay_protection;
+        self
+    }
+
+    /// Set how many nonces behind the last received one are still accepted, see
+    /// [`ReplayProtection`]. Frames older than the window are dropped, even if they were
+    /// never received. Defaults to, and is capped at, [`MAX_REPLAY_WINDOW`]
+    pub fn with_replay_window(mut self, size: u64) -> Self {
+        self.replay_window = size.min(MAX_REPLAY_WINDOW);
+        self
+    }
+
+    /// Only allow the given AEAD cipher suites, e.g. to comply with a FIPS requirement.
+    /// The handshake fails with [`IdentityError::SecureChannelNoCommonCipher`] if no suite
+    /// is allowed by both sides. Defaults to [`CipherSuite::supported`]
+    pub fn with_allowed_ciphers(mut self, ciphers: &[CipherSuite]) -> Self {
+        self.allowed_ciphers = ciphers.to_vec();
+        self
+    }
+
+    /// Mix an ML-KEM shared secret in the keys of spawned channels, in addition to the X25519
+    /// ones, so that recorded traffic can't be decrypted by an attacker who later breaks X25519.
+    ///
+    /// Initiators must enable this mode as well: when only one side enables it, the handshake
+    /// is rejected with [`IdentityError::SecureChannelPqHybridMismatch`]. This mode can't be
+    /// combined with [`SecureChannelListenerOptions::with_pre_shared_key`].
+    /// See [`crate::SecureChannelStats::pq_hybrid`]
+    #[cfg(feature = "pq_hybrid")]
+    pub fn with_pq_hybrid(mut self) -> Self {
+        self.pq_hybrid = true;
+        self
+    }
+
+    /// Compress the messages sent on spawned channels with the given algorithm before they are
+    /// encrypted, and decompress the received ones. The messages are only compressed if the
+    /// initiator selected the same algorithm, see [`crate::SecureChannelStats::compression`].
+    /// Messages smaller than [`COMPRESSION_THRESHOLD`] or which don't shrink are sent as is.
+    ///
+    /// Since the size of a compressed message depends on its content, compression makes the
+    /// channel vulnerable to CRIME/BREACH-style attacks: an attacker who can observe the
+    /// traffic and inject data in the messages may guess the secrets sent along with it,
+    /// for example tokens or credentials, from the sizes of the encrypted messages.
+    /// Only enable compression when the messages never mix secrets with data which can be
+    /// influenced by someone else.
+    /// This has no effect with [`SecureChannelListenerOptions::with_pre_shared_key`]
+    pub fn with_compression(mut self, algorithm: CompressionAlgorithm) -> Self {
+        self.compression = Some(algorithm);
+        self
+    }
+
+    /// Accept at most `max_handshakes` handshakes from the same source during any `per`
+    /// period. Excess handshakes are rejected with [`IdentityError::SecureChannelRateLimited`]
+    /// before any worker is created for them.
+    ///
+    /// The source of a handshake is the worker which delivered it to the listener: a transport
+    /// connection, a Secure Channel when channels are tunneled, or the local initiator.
+    /// Initiators of rejected handshakes don't get a response and time out
+    #[cfg(feature = "std")]
+    pub fn with_rate_limit(mut self, max_handshakes: u32, per: Duration) -> Self {
+        self.rate_limit = Some((max_handshakes, per));
+        self
+    }
+
+    /// Abort the handshakes which are not completed `timeout` after their first message was
+    /// received, and stop the workers created for them. By default, a handshake whose initiator
+    /// stops responding keeps its workers until the node is stopped
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = Some(timeout);
+        self
+    }
+
     /// Freshly generated [`FlowControlId`]
     pub fn spawner_flow_control_id(&self) -> FlowControlId {
         self.flow_control_id.clone()
@@ -193,6 +469,10 @@ impl SecureChannelListenerOptions {
 }
 
 impl SecureChannelListenerOptions {
+    pub(crate) fn check_key_exchange(&self) -> Result<()> {
+        check_key_exchange(self.pq_hybrid, &self.pre_shared_key)
+    }
+
     pub(crate) fn setup_flow_control_for_listener(
         &self,
         flow_controls: &FlowControls,
@@ -205,6 +485,33 @@ impl SecureChannelListenerOptions {
         flow_controls.add_spawner(address.clone(), &self.flow_control_id);
     }
 
+    /// Check that a handshake message was received from the producer of the required
+    /// transport, if any
+    pub(crate) fn check_transport(
+        &self,
+        flow_controls: &FlowControls,
+        src_addr: &Address,
+    ) -> Result<()> {
+        let required = match &self.required_transport {
+            Some(required) => required,
+            None => return Ok(()),
+        };
+
+        let authorized = flow_controls
+            .get_flow_control_with_producer(src_addr)
+            .map(|producer| {
+                producer.flow_control_id() == required
+                    || producer.spawner_flow_control_id().as_ref() == Some(required)
+            })
+            .unwrap_or(false);
+
+        if authorized {
+            Ok(())
+        } else {
+            Err(IdentityError::SecureChannelUnauthorizedTransport.into())
+        }
+    }
+
     pub(crate) fn setup_flow_control_for_channel(
         &self,
         flow_controls: &FlowControls,
@@ -252,3 +559,15 @@ impl SecureChannelListenerOptions {
         }
     }
 }
+
+/// The hybrid key exchange extends the default handshake, it doesn't apply to pre-shared keys
+fn check_key_exchange(pq_hybrid: bool, pre_shared_key: &Option<PreSharedKey>) -> Result<()> {
+    if pq_hybrid && pre_shared_key.is_some() {
+        return Err(Error::new(
+            Origin::Channel,
+            Kind::Misuse,
+            "the hybrid key exchange can't be used with a pre-shared key",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Length of a [`PreSharedKey`]
+pub const PRE_SHARED_KEY_LENGTH: usize = 32;
+
+/// Symmetric key shared out of band by both sides of a Secure Channel
+///
+/// See [`crate::SecureChannelOptions::with_pre_shared_key`]. The key is zeroized when dropped
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct PreSharedKey([u8; PRE_SHARED_KEY_LENGTH]);
+
+impl PreSharedKey {
+    /// Constructor
+    pub fn new(key: [u8; PRE_SHARED_KEY_LENGTH]) -> Self {
+        Self(key)
+    }
+
+    pub(crate) fn value(&self) -> &[u8; PRE_SHARED_KEY_LENGTH] {
+        &self.0
+    }
+}
+
+impl Debug for PreSharedKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Never print the key itself
+        f.write_str("PreSharedKey(..)")
+    }
+}
@@ -0,0 +1,77 @@
+use core::time::Duration;
+use ockam_core::compat::collections::{BTreeMap, VecDeque};
+use ockam_core::{Address, Result};
+use std::time::Instant;
+
+use crate::IdentityError;
+
+/// Limits the number of handshakes a Secure Channel Listener accepts from each source,
+/// see [`crate::SecureChannelListenerOptions::with_rate_limit`]
+#[derive(Clone, Debug)]
+pub(crate) struct HandshakeRateLimiter {
+    max_handshakes: u32,
+    per: Duration,
+    /// Times of the handshakes accepted from each source during the last `per` period
+    handshakes: BTreeMap<Address, VecDeque<Instant>>,
+}
+
+impl HandshakeRateLimiter {
+    pub(crate) fn new(max_handshakes: u32, per: Duration) -> Self {
+        Self {
+            max_handshakes,
+            per,
+            handshakes: BTreeMap::new(),
+        }
+    }
+
+    /// Record a handshake initiated by `source`, or reject it if that source already
+    /// initiated the maximum number of handshakes during the last period
+    pub(crate) fn check(&mut self, source: &Address) -> Result<()> {
+        self.check_at(source, Instant::now())
+    }
+
+    fn check_at(&mut self, source: &Address, now: Instant) -> Result<()> {
+        let per = self.per;
+        let is_recent = |t: &Instant| now.saturating_duration_since(*t) < per;
+
+        // Forget the sources which didn't initiate a handshake recently
+        self.handshakes
+            .retain(|_, handshakes| handshakes.back().map(is_recent).unwrap_or(false));
+
+        let handshakes = self.handshakes.entry(source.clone()).or_default();
+        while handshakes.front().map(|t| !is_recent(t)).unwrap_or(false) {
+            handshakes.pop_front();
+        }
+
+        if handshakes.len() >= self.max_handshakes as usize {
+            return Err(IdentityError::SecureChannelRateLimited.into());
+        }
+        handshakes.push_back(now);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_per_source() {
+        let mut limiter = HandshakeRateLimiter::new(2, Duration::from_secs(10));
+        let start = Instant::now();
+        let alice: Address = "alice".into();
+        let bob: Address = "bob".into();
+
+        assert!(limiter.check_at(&alice, start).is_ok());
+        assert!(limiter.check_at(&alice, start).is_ok());
+        assert!(limiter.check_at(&alice, start).is_err());
+        assert!(limiter.check_at(&bob, start).is_ok());
+
+        // Rejected handshakes don't extend the period
+        let later = start + Duration::from_secs(10);
+        assert!(limiter.check_at(&alice, later).is_ok());
+        assert!(limiter.check_at(&alice, later).is_ok());
+        assert!(limiter.check_at(&alice, later).is_err());
+    }
+}
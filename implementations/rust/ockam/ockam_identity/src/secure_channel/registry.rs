@@ -1,10 +1,12 @@
-use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::collections::{BTreeMap, BTreeSet, VecDeque};
 use ockam_core::compat::sync::{Arc, RwLock};
 use ockam_core::compat::vec::Vec;
-use ockam_core::{Address, Result};
+use ockam_core::{Address, Result, Route};
+use ockam_node::Context;
 
 use crate::models::Identifier;
-use crate::IdentityError;
+use crate::secure_channel::heartbeat::HeartbeatSignal;
+use crate::{IdentityError, ReplayProtectionCounters, SecureChannelStats};
 
 /// Known information about particular SecureChannel
 #[derive(Clone, Debug)]
@@ -17,6 +19,13 @@ pub struct SecureChannelRegistryEntry {
     my_id: Identifier,
     their_id: Identifier,
     their_decryptor_address: Address,
+    route: Route,
+    attributes_persisted: bool,
+    replay_counters: ReplayProtectionCounters,
+    stats: SecureChannelStats,
+    listener: Option<Address>,
+    outer_channel: Option<Address>,
+    depth: usize,
 }
 
 impl SecureChannelRegistryEntry {
@@ -31,6 +40,11 @@ impl SecureChannelRegistryEntry {
         my_id: Identifier,
         their_id: Identifier,
         their_decryptor_address: Address,
+        route: Route,
+        attributes_persisted: bool,
+        replay_counters: ReplayProtectionCounters,
+        stats: SecureChannelStats,
+        listener: Option<Address>,
     ) -> Self {
         Self {
             encryptor_messaging_address,
@@ -41,6 +55,13 @@ impl SecureChannelRegistryEntry {
             my_id,
             their_id,
             their_decryptor_address,
+            route,
+            attributes_persisted,
+            replay_counters,
+            stats,
+            listener,
+            outer_channel: None,
+            depth: 0,
         }
     }
 
@@ -83,6 +104,104 @@ impl SecureChannelRegistryEntry {
     pub fn their_decryptor_address(&self) -> Address {
         self.their_decryptor_address.clone()
     }
+
+    /// Route used by our `Encryptor` to reach their `Decryptor`, as resolved when the channel
+    /// was created. It includes the hops added on the way, for instance by transports, and
+    /// ends with [`Self::their_decryptor_address`]
+    pub fn route(&self) -> &Route {
+        &self.route
+    }
+
+    /// False if the attributes of the credentials presented by the other side couldn't be
+    /// persisted with [`crate::StorageFailurePolicy::BestEffort`]
+    pub fn attributes_persisted(&self) -> bool {
+        self.attributes_persisted
+    }
+
+    /// Live counters of the frames which arrived out of order or were dropped as replays
+    /// on our side of the channel
+    pub fn replay_counters(&self) -> &ReplayProtectionCounters {
+        &self.replay_counters
+    }
+
+    /// Live statistics of our side of the channel
+    pub fn stats(&self) -> &SecureChannelStats {
+        &self.stats
+    }
+
+    /// Address of the Secure Channel listener which accepted this channel.
+    /// None if we initiated the channel
+    pub fn listener(&self) -> Option<&Address> {
+        self.listener.as_ref()
+    }
+
+    /// Encryptor address of the Secure Channel this channel is carried over, if its route
+    /// starts with the encryptor of another channel of this node
+    pub fn outer_channel(&self) -> Option<&Address> {
+        self.outer_channel.as_ref()
+    }
+
+    /// Number of Secure Channels this channel is nested in. 0 if it isn't carried over
+    /// another Secure Channel
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+/// Reason why a Secure Channel was shut down by our side
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SecureChannelCloseReason {
+    /// The other side stopped answering heartbeats
+    HeartbeatTimeout,
+    /// The other side doesn't pass a [`crate::ReevaluatingTrustPolicy`] anymore
+    AuthorizationRevoked,
+}
+
+/// Maximum number of closed channels kept by the [`SecureChannelRegistry`], the oldest
+/// ones are forgotten first
+pub const MAX_CLOSED_CHANNELS: usize = 1024;
+
+/// Channels that were shut down by our side, in the order they were closed
+#[derive(Default)]
+struct ClosedChannels {
+    channels: BTreeMap<Address, (SecureChannelRegistryEntry, SecureChannelCloseReason)>,
+    order: VecDeque<Address>,
+}
+
+impl ClosedChannels {
+    fn insert(
+        &mut self,
+        encryptor_address: Address,
+        entry: SecureChannelRegistryEntry,
+        reason: SecureChannelCloseReason,
+    ) {
+        if self
+            .channels
+            .insert(encryptor_address.clone(), (entry, reason))
+            .is_some()
+        {
+            self.order.retain(|a| a != &encryptor_address);
+        }
+        self.order.push_back(encryptor_address);
+
+        while self.order.len() > MAX_CLOSED_CHANNELS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.channels.remove(&oldest);
+            }
+        }
+    }
+
+    fn remove(
+        &mut self,
+        encryptor_address: &Address,
+    ) -> Option<(SecureChannelRegistryEntry, SecureChannelCloseReason)> {
+        let removed = self.channels.remove(encryptor_address);
+        if removed.is_some() {
+            self.order.retain(|a| a != encryptor_address);
+        }
+        removed
+    }
 }
 
 /// Registry of all known Secure Channels
@@ -90,6 +209,13 @@ impl SecureChannelRegistryEntry {
 pub struct SecureChannelRegistry {
     // Encryptor address is used as a key
     registry: Arc<RwLock<BTreeMap<Address, SecureChannelRegistryEntry>>>,
+    // Channels that were shut down by our side, along with the reason
+    closed_channels: Arc<RwLock<ClosedChannels>>,
+    // Addresses of the running Secure Channel listeners
+    listeners: Arc<RwLock<BTreeSet<Address>>>,
+    // Contexts allowed to ask the encryptor of a channel with a `ReevaluatingTrustPolicy`,
+    // at its internal address, to check its trust policy right away
+    trust_reevaluation_triggers: Arc<RwLock<BTreeMap<Address, (Arc<Context>, Address)>>>,
 }
 
 impl SecureChannelRegistry {
@@ -97,18 +223,27 @@ impl SecureChannelRegistry {
     pub fn new() -> Self {
         Self {
             registry: Default::default(),
+            closed_channels: Default::default(),
+            listeners: Default::default(),
+            trust_reevaluation_triggers: Default::default(),
         }
     }
 }
 
 impl SecureChannelRegistry {
     /// Register new SecureChannel in that registry
-    pub fn register_channel(&self, info: SecureChannelRegistryEntry) -> Result<()> {
-        let res = self
-            .registry
-            .write()
-            .unwrap()
-            .insert(info.encryptor_messaging_address.clone(), info);
+    ///
+    /// If the route of the channel starts with the encryptor of a registered channel, the new
+    /// channel is recorded as an inner channel of that one
+    pub fn register_channel(&self, mut info: SecureChannelRegistryEntry) -> Result<()> {
+        let mut registry = self.registry.write().unwrap();
+
+        if let Some(outer) = info.route.iter().next().and_then(|next| registry.get(next)) {
+            info.outer_channel = Some(outer.encryptor_messaging_address.clone());
+            info.depth = outer.depth + 1;
+        }
+
+        let res = registry.insert(info.encryptor_messaging_address.clone(), info);
 
         if res.is_some() {
             return Err(IdentityError::DuplicateSecureChannel.into());
@@ -122,6 +257,10 @@ impl SecureChannelRegistry {
         &self,
         encryptor_address: &Address,
     ) -> Option<SecureChannelRegistryEntry> {
+        self.trust_reevaluation_triggers
+            .write()
+            .unwrap()
+            .remove(encryptor_address);
         self.registry.write().unwrap().remove(encryptor_address)
     }
 
@@ -142,6 +281,21 @@ impl SecureChannelRegistry {
             .cloned()
     }
 
+    /// Get list of the SecureChannels carried over the SecureChannel with given encryptor
+    /// messaging address. Only the channels directly nested in that channel are returned
+    pub fn get_inner_channels(
+        &self,
+        encryptor_address: &Address,
+    ) -> Vec<SecureChannelRegistryEntry> {
+        self.registry
+            .read()
+            .unwrap()
+            .values()
+            .filter(|entry| entry.outer_channel.as_ref() == Some(encryptor_address))
+            .cloned()
+            .collect()
+    }
+
     /// Get SecureChannel with given decryptor messaging address
     pub fn get_channel_by_decryptor_address(
         &self,
@@ -154,4 +308,210 @@ impl SecureChannelRegistry {
             .find(|(_, entry)| entry.decryptor_messaging_address == *decryptor_address)
             .map(|(_, entry)| entry.clone())
     }
+
+    /// Move a SecureChannel to the list of closed channels. At most [`MAX_CLOSED_CHANNELS`]
+    /// are kept, the oldest one is forgotten when that limit is exceeded
+    pub(crate) fn mark_channel_closed(
+        &self,
+        encryptor_address: &Address,
+        reason: SecureChannelCloseReason,
+    ) {
+        if let Some(entry) = self.unregister_channel(encryptor_address) {
+            self.closed_channels
+                .write()
+                .unwrap()
+                .insert(encryptor_address.clone(), entry, reason);
+        }
+    }
+
+    /// Reason why the SecureChannel with given encryptor messaging address was shut down by
+    /// our side. None if it's still open, if it was stopped explicitly, or if it was forgotten
+    pub fn close_reason(&self, encryptor_address: &Address) -> Option<SecureChannelCloseReason> {
+        self.closed_channels
+            .read()
+            .unwrap()
+            .channels
+            .get(encryptor_address)
+            .map(|(_, reason)| *reason)
+    }
+
+    /// Return true if the SecureChannel with given encryptor messaging address was shut down
+    /// because the other side stopped answering heartbeats
+    pub fn is_channel_dead(&self, encryptor_address: &Address) -> bool {
+        self.close_reason(encryptor_address) == Some(SecureChannelCloseReason::HeartbeatTimeout)
+    }
+
+    /// Get list of all SecureChannels that were shut down because the other side
+    /// stopped answering heartbeats
+    pub fn get_dead_channel_list(&self) -> Vec<SecureChannelRegistryEntry> {
+        self.get_closed_channel_list(SecureChannelCloseReason::HeartbeatTimeout)
+    }
+
+    /// Get list of all SecureChannels that were shut down for the given reason
+    pub fn get_closed_channel_list(
+        &self,
+        reason: SecureChannelCloseReason,
+    ) -> Vec<SecureChannelRegistryEntry> {
+        self.closed_channels
+            .read()
+            .unwrap()
+            .channels
+            .values()
+            .filter(|(_, r)| *r == reason)
+            .map(|(entry, _)| entry.clone())
+            .collect()
+    }
+
+    /// Remove a SecureChannel which was shut down because the other side stopped answering
+    /// heartbeats from the registry, e.g. once it has been re-established, and return removed
+    /// `SecureChannelRegistryEntry`. Channels closed for another reason are kept
+    pub fn forget_dead_channel(
+        &self,
+        encryptor_address: &Address,
+    ) -> Option<SecureChannelRegistryEntry> {
+        let mut closed_channels = self.closed_channels.write().unwrap();
+        match closed_channels.channels.get(encryptor_address) {
+            Some((_, SecureChannelCloseReason::HeartbeatTimeout)) => closed_channels
+                .remove(encryptor_address)
+                .map(|(entry, _)| entry),
+            _ => None,
+        }
+    }
+
+    /// Remove a closed SecureChannel from the registry, whatever the reason it was shut down,
+    /// and return removed `SecureChannelRegistryEntry`
+    pub fn forget_closed_channel(
+        &self,
+        encryptor_address: &Address,
+    ) -> Option<SecureChannelRegistryEntry> {
+        self.closed_channels
+            .write()
+            .unwrap()
+            .remove(encryptor_address)
+            .map(|(entry, _)| entry)
+    }
+
+    /// Register the context allowed to trigger a check of the trust policy of a channel, and
+    /// the internal address of its encryptor
+    pub(crate) fn register_trust_reevaluation_trigger(
+        &self,
+        encryptor_address: &Address,
+        trigger: Context,
+        encryptor_internal_address: Address,
+    ) {
+        self.trust_reevaluation_triggers.write().unwrap().insert(
+            encryptor_address.clone(),
+            (Arc::new(trigger), encryptor_internal_address),
+        );
+    }
+
+    /// Ask the encryptor of a channel to check its trust policy right away.
+    /// Fails with [`IdentityError::SecureChannelNotFound`] if the channel is not open or
+    /// doesn't re-evaluate its trust policy
+    pub(crate) async fn trigger_trust_reevaluation(
+        &self,
+        encryptor_address: &Address,
+    ) -> Result<()> {
+        let trigger = self
+            .trust_reevaluation_triggers
+            .read()
+            .unwrap()
+            .get(encryptor_address)
+            .cloned();
+        match trigger {
+            Some((ctx, encryptor_internal_address)) => {
+                ctx.send(encryptor_internal_address, HeartbeatSignal::ReevaluateTrust)
+                    .await
+            }
+            None => Err(IdentityError::SecureChannelNotFound.into()),
+        }
+    }
+
+    /// Register a Secure Channel listener. Fails if a listener already has that address
+    pub(crate) fn register_listener(&self, address: &Address) -> Result<()> {
+        if !self.listeners.write().unwrap().insert(address.clone()) {
+            return Err(IdentityError::DuplicateSecureChannelListener.into());
+        }
+
+        Ok(())
+    }
+
+    /// Unregister a stopped Secure Channel listener
+    pub(crate) fn unregister_listener(&self, address: &Address) {
+        self.listeners.write().unwrap().remove(address);
+    }
+
+    /// Get list of the addresses of all running Secure Channel listeners
+    pub fn get_listener_list(&self) -> Vec<Address> {
+        self.listeners.read().unwrap().iter().cloned().collect()
+    }
+
+    /// Get list of the SecureChannels accepted by the listener with given address
+    pub fn get_channels_by_listener(&self, listener: &Address) -> Vec<SecureChannelRegistryEntry> {
+        self.registry
+            .read()
+            .unwrap()
+            .values()
+            .filter(|entry| entry.listener.as_ref() == Some(listener))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::IDENTIFIER_LEN;
+    use ockam_core::route;
+
+    fn entry(encryptor_address: &Address) -> SecureChannelRegistryEntry {
+        let identifier = Identifier::try_from([0u8; IDENTIFIER_LEN].as_slice()).unwrap();
+        SecureChannelRegistryEntry::new(
+            encryptor_address.clone(),
+            Address::random_local(),
+            Address::random_local(),
+            Address::random_local(),
+            true,
+            identifier.clone(),
+            identifier,
+            Address::random_local(),
+            route![],
+            false,
+            Default::default(),
+            Default::default(),
+            None,
+        )
+    }
+
+    fn close(registry: &SecureChannelRegistry, reason: SecureChannelCloseReason) -> Address {
+        let encryptor_address = Address::random_local();
+        registry
+            .register_channel(entry(&encryptor_address))
+            .unwrap();
+        registry.mark_channel_closed(&encryptor_address, reason);
+        encryptor_address
+    }
+
+    #[test]
+    fn test_closed_channels_are_capped() {
+        let registry = SecureChannelRegistry::new();
+
+        let oldest = close(&registry, SecureChannelCloseReason::HeartbeatTimeout);
+        let second = close(&registry, SecureChannelCloseReason::AuthorizationRevoked);
+        for _ in 2..MAX_CLOSED_CHANNELS {
+            close(&registry, SecureChannelCloseReason::HeartbeatTimeout);
+        }
+        assert!(registry.is_channel_dead(&oldest));
+
+        // Forgetting a channel frees its slot
+        assert!(registry.forget_closed_channel(&second).is_some());
+        let newest = close(&registry, SecureChannelCloseReason::HeartbeatTimeout);
+        assert!(registry.is_channel_dead(&oldest));
+
+        // The oldest channel is forgotten once the limit is exceeded
+        close(&registry, SecureChannelCloseReason::HeartbeatTimeout);
+        assert!(registry.close_reason(&oldest).is_none());
+        assert!(registry.is_channel_dead(&newest));
+        assert_eq!(registry.get_dead_channel_list().len(), MAX_CLOSED_CHANNELS);
+    }
 }
@@ -0,0 +1,82 @@
+use crate::secure_channel::encryptor::KEY_RENEWAL_INTERVAL;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ockam_core::compat::sync::Arc;
+
+/// Maximum size of the replay window of a Secure Channel decryptor, in nonces.
+/// It spans the nonces of one key renewal interval
+pub const MAX_REPLAY_WINDOW: u64 = KEY_RENEWAL_INTERVAL;
+
+/// How a Secure Channel decryptor treats frames which don't arrive in order
+///
+/// Frames carrying a nonce which was already received are always dropped as replays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplayProtection {
+    /// Accept frames delayed by less than the replay window, which spans the nonces
+    /// of one key renewal interval by default
+    #[default]
+    Tolerant,
+    /// Only accept frames with a nonce greater than the nonce of all the previous frames.
+    /// Frames arriving out of order are dropped
+    Strict,
+}
+
+/// Counters of the frames which didn't arrive in order on one side of a Secure Channel
+///
+/// They are updated by the channel decryptor and can be read with
+/// [`crate::SecureChannelRegistryEntry::replay_counters`].
+/// Frames are classified with their nonce, before being authenticated.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayProtectionCounters {
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    out_of_order_accepted: AtomicUsize,
+    out_of_order_dropped: AtomicUsize,
+    replays_dropped: AtomicUsize,
+    out_of_window_dropped: AtomicUsize,
+}
+
+impl ReplayProtectionCounters {
+    /// Frames which arrived out of order and were accepted with [`ReplayProtection::Tolerant`]
+    pub fn out_of_order_accepted(&self) -> usize {
+        self.counters.out_of_order_accepted.load(Ordering::Relaxed)
+    }
+
+    /// Frames which arrived out of order and were dropped with [`ReplayProtection::Strict`]
+    pub fn out_of_order_dropped(&self) -> usize {
+        self.counters.out_of_order_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Frames dropped because a frame with the same nonce was already received
+    pub fn replays_dropped(&self) -> usize {
+        self.counters.replays_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Frames dropped because their nonce was too far behind, or ahead, of the last
+    /// received nonce
+    pub fn out_of_window_dropped(&self) -> usize {
+        self.counters.out_of_window_dropped.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_out_of_order_accepted(&self) {
+        Self::increment(&self.counters.out_of_order_accepted)
+    }
+
+    pub(crate) fn record_out_of_order_dropped(&self) {
+        Self::increment(&self.counters.out_of_order_dropped)
+    }
+
+    pub(crate) fn record_replay_dropped(&self) {
+        Self::increment(&self.counters.replays_dropped)
+    }
+
+    pub(crate) fn record_out_of_window_dropped(&self) {
+        Self::increment(&self.counters.out_of_window_dropped)
+    }
+
+    fn increment(counter: &AtomicUsize) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
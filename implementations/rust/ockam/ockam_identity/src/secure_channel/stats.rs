@@ -0,0 +1,137 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+use ockam_core::compat::sync::{Arc, Mutex};
+
+use crate::models::TimestampInSeconds;
+use crate::utils::now;
+use crate::{CipherSuite, CompressionAlgorithm};
+
+/// Statistics of one side of a Secure Channel
+///
+/// They are updated by the channel encryptor and decryptor and can be read with
+/// [`crate::SecureChannels::secure_channel_stats`] or
+/// [`crate::SecureChannelRegistryEntry::stats`].
+/// Heartbeats are counted along with the other messages.
+#[derive(Clone, Debug)]
+pub struct SecureChannelStats {
+    stats: Arc<Stats>,
+}
+
+#[derive(Debug)]
+struct Stats {
+    cipher_suite: CipherSuite,
+    pq_hybrid: bool,
+    compression: Option<CompressionAlgorithm>,
+    created_at: Option<TimestampInSeconds>,
+    messages_encrypted: AtomicUsize,
+    bytes_encrypted: AtomicUsize,
+    messages_decrypted: AtomicUsize,
+    bytes_decrypted: AtomicUsize,
+    last_rekey: Mutex<Option<TimestampInSeconds>>,
+}
+
+impl Default for SecureChannelStats {
+    fn default() -> Self {
+        Self::new(false, None)
+    }
+}
+
+impl SecureChannelStats {
+    pub(crate) fn new(pq_hybrid: bool, compression: Option<CompressionAlgorithm>) -> Self {
+        Self {
+            stats: Arc::new(Stats {
+                cipher_suite: CipherSuite::supported(),
+                pq_hybrid,
+                compression,
+                created_at: now().ok(),
+                messages_encrypted: Default::default(),
+                bytes_encrypted: Default::default(),
+                messages_decrypted: Default::default(),
+                bytes_decrypted: Default::default(),
+                last_rekey: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// AEAD cipher suite negotiated during the handshake
+    pub fn cipher_suite(&self) -> CipherSuite {
+        self.stats.cipher_suite
+    }
+
+    /// True if the channel keys were derived with the hybrid post-quantum key exchange
+    pub fn pq_hybrid(&self) -> bool {
+        self.stats.pq_hybrid
+    }
+
+    /// Compression negotiated during the handshake, if both sides selected the same algorithm
+    pub fn compression(&self) -> Option<CompressionAlgorithm> {
+        self.stats.compression
+    }
+
+    /// Number of messages encrypted on our side of the channel
+    pub fn messages_encrypted(&self) -> usize {
+        self.stats.messages_encrypted.load(Ordering::Relaxed)
+    }
+
+    /// Number of plaintext bytes encrypted on our side of the channel, after compression
+    pub fn bytes_encrypted(&self) -> usize {
+        self.stats.bytes_encrypted.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages successfully decrypted on our side of the channel
+    pub fn messages_decrypted(&self) -> usize {
+        self.stats.messages_decrypted.load(Ordering::Relaxed)
+    }
+
+    /// Number of plaintext bytes successfully decrypted on our side of the channel,
+    /// before decompression
+    pub fn bytes_decrypted(&self) -> usize {
+        self.stats.bytes_decrypted.load(Ordering::Relaxed)
+    }
+
+    /// Time of the last renewal of the encryption or decryption key, if any.
+    /// None if the keys were never renewed or if the system time is not available
+    pub fn last_rekey(&self) -> Option<TimestampInSeconds> {
+        *self.stats.last_rekey.lock().unwrap()
+    }
+
+    /// Time at which the channel was established.
+    /// None if the system time is not available
+    pub fn created_at(&self) -> Option<TimestampInSeconds> {
+        self.stats.created_at
+    }
+
+    /// Time elapsed since the channel was established.
+    /// None if the system time is not available
+    pub fn uptime(&self) -> Option<Duration> {
+        let created_at = self.created_at()?;
+        let current_time = now().ok()?;
+        Some(Duration::from_secs(
+            current_time.0.saturating_sub(created_at.0),
+        ))
+    }
+
+    pub(crate) fn record_encrypted(&self, bytes: usize) {
+        self.stats
+            .messages_encrypted
+            .fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .bytes_encrypted
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_decrypted(&self, bytes: usize) {
+        self.stats
+            .messages_decrypted
+            .fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .bytes_decrypted
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_rekey(&self) {
+        if let Ok(now) = now() {
+            *self.stats.last_rekey.lock().unwrap() = Some(now);
+        }
+    }
+}
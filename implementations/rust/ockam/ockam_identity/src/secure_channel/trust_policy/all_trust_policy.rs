@@ -1,3 +1,4 @@
+use core::time::Duration;
 use ockam_core::async_trait;
 use ockam_core::compat::boxed::Box;
 use ockam_core::{AsyncTryClone, Result};
@@ -25,6 +26,17 @@ impl<F: TrustPolicy, S: TrustPolicy> TrustPolicy for AllTrustPolicy<F, S> {
     async fn check(&self, trust_info: &SecureChannelTrustInfo) -> Result<bool> {
         Ok(self.first.check(trust_info).await? && self.second.check(trust_info).await?)
     }
+
+    fn reevaluation_interval(&self) -> Option<Duration> {
+        // Both policies are run again at the shortest of their intervals
+        match (
+            self.first.reevaluation_interval(),
+            self.second.reevaluation_interval(),
+        ) {
+            (Some(first), Some(second)) => Some(first.min(second)),
+            (first, second) => first.or(second),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,37 @@
+use core::time::Duration;
+use ockam_core::async_trait;
+use ockam_core::compat::boxed::Box;
+use ockam_core::Result;
+
+use crate::secure_channel::trust_policy::{SecureChannelTrustInfo, TrustPolicy};
+
+/// `TrustPolicy` which is checked during the handshake, and then again periodically for as long
+/// as the channel is open
+///
+/// The channel is shut down as soon as the inner policy doesn't succeed anymore, for instance
+/// because the other participant was removed from an allowlist. It is then reported by
+/// [`crate::SecureChannelRegistry::close_reason`] as
+/// [`crate::SecureChannelCloseReason::AuthorizationRevoked`].
+#[derive(Clone)]
+pub struct ReevaluatingTrustPolicy<T: TrustPolicy> {
+    inner: T,
+    interval: Duration,
+}
+
+impl<T: TrustPolicy> ReevaluatingTrustPolicy<T> {
+    /// Constructor
+    pub fn new(inner: T, interval: Duration) -> Self {
+        Self { inner, interval }
+    }
+}
+
+#[async_trait]
+impl<T: TrustPolicy> TrustPolicy for ReevaluatingTrustPolicy<T> {
+    async fn check(&self, trust_info: &SecureChannelTrustInfo) -> Result<bool> {
+        self.inner.check(trust_info).await
+    }
+
+    fn reevaluation_interval(&self) -> Option<Duration> {
+        Some(self.interval)
+    }
+}
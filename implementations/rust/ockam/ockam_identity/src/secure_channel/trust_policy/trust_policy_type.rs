@@ -1,3 +1,4 @@
+use core::time::Duration;
 use ockam_core::{
     async_trait,
     compat::{boxed::Box, sync::Arc},
@@ -36,6 +37,12 @@ pub trait TrustPolicy: Send + Sync + 'static {
     /// Check SecureChannel
     async fn check(&self, trust_info: &SecureChannelTrustInfo) -> Result<bool>;
 
+    /// Interval at which the check is run again once the channel is created, see
+    /// [`crate::ReevaluatingTrustPolicy`]. None if it's only run during the handshake
+    fn reevaluation_interval(&self) -> Option<Duration> {
+        None
+    }
+
     /// Run both `TrustPolicy` checks and succeed only if both succeeded
     fn and<O: TrustPolicy>(self, other: O) -> AllTrustPolicy<Self, O>
     where
@@ -59,6 +66,10 @@ impl<T: TrustPolicy + ?Sized> TrustPolicy for Box<T> {
     async fn check(&self, trust_info: &SecureChannelTrustInfo) -> Result<bool> {
         T::check(&**self, trust_info).await
     }
+
+    fn reevaluation_interval(&self) -> Option<Duration> {
+        T::reevaluation_interval(&**self)
+    }
 }
 
 #[async_trait]
@@ -66,4 +77,8 @@ impl<T: TrustPolicy + ?Sized> TrustPolicy for Arc<T> {
     async fn check(&self, trust_info: &SecureChannelTrustInfo) -> Result<bool> {
         T::check(&**self, trust_info).await
     }
+
+    fn reevaluation_interval(&self) -> Option<Duration> {
+        T::reevaluation_interval(&**self)
+    }
 }
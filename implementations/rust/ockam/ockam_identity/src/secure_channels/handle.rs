@@ -0,0 +1,234 @@
+use core::time::Duration;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Address, Error, Result};
+use ockam_node::callback::{new_callback, CallbackReceiver, CallbackSender};
+use ockam_node::Context;
+
+use crate::models::Identifier;
+use crate::{CipherSuite, IdentityError, SecureChannel};
+
+/// Status of a handshake started by an initiator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HandshakeStatus {
+    /// The handshake is still in progress
+    InProgress,
+    /// The channel was established
+    Completed,
+    /// The handshake failed, for example because the other side was rejected
+    Failed,
+    /// The handshake was cancelled with [`SecureChannelHandle::cancel`]
+    Cancelled,
+    /// The handshake didn't complete before the timeout
+    TimedOut,
+}
+
+/// Shared status of a handshake, it can only change once while the handshake is in progress
+#[derive(Clone)]
+struct SharedHandshakeStatus(Arc<Mutex<HandshakeStatus>>);
+
+impl SharedHandshakeStatus {
+    /// Set a final status if the handshake is still in progress and return the previous status
+    fn finish(&self, status: HandshakeStatus) -> HandshakeStatus {
+        let mut current = self.0.lock().unwrap();
+        let previous = *current;
+        if previous == HandshakeStatus::InProgress {
+            *current = status;
+        }
+        previous
+    }
+
+    fn get(&self) -> HandshakeStatus {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// Outcome of a successful handshake, returned by [`SecureChannelHandle::wait_for_handshake`]
+#[derive(Debug, Clone)]
+pub struct HandshakeComplete {
+    secure_channel: SecureChannel,
+    their_identifier: Identifier,
+    cipher_suite: CipherSuite,
+    pq_hybrid: bool,
+}
+
+impl HandshakeComplete {
+    pub(crate) fn new(
+        secure_channel: SecureChannel,
+        their_identifier: Identifier,
+        cipher_suite: CipherSuite,
+        pq_hybrid: bool,
+    ) -> Self {
+        Self {
+            secure_channel,
+            their_identifier,
+            cipher_suite,
+            pq_hybrid,
+        }
+    }
+
+    /// Established Secure Channel
+    pub fn secure_channel(&self) -> &SecureChannel {
+        &self.secure_channel
+    }
+
+    /// Identifier of the other side, verified by the trust policy
+    pub fn their_identifier(&self) -> &Identifier {
+        &self.their_identifier
+    }
+
+    /// Cipher suite encrypting the messages of the channel
+    pub fn cipher_suite(&self) -> CipherSuite {
+        self.cipher_suite
+    }
+
+    /// True if the keys were derived with the hybrid post-quantum key exchange
+    pub fn is_pq_hybrid(&self) -> bool {
+        self.pq_hybrid
+    }
+}
+
+/// Used by the initiator's handshake worker to report the outcome of the handshake
+pub(crate) struct HandshakeCompletion {
+    secure_channel: SecureChannel,
+    status: SharedHandshakeStatus,
+    completed: Arc<Mutex<Option<HandshakeComplete>>>,
+    sender: CallbackSender<Result<HandshakeComplete>>,
+}
+
+impl HandshakeCompletion {
+    /// Return true if the handshake was cancelled or timed out
+    pub(crate) fn is_abandoned(&self) -> bool {
+        matches!(
+            self.status.get(),
+            HandshakeStatus::Cancelled | HandshakeStatus::TimedOut
+        )
+    }
+
+    /// Report that the channel was established, unless the handshake was abandoned meanwhile
+    pub(crate) fn complete(self, their_identifier: Identifier, pq_hybrid: bool) {
+        let completed = HandshakeComplete::new(
+            self.secure_channel,
+            their_identifier,
+            CipherSuite::supported(),
+            pq_hybrid,
+        );
+        // Stored first, for a handle which times out while the handshake completes
+        *self.completed.lock().unwrap() = Some(completed.clone());
+        if self.status.finish(HandshakeStatus::Completed) == HandshakeStatus::InProgress {
+            // The handle may have been dropped without waiting for the channel
+            let _ = self.sender.send(Ok(completed));
+        }
+    }
+
+    /// Report that the handshake failed
+    pub(crate) fn fail(self, error: Error) {
+        if self.status.finish(HandshakeStatus::Failed) == HandshakeStatus::InProgress {
+            let _ = self.sender.send(Err(error));
+        }
+    }
+}
+
+/// Handle to a Secure Channel handshake in progress,
+/// returned by [`super::SecureChannels::create_secure_channel_with_handle()`]
+#[derive(Clone)]
+pub struct SecureChannelHandle {
+    secure_channel: SecureChannel,
+    handshake_worker: Address,
+    timeout: Duration,
+    status: SharedHandshakeStatus,
+    completed: Arc<Mutex<Option<HandshakeComplete>>>,
+    receiver: Arc<Mutex<Option<CallbackReceiver<Result<HandshakeComplete>>>>>,
+}
+
+impl SecureChannelHandle {
+    pub(crate) fn new(
+        secure_channel: SecureChannel,
+        handshake_worker: Address,
+        timeout: Duration,
+    ) -> (Self, HandshakeCompletion) {
+        let (receiver, sender) = new_callback();
+        let status = SharedHandshakeStatus(Arc::new(Mutex::new(HandshakeStatus::InProgress)));
+        let completed = Arc::new(Mutex::new(None));
+        let handle = Self {
+            secure_channel: secure_channel.clone(),
+            handshake_worker,
+            timeout,
+            status: status.clone(),
+            completed: completed.clone(),
+            receiver: Arc::new(Mutex::new(Some(receiver))),
+        };
+        let completion = HandshakeCompletion {
+            secure_channel,
+            status,
+            completed,
+            sender,
+        };
+        (handle, completion)
+    }
+
+    /// Secure Channel which is being established. It can only be used once the
+    /// handshake is completed
+    pub fn secure_channel(&self) -> &SecureChannel {
+        &self.secure_channel
+    }
+
+    /// Wait until the handshake is completed and return the established Secure Channel.
+    ///
+    /// See [`wait_for_handshake`](Self::wait_for_handshake) to also get the identifier of
+    /// the other side and the negotiated parameters.
+    pub async fn wait(&self, ctx: &Context) -> Result<SecureChannel> {
+        Ok(self.wait_for_handshake(ctx).await?.secure_channel)
+    }
+
+    /// Wait until the keys of the channel are derived and the other side is verified by
+    /// the trust policy.
+    ///
+    /// The address of the channel is allocated as soon as the handshake starts, but it only
+    /// accepts messages once the handshake is completed. This function fails with:
+    ///  - [`IdentityError::SecureChannelCancelled`] if the handshake was cancelled
+    ///  - [`IdentityError::SecureChannelHandshakeTimeout`] if the handshake didn't complete in time.
+    ///    The handshake is then stopped
+    ///  - the handshake error if the handshake failed, for example
+    ///    [`IdentityError::SecureChannelTrustCheckFailed`] if the other side was rejected
+    ///
+    /// The outcome of a handshake can only be awaited once.
+    pub async fn wait_for_handshake(&self, ctx: &Context) -> Result<HandshakeComplete> {
+        let receiver = self.receiver.lock().unwrap().take().ok_or_else(|| {
+            Error::new(
+                Origin::Channel,
+                Kind::Misuse,
+                "the outcome of this handshake was already awaited",
+            )
+        })?;
+
+        match receiver.receive_timeout(self.timeout).await {
+            Ok(result) => result,
+            Err(error) => match self.status.finish(HandshakeStatus::TimedOut) {
+                HandshakeStatus::InProgress if error.code().kind == Kind::Timeout => {
+                    let _ = ctx.stop_worker(self.handshake_worker.clone()).await;
+                    Err(IdentityError::SecureChannelHandshakeTimeout.into())
+                }
+                HandshakeStatus::Completed => self
+                    .completed
+                    .lock()
+                    .unwrap()
+                    .clone()
+                    .ok_or_else(|| IdentityError::SecureChannelCancelled.into()),
+                HandshakeStatus::Cancelled => Err(IdentityError::SecureChannelCancelled.into()),
+                _ => Err(error),
+            },
+        }
+    }
+
+    /// Cancel the handshake if it is still in progress.
+    ///
+    /// The handshake worker is stopped and no channel is left in the registry.
+    /// This is a no-op if the handshake is already completed.
+    pub async fn cancel(&self, ctx: &Context) -> Result<()> {
+        if self.status.finish(HandshakeStatus::Cancelled) != HandshakeStatus::InProgress {
+            return Ok(());
+        }
+        ctx.stop_worker(self.handshake_worker.clone()).await
+    }
+}
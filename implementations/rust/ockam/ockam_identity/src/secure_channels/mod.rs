@@ -1,4 +1,5 @@
 mod common;
+mod handle;
 /// Services for creating secure channels
 #[allow(clippy::module_inception)]
 pub mod secure_channels;
@@ -6,6 +7,7 @@ mod secure_channels_builder;
 mod secure_client;
 
 pub use common::*;
+pub use handle::*;
 pub use secure_channels::*;
 pub use secure_channels_builder::*;
 pub use secure_client::*;
@@ -5,12 +5,15 @@ use ockam_node::Context;
 
 use crate::identities::Identities;
 use crate::models::Identifier;
-use crate::secure_channel::handshake_worker::HandshakeWorker;
+use crate::secure_channel::handshake_worker::{HandshakeMode, HandshakeWorker};
 use crate::secure_channel::{
     Addresses, IdentityChannelListener, Role, SecureChannelListenerOptions, SecureChannelOptions,
     SecureChannelRegistry,
 };
-use crate::{SecureChannel, SecureChannelListener, SecureChannelsBuilder, Vault};
+use crate::{
+    CipherSuite, IdentityError, SecureChannel, SecureChannelHandle, SecureChannelListener,
+    SecureChannelStats, SecureChannelsBuilder, Vault,
+};
 
 /// Identity implementation
 #[derive(Clone)]
@@ -46,6 +49,16 @@ impl SecureChannels {
         self.secure_channel_registry.clone()
     }
 
+    /// Return the live statistics of the established Secure Channel with the given
+    /// encryptor address. Fails with [`IdentityError::SecureChannelNotFound`] if that
+    /// channel is unknown or was closed
+    pub fn secure_channel_stats(&self, channel: &Address) -> Result<SecureChannelStats> {
+        self.secure_channel_registry
+            .get_channel_by_encryptor_address(channel)
+            .map(|entry| entry.stats().clone())
+            .ok_or_else(|| IdentityError::SecureChannelNotFound.into())
+    }
+
     /// Create a builder for secure channels
     pub fn builder() -> SecureChannelsBuilder {
         SecureChannelsBuilder {
@@ -57,6 +70,11 @@ impl SecureChannels {
 
 impl SecureChannels {
     /// Spawns a SecureChannel listener at given `Address` with given [`SecureChannelListenerOptions`]
+    ///
+    /// Several listeners with their own options can run for the same identity, at different
+    /// addresses. Creating a listener at the address of a running listener fails.
+    /// The channels accepted by a listener can be found with
+    /// [`SecureChannelRegistry::get_channels_by_listener`].
     pub async fn create_secure_channel_listener(
         &self,
         ctx: &Context,
@@ -81,6 +99,10 @@ impl SecureChannels {
     }
 
     /// Initiate a SecureChannel using `Route` to the SecureChannel listener and [`SecureChannelOptions`]
+    ///
+    /// The route can start with the encryptor address of another SecureChannel, in which case
+    /// the new channel is carried over that channel, see
+    /// [`crate::SecureChannelRegistryEntry::outer_channel`]
     pub async fn create_secure_channel(
         &self,
         ctx: &Context,
@@ -88,48 +110,116 @@ impl SecureChannels {
         route: impl Into<Route>,
         options: impl Into<SecureChannelOptions>,
     ) -> Result<SecureChannel> {
+        self.create_secure_channel_with_handle(ctx, identifier, route, options)
+            .await?
+            .wait(ctx)
+            .await
+    }
+
+    /// Initiate a SecureChannel using `Route` to the SecureChannel listener and [`SecureChannelOptions`]
+    /// without waiting for the handshake to complete.
+    /// The returned [`SecureChannelHandle`] can be used to wait for the channel or to cancel the handshake
+    pub async fn create_secure_channel_with_handle(
+        &self,
+        ctx: &Context,
+        identifier: &Identifier,
+        route: impl Into<Route>,
+        options: impl Into<SecureChannelOptions>,
+    ) -> Result<SecureChannelHandle> {
         let addresses = Addresses::generate(Role::Initiator);
         let options = options.into();
         let flow_control_id = options.flow_control_id.clone();
+        // Fail right away if this side can't use the cipher suite of this build
+        CipherSuite::negotiate(&options.allowed_ciphers, &[CipherSuite::supported()])?;
+        options.check_key_exchange()?;
 
         let route = route.into();
         let next = route.next()?;
         options.setup_flow_control(ctx.flow_controls(), &addresses, next)?;
         let access_control = options.create_access_control(ctx.flow_controls());
 
-        // TODO: Allow manual PurposeKey management
-        let purpose_key = self
-            .identities
-            .purpose_keys()
-            .purpose_keys_creation()
-            .get_or_create_secure_channel_purpose_key(identifier)
-            .await?;
+        let mode = match options.pre_shared_key {
+            Some(pre_shared_key) => HandshakeMode::PreSharedKey(pre_shared_key),
+            // TODO: Allow manual PurposeKey management
+            None => HandshakeMode::PurposeKey(
+                self.identities
+                    .purpose_keys()
+                    .purpose_keys_creation()
+                    .get_or_create_secure_channel_purpose_key(identifier)
+                    .await?,
+            ),
+        };
+
+        let (handle, completion) = SecureChannelHandle::new(
+            SecureChannel::new(
+                addresses.encryptor.clone(),
+                addresses.encryptor_api.clone(),
+                flow_control_id,
+            ),
+            addresses.decryptor_remote.clone(),
+            options.timeout,
+        );
 
         HandshakeWorker::create(
             ctx,
             Arc::new(self.clone()),
             addresses.clone(),
             identifier.clone(),
-            purpose_key,
+            mode,
             options.trust_policy,
             access_control.decryptor_outgoing_access_control,
             options.credentials,
+            options.allowed_ciphers,
+            options.pq_hybrid,
+            options.compression,
             options.trust_context,
             Some(route),
-            Some(options.timeout),
+            Some(completion),
+            None,
+            options.heartbeat,
+            options.storage_failure_policy,
+            options.replay_protection,
+            options.replay_window,
+            None,
             Role::Initiator,
         )
         .await?;
 
-        Ok(SecureChannel::new(
-            addresses.encryptor,
-            addresses.encryptor_api,
-            flow_control_id,
-        ))
+        Ok(handle)
     }
 
     /// Stop a SecureChannel given an encryptor address
+    ///
+    /// Fails with [`IdentityError::SecureChannelHasInnerChannels`] if other SecureChannels
+    /// are carried over that channel, see [`Self::force_stop_secure_channel`]
     pub async fn stop_secure_channel(&self, ctx: &Context, channel: &Address) -> Result<()> {
+        if !self
+            .secure_channel_registry
+            .get_inner_channels(channel)
+            .is_empty()
+        {
+            return Err(IdentityError::SecureChannelHasInnerChannels.into());
+        }
+        ctx.stop_worker(channel.clone()).await
+    }
+
+    /// Check the trust policy of a SecureChannel given its encryptor address right away,
+    /// instead of waiting for its next periodic check, e.g. once its participant was removed
+    /// from an allowlist. The channel is shut down if the other side doesn't pass the policy
+    /// anymore, which is then reported by [`SecureChannelRegistry::close_reason`].
+    ///
+    /// The check runs in the channel's encryptor after this function returns. Only channels
+    /// created with a [`crate::ReevaluatingTrustPolicy`] can be checked again, for other
+    /// channels this fails with [`IdentityError::SecureChannelNotFound`]
+    pub async fn reevaluate_trust(&self, channel: &Address) -> Result<()> {
+        self.secure_channel_registry
+            .trigger_trust_reevaluation(channel)
+            .await
+    }
+
+    /// Stop a SecureChannel given an encryptor address, along with all the SecureChannels
+    /// carried over it
+    pub async fn force_stop_secure_channel(&self, ctx: &Context, channel: &Address) -> Result<()> {
         ctx.stop_worker(channel.clone()).await
     }
 }
@@ -7,6 +7,10 @@ use crate::models::{Attributes, CredentialSchemaIdentifier, TimestampInSeconds};
 use crate::IdentityError;
 
 /// Create a new timestamp using the system time
+///
+/// This is a wall-clock time which can jump backwards or forwards, e.g. after an NTP correction.
+/// It should only be used for absolute deadlines like a credential expiration.
+/// Relative delays and timeouts should rely on the monotonic clock of the node runtime instead
 #[cfg(feature = "std")]
 pub fn now() -> Result<TimestampInSeconds> {
     if let Ok(now) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
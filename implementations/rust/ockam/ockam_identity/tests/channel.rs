@@ -1,20 +1,27 @@
 use core::time::Duration;
 use ockam_core::compat::sync::Arc;
-use ockam_core::{route, Address, AllowAll, Any, DenyAll, Mailboxes, Result, Routed, Worker};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{
+    route, Address, AllowAll, Any, DenyAll, Error, LocalMessage, Mailboxes, Result, Routed, Worker,
+};
 use ockam_identity::models::{CredentialSchemaIdentifier, Identifier};
 use ockam_identity::secure_channels::secure_channels;
+use ockam_identity::storage::{InMemoryStorage, Storage};
 use ockam_identity::utils::AttributesBuilder;
 use ockam_identity::{
-    AuthorityService, DecryptionResponse, EncryptionRequest, EncryptionResponse,
-    IdentityAccessControlBuilder, IdentitySecureChannelLocalInfo, SecureChannelListenerOptions,
-    SecureChannelOptions, SecureChannels, TrustContext, TrustEveryonePolicy, TrustIdentifierPolicy,
-    Vault,
+    AuthorityService, CipherSuite, CompressionAlgorithm, DecryptionResponse, EncryptionRequest,
+    EncryptionResponse, IdentityAccessControlBuilder, IdentityError,
+    IdentitySecureChannelLocalInfo, PreSharedKey, ReevaluatingTrustPolicy,
+    SecureChannelCloseReason, SecureChannelListenerOptions, SecureChannelOptions,
+    SecureChannelRegistryEntry, SecureChannelTrustInfo, SecureChannels, StorageFailurePolicy,
+    TrustContext, TrustEveryonePolicy, TrustIdentifierPolicy, TrustPolicy, Vault,
 };
-use ockam_node::{Context, MessageReceiveOptions, WorkerBuilder};
+use ockam_node::{Context, MessageReceiveOptions, Metrics, WorkerBuilder};
 use ockam_vault::{
-    SoftwareVaultForSecureChannels, SoftwareVaultForSigning, SoftwareVaultForVerifyingSignatures,
+    SigningKeyType, SoftwareVaultForSecureChannels, SoftwareVaultForSigning,
+    SoftwareVaultForVerifyingSignatures,
 };
-use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
 
 #[ockam_macros::test]
 async fn test_channel(ctx: &mut Context) -> Result<()> {
@@ -416,6 +423,16 @@ async fn test_channel_registry(ctx: &mut Context) -> Result<()> {
     assert_eq!(bob_channel_data.my_id(), bob.identifier());
     assert_eq!(bob_channel_data.their_id(), alice.identifier());
 
+    // Each side reaches the decryptor of the other one directly
+    assert_eq!(
+        alice_channel_data.route(),
+        &route![bob_channel_data.decryptor_messaging_address().clone()]
+    );
+    assert_eq!(
+        bob_channel_data.route(),
+        &route![alice_channel_data.decryptor_messaging_address().clone()]
+    );
+
     ctx.stop().await
 }
 
@@ -705,6 +722,157 @@ async fn test_double_tunneled_secure_channel_works(ctx: &mut Context) -> Result<
     ctx.stop().await
 }
 
+#[ockam_macros::test]
+async fn test_nested_secure_channels(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let registry = secure_channels.secure_channel_registry();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+    let bob_inner_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_inner_listener",
+            SecureChannelListenerOptions::new().as_consumer(bob_listener.flow_control_id()),
+        )
+        .await?;
+    let bob_innermost_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_innermost_listener",
+            SecureChannelListenerOptions::new().as_consumer(bob_inner_listener.flow_control_id()),
+        )
+        .await?;
+
+    let outer = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    let inner = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![outer.clone(), "bob_inner_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    let innermost = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![inner.clone(), "bob_innermost_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    // Each channel is tracked with the channel it's carried over
+    let outer_entry = registry
+        .get_channel_by_encryptor_address(outer.encryptor_address())
+        .unwrap();
+    let inner_entry = registry
+        .get_channel_by_encryptor_address(inner.encryptor_address())
+        .unwrap();
+    let innermost_entry = registry
+        .get_channel_by_encryptor_address(innermost.encryptor_address())
+        .unwrap();
+    assert_eq!(outer_entry.depth(), 0);
+    assert_eq!(outer_entry.outer_channel(), None);
+    assert_eq!(inner_entry.depth(), 1);
+    assert_eq!(inner_entry.outer_channel(), Some(outer.encryptor_address()));
+    assert_eq!(innermost_entry.depth(), 2);
+    assert_eq!(
+        innermost_entry.outer_channel(),
+        Some(inner.encryptor_address())
+    );
+
+    let inner_channels = registry.get_inner_channels(outer.encryptor_address());
+    assert_eq!(inner_channels.len(), 1);
+    assert_eq!(
+        inner_channels[0].encryptor_messaging_address(),
+        inner.encryptor_address()
+    );
+
+    // The other side of the channels is nested the same way
+    let their_side = |entry: &SecureChannelRegistryEntry| {
+        registry
+            .get_channel_list()
+            .into_iter()
+            .find(|e| e.their_decryptor_address() == *entry.decryptor_messaging_address())
+            .unwrap()
+    };
+    let bob_outer = their_side(&outer_entry);
+    let bob_inner = their_side(&inner_entry);
+    assert_eq!(bob_outer.depth(), 0);
+    assert_eq!(bob_inner.depth(), 1);
+    assert_eq!(
+        bob_inner.outer_channel(),
+        Some(bob_outer.encryptor_messaging_address())
+    );
+
+    // Messages of the inner channels go through the outer one
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+    ctx.flow_controls()
+        .add_consumer("child", bob_innermost_listener.flow_control_id());
+    child_ctx
+        .send(
+            route![innermost.clone(), "child"],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+    assert_eq!("Hello, Bob!", child_ctx.receive::<String>().await?.body());
+    assert!(outer_entry.stats().messages_encrypted() > 0);
+
+    // A channel carrying other channels is only stopped when forced to
+    let err = secure_channels
+        .stop_secure_channel(ctx, outer.encryptor_address())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::Conflict);
+    assert!(registry
+        .get_channel_by_encryptor_address(outer.encryptor_address())
+        .is_some());
+
+    secure_channels
+        .force_stop_secure_channel(ctx, outer.encryptor_address())
+        .await?;
+    ctx.sleep(Duration::from_millis(250)).await;
+
+    let workers = ctx.list_workers().await?;
+    for entry in [&outer_entry, &inner_entry, &innermost_entry] {
+        assert!(registry
+            .get_channel_by_encryptor_address(entry.encryptor_messaging_address())
+            .is_none());
+        assert!(!workers.contains(entry.encryptor_messaging_address()));
+        assert!(!workers.contains(entry.decryptor_messaging_address()));
+    }
+
+    ctx.stop().await
+}
+
 #[ockam_macros::test]
 async fn test_many_times_tunneled_secure_channel_works(ctx: &mut Context) -> Result<()> {
     let secure_channels = secure_channels();
@@ -1136,3 +1304,1599 @@ async fn should_stop_encryptor__and__decryptor__in__secure_channel(
 
     ctx.stop().await
 }
+
+#[ockam_macros::test]
+async fn test_channel_heartbeat_keeps_channel_alive(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_heartbeat(Duration::from_millis(50), 2),
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+
+    // Let a few heartbeats go back and forth, they must not reach the application
+    ctx.sleep(Duration::from_millis(500)).await;
+
+    let registry = secure_channels.secure_channel_registry();
+    assert!(!registry.is_channel_dead(alice_channel.encryptor_address()));
+    assert_eq!(registry.get_channel_list().len(), 2);
+
+    child_ctx
+        .send(
+            route![alice_channel.clone(), child_ctx.address()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+
+    let msg = child_ctx.receive::<String>().await?;
+    assert_eq!("Hello, Bob!", msg.body());
+
+    let res = child_ctx
+        .receive_extended::<String>(MessageReceiveOptions::new().with_timeout_secs(1))
+        .await;
+    assert!(res.is_err(), "Heartbeats should not be surfaced");
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_heartbeat_detects_dead_peer(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let _bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_heartbeat(Duration::from_millis(100), 2),
+        )
+        .await?;
+
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    let registry = secure_channels.secure_channel_registry();
+    let bob_channel = registry
+        .get_channel_list()
+        .into_iter()
+        .find(|entry| !entry.is_initiator())
+        .unwrap();
+
+    // The other side disappears without notice
+    secure_channels
+        .stop_secure_channel(ctx, bob_channel.encryptor_messaging_address())
+        .await?;
+
+    ctx.sleep(Duration::from_millis(1000)).await;
+
+    assert!(registry.is_channel_dead(alice_channel.encryptor_address()));
+    assert!(registry
+        .get_channel_by_encryptor_address(alice_channel.encryptor_address())
+        .is_none());
+    assert_eq!(registry.get_dead_channel_list().len(), 1);
+
+    let workers = ctx.list_workers().await?;
+    assert!(!workers.contains(alice_channel.encryptor_address()));
+
+    assert!(registry
+        .forget_dead_channel(alice_channel.encryptor_address())
+        .is_some());
+    assert!(registry.get_dead_channel_list().is_empty());
+
+    ctx.stop().await
+}
+
+/// Trusts the identifiers of an allowlist which can be changed at any time
+struct SharedAllowlistPolicy(Arc<std::sync::Mutex<Vec<Identifier>>>);
+
+#[ockam_core::async_trait]
+impl TrustPolicy for SharedAllowlistPolicy {
+    async fn check(&self, trust_info: &SecureChannelTrustInfo) -> Result<bool> {
+        Ok(self
+            .0
+            .lock()
+            .unwrap()
+            .contains(trust_info.their_identity_id()))
+    }
+}
+
+#[ockam_macros::test]
+async fn test_channel_reevaluating_trust_policy(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let allowlist = Arc::new(std::sync::Mutex::new(vec![bob.identifier().clone()]));
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_trust_policy(ReevaluatingTrustPolicy::new(
+                SharedAllowlistPolicy(allowlist.clone()),
+                Duration::from_millis(100),
+            )),
+        )
+        .await?;
+
+    let mut bob_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "bob",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+    ctx.flow_controls()
+        .add_consumer("bob", bob_listener.flow_control_id());
+
+    // The channel stays open while Bob passes the policy
+    ctx.sleep(Duration::from_millis(250)).await;
+    ctx.send(
+        route![alice_channel.clone(), "bob"],
+        "Hello, Bob!".to_string(),
+    )
+    .await?;
+    assert_eq!(bob_ctx.receive::<String>().await?.body(), "Hello, Bob!");
+
+    let registry = secure_channels.secure_channel_registry();
+    assert_eq!(
+        registry.close_reason(alice_channel.encryptor_address()),
+        None
+    );
+
+    // Bob is removed from the allowlist, the channel is shut down at the next check
+    allowlist.lock().unwrap().clear();
+    ctx.sleep(Duration::from_millis(500)).await;
+
+    assert_eq!(
+        registry.close_reason(alice_channel.encryptor_address()),
+        Some(SecureChannelCloseReason::AuthorizationRevoked)
+    );
+    assert!(!registry.is_channel_dead(alice_channel.encryptor_address()));
+    assert!(registry
+        .get_channel_by_encryptor_address(alice_channel.encryptor_address())
+        .is_none());
+    assert_eq!(
+        registry
+            .get_closed_channel_list(SecureChannelCloseReason::AuthorizationRevoked)
+            .len(),
+        1
+    );
+
+    let workers = ctx.list_workers().await?;
+    assert!(!workers.contains(alice_channel.encryptor_address()));
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_reevaluate_trust_on_demand(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let allowlist = Arc::new(std::sync::Mutex::new(vec![bob.identifier().clone()]));
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_trust_policy(ReevaluatingTrustPolicy::new(
+                SharedAllowlistPolicy(allowlist.clone()),
+                Duration::from_secs(3600),
+            )),
+        )
+        .await?;
+
+    let registry = secure_channels.secure_channel_registry();
+
+    // The channel is shut down right away instead of at the next periodic check
+    allowlist.lock().unwrap().clear();
+    secure_channels
+        .reevaluate_trust(alice_channel.encryptor_address())
+        .await?;
+    ctx.sleep(Duration::from_millis(250)).await;
+
+    assert_eq!(
+        registry.close_reason(alice_channel.encryptor_address()),
+        Some(SecureChannelCloseReason::AuthorizationRevoked)
+    );
+
+    // The channel can't be checked anymore
+    assert!(secure_channels
+        .reevaluate_trust(alice_channel.encryptor_address())
+        .await
+        .is_err());
+
+    // Only channels which were shut down by heartbeats are forgotten as dead channels
+    assert!(registry
+        .forget_dead_channel(alice_channel.encryptor_address())
+        .is_none());
+    assert!(registry
+        .forget_closed_channel(alice_channel.encryptor_address())
+        .is_some());
+    assert_eq!(
+        registry.close_reason(alice_channel.encryptor_address()),
+        None
+    );
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_eddsa_curve25519_identities(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation
+        .identity_builder()
+        .with_random_key(SigningKeyType::EdDSACurve25519)
+        .build()
+        .await?;
+    let bob = identities_creation
+        .identity_builder()
+        .with_random_key(SigningKeyType::EdDSACurve25519)
+        .build()
+        .await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new()
+                .with_trust_policy(TrustIdentifierPolicy::new(alice.identifier().clone())),
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new()
+                .with_trust_policy(TrustIdentifierPolicy::new(bob.identifier().clone())),
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+
+    child_ctx
+        .send(
+            route![alice_channel, child_ctx.address()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+
+    let msg = child_ctx.receive::<String>().await?;
+    let local_info = IdentitySecureChannelLocalInfo::find_info(msg.local_message())?;
+    assert_eq!(&local_info.their_identity_id(), alice.identifier());
+    assert_eq!("Hello, Bob!", msg.body());
+
+    ctx.stop().await
+}
+
+/// Storage failing to write attributes a given number of times
+struct FailingAttributesStorage {
+    storage: InMemoryStorage,
+    remaining_failures: AtomicUsize,
+}
+
+impl FailingAttributesStorage {
+    fn new(failures: usize) -> Arc<Self> {
+        Arc::new(Self {
+            storage: InMemoryStorage::new(),
+            remaining_failures: AtomicUsize::new(failures),
+        })
+    }
+}
+
+#[ockam_core::async_trait]
+impl Storage for FailingAttributesStorage {
+    async fn get(&self, id: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        self.storage.get(id, key).await
+    }
+
+    async fn set(&self, id: &str, key: String, val: Vec<u8>) -> Result<()> {
+        if key == "ATTRIBUTES"
+            && self
+                .remaining_failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+        {
+            return Err(Error::new(Origin::Application, Kind::Io, "storage is down"));
+        }
+        self.storage.set(id, key, val).await
+    }
+
+    async fn del(&self, id: &str, key: &str) -> Result<()> {
+        self.storage.del(id, key).await
+    }
+
+    async fn keys(&self, namespace: &str) -> Result<Vec<String>> {
+        self.storage.keys(namespace).await
+    }
+}
+
+/// Create a Secure Channel between two identities presenting credentials to each other
+async fn create_channel_with_credentials(
+    ctx: &Context,
+    secure_channels: &SecureChannels,
+    policy: StorageFailurePolicy,
+) -> Result<(Identifier, Identifier, Address)> {
+    let identities_creation = secure_channels.identities().identities_creation();
+    let authority = identities_creation.create_identity().await?;
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let trust_context = TrustContext::new(
+        "test".to_string(),
+        Some(AuthorityService::new(
+            secure_channels.identities().credentials(),
+            authority.identifier().clone(),
+            None,
+        )),
+    );
+
+    let credentials_creation = secure_channels
+        .identities()
+        .credentials()
+        .credentials_creation();
+    let alice_credential = credentials_creation
+        .issue_credential(
+            authority.identifier(),
+            alice.identifier(),
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("is_alice", "true")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+    let bob_credential = credentials_creation
+        .issue_credential(
+            authority.identifier(),
+            bob.identifier(),
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("is_bob", "true")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new()
+                .with_trust_context(trust_context.clone())
+                .with_credential(bob_credential)
+                .with_storage_failure_policy(policy),
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new()
+                .with_trust_context(trust_context)
+                .with_credential(alice_credential)
+                .with_storage_failure_policy(policy)
+                .with_timeout(Duration::from_secs(5)),
+        )
+        .await?;
+
+    Ok((
+        alice.identifier().clone(),
+        bob.identifier().clone(),
+        alice_channel.encryptor_address().clone(),
+    ))
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn test_channel__attributes_storage_failure__fails_handshake(
+    ctx: &mut Context,
+) -> Result<()> {
+    let secure_channels = SecureChannels::builder()
+        .with_identities_storage(FailingAttributesStorage::new(usize::MAX))
+        .build();
+
+    let res =
+        create_channel_with_credentials(ctx, &secure_channels, StorageFailurePolicy::Fail).await;
+    assert!(res.is_err());
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn test_channel__attributes_storage_failure_best_effort__creates_channel(
+    ctx: &mut Context,
+) -> Result<()> {
+    let secure_channels = SecureChannels::builder()
+        .with_identities_storage(FailingAttributesStorage::new(usize::MAX))
+        .build();
+
+    let (_alice, bob, alice_channel) =
+        create_channel_with_credentials(ctx, &secure_channels, StorageFailurePolicy::BestEffort)
+            .await?;
+
+    let entry = secure_channels
+        .secure_channel_registry()
+        .get_channel_by_encryptor_address(&alice_channel)
+        .unwrap();
+    assert!(!entry.attributes_persisted());
+
+    let bob_attributes = secure_channels
+        .identities()
+        .repository()
+        .get_attributes(&bob)
+        .await?;
+    assert!(bob_attributes.is_none());
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn test_channel__attributes_storage_failure_retry__persists_attributes(
+    ctx: &mut Context,
+) -> Result<()> {
+    let secure_channels = SecureChannels::builder()
+        .with_identities_storage(FailingAttributesStorage::new(1))
+        .build();
+
+    let (_alice, bob, alice_channel) =
+        create_channel_with_credentials(ctx, &secure_channels, StorageFailurePolicy::Retry(2))
+            .await?;
+
+    let entry = secure_channels
+        .secure_channel_registry()
+        .get_channel_by_encryptor_address(&alice_channel)
+        .unwrap();
+    assert!(entry.attributes_persisted());
+
+    let bob_attributes = secure_channels
+        .identities()
+        .repository()
+        .get_attributes(&bob)
+        .await?
+        .unwrap();
+    assert_eq!(
+        "true".as_bytes(),
+        bob_attributes.attrs().get("is_bob".as_bytes()).unwrap()
+    );
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_pre_shared_key(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let pre_shared_key = PreSharedKey::new([7u8; 32]);
+
+    let bob_options = SecureChannelListenerOptions::new()
+        .with_trust_policy(TrustIdentifierPolicy::new(alice.identifier().clone()))
+        .with_pre_shared_key(pre_shared_key.clone());
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(ctx, bob.identifier(), "bob_listener", bob_options)
+        .await?;
+
+    let alice_options = SecureChannelOptions::new()
+        .with_trust_policy(TrustIdentifierPolicy::new(bob.identifier().clone()))
+        .with_pre_shared_key(pre_shared_key);
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            alice_options,
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+
+    child_ctx
+        .send(
+            route![alice_channel.clone(), child_ctx.address()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+
+    let msg = child_ctx.receive::<String>().await?;
+
+    let local_info = IdentitySecureChannelLocalInfo::find_info(msg.local_message())?;
+    assert_eq!(&local_info.their_identity_id(), alice.identifier());
+
+    let return_route = msg.return_route();
+    assert_eq!("Hello, Bob!", msg.body());
+
+    ctx.flow_controls()
+        .add_consumer("child", alice_channel.flow_control_id());
+
+    child_ctx
+        .send(return_route, "Hello, Alice!".to_string())
+        .await?;
+
+    let msg = child_ctx.receive::<String>().await?;
+
+    let local_info = IdentitySecureChannelLocalInfo::find_info(msg.local_message())?;
+    assert_eq!(&local_info.their_identity_id(), bob.identifier());
+    assert_eq!("Hello, Alice!", msg.body());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_pre_shared_key_mismatch(ctx: &mut Context) -> Result<()> {
+    let alice_sc_vault = SoftwareVaultForSecureChannels::create();
+    let alice_vault = Vault::new(
+        SoftwareVaultForSigning::create(),
+        alice_sc_vault.clone(),
+        SoftwareVaultForSigning::create(),
+        SoftwareVaultForVerifyingSignatures::create(),
+    );
+    let secure_channels_alice = SecureChannels::builder().with_vault(alice_vault).build();
+    let secure_channels_bob = secure_channels();
+
+    let alice = secure_channels_alice
+        .identities()
+        .identities_creation()
+        .create_identity()
+        .await?;
+    let bob = secure_channels_bob
+        .identities()
+        .identities_creation()
+        .create_identity()
+        .await?;
+
+    let bob_options = SecureChannelListenerOptions::new()
+        .with_trust_policy(TrustEveryonePolicy)
+        .with_pre_shared_key(PreSharedKey::new([7u8; 32]));
+    secure_channels_bob
+        .create_secure_channel_listener(ctx, bob.identifier(), "bob_listener", bob_options)
+        .await?;
+
+    let alice_options = SecureChannelOptions::new()
+        .with_trust_policy(TrustEveryonePolicy)
+        .with_pre_shared_key(PreSharedKey::new([8u8; 32]))
+        .with_timeout(Duration::from_millis(500));
+    let res = secure_channels_alice
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            alice_options,
+        )
+        .await;
+    assert!(res.is_err());
+
+    // The keys derived by Alice before detecting the mismatch are deleted
+    assert_eq!(alice_sc_vault.number_of_ephemeral_aead_secrets(), 0);
+    assert_eq!(alice_sc_vault.number_of_ephemeral_buffer_secrets(), 0);
+
+    ctx.stop().await
+}
+
+#[derive(Default)]
+struct SecureChannelMetrics {
+    encrypted: AtomicUsize,
+    decrypted: AtomicUsize,
+    handshakes: AtomicUsize,
+}
+
+impl Metrics for SecureChannelMetrics {
+    fn message_encrypted(&self, _channel: &Address, _bytes: usize) {
+        self.encrypted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn message_decrypted(&self, _channel: &Address, _bytes: usize) {
+        self.decrypted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn handshake_completed(&self, _channel: &Address, _duration: Duration) {
+        self.handshakes.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[ockam_macros::test]
+async fn test_channel_metrics(ctx: &mut Context) -> Result<()> {
+    let metrics = Arc::new(SecureChannelMetrics::default());
+    ctx.set_metrics(metrics.clone());
+
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+    ctx.flow_controls()
+        .add_consumer("child", alice_channel.flow_control_id());
+
+    child_ctx
+        .send(
+            route![alice_channel.clone(), child_ctx.address()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+    let msg = child_ctx.receive::<String>().await?;
+
+    child_ctx
+        .send(msg.return_route(), "Hello, Alice!".to_string())
+        .await?;
+    child_ctx.receive::<String>().await?;
+
+    // Both sides completed a handshake, and each message was encrypted and decrypted once
+    assert_eq!(metrics.handshakes.load(Ordering::Relaxed), 2);
+    assert_eq!(metrics.encrypted.load(Ordering::Relaxed), 2);
+    assert_eq!(metrics.decrypted.load(Ordering::Relaxed), 2);
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_handle_cancel_and_timeout(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    // Nobody answers to handshakes sent to this address
+    let _black_hole = ctx.new_detached("black_hole", AllowAll, AllowAll).await?;
+
+    let handle = secure_channels
+        .create_secure_channel_with_handle(
+            ctx,
+            alice.identifier(),
+            route!["black_hole"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    handle.cancel(ctx).await?;
+    let err = handle.wait(ctx).await.unwrap_err();
+    assert_eq!(err.code().kind, Kind::Cancelled);
+
+    let handle = secure_channels
+        .create_secure_channel_with_handle(
+            ctx,
+            alice.identifier(),
+            route!["black_hole"],
+            SecureChannelOptions::new().with_timeout(Duration::from_millis(200)),
+        )
+        .await?;
+    let err = handle.wait(ctx).await.unwrap_err();
+    assert_eq!(err.code().kind, Kind::Timeout);
+    // The handshake is already stopped
+    handle.cancel(ctx).await?;
+
+    ctx.sleep(Duration::from_millis(50)).await;
+    assert!(secure_channels
+        .secure_channel_registry()
+        .get_channel_list()
+        .is_empty());
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    // The other side is rejected by the trust policy
+    let handle = secure_channels
+        .create_secure_channel_with_handle(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new()
+                .with_trust_policy(TrustIdentifierPolicy::new(alice.identifier().clone())),
+        )
+        .await?;
+    let err = handle.wait(ctx).await.unwrap_err();
+    assert_ne!(err.code().kind, Kind::Cancelled);
+    assert_ne!(err.code().kind, Kind::Timeout);
+
+    // Cancelling an established channel is a no-op
+    let handle = secure_channels
+        .create_secure_channel_with_handle(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    let alice_channel = handle.wait(ctx).await?;
+    handle.cancel(ctx).await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+    child_ctx
+        .send(
+            route![alice_channel, child_ctx.address()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+    let msg = child_ctx.receive::<String>().await?.body();
+    assert_eq!("Hello, Bob!", msg);
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_stats(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+
+    // Enough messages to renew the keys once
+    let messages = 40;
+    for n in 0..messages {
+        child_ctx
+            .send(
+                route![alice_channel.clone(), child_ctx.address()],
+                format!("Hello, Bob! {n}"),
+            )
+            .await?;
+        child_ctx.receive::<String>().await?;
+    }
+
+    let alice_stats = secure_channels.secure_channel_stats(alice_channel.encryptor_address())?;
+    assert_eq!(alice_stats.messages_encrypted(), messages);
+    assert!(alice_stats.bytes_encrypted() > 0);
+    assert_eq!(alice_stats.messages_decrypted(), 0);
+    assert!(alice_stats.last_rekey().is_some());
+    assert!(alice_stats.created_at().is_some());
+    assert!(alice_stats.uptime().is_some());
+
+    let bob_channel = secure_channels
+        .secure_channel_registry()
+        .get_channel_list()
+        .into_iter()
+        .find(|entry| !entry.is_initiator())
+        .unwrap();
+    let bob_stats =
+        secure_channels.secure_channel_stats(bob_channel.encryptor_messaging_address())?;
+    assert_eq!(bob_stats.messages_decrypted(), messages);
+    assert_eq!(bob_stats.bytes_decrypted(), alice_stats.bytes_encrypted());
+    assert_eq!(bob_stats.messages_encrypted(), 0);
+    assert!(bob_stats.last_rekey().is_some());
+
+    // Unknown and closed channels have no statistics
+    let err = secure_channels
+        .secure_channel_stats(&Address::random_local())
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::NotFound);
+
+    secure_channels
+        .stop_secure_channel(ctx, alice_channel.encryptor_address())
+        .await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert!(secure_channels
+        .secure_channel_stats(alice_channel.encryptor_address())
+        .is_err());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_multiple_named_listeners(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+    let charlie = identities_creation.create_identity().await?;
+
+    let control_options = SecureChannelListenerOptions::new()
+        .with_trust_policy(TrustIdentifierPolicy::new(alice.identifier().clone()));
+    secure_channels
+        .create_secure_channel_listener(ctx, bob.identifier(), "control", control_options)
+        .await?;
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "data",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let err = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "control",
+            SecureChannelListenerOptions::new(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::AlreadyExists);
+
+    // Each listener keeps its own trust policy
+    secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["control"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    let res = secure_channels
+        .create_secure_channel(
+            ctx,
+            charlie.identifier(),
+            route!["control"],
+            SecureChannelOptions::new().with_timeout(Duration::from_millis(500)),
+        )
+        .await;
+    assert!(res.is_err());
+    secure_channels
+        .create_secure_channel(
+            ctx,
+            charlie.identifier(),
+            route!["data"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    ctx.sleep(Duration::from_millis(100)).await; // Wait for the responders to complete
+
+    let registry = secure_channels.secure_channel_registry();
+    assert_eq!(
+        registry.get_listener_list(),
+        vec![Address::from("control"), Address::from("data")]
+    );
+
+    let control_channels = registry.get_channels_by_listener(&"control".into());
+    assert_eq!(control_channels.len(), 1);
+    assert_eq!(control_channels[0].their_id(), alice.identifier());
+
+    let data_channels = registry.get_channels_by_listener(&"data".into());
+    assert_eq!(data_channels.len(), 1);
+    assert_eq!(data_channels[0].their_id(), charlie.identifier());
+
+    assert!(registry
+        .get_channel_list()
+        .iter()
+        .filter(|entry| entry.is_initiator())
+        .all(|entry| entry.listener().is_none()));
+
+    // The name can be reused once the listener is stopped
+    ctx.stop_worker("data").await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(registry.get_listener_list(), vec![Address::from("control")]);
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "data",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_allowed_ciphers(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let supported = CipherSuite::supported();
+    let other = if supported == CipherSuite::Aes256Gcm {
+        CipherSuite::ChaCha20Poly1305
+    } else {
+        CipherSuite::Aes256Gcm
+    };
+
+    // A listener which doesn't allow the suite of this build can't accept any channel
+    let err = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().with_allowed_ciphers(&[other]),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::Unsupported);
+
+    // Bob only allows one suite, e.g. AES-256-GCM to comply with FIPS
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().with_allowed_ciphers(&[supported]),
+        )
+        .await?;
+
+    // Alice's suites and Bob's suites are disjoint
+    let err = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_allowed_ciphers(&[other]),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::Unsupported);
+    assert!(
+        err.to_string()
+            .contains(&IdentityError::SecureChannelNoCommonCipher.to_string()),
+        "the channel is rejected because no suite is allowed by both sides: {err}"
+    );
+
+    // Alice and Bob share a suite, which is negotiated
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_allowed_ciphers(&[other, supported]),
+        )
+        .await?;
+
+    let stats = secure_channels.secure_channel_stats(alice_channel.encryptor_address())?;
+    assert_eq!(stats.cipher_suite(), supported);
+
+    let bob_channels = secure_channels
+        .secure_channel_registry()
+        .get_channel_list()
+        .into_iter()
+        .filter(|entry| !entry.is_initiator())
+        .collect::<Vec<_>>();
+    assert_eq!(bob_channels.len(), 1);
+    assert_eq!(bob_channels[0].stats().cipher_suite(), supported);
+
+    ctx.stop().await
+}
+
+#[cfg(feature = "pq_hybrid")]
+#[ockam_macros::test]
+async fn test_pq_hybrid(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().with_pq_hybrid(),
+        )
+        .await?;
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_classical_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_pq_hybrid(),
+        )
+        .await?;
+
+    let stats = secure_channels.secure_channel_stats(alice_channel.encryptor_address())?;
+    assert!(stats.pq_hybrid());
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+
+    child_ctx
+        .send(
+            route![alice_channel, child_ctx.address()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+    let msg = child_ctx.receive::<String>().await?;
+    assert_eq!("Hello, Bob!", msg.body());
+
+    // The handshake is rejected when only one side enables the hybrid key exchange
+    let res = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_classical_listener"],
+            SecureChannelOptions::new()
+                .with_pq_hybrid()
+                .with_timeout(Duration::from_millis(500)),
+        )
+        .await;
+    assert!(res.is_err());
+
+    let res = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_timeout(Duration::from_millis(500)),
+        )
+        .await;
+    assert!(res.is_err());
+
+    // The hybrid key exchange doesn't apply to pre-shared keys
+    let err = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new()
+                .with_pq_hybrid()
+                .with_pre_shared_key(PreSharedKey::new([7u8; 32])),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::Misuse);
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_listener_rate_limit(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_limited_listener",
+            SecureChannelListenerOptions::new()
+                .as_consumer(bob_listener.flow_control_id())
+                .with_rate_limit(2, Duration::from_secs(60)),
+        )
+        .await?;
+
+    // Handshakes tunneled through the same channel come from the same source
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    for _ in 0..2 {
+        secure_channels
+            .create_secure_channel(
+                ctx,
+                alice.identifier(),
+                route![alice_channel.clone(), "bob_limited_listener"],
+                SecureChannelOptions::new().with_timeout(Duration::from_secs(5)),
+            )
+            .await?;
+    }
+
+    let res = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![alice_channel, "bob_limited_listener"],
+            SecureChannelOptions::new().with_timeout(Duration::from_millis(500)),
+        )
+        .await;
+    assert!(res.is_err());
+
+    // Other sources are not affected
+    let alice_other_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![alice_other_channel, "bob_limited_listener"],
+            SecureChannelOptions::new().with_timeout(Duration::from_secs(5)),
+        )
+        .await?;
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_wait_for_handshake(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let handle = secure_channels
+        .create_secure_channel_with_handle(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    let completed = handle.wait_for_handshake(ctx).await?;
+    assert_eq!(
+        completed.secure_channel().encryptor_address(),
+        handle.secure_channel().encryptor_address()
+    );
+    assert_eq!(completed.their_identifier(), bob.identifier());
+    assert_eq!(completed.cipher_suite(), CipherSuite::supported());
+    assert!(!completed.is_pq_hybrid());
+
+    // The address is allocated right away, but the handshake fails
+    let handle = secure_channels
+        .create_secure_channel_with_handle(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new()
+                .with_trust_policy(TrustIdentifierPolicy::new(alice.identifier().clone())),
+        )
+        .await?;
+    let address = handle.secure_channel().encryptor_address().clone();
+    let err = handle.wait_for_handshake(ctx).await.unwrap_err();
+    assert_ne!(err.code().kind, Kind::Timeout);
+
+    ctx.sleep(Duration::from_millis(50)).await;
+    assert!(secure_channels
+        .secure_channel_registry()
+        .get_channel_by_encryptor_address(&address)
+        .is_none());
+
+    ctx.stop().await
+}
+
+/// Forwards the first `forwarded` messages to the next hop of their onward route, drops the
+/// other ones, and records the last address of the return route of the second message
+struct Withholder {
+    forwarded: usize,
+    responder: Arc<std::sync::Mutex<Option<Address>>>,
+}
+
+#[ockam_core::async_trait]
+impl Worker for Withholder {
+    type Message = Any;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        if self.forwarded == 0 {
+            return Ok(());
+        }
+        self.forwarded -= 1;
+        if self.forwarded == 0 {
+            *self.responder.lock().unwrap() = Some(msg.return_route().recipient()?);
+        }
+
+        let mut local_msg = msg.into_local_message();
+        let transport = local_msg.transport_mut();
+        transport.onward_route.step()?;
+        transport.return_route.modify().prepend(ctx.address());
+        ctx.forward(local_msg).await
+    }
+}
+
+#[ockam_macros::test]
+async fn test_listener_handshake_timeout(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().with_handshake_timeout(Duration::from_millis(300)),
+        )
+        .await?;
+
+    // The first two handshake messages are delivered, the last one is withheld
+    let responder = Arc::new(std::sync::Mutex::new(None));
+    ctx.start_worker(
+        "withholder",
+        Withholder {
+            forwarded: 2,
+            responder: responder.clone(),
+        },
+    )
+    .await?;
+
+    let handle = secure_channels
+        .create_secure_channel_with_handle(
+            ctx,
+            alice.identifier(),
+            route!["withholder", "bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    ctx.sleep(Duration::from_millis(100)).await;
+    let responder = responder
+        .lock()
+        .unwrap()
+        .clone()
+        .expect("the responder should have replied");
+    assert!(ctx.list_workers().await?.contains(&responder));
+
+    // The responder is stopped after the timeout
+    ctx.sleep(Duration::from_millis(400)).await;
+    assert!(!ctx.list_workers().await?.contains(&responder));
+
+    handle.cancel(ctx).await?;
+    ctx.stop().await
+}
+
+struct Recorder {
+    frames: Arc<std::sync::Mutex<Vec<LocalMessage>>>,
+}
+
+#[ockam_core::async_trait]
+impl Worker for Recorder {
+    type Message = Any;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut local_msg = msg.into_local_message();
+        let transport = local_msg.transport_mut();
+        transport.onward_route.step()?;
+        transport.return_route.modify().prepend(ctx.address());
+        self.frames.lock().unwrap().push(local_msg.clone());
+        ctx.forward(local_msg).await
+    }
+}
+
+#[ockam_macros::test]
+async fn test_channel_replayed_frames_are_dropped(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().with_replay_window(4),
+        )
+        .await?;
+
+    let frames = Arc::new(std::sync::Mutex::new(vec![]));
+    ctx.start_worker(
+        "recorder",
+        Recorder {
+            frames: frames.clone(),
+        },
+    )
+    .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["recorder", "bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+
+    let mut first_frame = None;
+    for n in 1..10 {
+        child_ctx
+            .send(route![alice_channel.clone(), "child"], n.to_string())
+            .await?;
+        assert_eq!(child_ctx.receive::<String>().await?.body(), n.to_string());
+
+        let frame = frames.lock().unwrap().last().cloned().unwrap();
+        match n {
+            1 => first_frame = Some(frame),
+            // The captured frame is re-injected on the wire, it is never delivered
+            2 => ctx.forward(frame).await?,
+            _ => {}
+        }
+    }
+
+    // A frame older than the replay window is dropped as well
+    ctx.forward(first_frame.unwrap()).await?;
+    let res = child_ctx
+        .receive_extended::<String>(MessageReceiveOptions::new().with_timeout_secs(1))
+        .await;
+    assert!(res.is_err(), "a replayed frame must not be delivered");
+
+    let bob_channel = secure_channels
+        .secure_channel_registry()
+        .get_channel_list()
+        .into_iter()
+        .find(|entry| !entry.is_initiator())
+        .unwrap();
+    assert_eq!(bob_channel.replay_counters().replays_dropped(), 1);
+    assert_eq!(bob_channel.replay_counters().out_of_window_dropped(), 1);
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_compression(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().with_compression(CompressionAlgorithm::Deflate),
+        )
+        .await?;
+
+    let compressed_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_compression(CompressionAlgorithm::Deflate),
+        )
+        .await?;
+    // Compression is not used if only one side selected it
+    let uncompressed_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+
+    let large_message = r#"{"sensor": "temperature", "value": 21.5}"#.repeat(50);
+    let small_message = "Hello, Bob!".to_string();
+
+    for (channel, compression) in [
+        (&compressed_channel, Some(CompressionAlgorithm::Deflate)),
+        (&uncompressed_channel, None),
+    ] {
+        for message in [&large_message, &small_message] {
+            child_ctx
+                .send(
+                    route![channel.clone(), child_ctx.address()],
+                    message.clone(),
+                )
+                .await?;
+            let received = child_ctx.receive::<String>().await?;
+            assert_eq!(&received.body(), message);
+        }
+
+        let stats = secure_channels.secure_channel_stats(channel.encryptor_address())?;
+        assert_eq!(stats.compression(), compression);
+        if compression.is_some() {
+            assert!(stats.bytes_encrypted() < large_message.len());
+        } else {
+            assert!(stats.bytes_encrypted() > large_message.len() + small_message.len());
+        }
+    }
+
+    ctx.stop().await
+}
@@ -1,9 +1,19 @@
 use core::time::Duration;
 
-use ockam_core::{route, Result};
-use ockam_identity::SecureChannelOptions;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{route, AllowAll, MessageHeaders, Result};
+use ockam_identity::{
+    secure_channels, IdentityError, SecureChannelListenerOptions, SecureChannelOptions,
+};
 use ockam_node::Context;
-use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions, TcpTransport};
+use ockam_transport_tcp::{
+    MemoryTransport, TcpConnection, TcpConnectionOptions, TcpListener, TcpListenerOptions,
+    TcpTransport,
+};
+use ockam_transport_websocket::{
+    WebSocketConnectionOptions, WebSocketListenerOptions, WebSocketTransport,
+};
+use std::time::Instant;
 
 use crate::common::message_flow_auth::{
     create_secure_channel, create_secure_channel_listener, message_should_not_pass,
@@ -11,6 +21,41 @@ use crate::common::message_flow_auth::{
 
 mod common;
 
+/// Transport of the tests which run both over TCP and over the in-memory transport,
+/// which doesn't touch the OS network stack
+enum Transport {
+    Tcp(TcpTransport),
+    Memory(MemoryTransport),
+}
+
+impl Transport {
+    async fn tcp(ctx: &Context) -> Result<Self> {
+        Ok(Self::Tcp(TcpTransport::create(ctx).await?))
+    }
+
+    async fn memory(ctx: &Context) -> Result<Self> {
+        Ok(Self::Memory(MemoryTransport::create(ctx).await?))
+    }
+
+    async fn listen(&self, options: TcpListenerOptions) -> Result<TcpListener> {
+        match self {
+            Self::Tcp(tcp) => tcp.listen("127.0.0.1:0", options).await,
+            Self::Memory(memory) => memory.listen("127.0.0.1:0", options).await,
+        }
+    }
+
+    async fn connect(
+        &self,
+        listener: &TcpListener,
+        options: TcpConnectionOptions,
+    ) -> Result<TcpConnection> {
+        match self {
+            Self::Tcp(tcp) => tcp.connect(listener.socket_string(), options).await,
+            Self::Memory(memory) => memory.connect(listener.socket_string(), options).await,
+        }
+    }
+}
+
 // Alice: TCP connection + Secure Channel
 // Bob: TCP listener + Secure Channel listener
 #[ockam_macros::test]
@@ -100,3 +145,351 @@ async fn test2(ctx: &mut Context) -> Result<()> {
 
     ctx.stop().await
 }
+
+// Alice: WebSocket connection + Secure Channel
+// Bob: WebSocket listener + Secure Channel listener
+#[ockam_macros::test]
+async fn test1_websocket(ctx: &mut Context) -> Result<()> {
+    let ws_bob = WebSocketTransport::create(ctx).await?;
+    let listener = ws_bob
+        .listen("127.0.0.1:0", WebSocketListenerOptions::new())
+        .await?;
+
+    let ws_alice = WebSocketTransport::create(ctx).await?;
+    let connection_to_bob = ws_alice
+        .connect(listener.socket_string(), WebSocketConnectionOptions::new())
+        .await?
+        .sender_address()
+        .clone();
+
+    ctx.sleep(Duration::from_millis(50)).await; // Wait for workers to add themselves to the registry
+    let connection_to_alice = ws_bob
+        .registry()
+        .get_all_sender_workers()
+        .last()
+        .unwrap()
+        .clone();
+
+    message_should_not_pass(ctx, &connection_to_bob).await?;
+    message_should_not_pass(ctx, connection_to_alice.address()).await?;
+
+    let bob_listener_info = create_secure_channel_listener(ctx, listener.flow_control_id()).await?;
+
+    let channel_to_bob = create_secure_channel(ctx, &connection_to_bob).await?;
+    ctx.sleep(Duration::from_millis(50)).await; // Wait for workers to add themselves to the registry
+    let channel_to_alice = bob_listener_info.get_channel();
+
+    message_should_not_pass(ctx, &channel_to_bob.address).await?;
+    message_should_not_pass(ctx, &channel_to_alice).await?;
+
+    ctx.stop().await
+}
+
+// Alice: WebSocket connection + Secure Channel listener
+// Bob: WebSocket listener + Secure Channel
+#[ockam_macros::test]
+async fn test2_websocket(ctx: &mut Context) -> Result<()> {
+    let ws_bob = WebSocketTransport::create(ctx).await?;
+    let listener = ws_bob
+        .listen("127.0.0.1:0", WebSocketListenerOptions::new())
+        .await?;
+
+    let ws_alice = WebSocketTransport::create(ctx).await?;
+    let alice_ws_options = WebSocketConnectionOptions::new();
+    let alice_flow_control_id = alice_ws_options.flow_control_id();
+    let connection_to_bob = ws_alice
+        .connect(listener.socket_string(), alice_ws_options)
+        .await?
+        .sender_address()
+        .clone();
+    ctx.sleep(Duration::from_millis(50)).await; // Wait for workers to add themselves to the registry
+    let connection_to_alice = ws_bob
+        .registry()
+        .get_all_sender_workers()
+        .last()
+        .unwrap()
+        .clone();
+
+    message_should_not_pass(ctx, &connection_to_bob).await?;
+    message_should_not_pass(ctx, connection_to_alice.address()).await?;
+
+    let alice_listener_info = create_secure_channel_listener(ctx, &alice_flow_control_id).await?;
+
+    let channel_to_alice = create_secure_channel(ctx, connection_to_alice.address()).await?;
+    ctx.sleep(Duration::from_millis(50)).await; // Wait for workers to add themselves to the registry
+    let channel_to_bob = alice_listener_info.get_channel();
+
+    message_should_not_pass(ctx, &channel_to_alice.address).await?;
+    message_should_not_pass(ctx, &channel_to_bob).await?;
+
+    ctx.stop().await
+}
+
+// Alice: TCP connection + Secure Channel
+// Bob: two TCP listeners + Secure Channel listener requiring the first TCP listener
+#[ockam_macros::test]
+async fn test3(ctx: &mut Context) -> Result<()> {
+    test3_over(ctx, Transport::tcp(ctx).await?).await?;
+    ctx.stop().await
+}
+
+// Same as test3 over the in-memory transport
+#[ockam_macros::test]
+async fn test3_memory(ctx: &mut Context) -> Result<()> {
+    test3_over(ctx, Transport::memory(ctx).await?).await?;
+    ctx.stop().await
+}
+
+async fn test3_over(ctx: &mut Context, transport: Transport) -> Result<()> {
+    let listener = transport.listen(TcpListenerOptions::new()).await?;
+    let other_listener = transport.listen(TcpListenerOptions::new()).await?;
+
+    let connection_to_bob = transport
+        .connect(&listener, TcpConnectionOptions::new())
+        .await?;
+    let other_connection_to_bob = transport
+        .connect(&other_listener, TcpConnectionOptions::new())
+        .await?;
+
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "listener",
+            SecureChannelListenerOptions::new()
+                .as_consumer(other_listener.flow_control_id())
+                .require_transport(listener.flow_control_id()),
+        )
+        .await?;
+
+    let res = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![connection_to_bob, "listener"],
+            SecureChannelOptions::new().with_timeout(Duration::from_secs(1)),
+        )
+        .await;
+    assert!(
+        res.is_ok(),
+        "A handshake received through the required TCP listener is accepted"
+    );
+
+    // The rejection is sent back to the initiator, which doesn't wait for its timeout
+    let start = Instant::now();
+    let err = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![other_connection_to_bob, "listener"],
+            SecureChannelOptions::new().with_timeout(Duration::from_secs(10)),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().origin, Origin::Identity);
+    assert_eq!(err.code().kind, Kind::Misuse);
+    assert!(
+        err.to_string()
+            .contains(&IdentityError::SecureChannelUnauthorizedTransport.to_string()),
+        "A handshake which didn't go through the required TCP listener is rejected: {err}"
+    );
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    Ok(())
+}
+
+// Alice: TCP connection sending message extensions + Secure Channel, sending a traced message
+// Bob: TCP listener + Secure Channel listener
+#[ockam_macros::test]
+async fn test4(ctx: &mut Context) -> Result<()> {
+    let tcp_bob = TcpTransport::create(ctx).await?;
+    let listener = tcp_bob
+        .listen("127.0.0.1:0", TcpListenerOptions::new())
+        .await?;
+
+    let tcp_alice = TcpTransport::create(ctx).await?;
+    let connection_to_bob = tcp_alice
+        .connect(
+            listener.socket_string(),
+            TcpConnectionOptions::new().with_message_extensions(),
+        )
+        .await?;
+
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let listener_options =
+        SecureChannelListenerOptions::new().as_consumer(listener.flow_control_id());
+    let bob_flow_control_id = listener_options.spawner_flow_control_id();
+    secure_channels
+        .create_secure_channel_listener(ctx, bob.identifier(), "listener", listener_options)
+        .await?;
+
+    let channel_to_bob = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![connection_to_bob.clone(), "listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let mut bob_ctx = ctx.new_detached("bob_ctx", AllowAll, AllowAll).await?;
+    ctx.flow_controls()
+        .add_consumer("bob_ctx", &bob_flow_control_id);
+
+    let mut trace_receiver = ctx
+        .send_traced(
+            route![channel_to_bob.encryptor_address().clone(), "bob_ctx"],
+            "Hello".to_string(),
+        )
+        .await?;
+    let msg = bob_ctx.receive::<String>().await?;
+    assert_eq!(msg.body(), "Hello");
+
+    let trace = trace_receiver.receive().await?;
+    let hops = trace.addresses();
+    assert_eq!(hops.len(), 6, "unexpected hops {:?}", hops);
+    assert_eq!(hops[0], ctx.address());
+    assert_eq!(&hops[1], channel_to_bob.encryptor_address());
+    assert_eq!(&hops[2], connection_to_bob.sender_address());
+    // hops[3] and hops[4] are Bob's TCP receiver and Secure Channel decryptor
+    assert_eq!(hops[5], "bob_ctx".into());
+
+    ctx.stop().await
+}
+
+// Alice: TCP connection + Secure Channel, sending a message with headers
+// Bob: TCP listener + Secure Channel listener
+#[ockam_macros::test]
+async fn test6(ctx: &mut Context) -> Result<()> {
+    test6_over(ctx, Transport::tcp(ctx).await?).await?;
+    ctx.stop().await
+}
+
+// Same as test6 over the in-memory transport
+#[ockam_macros::test]
+async fn test6_memory(ctx: &mut Context) -> Result<()> {
+    test6_over(ctx, Transport::memory(ctx).await?).await?;
+    ctx.stop().await
+}
+
+async fn test6_over(ctx: &mut Context, transport: Transport) -> Result<()> {
+    let listener = transport.listen(TcpListenerOptions::new()).await?;
+    let connection_to_bob = transport
+        .connect(&listener, TcpConnectionOptions::new())
+        .await?;
+
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let listener_options =
+        SecureChannelListenerOptions::new().as_consumer(listener.flow_control_id());
+    let bob_flow_control_id = listener_options.spawner_flow_control_id();
+    secure_channels
+        .create_secure_channel_listener(ctx, bob.identifier(), "listener", listener_options)
+        .await?;
+
+    let channel_to_bob = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![connection_to_bob.clone(), "listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let mut bob_ctx = ctx.new_detached("bob_ctx", AllowAll, AllowAll).await?;
+    ctx.flow_controls()
+        .add_consumer("bob_ctx", &bob_flow_control_id);
+
+    let headers = MessageHeaders::new()
+        .with("trace-id", "4bf92f3577b34da6")?
+        .with("tenant", "acme")?;
+    ctx.send_with_headers(
+        route![channel_to_bob.encryptor_address().clone(), "bob_ctx"],
+        "Hello".to_string(),
+        headers.clone(),
+    )
+    .await?;
+    let msg = bob_ctx.receive::<String>().await?;
+    assert_eq!(msg.headers()?, headers);
+    assert_eq!(msg.body(), "Hello");
+
+    // Messages sent without headers have empty headers
+    ctx.send(
+        route![channel_to_bob.encryptor_address().clone(), "bob_ctx"],
+        "Hello".to_string(),
+    )
+    .await?;
+    let msg = bob_ctx.receive::<String>().await?;
+    assert!(msg.headers()?.is_empty());
+
+    Ok(())
+}
+
+// Alice: TCP connection + Secure Channel
+// Bob: TCP listener + Secure Channel listener
+#[ockam_macros::test]
+async fn test7(ctx: &mut Context) -> Result<()> {
+    test7_over(ctx, Transport::tcp(ctx).await?).await?;
+    ctx.stop().await
+}
+
+// Same as test7 over the in-memory transport
+#[ockam_macros::test]
+async fn test7_memory(ctx: &mut Context) -> Result<()> {
+    test7_over(ctx, Transport::memory(ctx).await?).await?;
+    ctx.stop().await
+}
+
+async fn test7_over(ctx: &mut Context, transport: Transport) -> Result<()> {
+    let listener = transport.listen(TcpListenerOptions::new()).await?;
+    let connection_to_bob = transport
+        .connect(&listener, TcpConnectionOptions::new())
+        .await?;
+
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "listener",
+            SecureChannelListenerOptions::new().as_consumer(listener.flow_control_id()),
+        )
+        .await?;
+
+    let channel_to_bob = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![connection_to_bob.clone(), "listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    // The route of the channel goes through the TCP connection to the decryptor of Bob
+    let entry = secure_channels
+        .secure_channel_registry()
+        .get_channel_by_encryptor_address(channel_to_bob.encryptor_address())
+        .unwrap();
+    let route = entry.route();
+    assert_eq!(route.len(), 2);
+    assert_eq!(route.next()?, connection_to_bob.sender_address());
+    assert_eq!(route.recipient()?, entry.their_decryptor_address());
+
+    Ok(())
+}
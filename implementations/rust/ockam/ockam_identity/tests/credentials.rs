@@ -1,17 +1,21 @@
-use std::sync::atomic::{AtomicI8, Ordering};
+use std::sync::atomic::{AtomicI8, AtomicUsize, Ordering};
 use std::time::Duration;
 
+use ockam_core::compat::collections::BTreeMap;
 use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::Kind;
 use ockam_core::{async_trait, Any, DenyAll};
 use ockam_core::{route, Result, Routed, Worker};
 use ockam_identity::models::CredentialSchemaIdentifier;
 use ockam_identity::secure_channels::secure_channels;
-use ockam_identity::utils::AttributesBuilder;
+use ockam_identity::utils::{now, AttributesBuilder};
 use ockam_identity::{
-    AuthorityService, CredentialAccessControl, CredentialsMemoryRetriever,
-    SecureChannelListenerOptions, SecureChannelOptions, TrustContext, TrustIdentifierPolicy,
+    AttributesEntry, AuthorityService, CredentialAccessControl, CredentialsMemoryRetriever,
+    Identities, IdentityAttributesWriter, SecureChannelListenerOptions, SecureChannelOptions,
+    TrustContext, TrustIdentifierPolicy, Vault,
 };
 use ockam_node::{Context, WorkerBuilder};
+use ockam_vault::{Sha256Output, Signature, VaultForVerifyingSignatures, VerifyingPublicKey};
 
 #[ockam_macros::test]
 async fn full_flow_oneway(ctx: &mut Context) -> Result<()> {
@@ -329,3 +333,173 @@ impl Worker for CountingWorker {
         Ok(())
     }
 }
+
+#[tokio::test]
+async fn get_attributes() -> Result<()> {
+    let identities = Identities::builder().build();
+    let subject = identities
+        .identities_creation()
+        .create_identity()
+        .await?
+        .identifier()
+        .clone();
+    assert!(identities.get_attributes(&subject).await?.is_none());
+    assert_eq!(
+        identities.get_attribute::<String>(&subject, "role").await?,
+        None
+    );
+
+    let repository = identities.repository();
+    repository
+        .put_attributes(
+            &subject,
+            AttributesEntry::new(BTreeMap::new(), now()?, None, None),
+        )
+        .await?;
+    let entry = identities.get_attributes(&subject).await?.unwrap();
+    assert!(entry.attrs().is_empty());
+
+    repository
+        .put_attribute_value(&subject, b"role".to_vec(), b"admin".to_vec())
+        .await?;
+    repository
+        .put_attribute_value(&subject, b"is_superuser".to_vec(), b"true".to_vec())
+        .await?;
+    repository
+        .put_attribute_value(&subject, b"invalid".to_vec(), vec![0xff])
+        .await?;
+
+    assert_eq!(
+        identities.get_attribute::<String>(&subject, "role").await?,
+        Some("admin".to_string())
+    );
+    assert_eq!(
+        identities
+            .get_attribute::<bool>(&subject, "is_superuser")
+            .await?,
+        Some(true)
+    );
+    assert_eq!(
+        identities
+            .get_attribute::<Vec<u8>>(&subject, "invalid")
+            .await?,
+        Some(vec![0xff])
+    );
+    assert_eq!(
+        identities
+            .get_attribute::<String>(&subject, "missing")
+            .await?,
+        None
+    );
+    let err = identities
+        .get_attribute::<String>(&subject, "invalid")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::Serialization);
+
+    Ok(())
+}
+
+/// Counts the signatures it verifies
+struct CountingVerifyingVault {
+    verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
+    verifications: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl VaultForVerifyingSignatures for CountingVerifyingVault {
+    async fn sha256(&self, data: &[u8]) -> Result<Sha256Output> {
+        self.verifying_vault.sha256(data).await
+    }
+
+    async fn verify_signature(
+        &self,
+        verifying_public_key: &VerifyingPublicKey,
+        data: &[u8],
+        signature: &Signature,
+    ) -> Result<bool> {
+        self.verifications.fetch_add(1, Ordering::Relaxed);
+        self.verifying_vault
+            .verify_signature(verifying_public_key, data, signature)
+            .await
+    }
+}
+
+#[tokio::test]
+async fn repeated_credential_verification_is_cached() -> Result<()> {
+    let mut counts = vec![];
+    for cache_capacity in [0, 16] {
+        let verifications = Arc::new(AtomicUsize::new(0));
+        let vault = Vault::new(
+            Vault::create_identity_vault(),
+            Vault::create_secure_channel_vault(),
+            Vault::create_credential_vault(),
+            Arc::new(CountingVerifyingVault {
+                verifying_vault: Vault::create_verifying_vault(),
+                verifications: verifications.clone(),
+            }),
+        );
+        let identities = Identities::builder()
+            .with_vault(vault)
+            .with_credentials_cache(cache_capacity, Duration::from_secs(60))
+            .build();
+
+        let authority = identities.identities_creation().create_identity().await?;
+        let subject = identities.identities_creation().create_identity().await?;
+        let credentials = identities.credentials();
+        let credential = credentials
+            .credentials_creation()
+            .issue_credential(
+                authority.identifier(),
+                subject.identifier(),
+                AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                    .with_attribute("group", "fleet")
+                    .build(),
+                Duration::from_secs(60),
+            )
+            .await?;
+
+        verifications.store(0, Ordering::Relaxed);
+        for _ in 0..10 {
+            credentials
+                .credentials_verification()
+                .verify_credential(
+                    Some(subject.identifier()),
+                    &[authority.identifier().clone()],
+                    &credential,
+                )
+                .await?;
+        }
+        counts.push(verifications.load(Ordering::Relaxed));
+
+        // The cache still checks the subject and the authority of the credential
+        let verification = credentials.credentials_verification();
+        assert!(verification
+            .verify_credential(
+                Some(authority.identifier()),
+                &[authority.identifier().clone()],
+                &credential,
+            )
+            .await
+            .is_err());
+        assert!(verification
+            .verify_credential(
+                Some(subject.identifier()),
+                &[subject.identifier().clone()],
+                &credential,
+            )
+            .await
+            .is_err());
+
+        assert_eq!(identities.credentials_cache().len(), cache_capacity.min(1));
+        identities.credentials_cache().clear();
+        assert!(identities.credentials_cache().is_empty());
+    }
+
+    // Without the cache, the signatures are checked on every verification
+    let (uncached, cached) = (counts[0], counts[1]);
+    assert!(cached > 0);
+    assert_eq!(uncached, 10 * cached);
+
+    Ok(())
+}
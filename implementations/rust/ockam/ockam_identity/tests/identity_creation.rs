@@ -1,8 +1,8 @@
 use core::str::FromStr;
 use ockam_core::Result;
 use ockam_identity::models::Identifier;
-use ockam_identity::{identities, Identity};
-use ockam_vault::SigningKeyType;
+use ockam_identity::{identities, Identities, Identity, Vault};
+use ockam_vault::{SigningKeyType, SoftwareVaultForSigning};
 
 #[tokio::test]
 async fn create_and_retrieve() -> Result<()> {
@@ -78,3 +78,100 @@ async fn create_p256() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn export_and_import_private_identity() -> Result<()> {
+    let vault = Vault::create();
+    let identities = Identities::builder().with_vault(vault.clone()).build();
+    let identity = identities.identities_creation().create_identity().await?;
+
+    let exported = identities
+        .identities_creation()
+        .export_private_identity(identity.identifier())
+        .await?;
+
+    // A restarted node only keeps the vault
+    let restarted = Identities::builder().with_vault(vault).build();
+    let imported = restarted
+        .identities_creation()
+        .import_exported_private_identity(&exported)
+        .await?;
+    assert_eq!(imported, identity);
+
+    // The secret key is usable to sign new changes
+    restarted
+        .identities_creation()
+        .rotate_identity(imported.identifier())
+        .await?;
+
+    // A peer which knew the original identity accepts the imported one
+    let peer = identities();
+    peer.identities_creation()
+        .import(Some(identity.identifier()), &identity.export()?)
+        .await?;
+    peer.identities_creation()
+        .import(Some(identity.identifier()), &imported.export()?)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn import_private_identity_without_secret_key_fails() -> Result<()> {
+    let identities = identities();
+    let identity = identities.identities_creation().create_identity().await?;
+
+    let exported = identities
+        .identities_creation()
+        .export_private_identity(identity.identifier())
+        .await?;
+
+    // This vault doesn't contain the secret key
+    let other = Identities::builder().with_vault(Vault::create()).build();
+    let result = other
+        .identities_creation()
+        .import_exported_private_identity(&exported)
+        .await;
+
+    let error = result.expect_err("the secret key is not in the vault");
+    assert!(error.to_string().contains("MissingSecretKey"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_identity_with_seed_is_deterministic() -> Result<()> {
+    let create = |seed: [u8; 32]| async move {
+        let signing_vault = SoftwareVaultForSigning::create();
+        let vault = Vault::new(
+            signing_vault.clone(),
+            Vault::create_secure_channel_vault(),
+            Vault::create_credential_vault(),
+            Vault::create_verifying_vault(),
+        );
+        let identities = Identities::builder().with_vault(vault).build();
+        identities
+            .identities_creation()
+            .create_identity_with_seed(&signing_vault, seed)
+            .await
+    };
+
+    let identity1 = create([1; 32]).await?;
+    let identity2 = create([1; 32]).await?;
+    let identity3 = create([2; 32]).await?;
+    assert_eq!(identity1.identifier(), identity2.identifier());
+    assert_eq!(identity1, identity2);
+    assert_ne!(identity1.identifier(), identity3.identifier());
+
+    // The seeded key must be stored in the signing vault of the identities,
+    // and it is not left in the other vault
+    let other_vault = SoftwareVaultForSigning::create();
+    let res = identities()
+        .identities_creation()
+        .create_identity_with_seed(&other_vault, [1; 32])
+        .await;
+    assert!(res.is_err());
+    assert_eq!(other_vault.number_of_keys().await?, 0);
+
+    Ok(())
+}
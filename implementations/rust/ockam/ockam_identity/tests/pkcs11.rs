@@ -0,0 +1,83 @@
+use ockam_core::Result;
+use ockam_identity::{Identities, Vault};
+use ockam_vault::{SigningKeyType, VaultForSigning};
+use ockam_vault_pkcs11::{Pkcs11Config, Pkcs11SigningVault};
+use std::sync::Arc;
+
+/// These tests need to be executed with a PKCS#11 token, for example SoftHSM,
+/// configured with the following environment variables
+/// PKCS11_MODULE: path to the PKCS#11 module (e.g. /usr/lib/softhsm/libsofthsm2.so)
+/// PKCS11_SLOT: id of the slot containing the token
+/// PKCS11_PIN: user PIN of the token
+async fn create_pkcs11_vault() -> Result<Arc<Pkcs11SigningVault>> {
+    let module = std::env::var("PKCS11_MODULE").expect("PKCS11_MODULE is not set");
+    let slot = std::env::var("PKCS11_SLOT")
+        .expect("PKCS11_SLOT is not set")
+        .parse()
+        .expect("PKCS11_SLOT is not a number");
+    let pin = std::env::var("PKCS11_PIN").expect("PKCS11_PIN is not set");
+
+    Ok(Arc::new(
+        Pkcs11SigningVault::create(Pkcs11Config::new(module, slot, pin)).await?,
+    ))
+}
+
+#[tokio::test]
+#[ignore]
+async fn create_identity_with_pkcs11_random_key() -> Result<()> {
+    let mut vault = Vault::create();
+    let pkcs11_vault = create_pkcs11_vault().await?;
+    vault.identity_vault = pkcs11_vault.clone();
+    let identities = Identities::builder().with_vault(vault.clone()).build();
+
+    let identity = identities
+        .identities_creation()
+        .identity_builder()
+        .with_random_key(SigningKeyType::ECDSASHA256CurveP256)
+        .build()
+        .await?;
+
+    identities
+        .identities_creation()
+        .import(Some(identity.identifier()), &identity.export()?)
+        .await?;
+
+    let key = identities
+        .identities_keys()
+        .get_secret_key(&identity)
+        .await?;
+
+    pkcs11_vault.delete_signing_secret_key(key).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore]
+async fn create_identity_with_pkcs11_pregenerated_key() -> Result<()> {
+    let mut vault = Vault::create();
+    let pkcs11_vault = create_pkcs11_vault().await?;
+    vault.identity_vault = pkcs11_vault.clone();
+    let identities = Identities::builder().with_vault(vault.clone()).build();
+
+    // create a secret key on the token
+    let key_id = pkcs11_vault
+        .generate_signing_secret_key(SigningKeyType::ECDSASHA256CurveP256)
+        .await?;
+
+    let identity = identities
+        .identities_creation()
+        .identity_builder()
+        .with_existing_key(key_id.clone())
+        .build()
+        .await?;
+
+    identities
+        .identities_creation()
+        .import(Some(identity.identifier()), &identity.export()?)
+        .await?;
+
+    pkcs11_vault.delete_signing_secret_key(key_id).await?;
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use ockam_core::Result;
+use ockam_identity::{identities, SealingGroup};
+
+#[allow(non_snake_case)]
+#[tokio::test]
+async fn seal_to_group__members_open_and_verify_sender__non_member_cannot_open() -> Result<()> {
+    let sender_identities = identities();
+    let member1_identities = identities();
+    let member2_identities = identities();
+    let outsider_identities = identities();
+
+    let sender = sender_identities
+        .identities_creation()
+        .create_identity()
+        .await?;
+    let member1 = member1_identities
+        .identities_creation()
+        .create_identity()
+        .await?;
+    let member2 = member2_identities
+        .identities_creation()
+        .create_identity()
+        .await?;
+    let outsider = outsider_identities
+        .identities_creation()
+        .create_identity()
+        .await?;
+
+    // Members only share their public key material with the sender
+    let member1_public_key = member1_identities
+        .sealed_messages()
+        .member_public_key(member1.identifier())
+        .await?;
+    let member2_public_key = member2_identities
+        .sealed_messages()
+        .member_public_key(member2.identifier())
+        .await?;
+    // Creating the key makes sure the outsider fails because it is not a member
+    outsider_identities
+        .sealed_messages()
+        .member_public_key(outsider.identifier())
+        .await?;
+
+    let group = SealingGroup::new("engineering")
+        .with_member(member1.identifier().clone(), member1_public_key)
+        .with_member(member2.identifier().clone(), member2_public_key);
+
+    let sealed = sender_identities
+        .sealed_messages()
+        .seal(sender.identifier(), &group, b"hello group")
+        .await?;
+
+    for (member_identities, member) in [
+        (&member1_identities, &member1),
+        (&member2_identities, &member2),
+    ] {
+        let opened = member_identities
+            .sealed_messages()
+            .open(member.identifier(), &sealed)
+            .await?;
+        assert_eq!(opened.group, "engineering");
+        assert_eq!(&opened.sender, sender.identifier());
+        assert_eq!(opened.payload, b"hello group");
+    }
+
+    let res = outsider_identities
+        .sealed_messages()
+        .open(outsider.identifier(), &sealed)
+        .await;
+    assert!(res.is_err());
+
+    // Even pretending to be a member doesn't help without the member's secret key
+    let res = outsider_identities
+        .sealed_messages()
+        .open(member1.identifier(), &sealed)
+        .await;
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[tokio::test]
+async fn tampered_sealed_message__open__should_fail() -> Result<()> {
+    let sender_identities = identities();
+    let member_identities = identities();
+
+    let sender = sender_identities
+        .identities_creation()
+        .create_identity()
+        .await?;
+    let member = member_identities
+        .identities_creation()
+        .create_identity()
+        .await?;
+
+    let member_public_key = member_identities
+        .sealed_messages()
+        .member_public_key(member.identifier())
+        .await?;
+    let group =
+        SealingGroup::new("group").with_member(member.identifier().clone(), member_public_key);
+
+    let mut sealed = sender_identities
+        .sealed_messages()
+        .seal(sender.identifier(), &group, b"hello")
+        .await?;
+    let last = sealed.data.len() - 1;
+    sealed.data[last] ^= 1;
+
+    let res = member_identities
+        .sealed_messages()
+        .open(member.identifier(), &sealed)
+        .await;
+    assert!(res.is_err());
+
+    Ok(())
+}
@@ -1,11 +1,170 @@
-/// Sender used to send payload messages
-pub type MessageSender<T> = crate::tokio::sync::mpsc::Sender<T>;
-/// Receiver used to receive payload messages
-pub type MessageReceiver<T> = crate::tokio::sync::mpsc::Receiver<T>;
-
-/// Create message channel
-pub fn message_channel<T>() -> (MessageSender<T>, MessageReceiver<T>) {
-    crate::tokio::sync::mpsc::channel(16)
+use crate::error::NodeError;
+#[cfg(feature = "std")]
+use crate::error::WorkerReason;
+use crate::MailboxCapacity;
+#[cfg(feature = "std")]
+use crate::OverflowPolicy;
+use core::fmt::Debug;
+use ockam_core::Result;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, Weak};
+
+/// Sender used to send payload messages to a mailbox
+///
+/// Sending applies the [`OverflowPolicy`](crate::OverflowPolicy) of the mailbox when it's full.
+#[derive(Debug)]
+pub struct MessageSender<T> {
+    sender: crate::tokio::sync::mpsc::Sender<T>,
+    #[cfg(feature = "std")]
+    overflow_policy: OverflowPolicy,
+    /// Receiving end of the mailbox, used to drop the oldest message with
+    /// [`OverflowPolicy::DropOldest`]. It's not owned by the senders so that the channel
+    /// is closed when the receiver is dropped.
+    #[cfg(feature = "std")]
+    receiver: Weak<Mutex<crate::tokio::sync::mpsc::Receiver<T>>>,
+}
+
+impl<T> Clone for MessageSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            #[cfg(feature = "std")]
+            overflow_policy: self.overflow_policy,
+            #[cfg(feature = "std")]
+            receiver: self.receiver.clone(),
+        }
+    }
+}
+
+impl<T: Debug> MessageSender<T> {
+    /// Send a message to the mailbox
+    #[cfg(feature = "std")]
+    pub async fn send(&self, msg: T) -> Result<()> {
+        use crate::tokio::sync::mpsc::error::{SendError, TrySendError};
+
+        if self.overflow_policy == OverflowPolicy::Block {
+            return self
+                .sender
+                .send(msg)
+                .await
+                .map_err(NodeError::from_send_err);
+        }
+
+        let mut msg = msg;
+        loop {
+            match self.sender.try_send(msg) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Closed(msg)) => {
+                    return Err(NodeError::from_send_err(SendError(msg)))
+                }
+                Err(TrySendError::Full(_))
+                    if self.overflow_policy == OverflowPolicy::DropNewest =>
+                {
+                    return Err(
+                        NodeError::WorkerState(WorkerReason::MailboxFull).resource_exhausted()
+                    )
+                }
+                Err(TrySendError::Full(rejected)) => {
+                    msg = rejected;
+                    // Make room for the new message. If the receiver is busy it's
+                    // taking a message out of the mailbox, so we can just try again.
+                    let receiver = match self.receiver.upgrade() {
+                        Some(receiver) => receiver,
+                        // The receiver was dropped in the meantime, the next attempt will fail
+                        None => continue,
+                    };
+                    let dropped = match receiver.try_lock() {
+                        Ok(mut receiver) => receiver.try_recv().is_ok(),
+                        Err(_) => false,
+                    };
+                    if dropped {
+                        trace!("Dropped the oldest message of a full mailbox");
+                    } else {
+                        crate::tokio::task::yield_now().await
+                    }
+                }
+            }
+        }
+    }
+
+    /// Send a message to the mailbox
+    #[cfg(not(feature = "std"))]
+    pub async fn send(&self, msg: T) -> Result<()> {
+        self.sender
+            .send(msg)
+            .await
+            .map_err(NodeError::from_send_err)
+    }
+}
+
+/// Receiving end of a mailbox
+#[derive(Debug)]
+enum MailboxReceiver<T> {
+    Owned(crate::tokio::sync::mpsc::Receiver<T>),
+    /// Shared with the senders, which drop the oldest message when the mailbox is full
+    #[cfg(feature = "std")]
+    Shared(Arc<Mutex<crate::tokio::sync::mpsc::Receiver<T>>>),
+}
+
+/// Receiver used to receive payload messages from a mailbox
+#[derive(Debug)]
+pub struct MessageReceiver<T> {
+    receiver: MailboxReceiver<T>,
+}
+
+impl<T> MessageReceiver<T> {
+    /// Receive the next message, or `None` if all the senders were dropped
+    pub async fn recv(&mut self) -> Option<T> {
+        match &mut self.receiver {
+            MailboxReceiver::Owned(receiver) => receiver.recv().await,
+            // The lock is only held while polling, never across an await point
+            #[cfg(feature = "std")]
+            MailboxReceiver::Shared(receiver) => {
+                core::future::poll_fn(|cx| receiver.lock().unwrap().poll_recv(cx)).await
+            }
+        }
+    }
+
+    /// Return the next message if one is already waiting in the mailbox
+    #[cfg(feature = "std")]
+    pub fn try_recv(&mut self) -> Option<T> {
+        match &mut self.receiver {
+            MailboxReceiver::Owned(receiver) => receiver.try_recv().ok(),
+            MailboxReceiver::Shared(receiver) => receiver.lock().unwrap().try_recv().ok(),
+        }
+    }
+}
+
+/// Create message channel for a mailbox with the given capacity
+pub fn message_channel<T>(capacity: MailboxCapacity) -> (MessageSender<T>, MessageReceiver<T>) {
+    let (sender, receiver) = crate::tokio::sync::mpsc::channel(capacity.capacity());
+
+    // Only the mailboxes dropping their oldest message need a lock around their receiver
+    #[cfg(feature = "std")]
+    if capacity.overflow_policy() == OverflowPolicy::DropOldest {
+        let receiver = Arc::new(Mutex::new(receiver));
+        let sender = MessageSender {
+            sender,
+            overflow_policy: capacity.overflow_policy(),
+            receiver: Arc::downgrade(&receiver),
+        };
+        let receiver = MessageReceiver {
+            receiver: MailboxReceiver::Shared(receiver),
+        };
+        return (sender, receiver);
+    }
+
+    let sender = MessageSender {
+        sender,
+        #[cfg(feature = "std")]
+        overflow_policy: capacity.overflow_policy(),
+        #[cfg(feature = "std")]
+        receiver: Weak::new(),
+    };
+    let receiver = MessageReceiver {
+        receiver: MailboxReceiver::Owned(receiver),
+    };
+    (sender, receiver)
 }
 
 /// Router sender
@@ -1,6 +1,9 @@
-use crate::channel_types::{SmallReceiver, SmallSender};
+use super::PendingMessage;
+use crate::channel_types::{MessageReceiver, SmallSender};
+use crate::metrics_recorder::MetricsRecorder;
+use crate::relay::RunningRelays;
 use crate::tokio::runtime::Handle;
-use crate::{error::*, AsyncDropSender, NodeMessage};
+use crate::{error::*, AsyncDropSender, NodeMessage, WorkerInfo};
 use core::sync::atomic::AtomicUsize;
 use ockam_core::compat::collections::HashMap;
 use ockam_core::compat::sync::{Arc, RwLock};
@@ -16,17 +19,31 @@ use ockam_transport_core::Transport;
 /// A default timeout in seconds
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Default maximum size of the payload of a message, in bytes
+///
+/// The workers of a transport may only accept smaller messages, see
+/// [`crate::WorkerBuilder`]: the TCP transport frames messages with a 16-bit length, and
+/// sending a message which doesn't fit to a TCP connection fails.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
 /// Context contains Node state and references to the runtime.
 pub struct Context {
     pub(super) mailboxes: Mailboxes,
     pub(super) sender: SmallSender<NodeMessage>,
     pub(super) rt: Handle,
-    pub(super) receiver: SmallReceiver<RelayMessage>,
+    pub(super) receiver: MessageReceiver<RelayMessage>,
     pub(super) async_drop_sender: Option<AsyncDropSender>,
     pub(super) mailbox_count: Arc<AtomicUsize>,
     /// List of transports used to resolve external addresses to local workers in routes
     pub(super) transports: Arc<RwLock<HashMap<TransportType, Arc<dyn Transport>>>>,
     pub(super) flow_controls: FlowControls,
+    /// Recorder shared by all the contexts of the node
+    pub(super) metrics: MetricsRecorder,
+    pub(super) max_message_size: usize,
+    /// Relays of the node which didn't return yet, shared by all the contexts of the node
+    pub(super) running_relays: RunningRelays,
+    /// Message taken from the mailbox by a `receive` call which was cancelled
+    pub(super) pending_message: Option<PendingMessage>,
 }
 
 /// This trait can be used to integrate transports into a node
@@ -48,6 +65,12 @@ impl Debug for Context {
 }
 
 impl Context {
+    /// Maximum size of the payload of the messages sent or received by this node, see
+    /// [`crate::NodeBuilder::with_max_message_size`]
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
     /// Return runtime clone
     pub fn runtime(&self) -> &Handle {
         &self.rt
@@ -58,6 +81,11 @@ impl Context {
         self.mailbox_count.clone()
     }
 
+    /// Return the relays of the node which didn't return yet
+    pub(crate) fn running_relays(&self) -> &RunningRelays {
+        &self.running_relays
+    }
+
     /// Return a reference to sender
     pub(crate) fn sender(&self) -> &SmallSender<NodeMessage> {
         &self.sender
@@ -128,6 +156,26 @@ impl Context {
             .take_workers()
     }
 
+    /// Return a description of all the workers and processors registered on a node,
+    /// including their [`WorkerKind`](crate::WorkerKind)
+    ///
+    /// The list is a snapshot of the node registry: it is consistent even while other
+    /// workers are started or stopped.
+    pub async fn list_workers_info(&self) -> Result<Vec<WorkerInfo>> {
+        let (msg, mut reply_rx) = NodeMessage::list_workers_info();
+
+        self.sender
+            .send(msg)
+            .await
+            .map_err(NodeError::from_send_err)?;
+
+        reply_rx
+            .recv()
+            .await
+            .ok_or_else(|| NodeError::NodeState(NodeReason::Unknown).internal())??
+            .take_workers_info()
+    }
+
     /// Send a shutdown acknowledgement to the router
     pub(crate) async fn send_stop_ack(&self) -> Result<()> {
         self.sender
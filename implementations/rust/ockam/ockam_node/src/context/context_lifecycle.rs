@@ -12,9 +12,11 @@ use ockam_transport_core::Transport;
 
 use crate::async_drop::AsyncDrop;
 use crate::channel_types::{message_channel, small_channel, SmallReceiver, SmallSender};
+use crate::metrics_recorder::MetricsRecorder;
+use crate::relay::{CtrlSignal, RunningRelays};
 use crate::tokio::{self, runtime::Handle};
-use crate::{debugger, Context};
-use crate::{error::*, relay::CtrlSignal, router::SenderPair, NodeMessage};
+use crate::{debugger, Context, MailboxCapacity};
+use crate::{error::*, router::SenderPair, NodeMessage, WorkerKind};
 
 /// A special type of `Context` that has no worker relay and inherits
 /// the parent `Context`'s access control
@@ -55,15 +57,20 @@ impl Context {
     ///
     /// `async_drop_sender` must be provided when creating a detached
     /// Context type (i.e. not backed by a worker relay).
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         rt: Handle,
         sender: SmallSender<NodeMessage>,
         mailboxes: Mailboxes,
+        mailbox_capacity: MailboxCapacity,
         async_drop_sender: Option<AsyncDropSender>,
         transports: Arc<RwLock<HashMap<TransportType, Arc<dyn Transport>>>>,
         flow_controls: &FlowControls,
+        metrics: MetricsRecorder,
+        max_message_size: usize,
+        running_relays: RunningRelays,
     ) -> (Self, SenderPair, SmallReceiver<CtrlSignal>) {
-        let (mailbox_tx, receiver) = message_channel();
+        let (mailbox_tx, receiver) = message_channel(mailbox_capacity);
         let (ctrl_tx, ctrl_rx) = small_channel();
         (
             Self {
@@ -75,6 +82,10 @@ impl Context {
                 mailbox_count: Arc::new(0.into()),
                 transports,
                 flow_controls: flow_controls.clone(),
+                metrics,
+                max_message_size,
+                running_relays,
+                pending_message: None,
             },
             SenderPair {
                 msgs: mailbox_tx,
@@ -87,33 +98,46 @@ impl Context {
     pub(crate) fn copy_with_mailboxes(
         &self,
         mailboxes: Mailboxes,
+        mailbox_capacity: MailboxCapacity,
     ) -> (Context, SenderPair, SmallReceiver<CtrlSignal>) {
         Context::new(
             self.runtime().clone(),
             self.sender().clone(),
             mailboxes,
+            mailbox_capacity,
             None,
             self.transports.clone(),
             &self.flow_controls,
+            self.metrics.clone(),
+            self.max_message_size,
+            self.running_relays.clone(),
         )
     }
 
     pub(crate) fn copy_with_mailboxes_detached(
         &self,
         mailboxes: Mailboxes,
+        mailbox_capacity: MailboxCapacity,
         drop_sender: AsyncDropSender,
     ) -> (Context, SenderPair, SmallReceiver<CtrlSignal>) {
         Context::new(
             self.runtime().clone(),
             self.sender().clone(),
             mailboxes,
+            mailbox_capacity,
             Some(drop_sender),
             self.transports.clone(),
             &self.flow_controls,
+            self.metrics.clone(),
+            self.max_message_size,
+            self.running_relays.clone(),
         )
     }
 
     /// Utility function to sleep tasks from other crates
+    ///
+    /// The duration is measured with the runtime monotonic clock, so it's not affected
+    /// by wall-clock adjustments
     #[doc(hidden)]
     pub async fn sleep(&self, dur: Duration) {
         tokio::time::sleep(dur).await;
@@ -124,7 +148,9 @@ impl Context {
         &self,
         mailboxes: Mailboxes,
     ) -> Result<DetachedContext> {
-        let ctx = self.new_detached_impl(mailboxes).await?;
+        let ctx = self
+            .new_detached_impl(mailboxes, MailboxCapacity::default())
+            .await?;
 
         debugger::log_inherit_context("DETACHED_WITH_MB", self, &ctx);
 
@@ -144,14 +170,38 @@ impl Context {
         outgoing: impl OutgoingAccessControl,
     ) -> Result<DetachedContext> {
         let mailboxes = Mailboxes::main(address.into(), Arc::new(incoming), Arc::new(outgoing));
-        let ctx = self.new_detached_impl(mailboxes).await?;
+        let ctx = self
+            .new_detached_impl(mailboxes, MailboxCapacity::default())
+            .await?;
+
+        debugger::log_inherit_context("DETACHED", self, &ctx);
+
+        Ok(ctx)
+    }
+
+    /// Create a new detached `Context` with a bounded mailbox
+    ///
+    /// See [`new_detached()`](Self::new_detached) and [`MailboxCapacity`].
+    pub async fn new_detached_with_mailbox_capacity(
+        &self,
+        address: impl Into<Address>,
+        incoming: impl IncomingAccessControl,
+        outgoing: impl OutgoingAccessControl,
+        mailbox_capacity: MailboxCapacity,
+    ) -> Result<DetachedContext> {
+        let mailboxes = Mailboxes::main(address.into(), Arc::new(incoming), Arc::new(outgoing));
+        let ctx = self.new_detached_impl(mailboxes, mailbox_capacity).await?;
 
         debugger::log_inherit_context("DETACHED", self, &ctx);
 
         Ok(ctx)
     }
 
-    async fn new_detached_impl(&self, mailboxes: Mailboxes) -> Result<DetachedContext> {
+    async fn new_detached_impl(
+        &self,
+        mailboxes: Mailboxes,
+        mailbox_capacity: MailboxCapacity,
+    ) -> Result<DetachedContext> {
         // A detached Context exists without a worker relay, which
         // requires special shutdown handling.  To allow the Drop
         // handler to interact with the Node runtime, we use an
@@ -165,11 +215,18 @@ impl Context {
 
         // Create a new context and get access to the mailbox senders
         let addresses = mailboxes.addresses();
-        let (ctx, sender, _) = self.copy_with_mailboxes_detached(mailboxes, drop_sender);
+        let (ctx, sender, _) =
+            self.copy_with_mailboxes_detached(mailboxes, mailbox_capacity, drop_sender);
 
         // Create a "detached relay" and register it with the router
-        let (msg, mut rx) =
-            NodeMessage::start_worker(addresses, sender, true, Arc::clone(&self.mailbox_count));
+        let (msg, mut rx) = NodeMessage::start_worker(
+            addresses,
+            sender,
+            true,
+            Arc::clone(&self.mailbox_count),
+            None,
+            WorkerKind::application(),
+        );
         self.sender
             .send(msg)
             .await
@@ -195,12 +252,13 @@ mod tests {
 
         // after a copy with new mailboxes the list of transports should be intact
         let mailboxes = Mailboxes::new(Mailbox::deny_all("address"), vec![]);
-        let (copy, _, _) = ctx.copy_with_mailboxes(mailboxes.clone());
+        let (copy, _, _) = ctx.copy_with_mailboxes(mailboxes.clone(), MailboxCapacity::default());
         assert!(copy.is_transport_registered(transport.transport_type()));
 
         // after a detached copy with new mailboxes the list of transports should be intact
         let (_, drop_sender) = AsyncDrop::new(ctx.sender.clone());
-        let (copy, _, _) = ctx.copy_with_mailboxes_detached(mailboxes, drop_sender);
+        let (copy, _, _) =
+            ctx.copy_with_mailboxes_detached(mailboxes, MailboxCapacity::default(), drop_sender);
         assert!(copy.is_transport_registered(transport.transport_type()));
 
         ctx.stop().await
@@ -0,0 +1,28 @@
+use ockam_core::compat::sync::Arc;
+
+use crate::{Context, Metrics};
+
+impl Context {
+    /// Install a [`Metrics`] recorder for all the workers of this node,
+    /// replacing the previous one
+    pub fn set_metrics(&self, metrics: Arc<dyn Metrics>) {
+        self.metrics.install(metrics)
+    }
+
+    /// Remove the [`Metrics`] recorder of this node
+    pub fn remove_metrics(&self) {
+        self.metrics.uninstall()
+    }
+
+    /// Return true if a [`Metrics`] recorder is installed on this node
+    pub fn has_metrics(&self) -> bool {
+        self.metrics.is_installed()
+    }
+
+    /// Emit an event to the [`Metrics`] recorder of this node.
+    /// The event is only created when a recorder is installed
+    #[inline]
+    pub fn record_metrics(&self, event: impl FnOnce(&dyn Metrics)) {
+        self.metrics.record(event)
+    }
+}
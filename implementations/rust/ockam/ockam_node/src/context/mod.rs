@@ -1,8 +1,10 @@
 #[allow(clippy::module_inception)]
 mod context;
 mod context_lifecycle;
+mod metrics;
 mod receive_message;
 mod register_router;
+mod route_trace;
 mod send_message;
 mod stop_env;
 mod transports;
@@ -10,8 +12,10 @@ mod worker_lifecycle;
 
 pub use context::*;
 pub use context_lifecycle::*;
+pub use metrics::*;
 pub use receive_message::*;
 pub use register_router::*;
+pub use route_trace::*;
 pub use send_message::*;
 pub use stop_env::*;
 pub use transports::*;
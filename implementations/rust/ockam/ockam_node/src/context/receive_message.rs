@@ -56,36 +56,105 @@ impl MessageReceiveOptions {
     }
 }
 
+/// A message taken from the mailbox by [`Context::receive`] which was not returned yet.
+///
+/// It is kept in the [`Context`] while the access control and the route trace are
+/// awaited, so that it's returned by the next call if the future of `receive` is dropped.
+pub(super) enum PendingMessage {
+    /// Taken from the mailbox, and not checked by the incoming access control yet
+    Received(RelayMessage),
+    /// Authorized and recorded, to be returned once its route trace is sent
+    Delivered(RelayMessage),
+}
+
 impl Context {
     /// Wait for the next message from the mailbox
     pub(crate) async fn receiver_next(&mut self) -> Result<Option<RelayMessage>> {
         loop {
-            let relay_msg = if let Some(msg) = self.receiver.recv().await.map(|msg| {
-                trace!("{}: received new message!", self.address());
+            if self.pending_message.is_none() {
+                let relay_msg = match self.receiver.recv().await {
+                    Some(msg) => msg,
+                    // no more messages
+                    None => return Ok(None),
+                };
+                self.take_from_mailbox(relay_msg);
+            }
 
-                // First we update the mailbox fill metrics
-                self.mailbox_count.fetch_sub(1, Ordering::Acquire);
+            if let Some(relay_msg) = self.deliver_pending().await? {
+                return Ok(Some(relay_msg));
+            }
+        }
+    }
 
-                msg
-            }) {
-                msg
-            } else {
-                // no more messages
-                return Ok(None);
-            };
+    /// Return the next message if one is already waiting in the mailbox
+    #[cfg(feature = "std")]
+    pub(crate) async fn receiver_try_next(&mut self) -> Result<Option<RelayMessage>> {
+        loop {
+            if self.pending_message.is_none() {
+                let relay_msg = match self.receiver.try_recv() {
+                    Some(msg) => msg,
+                    None => return Ok(None),
+                };
+                self.take_from_mailbox(relay_msg);
+            }
+
+            if let Some(relay_msg) = self.deliver_pending().await? {
+                return Ok(Some(relay_msg));
+            }
+        }
+    }
 
-            debugger::log_incoming_message(self, &relay_msg);
+    /// Keep a message taken from the mailbox until it's delivered
+    fn take_from_mailbox(&mut self, relay_msg: RelayMessage) {
+        trace!("{}: received new message!", self.address());
 
-            if !self.mailboxes.is_incoming_authorized(&relay_msg).await? {
+        // First we update the mailbox fill metrics
+        self.mailbox_count.fetch_sub(1, Ordering::Acquire);
+
+        debugger::log_incoming_message(self, &relay_msg);
+
+        self.pending_message = Some(PendingMessage::Received(relay_msg));
+    }
+
+    /// Check and return the pending message, or None if it was rejected by the incoming
+    /// access control.
+    ///
+    /// The message stays pending across each `.await`, so that this function can be cancelled
+    /// and called again without losing it
+    async fn deliver_pending(&mut self) -> Result<Option<RelayMessage>> {
+        if let Some(PendingMessage::Received(relay_msg)) = &self.pending_message {
+            let authorized = self.mailboxes.is_incoming_authorized(relay_msg).await;
+            let relay_msg = match self.pending_message.take() {
+                Some(PendingMessage::Received(relay_msg)) => relay_msg,
+                _ => return Ok(None),
+            };
+
+            if !authorized? {
                 warn!(
                     "Message received from {} for {} did not pass incoming access control",
                     relay_msg.return_route(),
                     relay_msg.destination()
                 );
-                continue;
+                return Ok(None);
+            }
+
+            self.record_metrics(|m| {
+                m.message_received(
+                    relay_msg.destination(),
+                    relay_msg.local_message().transport().payload.len(),
+                )
+            });
+
+            let (relay_msg, delivered) = Self::record_delivery(relay_msg)?;
+            self.pending_message = Some(PendingMessage::Delivered(relay_msg));
+            if let Some(delivered) = delivered {
+                self.send_delivered_trace(delivered).await;
             }
+        }
 
-            return Ok(Some(relay_msg));
+        match self.pending_message.take() {
+            Some(PendingMessage::Delivered(relay_msg)) => Ok(Some(relay_msg)),
+            _ => Ok(None),
         }
     }
 
@@ -115,6 +184,9 @@ impl Context {
 
     /// Block the current worker to wait for a typed message
     ///
+    /// This function is cancellation safe: if its future is dropped, for instance by
+    /// `tokio::select!`, a message it already took from the mailbox is returned by the next call.
+    ///
     /// This function may return a `Err(FailedLoadData)` if the
     /// underlying worker was shut down, or `Err(Timeout)` if the call
     /// was waiting for longer than the `default timeout`.
@@ -128,6 +200,8 @@ impl Context {
     }
 
     /// Wait to receive a typed message
+    ///
+    /// Like [`receive()`](Self::receive), this function is cancellation safe.
     pub async fn receive_extended<M: Message>(
         &mut self,
         options: MessageReceiveOptions,
@@ -0,0 +1,124 @@
+use ockam_core::{
+    Address, Message, RelayMessage, Result, Route, RouteTrace, Routed, ROUTE_TRACE_IDENTIFIER,
+};
+
+use crate::{Context, MessageReceiveOptions};
+
+/// Receiving side of the [`RouteTrace`] of a message sent with [`Context::send_traced`]
+pub struct RouteTraceReceiver {
+    ctx: Context,
+}
+
+impl RouteTraceReceiver {
+    /// Address to which the trace is sent once the message is delivered
+    pub fn collector(&self) -> Address {
+        self.ctx.address()
+    }
+
+    /// Wait for the trace of the message
+    ///
+    /// Use [`receive_extended()`](Self::receive_extended) to use a specific timeout period.
+    pub async fn receive(&mut self) -> Result<RouteTrace> {
+        self.receive_extended(MessageReceiveOptions::new()).await
+    }
+
+    /// Wait for the trace of the message
+    pub async fn receive_extended(&mut self, options: MessageReceiveOptions) -> Result<RouteTrace> {
+        let msg: Routed<RouteTrace> = self.ctx.receive_extended(options).await?;
+        Ok(msg.body())
+    }
+}
+
+impl Context {
+    /// Send a message to an address or via a fully-qualified route and trace its path
+    ///
+    /// Every worker handling the message records its address along with a timestamp in a
+    /// [`RouteTrace`]. Once the message reaches the last address of its onward route, the
+    /// trace is sent back along the return route of the message to the returned
+    /// [`RouteTraceReceiver`].
+    ///
+    /// The trace is carried as [`ockam_core::LocalInfo`] on each node and kept by secure
+    /// channels, where it is not encrypted. It is only embedded in the messages sent to peers
+    /// supporting message extensions, see [`ockam_core::EXTENDED_TRANSPORT_MESSAGE_VERSION`],
+    /// that is by TCP connections and listeners created with `with_message_extensions`.
+    /// Tracing stops at a hop towards any other peer, or at a worker which sends a new message
+    /// instead of forwarding the one it received. The trace itself can be rejected by access controls or flow controls on its
+    /// way back.
+    pub async fn send_traced<R, M>(&self, route: R, msg: M) -> Result<RouteTraceReceiver>
+    where
+        R: Into<Route>,
+        M: Message + Send + 'static,
+    {
+        let route = route.into();
+        let ctx = self.new_send_and_receive_context(&route).await?;
+
+        let mut trace = RouteTrace::new(ctx.address());
+        trace.add_hop(self.address());
+        self.send_with_local_info(route, msg, vec![trace.to_local_info()?])
+            .await?;
+
+        Ok(RouteTraceReceiver { ctx })
+    }
+
+    /// Record that a traced message was received, and return its trace if this is the
+    /// final destination of the message, to be sent with [`Context::send_delivered_trace`]
+    pub(super) fn record_delivery(
+        relay_msg: RelayMessage,
+    ) -> Result<(RelayMessage, Option<DeliveredTrace>)> {
+        let mut trace = match RouteTrace::find_info(relay_msg.local_message())? {
+            Some(trace) => trace,
+            None => return Ok((relay_msg, None)),
+        };
+
+        let source = relay_msg.source().clone();
+        let destination = relay_msg.destination().clone();
+        let mut local_msg = relay_msg.into_local_message();
+        trace.add_hop(destination.clone());
+
+        let delivered = if local_msg.transport().onward_route.len() > 1 {
+            local_msg.replace_local_info(trace.to_local_info()?);
+            None
+        } else {
+            local_msg.clear_local_info(ROUTE_TRACE_IDENTIFIER);
+
+            // The trace follows the return route, up to the collector instead of the sender
+            let mut return_route = local_msg.transport().return_route.clone();
+            let collector_route: Route = return_route
+                .modify()
+                .pop_back()
+                .append(trace.collector().clone())
+                .into();
+            Some(DeliveredTrace {
+                collector_route,
+                trace,
+                destination: destination.clone(),
+            })
+        };
+
+        Ok((RelayMessage::new(source, destination, local_msg), delivered))
+    }
+
+    /// Send the trace of a message delivered to its final destination back to its collector
+    pub(super) async fn send_delivered_trace(&self, delivered: DeliveredTrace) {
+        if let Err(e) = self
+            .send_from_address(
+                delivered.collector_route,
+                delivered.trace,
+                delivered.destination.clone(),
+            )
+            .await
+        {
+            warn!(
+                "Failed to send the route trace of a message received by {}: {}",
+                delivered.destination, e
+            );
+        }
+    }
+}
+
+/// Trace of a message which reached its final destination
+pub(super) struct DeliveredTrace {
+    collector_route: Route,
+    trace: RouteTrace,
+    destination: Address,
+}
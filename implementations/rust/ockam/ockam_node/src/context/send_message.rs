@@ -1,15 +1,19 @@
 use crate::channel_types::small_channel;
 use crate::context::MessageWait;
+use crate::tokio::time::timeout;
 use crate::{debugger, Context, MessageReceiveOptions, DEFAULT_TIMEOUT};
 use crate::{error::*, NodeMessage};
 use core::time::Duration;
 use ockam_core::compat::{sync::Arc, vec::Vec};
 use ockam_core::{
     errcode::{Kind, Origin},
-    route, Address, AllowAll, AllowOnwardAddress, Error, LocalMessage, Mailboxes, Message,
-    RelayMessage, Result, Route, Routed, TransportMessage,
+    route, Address, AllowAll, AllowOnwardAddress, Any, Correlated, CorrelationId, Decodable,
+    Encodable, Error, LocalMessage, Mailboxes, Message, RelayMessage, Result, Route, Routed,
+    TransportMessage,
 };
-use ockam_core::{LocalInfo, Mailbox};
+#[cfg(feature = "std")]
+use ockam_core::{Codec, NeutralMessage};
+use ockam_core::{LocalInfo, Mailbox, MessageHeaders};
 
 /// Full set of options to `send_and_receive_extended` function
 pub struct MessageSendReceiveOptions {
@@ -83,7 +87,104 @@ impl Context {
         M: Message,
     {
         let route: Route = route.into();
+        let mut child_ctx = self.new_send_and_receive_context(&route).await?;
 
+        child_ctx.send(route, msg).await?;
+        child_ctx
+            .receive_extended::<M>(
+                MessageReceiveOptions::new().with_message_wait(options.message_wait),
+            )
+            .await
+    }
+
+    /// Using a temporary new context, send a request tagged with a [`CorrelationId`] and wait
+    /// for the response carrying the same [`CorrelationId`], with default timeout
+    ///
+    /// Messages received in the meantime which are not the response to that request are
+    /// ignored. The receiving worker is expected to handle [`Correlated`] messages and to
+    /// answer with [`Routed::correlated_response`].
+    pub async fn send_and_receive_correlated<Req, Resp>(
+        &self,
+        route: impl Into<Route>,
+        req: Req,
+    ) -> Result<Resp>
+    where
+        Req: Message,
+        Resp: Message,
+    {
+        Ok(self
+            .send_and_receive_correlated_extended::<Req, Resp>(
+                route,
+                req,
+                MessageSendReceiveOptions::new(),
+            )
+            .await?
+            .body())
+    }
+
+    /// Using a temporary new context, send a request tagged with a [`CorrelationId`] and wait
+    /// for the response carrying the same [`CorrelationId`]
+    ///
+    /// The timeout applies to the whole exchange, not to each received message.
+    /// The temporary context is dropped when this function returns, whether the call succeeds,
+    /// times out or fails, so no correlation state outlives the call.
+    pub async fn send_and_receive_correlated_extended<Req, Resp>(
+        &self,
+        route: impl Into<Route>,
+        req: Req,
+        options: MessageSendReceiveOptions,
+    ) -> Result<Routed<Resp>>
+    where
+        Req: Message,
+        Resp: Message,
+    {
+        let route: Route = route.into();
+        let mut child_ctx = self.new_send_and_receive_context(&route).await?;
+
+        let correlation_id = CorrelationId::random();
+        child_ctx
+            .send(route, Correlated::new(correlation_id, &req)?)
+            .await?;
+
+        let receive_response = async {
+            loop {
+                let msg = child_ctx
+                    .receive_extended::<Any>(MessageReceiveOptions::new().without_timeout())
+                    .await?;
+                let (msg_addr, local_msg) = msg.dissolve();
+                let src_addr = msg.src_addr();
+
+                let correlated = match Correlated::decode(&local_msg.transport().payload) {
+                    Ok(correlated) => correlated,
+                    Err(_) => {
+                        debug!("{}: ignoring an uncorrelated message", child_ctx.address());
+                        continue;
+                    }
+                };
+
+                if correlated.correlation_id() != correlation_id {
+                    debug!(
+                        "{}: ignoring a response to another request",
+                        child_ctx.address()
+                    );
+                    continue;
+                }
+
+                let body = correlated.body::<Resp>()?;
+                return Ok(Routed::new(body, msg_addr, src_addr, local_msg));
+            }
+        };
+
+        match options.message_wait {
+            MessageWait::Timeout(timeout_duration) => timeout(timeout_duration, receive_response)
+                .await
+                .map_err(|e| NodeError::Data.with_elapsed(e))?,
+            MessageWait::Blocking => receive_response.await,
+        }
+    }
+
+    /// Create a detached context able to receive the response to a message sent to the given route
+    pub(super) async fn new_send_and_receive_context(&self, route: &Route) -> Result<Context> {
         let next = route.next()?.clone();
         let address = Address::random_tagged("Context.send_and_receive.detached");
         let mailboxes = Mailboxes::new(
@@ -104,14 +205,7 @@ impl Context {
             self.flow_controls.add_consumer(address, &flow_control_id);
         }
 
-        let mut child_ctx = self.new_detached_with_mailboxes(mailboxes).await?;
-
-        child_ctx.send(route, msg).await?;
-        child_ctx
-            .receive_extended::<M>(
-                MessageReceiveOptions::new().with_message_wait(options.message_wait),
-            )
-            .await
+        self.new_detached_with_mailboxes(mailboxes).await
     }
 
     /// Send a message to another address associated with this worker
@@ -132,6 +226,28 @@ impl Context {
         }
     }
 
+    /// Send a message to an address or via a fully-qualified route
+    /// after encoding it with the given [`Codec`].
+    ///
+    /// The codec is attached to the message as [`LocalInfo`], so that the receiving
+    /// worker can decode it with [`Routed::decode_with_codec`]. It is sent to other nodes
+    /// along with the encoded payload when they support message extensions, see
+    /// [`ockam_core::EXTENDED_TRANSPORT_MESSAGE_VERSION`].
+    #[cfg(feature = "std")]
+    pub async fn send_with_codec<R, M>(&self, route: R, msg: M, codec: Codec) -> Result<()>
+    where
+        R: Into<Route>,
+        M: serde::Serialize,
+    {
+        let payload = codec.encode(&msg)?;
+        self.send_with_local_info(
+            route,
+            NeutralMessage::from(payload),
+            vec![codec.to_local_info()?],
+        )
+        .await
+    }
+
     /// Send a message to an address or via a fully-qualified route
     ///
     /// Routes can be constructed from a set of [`Address`]es, or via
@@ -186,6 +302,28 @@ impl Context {
             .await
     }
 
+    /// Send a message to an address or via a fully-qualified route along with headers,
+    /// which can be read by every worker handling the message with
+    /// [`Routed::headers`](ockam_core::Routed::headers)
+    ///
+    /// The headers are kept by the secure channels and by the transports, if the other side
+    /// supports message extensions, see [`ockam_core::EXTENDED_TRANSPORT_MESSAGE_VERSION`].
+    /// Like any [`LocalInfo`], they are lost at a worker which sends a new message instead of
+    /// forwarding the one it received.
+    pub async fn send_with_headers<R, M>(
+        &self,
+        route: R,
+        msg: M,
+        headers: MessageHeaders,
+    ) -> Result<()>
+    where
+        R: Into<Route>,
+        M: Message + Send + 'static,
+    {
+        self.send_with_local_info(route, msg, vec![headers.to_local_info()?])
+            .await
+    }
+
     /// Send a message to an address or via a fully-qualified route
     ///
     /// Routes can be constructed from a set of [`Address`]es, or via
@@ -228,6 +366,11 @@ impl Context {
             return Err(Error::new_without_cause(Origin::Node, Kind::Invalid));
         }
 
+        let payload = msg.encode().map_err(|_| NodeError::Data.internal())?;
+        if payload.len() > self.max_message_size {
+            return Err(NodeError::MessageTooLarge(payload.len()).resource_exhausted());
+        }
+
         // First resolve the next hop in the route
         let (reply_tx, mut reply_rx) = small_channel();
         let next = match route.next() {
@@ -244,15 +387,15 @@ impl Context {
             .send(req)
             .await
             .map_err(NodeError::from_send_err)?;
-        let (addr, sender) = reply_rx
+        let (addr, sender, max_message_size) = reply_rx
             .recv()
             .await
             .ok_or_else(|| NodeError::NodeState(NodeReason::Unknown).internal())??
             .take_sender()?;
 
         // Pack the payload into a TransportMessage
-        let payload = msg.encode().map_err(|_| NodeError::Data.internal())?;
         let transport_msg = TransportMessage::v1(route, route![sending_address.clone()], payload);
+        check_message_size(&transport_msg, max_message_size)?;
 
         // Pack transport message into a LocalMessage wrapper
         let local_msg = LocalMessage::new(transport_msg, local_info);
@@ -271,11 +414,16 @@ impl Context {
             return Ok(());
         }
 
-        // Send the packed user message with associated route
-        sender
-            .send(relay_msg)
-            .await
-            .map_err(NodeError::from_send_err)?;
+        self.record_metrics(|m| {
+            m.message_sent(
+                relay_msg.source(),
+                relay_msg.local_message().transport().payload.len(),
+            )
+        });
+
+        // Send the packed user message with associated route, this fails if the
+        // recipient's mailbox is full and drops new messages
+        sender.send(relay_msg).await?;
 
         Ok(())
     }
@@ -336,11 +484,12 @@ impl Context {
             .send(req)
             .await
             .map_err(NodeError::from_send_err)?;
-        let (addr, sender) = reply_rx
+        let (addr, sender, max_message_size) = reply_rx
             .recv()
             .await
             .ok_or_else(|| NodeError::NodeState(NodeReason::Unknown).internal())??
             .take_sender()?;
+        check_message_size(local_msg.transport(), max_message_size)?;
 
         // Pack the transport message into a RelayMessage wrapper
         let relay_msg = RelayMessage::new(sending_address, addr, local_msg);
@@ -356,12 +505,28 @@ impl Context {
             return Ok(());
         }
 
+        self.record_metrics(|m| {
+            m.message_sent(
+                relay_msg.source(),
+                relay_msg.local_message().transport().payload.len(),
+            )
+        });
+
         // Forward the message
-        sender
-            .send(relay_msg)
-            .await
-            .map_err(NodeError::from_send_err)?;
+        sender.send(relay_msg).await?;
 
         Ok(())
     }
 }
+
+/// Reject the messages which are larger than what their next hop accepts, for example a
+/// transport which can't frame them, so that the error is returned to the sender
+fn check_message_size(msg: &TransportMessage, max_message_size: Option<usize>) -> Result<()> {
+    if let Some(max_message_size) = max_message_size {
+        let size = msg.encoded_len();
+        if size > max_message_size {
+            return Err(NodeError::MessageTooLarge(size).resource_exhausted());
+        }
+    }
+    Ok(())
+}
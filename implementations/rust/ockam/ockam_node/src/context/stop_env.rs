@@ -1,5 +1,6 @@
 use crate::Context;
 use crate::{error::*, NodeMessage, ShutdownType};
+use core::time::Duration;
 use ockam_core::{
     errcode::{Kind, Origin},
     Error, Result,
@@ -37,7 +38,25 @@ impl Context {
     /// This call will hang until a safe shutdown has been completed
     /// or the desired timeout has been reached.
     pub async fn stop_timeout(&self, seconds: u8) -> Result<()> {
-        let (req, mut rx) = NodeMessage::stop_node(ShutdownType::Graceful(seconds));
+        self.stop_with(ShutdownType::Graceful(seconds)).await
+    }
+
+    /// Signal to the local runtime to shut down, stopping the workers in the
+    /// reverse order of their dependencies
+    ///
+    /// Application workers are stopped first, then the secure channels and
+    /// finally the transports, so that the secure channels and their users
+    /// can still send their last messages while they are stopping. The
+    /// workers still running after `timeout` are logged and force-stopped.
+    /// See [`ShutdownType::Ordered`].
+    ///
+    /// This call will hang until the shutdown has been completed.
+    pub async fn shutdown_graceful(&self, timeout: Duration) -> Result<()> {
+        self.stop_with(ShutdownType::Ordered(timeout)).await
+    }
+
+    async fn stop_with(&self, shutdown_type: ShutdownType) -> Result<()> {
+        let (req, mut rx) = NodeMessage::stop_node(shutdown_type);
         self.sender
             .send(req)
             .await
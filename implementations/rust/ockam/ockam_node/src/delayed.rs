@@ -60,6 +60,9 @@ impl<M: Message + Clone> DelayedEvent<M> {
     }
 
     /// Schedule heartbeat. Cancels already scheduled heartbeat if there is such heartbeat
+    ///
+    /// The delay is measured with the runtime monotonic clock, which makes heartbeats and
+    /// idle timeouts built on top of it immune to wall-clock jumps
     pub async fn schedule(&mut self, duration: Duration) -> Result<()> {
         self.cancel();
 
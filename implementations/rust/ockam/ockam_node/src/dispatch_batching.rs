@@ -0,0 +1,108 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ockam_core::compat::sync::Arc;
+
+/// How many ready messages a worker handles in a row before giving control back
+/// to the scheduler
+///
+/// By default a worker goes back to the scheduler after each message. With a batch size
+/// greater than 1, the messages already waiting in the mailbox are handled in the same
+/// scheduling quantum, up to the batch size. The worker then yields, so that a worker
+/// with a busy mailbox can't starve the other tasks of the node.
+///
+/// Batching is only supported with the `std` feature, it's ignored otherwise.
+#[derive(Clone, Debug)]
+pub struct DispatchBatching {
+    batch_size: usize,
+    stats: DispatchStats,
+}
+
+impl DispatchBatching {
+    /// Constructor. A batch size of 0 is rounded up to 1, which disables batching.
+    pub fn new(batch_size: usize) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            stats: DispatchStats::default(),
+        }
+    }
+
+    /// Handle messages one at a time
+    pub fn disabled() -> Self {
+        Self::new(1)
+    }
+
+    /// Maximum number of messages handled before yielding
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Return a handle to observe the dispatching of the worker using this configuration
+    pub fn stats(&self) -> DispatchStats {
+        self.stats.clone()
+    }
+
+    /// Record a batch of handled messages, and return true if it was full
+    #[cfg(feature = "std")]
+    pub(crate) fn record_batch(&self, messages: usize) -> bool {
+        let full = self.batch_size > 1 && messages == self.batch_size;
+        self.stats.record_batch(messages, full);
+        full
+    }
+}
+
+impl Default for DispatchBatching {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+/// Counters updated while a worker dispatches its messages
+///
+/// Cloning returns a handle to the same counters.
+#[derive(Clone, Debug, Default)]
+pub struct DispatchStats {
+    counters: Arc<DispatchCounters>,
+}
+
+#[derive(Debug, Default)]
+struct DispatchCounters {
+    messages: AtomicUsize,
+    batches: AtomicUsize,
+    full_batches: AtomicUsize,
+}
+
+impl DispatchStats {
+    /// Number of messages handled by the worker
+    pub fn messages(&self) -> usize {
+        self.counters.messages.load(Ordering::Relaxed)
+    }
+
+    /// Number of scheduling quanta used to handle those messages
+    pub fn batches(&self) -> usize {
+        self.counters.batches.load(Ordering::Relaxed)
+    }
+
+    /// Number of batches which reached the batch size, after which the worker had to yield
+    /// even though more messages were waiting
+    pub fn full_batches(&self) -> usize {
+        self.counters.full_batches.load(Ordering::Relaxed)
+    }
+
+    /// Average number of messages handled per batch
+    pub fn average_batch_size(&self) -> f64 {
+        match self.batches() {
+            0 => 0.0,
+            batches => self.messages() as f64 / batches as f64,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn record_batch(&self, messages: usize, full: bool) {
+        self.counters
+            .messages
+            .fetch_add(messages, Ordering::Relaxed);
+        self.counters.batches.fetch_add(1, Ordering::Relaxed);
+        if full {
+            self.counters.full_batches.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
@@ -22,6 +22,8 @@ pub enum NodeError {
     WorkerState(WorkerReason),
     /// A failure occurred because of invalid address router state
     RouterState(RouterReason),
+    /// A message of the given size exceeds the maximum message size of the node
+    MessageTooLarge(usize),
 }
 
 impl NodeError {
@@ -41,6 +43,10 @@ impl NodeError {
     pub fn internal(self) -> Error {
         Error::new(Origin::Node, Kind::Internal, self)
     }
+    /// Turn a NodeError into a Kind::ResourceExhausted ockam_core::Error
+    pub fn resource_exhausted(self) -> Error {
+        Error::new(Origin::Node, Kind::ResourceExhausted, self)
+    }
     /// Create an ockam_core::Error based on a tokio::SendError
     pub(crate) fn from_send_err<T: fmt::Debug>(err: SendError<T>) -> Error {
         Error::new(
@@ -70,6 +76,8 @@ impl fmt::Display for NodeError {
                 Self::NodeState(reason) => format!("failed because node state: {}", reason),
                 Self::WorkerState(reason) => format!("failed because worker state: {}", reason),
                 Self::RouterState(reason) => format!("failed because router state: {}", reason),
+                Self::MessageTooLarge(size) =>
+                    format!("message of {} bytes exceeds the maximum message size", size),
             }
         )
     }
@@ -142,6 +150,10 @@ pub enum WorkerReason {
     Faulty,
     /// The worker is otherwise corrupt and can not be recovered
     Corrupt,
+    /// The mailbox of the worker is full
+    MailboxFull,
+    /// The message handler of the worker panicked
+    Panicked,
 }
 
 impl fmt::Display for WorkerReason {
@@ -153,6 +165,8 @@ impl fmt::Display for WorkerReason {
                 Self::Shutdown => "target worker is shutting down",
                 Self::Faulty => "target worker is faulty and waiting for supervisor",
                 Self::Corrupt => "target worker is corrupt and can not be recovered",
+                Self::MailboxFull => "the mailbox of the target worker is full",
+                Self::Panicked => "the message handler of the worker panicked",
             }
         )
     }
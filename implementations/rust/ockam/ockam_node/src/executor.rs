@@ -1,12 +1,14 @@
 // use crate::message::BaseMessage;
 
 use crate::channel_types::SmallSender;
+use crate::relay::RunningRelays;
 use crate::{
     router::{Router, SenderPair},
     tokio::runtime::{Handle, Runtime},
     NodeMessage,
 };
 use core::future::Future;
+use core::time::Duration;
 use ockam_core::{Address, Result};
 
 #[cfg(feature = "metrics")]
@@ -24,16 +26,30 @@ use ockam_core::{
     Error,
 };
 
+/// Default time given to the workers of a node to run their `shutdown` hook once the
+/// node is stopped
+pub const DEFAULT_SHUTDOWN_HOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Underlying Ockam node executor
 ///
 /// This type is a small wrapper around an inner async runtime (`tokio` by
 /// default) and the Ockam router. In most cases it is recommended you use the
 /// `ockam::node` function annotation instead!
+///
+/// Workers and processors run as tasks on the multi-threaded runtime, whose work-stealing
+/// scheduler moves them between threads as needed. Pinning them to a thread, or migrating
+/// them manually, is not supported: workloads requiring thread affinity must run on a
+/// dedicated runtime.
 pub struct Executor {
     /// Reference to the runtime needed to spawn tasks
     rt: Runtime,
     /// Main worker and application router
     router: Router,
+    /// Relays of the workers and processors which didn't return yet
+    running_relays: RunningRelays,
+    /// Time given to the relays to return once the router stopped
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    shutdown_hook_timeout: Duration,
     /// Metrics collection endpoint
     #[cfg(feature = "metrics")]
     metrics: Arc<Metrics>,
@@ -49,14 +65,24 @@ impl Executor {
         Self {
             rt,
             router,
+            running_relays: Default::default(),
+            shutdown_hook_timeout: DEFAULT_SHUTDOWN_HOOK_TIMEOUT,
             #[cfg(feature = "metrics")]
             metrics,
         }
     }
 
     /// Start the router asynchronously
+    ///
+    /// Once the router stopped, this waits for the workers to run their `shutdown` hook,
+    /// like [`Executor::execute`]
     pub async fn start_router(&mut self) -> Result<()> {
-        self.router.run().await
+        self.router.run().await?;
+
+        #[cfg(feature = "std")]
+        wait_shutdown_hooks(&self.running_relays, self.shutdown_hook_timeout).await;
+
+        Ok(())
     }
 
     /// Get access to the internal message sender
@@ -69,6 +95,16 @@ impl Executor {
         self.rt.handle()
     }
 
+    /// Relays of the workers and processors which didn't return yet
+    pub(crate) fn running_relays(&self) -> RunningRelays {
+        self.running_relays.clone()
+    }
+
+    /// Set the time given to the workers to run their `shutdown` hook once the node is stopped
+    pub(crate) fn set_shutdown_hook_timeout(&mut self, timeout: Duration) {
+        self.shutdown_hook_timeout = timeout;
+    }
+
     /// Initialize the root application worker
     pub(crate) fn initialize_system<S: Into<Address>>(&mut self, address: S, senders: SenderPair) {
         trace!("Initializing node executor");
@@ -100,6 +136,13 @@ impl Executor {
         // Then block on the execution of the router
         self.rt.block_on(self.router.run())?;
 
+        // Let the workers which were stopped, or force-stopped after the shutdown timeout,
+        // finish running their shutdown hook before the runtime can be dropped
+        self.rt.block_on(wait_shutdown_hooks(
+            &self.running_relays,
+            self.shutdown_hook_timeout,
+        ));
+
         // Shut down metrics collector
         #[cfg(feature = "metrics")]
         alive.fetch_or(true, Ordering::Acquire);
@@ -134,6 +177,9 @@ impl Executor {
     ///
     /// Any errors encountered by the router or provided application
     /// code will be returned from this function.
+    ///
+    /// Unlike the `std` version, this doesn't wait for the workers to run their `shutdown`
+    /// hook once the router stopped.
     // TODO @antoinevg - support @thomm join & merge with std version
     pub fn execute<F>(&mut self, future: F) -> Result<()>
     where
@@ -149,3 +195,14 @@ impl Executor {
         Ok(())
     }
 }
+
+/// Wait for the relays which didn't return yet to run the `shutdown` hook of their worker
+#[cfg(feature = "std")]
+async fn wait_shutdown_hooks(running_relays: &RunningRelays, timeout: Duration) {
+    if !running_relays.wait_stopped(timeout).await {
+        warn!(
+            "{} workers didn't complete their shutdown in time",
+            running_relays.count()
+        );
+    }
+}
@@ -52,27 +52,43 @@ pub mod callback;
 mod async_drop;
 mod context;
 mod delayed;
+mod dispatch_batching;
 mod error;
 mod executor;
+mod mailbox_capacity;
 mod messages;
+mod metrics_recorder;
 mod node;
 mod parser;
 mod processor_builder;
 mod relay;
+#[cfg(feature = "std")]
+mod reliable;
 mod router;
+#[cfg(feature = "std")]
+mod supervisor;
 
 /// Support for storing persistent values
 pub mod storage;
 mod worker_builder;
+mod worker_info;
 
 pub use context::*;
 pub use delayed::*;
+pub use dispatch_batching::*;
 pub use error::*;
 pub use executor::*;
+pub use mailbox_capacity::*;
 pub use messages::*;
+pub use metrics_recorder::{Metrics, NoopMetrics};
 pub use processor_builder::ProcessorBuilder;
+#[cfg(feature = "std")]
+pub use reliable::*;
 pub use storage::*;
+#[cfg(feature = "std")]
+pub use supervisor::*;
 pub use worker_builder::WorkerBuilder;
+pub use worker_info::*;
 
 pub use node::{NodeBuilder, NullWorker};
 
@@ -0,0 +1,55 @@
+/// What happens when a message is sent to a full mailbox
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The sender waits until there is room in the mailbox.
+    ///
+    /// When the sender is a transport receiver, it also stops reading from its socket,
+    /// so the remote peer is slowed down as well.
+    Block,
+    /// The oldest message in the mailbox is dropped to make room for the new one
+    DropOldest,
+    /// The new message is dropped and the sender gets an error
+    /// with the [`Kind::ResourceExhausted`](ockam_core::errcode::Kind::ResourceExhausted) kind
+    DropNewest,
+}
+
+/// Maximum number of messages waiting in the mailbox of a worker, and what to do
+/// when it's reached
+///
+/// `OverflowPolicy::DropOldest` and `OverflowPolicy::DropNewest` are only supported with the
+/// `std` feature, otherwise senders always block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MailboxCapacity {
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+}
+
+impl MailboxCapacity {
+    /// Default number of messages waiting in a mailbox
+    pub const DEFAULT_CAPACITY: usize = 16;
+
+    /// Constructor. A capacity of 0 is rounded up to 1.
+    pub fn new(capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            overflow_policy,
+        }
+    }
+
+    /// Maximum number of messages waiting in the mailbox
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// What happens when the mailbox is full
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+}
+
+impl Default for MailboxCapacity {
+    /// [`MailboxCapacity::DEFAULT_CAPACITY`] messages, blocking the senders when it's reached
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY, OverflowPolicy::Block)
+    }
+}
@@ -2,8 +2,9 @@ use crate::channel_types::{small_channel, MessageSender, SmallReceiver, SmallSen
 use crate::{
     error::{NodeError, NodeReason, RouterReason, WorkerReason},
     router::SenderPair,
+    WorkerInfo, WorkerKind,
 };
-use core::{fmt, sync::atomic::AtomicUsize};
+use core::{fmt, sync::atomic::AtomicUsize, time::Duration};
 use ockam_core::compat::{string::String, sync::Arc, vec::Vec};
 use ockam_core::{Address, Error, RelayMessage, Result, TransportType};
 
@@ -20,17 +21,28 @@ pub enum NodeMessage {
         detached: bool,
         /// A mechanism to read channel fill-state for a worker
         mailbox_count: Arc<AtomicUsize>,
+        /// Maximum size of the encoded messages accepted by this worker
+        max_message_size: Option<usize>,
+        /// Kind of the worker
+        kind: WorkerKind,
         /// Reply channel for command confirmation
         reply: SmallSender<NodeReplyResult>,
     },
     /// Return a list of all worker addresses
     ListWorkers(SmallSender<NodeReplyResult>),
+    /// Return a description of all workers
+    ListWorkersInfo(SmallSender<NodeReplyResult>),
     /// Add an existing address to a cluster
     SetCluster(Address, String, SmallSender<NodeReplyResult>),
     /// Stop an existing worker
     StopWorker(Address, bool, SmallSender<NodeReplyResult>),
     /// Start a new processor
-    StartProcessor(Address, SenderPair, SmallSender<NodeReplyResult>),
+    StartProcessor(
+        Address,
+        SenderPair,
+        WorkerKind,
+        SmallSender<NodeReplyResult>,
+    ),
     /// Stop an existing processor
     StopProcessor(Address, SmallSender<NodeReplyResult>),
     /// Stop the node (and all workers)
@@ -54,9 +66,10 @@ impl fmt::Display for NodeMessage {
         match self {
             NodeMessage::StartWorker { .. } => write!(f, "StartWorker"),
             NodeMessage::ListWorkers(_) => write!(f, "ListWorkers"),
+            NodeMessage::ListWorkersInfo(_) => write!(f, "ListWorkersInfo"),
             NodeMessage::SetCluster(_, _, _) => write!(f, "SetCluster"),
             NodeMessage::StopWorker(_, _, _) => write!(f, "StopWorker"),
-            NodeMessage::StartProcessor(_, _, _) => write!(f, "StartProcessor"),
+            NodeMessage::StartProcessor(_, _, _, _) => write!(f, "StartProcessor"),
             NodeMessage::StopProcessor(_, _) => write!(f, "StopProcessor"),
             NodeMessage::StopNode(_, _) => write!(f, "StopNode"),
             NodeMessage::AbortNode => write!(f, "AbortNode"),
@@ -78,11 +91,18 @@ impl NodeMessage {
     ///               relay behind it that can respond to shutdown
     ///               commands.  Setting this to `true` will disable
     ///               stop ACK support in the router
+    ///
+    /// * `max_message_size`: maximum size of the encoded messages which
+    ///                       can be sent to this worker
+    ///
+    /// * `kind`: kind of the worker, reported by `Context::list_workers_info`
     pub fn start_worker(
         addrs: Vec<Address>,
         senders: SenderPair,
         detached: bool,
         mailbox_count: Arc<AtomicUsize>,
+        max_message_size: Option<usize>,
+        kind: WorkerKind,
     ) -> (Self, SmallReceiver<NodeReplyResult>) {
         let (reply, rx) = small_channel();
         (
@@ -91,6 +111,8 @@ impl NodeMessage {
                 senders,
                 detached,
                 mailbox_count,
+                max_message_size,
+                kind,
                 reply,
             },
             rx,
@@ -101,9 +123,10 @@ impl NodeMessage {
     pub fn start_processor(
         address: Address,
         senders: SenderPair,
+        kind: WorkerKind,
     ) -> (Self, SmallReceiver<NodeReplyResult>) {
         let (tx, rx) = small_channel();
-        (Self::StartProcessor(address, senders, tx), rx)
+        (Self::StartProcessor(address, senders, kind, tx), rx)
     }
 
     /// Create a stop worker message and reply receiver
@@ -118,6 +141,12 @@ impl NodeMessage {
         (Self::ListWorkers(tx), rx)
     }
 
+    /// Create a list workers info message and reply receiver
+    pub fn list_workers_info() -> (Self, SmallReceiver<NodeReplyResult>) {
+        let (tx, rx) = small_channel();
+        (Self::ListWorkersInfo(tx), rx)
+    }
+
     /// Create a set cluster message and reply receiver
     pub fn set_cluster(addr: Address, label: String) -> (Self, SmallReceiver<NodeReplyResult>) {
         let (tx, rx) = small_channel();
@@ -164,12 +193,16 @@ pub enum RouterReply {
     Ok,
     /// A list of worker addresses
     Workers(Vec<Address>),
+    /// A description of all workers
+    WorkersInfo(Vec<WorkerInfo>),
     /// Message sender to a specific worker
     Sender {
         /// The address a message is being sent to
         addr: Address,
         /// The relay sender
         sender: MessageSender<RelayMessage>,
+        /// Maximum size of the encoded messages accepted by the worker
+        max_message_size: Option<usize>,
     },
     /// Indicate the 'ready' state of an address
     State(bool),
@@ -199,6 +232,16 @@ pub enum ShutdownType {
     /// when reaching the timeout (failover into `Immediate`
     /// strategy).  **A given timeout of `0` will wait forever!**
     Graceful(u8),
+    /// Execute a graceful shutdown given a maximum timeout, stopping the
+    /// workers in the reverse order of their dependencies
+    ///
+    /// This strategy is the same as `Graceful`, except that the clusters
+    /// of transport workers, whose names start with `_internals.transport.`,
+    /// are stopped last regardless of their creation order. Application
+    /// workers are stopped first, then the other clusters, like the one of
+    /// secure channels, so that their workers can still use the transports
+    /// while they are stopping. See `Context::shutdown_graceful`.
+    Ordered(Duration),
     /// Immediately shutdown workers and run shutdown hooks
     ///
     /// This strategy can lead to data loss:
@@ -259,15 +302,32 @@ impl RouterReply {
         Ok(Self::Workers(v))
     }
 
+    /// Return [RouterReply::WorkersInfo] for the given descriptions
+    pub fn workers_info(v: Vec<WorkerInfo>) -> NodeReplyResult {
+        Ok(Self::WorkersInfo(v))
+    }
+
     /// Return [RouterReply::Sender] for the given information
-    pub fn sender(addr: Address, sender: MessageSender<RelayMessage>) -> NodeReplyResult {
-        Ok(RouterReply::Sender { addr, sender })
+    pub fn sender(
+        addr: Address,
+        sender: MessageSender<RelayMessage>,
+        max_message_size: Option<usize>,
+    ) -> NodeReplyResult {
+        Ok(RouterReply::Sender {
+            addr,
+            sender,
+            max_message_size,
+        })
     }
 
     /// Consume the wrapper and return [RouterReply::Sender]
-    pub fn take_sender(self) -> Result<(Address, MessageSender<RelayMessage>)> {
+    pub fn take_sender(self) -> Result<(Address, MessageSender<RelayMessage>, Option<usize>)> {
         match self {
-            Self::Sender { addr, sender } => Ok((addr, sender)),
+            Self::Sender {
+                addr,
+                sender,
+                max_message_size,
+            } => Ok((addr, sender, max_message_size)),
             _ => Err(NodeError::NodeState(NodeReason::Unknown).internal()),
         }
     }
@@ -280,6 +340,14 @@ impl RouterReply {
         }
     }
 
+    /// Consume the wrapper and return [RouterReply::WorkersInfo]
+    pub fn take_workers_info(self) -> Result<Vec<WorkerInfo>> {
+        match self {
+            Self::WorkersInfo(w) => Ok(w),
+            _ => Err(NodeError::NodeState(NodeReason::Unknown).internal()),
+        }
+    }
+
     /// Consume the wrapper and return [RouterReply::State]
     pub fn take_state(self) -> Result<bool> {
         match self {
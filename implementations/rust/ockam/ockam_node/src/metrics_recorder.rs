@@ -0,0 +1,87 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::Address;
+
+/// Recorder for the events emitted by the workers of a node
+///
+/// All the methods do nothing by default, so that an implementation only needs to override
+/// the events it's interested in, for example to feed counters and histograms of a
+/// monitoring system. The methods are called from the workers tasks, they should return
+/// quickly and never block.
+///
+/// A recorder is installed with [`crate::NodeBuilder::with_metrics`] or
+/// [`crate::Context::set_metrics`].
+pub trait Metrics: Send + Sync + 'static {
+    /// A worker sent a message of `bytes` bytes from its address `from`
+    fn message_sent(&self, _from: &Address, _bytes: usize) {}
+
+    /// A worker received a message of `bytes` bytes on its address `to`
+    fn message_received(&self, _to: &Address, _bytes: usize) {}
+
+    /// A worker took `duration` to handle a message
+    fn message_handled(&self, _worker: &Address, _duration: Duration) {}
+
+    /// `bytes` bytes were written to the connection of the transport worker `connection`
+    fn transport_bytes_sent(&self, _connection: &Address, _bytes: usize) {}
+
+    /// `bytes` bytes were read from the connection of the transport worker `connection`
+    fn transport_bytes_received(&self, _connection: &Address, _bytes: usize) {}
+
+    /// A message of `bytes` bytes was encrypted by the secure channel `channel`
+    fn message_encrypted(&self, _channel: &Address, _bytes: usize) {}
+
+    /// A message of `bytes` bytes was decrypted by the secure channel `channel`
+    fn message_decrypted(&self, _channel: &Address, _bytes: usize) {}
+
+    /// The handshake of the secure channel `channel` completed in `duration`
+    fn handshake_completed(&self, _channel: &Address, _duration: Duration) {}
+}
+
+/// [`Metrics`] recorder ignoring all the events
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+/// Slot holding the [`Metrics`] recorder shared by all the contexts of a node
+///
+/// When no recorder is installed, recording an event only costs the load of a flag.
+#[derive(Clone, Default)]
+pub(crate) struct MetricsRecorder {
+    slot: Arc<MetricsSlot>,
+}
+
+#[derive(Default)]
+struct MetricsSlot {
+    installed: AtomicBool,
+    metrics: RwLock<Option<Arc<dyn Metrics>>>,
+}
+
+impl MetricsRecorder {
+    pub(crate) fn install(&self, metrics: Arc<dyn Metrics>) {
+        *self.slot.metrics.write().unwrap() = Some(metrics);
+        self.slot.installed.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn uninstall(&self) {
+        self.slot.installed.store(false, Ordering::Release);
+        *self.slot.metrics.write().unwrap() = None;
+    }
+
+    pub(crate) fn is_installed(&self) -> bool {
+        self.slot.installed.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub(crate) fn record(&self, event: impl FnOnce(&dyn Metrics)) {
+        if !self.is_installed() {
+            return;
+        }
+        // Don't hold the lock while the recorder runs
+        let metrics = self.slot.metrics.read().unwrap().clone();
+        if let Some(metrics) = metrics {
+            event(metrics.as_ref())
+        }
+    }
+}
@@ -2,7 +2,12 @@ use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::FlowControls;
 use ockam_core::{Address, AllowAll, Mailbox, Mailboxes};
 
-use crate::{debugger, Context, Executor};
+use crate::metrics_recorder::MetricsRecorder;
+use crate::{
+    debugger, Context, Executor, MailboxCapacity, Metrics, DEFAULT_MAX_MESSAGE_SIZE,
+    DEFAULT_SHUTDOWN_HOOK_TIMEOUT,
+};
+use core::time::Duration;
 
 /// A minimal worker implementation that does nothing
 pub struct NullWorker;
@@ -19,6 +24,9 @@ impl ockam_core::Worker for NullWorker {
 /// builder API to customise the underlying node that is created.
 pub struct NodeBuilder {
     logging: bool,
+    metrics: Option<Arc<dyn Metrics>>,
+    max_message_size: usize,
+    shutdown_hook_timeout: Duration,
 }
 
 impl Default for NodeBuilder {
@@ -30,12 +38,48 @@ impl Default for NodeBuilder {
 impl NodeBuilder {
     /// Create a node
     pub fn new() -> Self {
-        Self { logging: true }
+        Self {
+            logging: true,
+            metrics: None,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            shutdown_hook_timeout: DEFAULT_SHUTDOWN_HOOK_TIMEOUT,
+        }
     }
 
     /// Disable logging on this node
     pub fn no_logging(self) -> Self {
-        Self { logging: false }
+        Self {
+            logging: false,
+            ..self
+        }
+    }
+
+    /// Install a [`Metrics`] recorder for all the workers of this node
+    pub fn with_metrics(self, metrics: Arc<dyn Metrics>) -> Self {
+        Self {
+            metrics: Some(metrics),
+            ..self
+        }
+    }
+
+    /// Set the maximum size of the payload of the messages sent or received by this node.
+    /// Sending a larger message fails immediately, and transports reject larger incoming
+    /// messages. Defaults to [`DEFAULT_MAX_MESSAGE_SIZE`]
+    pub fn with_max_message_size(self, max_message_size: usize) -> Self {
+        Self {
+            max_message_size,
+            ..self
+        }
+    }
+
+    /// Maximum time given to the workers and processors to run their `shutdown` hook once
+    /// the node is stopped, including when they are force-stopped because the node
+    /// shutdown timed out. Defaults to [`DEFAULT_SHUTDOWN_HOOK_TIMEOUT`]
+    pub fn with_shutdown_hook_timeout(self, shutdown_hook_timeout: Duration) -> Self {
+        Self {
+            shutdown_hook_timeout,
+            ..self
+        }
     }
 
     /// Consume this builder and yield a new Ockam Node
@@ -51,8 +95,14 @@ impl NodeBuilder {
         let flow_controls = FlowControls::new();
 
         let mut exe = Executor::new(&flow_controls);
+        exe.set_shutdown_hook_timeout(self.shutdown_hook_timeout);
         let addr: Address = "app".into();
 
+        let metrics = MetricsRecorder::default();
+        if let Some(recorder) = self.metrics {
+            metrics.install(recorder);
+        }
+
         // The root application worker needs a mailbox and relay to accept
         // messages from workers, and to buffer incoming transcoded data.
         let (ctx, sender, _) = Context::new(
@@ -62,9 +112,13 @@ impl NodeBuilder {
                 Mailbox::new(addr, Arc::new(AllowAll), Arc::new(AllowAll)),
                 vec![],
             ),
+            MailboxCapacity::default(),
             None,
             Default::default(),
             &flow_controls,
+            metrics,
+            self.max_message_size,
+            exe.running_relays(),
         );
 
         debugger::log_inherit_context("NODE", &ctx, &ctx);
@@ -1,6 +1,6 @@
 use crate::debugger;
 use crate::error::{NodeError, NodeReason};
-use crate::{relay::ProcessorRelay, Context, NodeMessage};
+use crate::{relay::ProcessorRelay, Context, MailboxCapacity, NodeMessage, WorkerKind};
 use ockam_core::compat::sync::Arc;
 use ockam_core::{
     errcode::{Kind, Origin},
@@ -38,6 +38,7 @@ where
         ProcessorBuilderOneAddress {
             incoming_ac: Arc::new(DenyAll),
             outgoing_ac: Arc::new(DenyAll),
+            kind: WorkerKind::application(),
             processor: self.processor,
             address: address.into(),
         }
@@ -47,6 +48,7 @@ where
     pub fn with_mailboxes(self, mailboxes: Mailboxes) -> ProcessorBuilderMultipleAddresses<P> {
         ProcessorBuilderMultipleAddresses {
             mailboxes,
+            kind: WorkerKind::application(),
             processor: self.processor,
         }
     }
@@ -57,6 +59,7 @@ where
     P: Processor<Context = Context>,
 {
     mailboxes: Mailboxes,
+    kind: WorkerKind,
     processor: P,
 }
 
@@ -64,9 +67,15 @@ impl<P> ProcessorBuilderMultipleAddresses<P>
 where
     P: Processor<Context = Context>,
 {
+    /// Set the [`WorkerKind`] reported by [`Context::list_workers_info`]
+    pub fn with_kind(mut self, kind: impl Into<WorkerKind>) -> Self {
+        self.kind = kind.into();
+        self
+    }
+
     /// Consume this builder and start a new Ockam [`Processor`] from the given context
     pub async fn start(self, context: &Context) -> Result<()> {
-        start(context, self.mailboxes, self.processor).await
+        start(context, self.mailboxes, self.kind, self.processor).await
     }
 }
 
@@ -76,6 +85,7 @@ where
 {
     incoming_ac: Arc<dyn IncomingAccessControl>,
     outgoing_ac: Arc<dyn OutgoingAccessControl>,
+    kind: WorkerKind,
     address: Address,
     processor: P,
 }
@@ -89,6 +99,7 @@ where
         start(
             context,
             Mailboxes::main(self.address, self.incoming_ac, self.outgoing_ac),
+            self.kind,
             self.processor,
         )
         .await
@@ -134,10 +145,21 @@ where
         self.outgoing_ac = outgoing_access_control.clone();
         self
     }
+
+    /// Set the [`WorkerKind`] reported by [`Context::list_workers_info`]
+    pub fn with_kind(mut self, kind: impl Into<WorkerKind>) -> Self {
+        self.kind = kind.into();
+        self
+    }
 }
 
 /// Consume this builder and start a new Ockam [`Processor`] from the given context
-pub async fn start<P>(context: &Context, mailboxes: Mailboxes, processor: P) -> Result<()>
+pub async fn start<P>(
+    context: &Context,
+    mailboxes: Mailboxes,
+    kind: WorkerKind,
+    processor: P,
+) -> Result<()>
 where
     P: Processor<Context = Context>,
 {
@@ -151,7 +173,7 @@ where
     let main_address = mailboxes.main_address().clone();
 
     // Pass it to the context
-    let (ctx, sender, ctrl_rx) = context.copy_with_mailboxes(mailboxes);
+    let (ctx, sender, ctrl_rx) = context.copy_with_mailboxes(mailboxes, MailboxCapacity::default());
 
     debugger::log_inherit_context("PROCESSOR", context, &ctx);
 
@@ -159,7 +181,7 @@ where
     ProcessorRelay::<P>::init(context.runtime(), processor, ctx, ctrl_rx);
 
     // Send start request to router
-    let (msg, mut rx) = NodeMessage::start_processor(main_address.clone(), sender);
+    let (msg, mut rx) = NodeMessage::start_processor(main_address.clone(), sender, kind);
     context
         .sender()
         .send(msg)
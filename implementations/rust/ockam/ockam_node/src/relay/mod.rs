@@ -1,7 +1,9 @@
 mod processor_relay;
+mod running_relays;
 mod worker_relay;
 
 pub use processor_relay::*;
+pub(crate) use running_relays::*;
 pub use worker_relay::*;
 
 /// A signal type used to communicate between router and worker relay
@@ -110,7 +110,11 @@ where
         ctx: Context,
         ctrl_rx: SmallReceiver<CtrlSignal>,
     ) {
+        let running = ctx.running_relays().track();
         let relay = ProcessorRelay::<P>::new(processor, ctx);
-        rt.spawn(relay.run(ctrl_rx));
+        rt.spawn(async move {
+            relay.run(ctrl_rx).await;
+            drop(running);
+        });
     }
 }
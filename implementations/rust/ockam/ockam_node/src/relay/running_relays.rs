@@ -0,0 +1,68 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ockam_core::compat::sync::Arc;
+
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "std")]
+use tokio::sync::Notify;
+
+/// Number of worker and processor relays of a node which didn't return yet
+///
+/// The executor uses it to let the relays run the `shutdown` hook of their worker
+/// when the router stops, before the runtime is dropped.
+#[derive(Clone, Default)]
+pub(crate) struct RunningRelays {
+    count: Arc<AtomicUsize>,
+    /// Notified when the last running relay returns
+    #[cfg(feature = "std")]
+    all_stopped: Arc<Notify>,
+}
+
+impl RunningRelays {
+    /// Count a relay as running until the returned guard is dropped
+    pub(crate) fn track(&self) -> RunningRelayGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        RunningRelayGuard {
+            relays: self.clone(),
+        }
+    }
+
+    /// Number of running relays
+    pub(crate) fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Wait until all the relays returned, or until the timeout elapsed.
+    /// Return false if some relays are still running
+    #[cfg(feature = "std")]
+    pub(crate) async fn wait_stopped(&self, timeout: Duration) -> bool {
+        let all_stopped = async {
+            loop {
+                // Created before checking the count, so that a relay returning in between
+                // still wakes us up
+                let notified = self.all_stopped.notified();
+                if self.count() == 0 {
+                    break;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, all_stopped).await.is_ok()
+    }
+}
+
+/// Guard decrementing the number of [`RunningRelays`] when dropped
+pub(crate) struct RunningRelayGuard {
+    relays: RunningRelays,
+}
+
+impl Drop for RunningRelayGuard {
+    fn drop(&mut self) {
+        #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+        let previous = self.relays.count.fetch_sub(1, Ordering::SeqCst);
+        #[cfg(feature = "std")]
+        if previous == 1 {
+            self.relays.all_stopped.notify_waiters();
+        }
+    }
+}
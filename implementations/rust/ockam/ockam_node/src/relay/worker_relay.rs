@@ -1,9 +1,17 @@
 use crate::channel_types::SmallReceiver;
 use crate::relay::CtrlSignal;
+#[cfg(feature = "std")]
+use crate::supervisor::{Decision, Failure, Supervisor};
 use crate::tokio::runtime::Handle;
-use crate::{parser, Context};
+use crate::{parser, Context, DispatchBatching};
+#[cfg(feature = "std")]
+use crate::{NodeError, WorkerReason};
 use ockam_core::{Message, RelayMessage, Result, Routed, Worker};
 
+/// Sequence number of the next message handled on this node, see [`WorkerRelay::message_span`]
+#[cfg(feature = "tracing_spans")]
+static NEXT_MESSAGE_ID: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(0);
+
 /// Worker relay machinery
 ///
 /// Every worker in the Ockam runtime needs a certain amount of logic
@@ -12,11 +20,36 @@ use ockam_core::{Message, RelayMessage, Result, Routed, Worker};
 pub struct WorkerRelay<W> {
     worker: W,
     ctx: Context,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    batching: DispatchBatching,
+    #[cfg(feature = "std")]
+    supervisor: Option<Supervisor<W>>,
+    /// Failure of the last handled message, if it failed in the worker handler
+    #[cfg(feature = "std")]
+    failure: Option<Failure>,
+    /// Set once a supervised worker asked to be stopped
+    #[cfg(feature = "std")]
+    stopping: bool,
 }
 
 impl<W: Worker> WorkerRelay<W> {
-    pub fn new(worker: W, ctx: Context) -> Self {
-        Self { worker, ctx }
+    pub fn new(
+        worker: W,
+        ctx: Context,
+        batching: DispatchBatching,
+        #[cfg(feature = "std")] supervisor: Option<Supervisor<W>>,
+    ) -> Self {
+        Self {
+            worker,
+            ctx,
+            batching,
+            #[cfg(feature = "std")]
+            supervisor,
+            #[cfg(feature = "std")]
+            failure: None,
+            #[cfg(feature = "std")]
+            stopping: false,
+        }
     }
 }
 
@@ -56,6 +89,139 @@ where
         Ok(routed)
     }
 
+    /// Call the worker handle function on a message received from the mailbox, inside
+    /// a [`message_span`](Self::message_span) with the `tracing_spans` feature
+    async fn handle_relay_message(&mut self, relay_msg: RelayMessage) -> Result<()> {
+        #[cfg(feature = "tracing_spans")]
+        let span = self.message_span(&relay_msg);
+
+        let routed = Self::wrap_direct_message(relay_msg)?;
+        let handled = self.handle_message(routed);
+        #[cfg(feature = "tracing_spans")]
+        let handled = tracing::Instrument::instrument(handled, span);
+        handled.await
+    }
+
+    /// Span in which a message is handled, and the work awaited by the handler runs
+    ///
+    /// The span carries the address of the worker, a sequence number identifying the
+    /// message on this node, and the [`ockam_core::flow_control::FlowControlId`] of the
+    /// producer which sent the message, if any, as the session of the message.
+    ///
+    /// Both ids are local to this node: they are not sent along with the message, so the
+    /// spans of a message forwarded by several workers, or handled on several nodes, can't
+    /// be correlated by their ids. Only the nesting of the spans on a given node, and their
+    /// worker addresses, relate them.
+    #[cfg(feature = "tracing_spans")]
+    fn message_span(&self, relay_msg: &RelayMessage) -> tracing::Span {
+        let message_id = NEXT_MESSAGE_ID.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+        let span = tracing::info_span!(
+            "worker_message",
+            worker = %relay_msg.destination(),
+            message_id,
+            session_id = tracing::field::Empty,
+        );
+        if let Some(producer) = self
+            .ctx
+            .flow_controls()
+            .find_flow_control_with_producer_address(relay_msg.source())
+        {
+            span.record(
+                "session_id",
+                tracing::field::display(producer.flow_control_id()),
+            );
+        }
+        span
+    }
+
+    /// Call the worker handle function
+    ///
+    /// The failures of a supervised worker are recorded, and its panics are caught
+    async fn handle_message(&mut self, routed: Routed<M>) -> Result<()> {
+        #[cfg(feature = "std")]
+        if self.supervisor.is_some() {
+            use futures::FutureExt;
+
+            let handled = core::panic::AssertUnwindSafe(self.call_handler(routed))
+                .catch_unwind()
+                .await;
+            return match handled {
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(e)) => {
+                    self.failure = Some(Failure::Error);
+                    Err(e)
+                }
+                Err(_) => {
+                    self.failure = Some(Failure::Panic);
+                    Err(NodeError::WorkerState(WorkerReason::Panicked).internal())
+                }
+            };
+        }
+
+        self.call_handler(routed).await
+    }
+
+    /// Restart or stop a supervised worker after a failure of its handler
+    #[cfg(feature = "std")]
+    async fn supervise(&mut self) {
+        let failure = match self.failure.take() {
+            Some(failure) => failure,
+            None => return,
+        };
+        let supervisor = match self.supervisor.as_mut() {
+            Some(supervisor) if !self.stopping => supervisor,
+            _ => return,
+        };
+
+        let address = self.ctx.address();
+        match supervisor.on_failure(failure) {
+            Decision::Restart => {
+                info!("Restarting worker '{}' after a {}", address, failure);
+                if let Err(e) = self.worker.shutdown(&mut self.ctx).await {
+                    warn!("Failure during '{}' worker shutdown: {}", address, e);
+                }
+                self.worker = supervisor.new_worker();
+                if let Err(e) = self.worker.initialize(&mut self.ctx).await {
+                    error!(
+                        "Failure during '{}' worker initialisation, stopping it: {}",
+                        address, e
+                    );
+                    self.stopping = true;
+                    if let Err(e) = self.ctx.stop_worker(address.clone()).await {
+                        error!("Failed to stop supervised worker '{}': {}", address, e);
+                    }
+                }
+            }
+            Decision::Stop => {
+                warn!(
+                    "Stopping worker '{}' after a {}, restart policy: {:?}",
+                    address,
+                    failure,
+                    supervisor.policy()
+                );
+                self.stopping = true;
+                if let Err(e) = self.ctx.stop_worker(address.clone()).await {
+                    error!("Failed to stop supervised worker '{}': {}", address, e);
+                }
+            }
+        }
+    }
+
+    /// Call the worker handle function, timing it when a metrics recorder is installed
+    async fn call_handler(&mut self, routed: Routed<M>) -> Result<()> {
+        #[cfg(feature = "std")]
+        if self.ctx.has_metrics() {
+            let start = std::time::Instant::now();
+            let result = self.worker.handle_message(&mut self.ctx, routed).await;
+            let address = self.ctx.address();
+            self.ctx
+                .record_metrics(|m| m.message_handled(&address, start.elapsed()));
+            return result;
+        }
+
+        self.worker.handle_message(&mut self.ctx, routed).await
+    }
+
     /// Receive and handle a single message
     ///
     /// Report errors as they occur, and signal whether the loop should
@@ -70,13 +236,56 @@ where
         };
 
         // Call the worker handle function - pass errors up
-        let routed = Self::wrap_direct_message(relay_msg)?;
-        self.worker.handle_message(&mut self.ctx, routed).await?;
+        self.handle_relay_message(relay_msg).await?;
 
         // Signal to the outer loop that we would like to run again
         Ok(true)
     }
 
+    /// Receive and handle a message, then the messages already waiting in the
+    /// mailbox, up to the batch size
+    ///
+    /// When the batch is full the task yields, to let the other workers run
+    #[cfg(feature = "std")]
+    async fn recv_batch(&mut self) -> Result<bool> {
+        if !self.recv_message().await? {
+            return Ok(false);
+        }
+
+        let mut handled = 1;
+        let mut result = Ok(true);
+        while handled < self.batching.batch_size() {
+            match self.recv_ready_message().await {
+                Ok(true) => handled += 1,
+                Ok(false) => break,
+                Err(e) => {
+                    handled += 1;
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        if self.batching.record_batch(handled) {
+            crate::tokio::task::yield_now().await;
+        }
+
+        result
+    }
+
+    /// Handle a message if one is already waiting in the mailbox
+    #[cfg(feature = "std")]
+    async fn recv_ready_message(&mut self) -> Result<bool> {
+        let relay_msg = match self.ctx.receiver_try_next().await? {
+            Some(msg) => msg,
+            None => return Ok(false),
+        };
+
+        self.handle_relay_message(relay_msg).await?;
+
+        Ok(true)
+    }
+
     #[cfg_attr(not(feature = "std"), allow(unused_mut))]
     #[cfg_attr(not(feature = "std"), allow(unused_variables))]
     async fn run(mut self, mut ctrl_rx: SmallReceiver<CtrlSignal>) {
@@ -100,7 +309,7 @@ where
         #[cfg(feature = "std")]
         loop {
             crate::tokio::select! {
-                result = self.recv_message() => {
+                result = self.recv_batch() => {
                     match result {
                         // Successful message handling -- keep running
                         Ok(true) => {},
@@ -114,16 +323,19 @@ where
                             error!("Error encountered during '{}' message handling: {:?}", address, e);
                             #[cfg(not(feature = "debugger"))]
                             error!("Error encountered during '{}' message handling: {}", address, e);
+                            self.supervise().await;
                         }
                     }
                 },
                 result = ctrl_rx.recv() => {
                     if result.is_some() {
                         debug!("Relay received shutdown signal, terminating!");
-                        break;
+                    } else {
+                        // The router dropped our address record, the node is being
+                        // force-stopped
+                        debug!("Relay control channel closed, terminating!");
                     }
-
-                    // We are stopping
+                    break;
                 }
             };
         }
@@ -164,8 +376,25 @@ where
     }
 
     /// Build and spawn a new worker relay, returning a send handle to it
-    pub(crate) fn init(rt: &Handle, worker: W, ctx: Context, ctrl_rx: SmallReceiver<CtrlSignal>) {
-        let relay = WorkerRelay::new(worker, ctx);
-        rt.spawn(relay.run(ctrl_rx));
+    pub(crate) fn init(
+        rt: &Handle,
+        worker: W,
+        ctx: Context,
+        ctrl_rx: SmallReceiver<CtrlSignal>,
+        batching: DispatchBatching,
+        #[cfg(feature = "std")] supervisor: Option<Supervisor<W>>,
+    ) {
+        let relay = WorkerRelay::new(
+            worker,
+            ctx,
+            batching,
+            #[cfg(feature = "std")]
+            supervisor,
+        );
+        let running = relay.ctx.running_relays().track();
+        rt.spawn(async move {
+            relay.run(ctrl_rx).await;
+            drop(running);
+        });
     }
 }
@@ -0,0 +1,19 @@
+use ockam_core::compat::vec::Vec;
+use ockam_core::{Message, Route};
+use serde::{Deserialize, Serialize};
+
+/// Messages exchanged by the sender and the receiver of a [`crate::ReliableChannel`]
+#[derive(Serialize, Deserialize, Message, Clone, Debug)]
+pub(super) enum ReliableMessage {
+    /// A message sent through the channel, numbered from 0 in sending order
+    Data {
+        seq: u64,
+        onward_route: Route,
+        return_route: Route,
+        payload: Vec<u8>,
+    },
+    /// All the messages with a sequence number lower than `next` were received
+    Ack { next: u64 },
+    /// The sender was stopped, the receiver can forget about it
+    Close,
+}
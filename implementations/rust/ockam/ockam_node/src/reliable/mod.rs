@@ -0,0 +1,122 @@
+mod messages;
+mod options;
+mod receiver;
+mod sender;
+
+pub use options::*;
+pub use receiver::MAX_OUT_OF_ORDER_MESSAGES;
+
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Address, Error, Message, Result, Route};
+
+use crate::reliable::receiver::ReliableReceiver;
+use crate::reliable::sender::ReliableSender;
+use crate::Context;
+
+/// Handle to the sending side of a reliable channel
+///
+/// A reliable channel delivers messages in order and without gaps over routes which may drop
+/// or reorder them, for example a UDP transport or a secure channel on top of it.
+/// Messages are numbered by the channel and acknowledged by a reliable listener, created with
+/// [`Context::start_reliable_listener`], which buffers out-of-order messages and releases them
+/// in sequence. Unacknowledged messages are retransmitted with an exponential backoff, see
+/// [`ReliableChannelOptions`].
+///
+/// Send messages with [`Context::send_reliable`], or directly to
+/// `route![channel.address(), destination]`. Replies to the delivered messages travel back
+/// through the channel sender, without any delivery guarantee.
+#[derive(Clone, Debug)]
+pub struct ReliableChannel {
+    address: Address,
+    failed: Arc<Mutex<Option<u64>>>,
+}
+
+impl ReliableChannel {
+    /// Address of the channel sender
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Return an error if the channel failed
+    ///
+    /// The channel fails and stops when a message is still not acknowledged after the maximum
+    /// number of retransmissions. That message and the ones sent after it may not have been
+    /// delivered.
+    pub fn check(&self) -> Result<()> {
+        match *self.failed.lock().unwrap() {
+            Some(seq) => Err(Error::new(
+                Origin::Node,
+                Kind::Timeout,
+                format!(
+                    "reliable channel {} failed: message {} was not acknowledged",
+                    self.address, seq
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl From<ReliableChannel> for Address {
+    fn from(channel: ReliableChannel) -> Self {
+        channel.address
+    }
+}
+
+impl Context {
+    /// Create a [`ReliableChannel`] to the reliable listener at the end of `route`
+    pub async fn create_reliable_channel(
+        &self,
+        route: impl Into<Route>,
+        options: ReliableChannelOptions,
+    ) -> Result<ReliableChannel> {
+        let address = Address::random_tagged("ReliableSender.main");
+        let failed = Arc::new(Mutex::new(None));
+        ReliableSender::create(self, address.clone(), route.into(), options, failed.clone())
+            .await?;
+
+        Ok(ReliableChannel { address, failed })
+    }
+
+    /// Start a listener receiving the messages of [`ReliableChannel`]s on the given address
+    ///
+    /// Messages coming through a transport or a secure channel are only accepted if the
+    /// listener address is added as a consumer of their flow control.
+    ///
+    /// The listener is a spawner for [`ReliableListenerOptions::spawner_flow_control_id`]:
+    /// the messages of every channel are delivered by a producer of their own, and only to
+    /// the consumers of that [`ockam_core::flow_control::FlowControlId`], which must be
+    /// added explicitly.
+    pub async fn start_reliable_listener(
+        &self,
+        address: impl Into<Address>,
+        options: ReliableListenerOptions,
+    ) -> Result<()> {
+        let address = address.into();
+        self.flow_controls()
+            .add_spawner(address.clone(), &options.flow_control_id);
+        self.start_worker(address, ReliableReceiver::new(&options))
+            .await
+    }
+
+    /// Send a message to `route` through a [`ReliableChannel`]
+    ///
+    /// Fails if the channel failed to deliver a previous message, see
+    /// [`ReliableChannel::check`]
+    pub async fn send_reliable<R, M>(
+        &self,
+        channel: &ReliableChannel,
+        route: R,
+        msg: M,
+    ) -> Result<()>
+    where
+        R: Into<Route>,
+        M: Message + Send + 'static,
+    {
+        channel.check()?;
+        let mut route: Route = route.into();
+        route.modify().prepend(channel.address.clone());
+        self.send(route, msg).await
+    }
+}
@@ -0,0 +1,115 @@
+use core::time::Duration;
+use ockam_core::flow_control::{FlowControlId, FlowControls};
+
+/// Default delay before the first retransmission of a message
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Default maximum delay between two retransmissions of a message
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Default number of retransmissions of a message before the channel fails
+pub const DEFAULT_MAX_RETRANSMISSIONS: u32 = 8;
+
+/// Default time after which the receiving state of a silent [`crate::ReliableChannel`] is
+/// forgotten by the reliable listener
+pub const DEFAULT_SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Default maximum number of [`crate::ReliableChannel`]s tracked by a reliable listener
+pub const DEFAULT_MAX_SESSIONS: usize = 1024;
+
+/// Options for a [`crate::ReliableChannel`]
+#[derive(Clone, Debug)]
+pub struct ReliableChannelOptions {
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) max_retransmissions: u32,
+}
+
+impl Default for ReliableChannelOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReliableChannelOptions {
+    /// Default options: messages are retransmitted after [`DEFAULT_INITIAL_BACKOFF`], the delay
+    /// doubles up to [`DEFAULT_MAX_BACKOFF`] and the channel fails after
+    /// [`DEFAULT_MAX_RETRANSMISSIONS`] retransmissions of the same message
+    pub fn new() -> Self {
+        Self {
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            max_retransmissions: DEFAULT_MAX_RETRANSMISSIONS,
+        }
+    }
+
+    /// Set the delay before the first retransmission of an unacknowledged message
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Set the maximum delay between two retransmissions of a message
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the number of retransmissions of a message after which the channel fails
+    pub fn with_max_retransmissions(mut self, max_retransmissions: u32) -> Self {
+        self.max_retransmissions = max_retransmissions;
+        self
+    }
+
+    /// Delay before the next retransmission, after a retransmission delayed by `backoff`
+    pub(super) fn next_backoff(&self, backoff: Duration) -> Duration {
+        backoff.saturating_mul(2).min(self.max_backoff)
+    }
+}
+
+/// Options for a reliable listener, see [`crate::Context::start_reliable_listener`]
+#[derive(Clone, Debug)]
+pub struct ReliableListenerOptions {
+    pub(crate) flow_control_id: FlowControlId,
+    pub(crate) session_idle_timeout: Duration,
+    pub(crate) max_sessions: usize,
+}
+
+impl Default for ReliableListenerOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReliableListenerOptions {
+    /// Default options, with a freshly generated [`FlowControlId`]
+    pub fn new() -> Self {
+        Self {
+            flow_control_id: FlowControls::generate_flow_control_id(),
+            session_idle_timeout: DEFAULT_SESSION_IDLE_TIMEOUT,
+            max_sessions: DEFAULT_MAX_SESSIONS,
+        }
+    }
+
+    /// Set the time after which a channel which didn't send any message is forgotten.
+    /// Channels are forgotten right away when their sender is stopped. A forgotten channel
+    /// which sends messages again fails, since its messages can't be delivered in order
+    pub fn with_session_idle_timeout(mut self, session_idle_timeout: Duration) -> Self {
+        self.session_idle_timeout = session_idle_timeout;
+        self
+    }
+
+    /// Set the maximum number of channels tracked at the same time. Messages opening a new
+    /// channel are dropped, without being acknowledged, while that limit is reached.
+    /// Defaults to [`DEFAULT_MAX_SESSIONS`]
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions;
+        self
+    }
+
+    /// Getter for freshly generated [`FlowControlId`]. The listener is a spawner for this
+    /// [`FlowControlId`], the messages it delivers are only accepted by its consumers
+    pub fn spawner_flow_control_id(&self) -> FlowControlId {
+        self.flow_control_id.clone()
+    }
+}
@@ -0,0 +1,256 @@
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::collections::{BTreeMap, HashMap};
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
+use ockam_core::{
+    async_trait, Address, Any, Decodable, DenyAll, LocalMessage, Mailboxes, Result, Route, Routed,
+    TransportMessage, Worker,
+};
+use std::time::Instant;
+
+use crate::reliable::messages::ReliableMessage;
+use crate::reliable::ReliableListenerOptions;
+use crate::Context;
+
+/// Maximum number of messages buffered for one sender while waiting for a missing message.
+/// Messages numbered that far or further ahead of the missing one are dropped and
+/// retransmitted later by the sender
+pub const MAX_OUT_OF_ORDER_MESSAGES: usize = 1024;
+
+/// A message received ahead of a missing one
+struct BufferedMessage {
+    onward_route: Route,
+    return_route: Route,
+    payload: Vec<u8>,
+}
+
+/// Delivery state of one [`crate::ReliableChannel`]
+///
+/// The messages of the channel are delivered from the address of the session, which is a
+/// producer with its own [`FlowControlId`], spawned by the listener. They can only reach the
+/// consumers of the listener [`FlowControlId`].
+struct Session {
+    /// Sequence number of the next message to deliver
+    next: u64,
+    buffer: BTreeMap<u64, BufferedMessage>,
+    ctx: Context,
+    last_activity: Instant,
+}
+
+impl Session {
+    async fn create(ctx: &Context, listener_flow_control_id: &FlowControlId) -> Result<Self> {
+        let address = Address::random_tagged("ReliableReceiver.session");
+        let flow_control_id = FlowControls::generate_flow_control_id();
+        ctx.flow_controls().add_producer(
+            address.clone(),
+            &flow_control_id,
+            Some(listener_flow_control_id),
+            vec![],
+        );
+        let outgoing_access_control = FlowControlOutgoingAccessControl::new(
+            ctx.flow_controls(),
+            flow_control_id,
+            Some(listener_flow_control_id.clone()),
+        );
+        let ctx = ctx
+            .new_detached_with_mailboxes(Mailboxes::main(
+                address,
+                Arc::new(DenyAll),
+                Arc::new(outgoing_access_control),
+            ))
+            .await?;
+
+        Ok(Self {
+            next: 0,
+            buffer: BTreeMap::new(),
+            ctx,
+            last_activity: Instant::now(),
+        })
+    }
+
+    /// Buffer a message received ahead of the next one to deliver. Returns false if it was
+    /// dropped, because it was already delivered or is too far ahead
+    fn buffer(&mut self, seq: u64, message: BufferedMessage) -> bool {
+        if seq <= self.next || seq - self.next >= MAX_OUT_OF_ORDER_MESSAGES as u64 {
+            return false;
+        }
+        self.buffer.insert(seq, message);
+        true
+    }
+
+    /// Deliver a message to its destination. Replies are sent back to the sender, which
+    /// forwards them to the original return route
+    async fn deliver(&self, sender_route: &Route, message: BufferedMessage) -> Result<()> {
+        if message.onward_route.is_empty() {
+            warn!(
+                "ReliableReceiver {} dropped a message without a destination",
+                self.ctx.address()
+            );
+            return Ok(());
+        }
+        let mut return_route = sender_route.clone();
+        return_route.modify().append_route(message.return_route);
+        let transport = TransportMessage::v1(message.onward_route, return_route, message.payload);
+        self.ctx.forward(LocalMessage::new(transport, vec![])).await
+    }
+}
+
+/// Receiving side of [`crate::ReliableChannel`]s
+///
+/// The channels are identified by the address of their sender, at the end of the return route
+/// of their messages.
+pub(super) struct ReliableReceiver {
+    flow_control_id: FlowControlId,
+    session_idle_timeout: Duration,
+    max_sessions: usize,
+    sessions: HashMap<Address, Session>,
+}
+
+impl ReliableReceiver {
+    pub(super) fn new(options: &ReliableListenerOptions) -> Self {
+        Self {
+            flow_control_id: options.flow_control_id.clone(),
+            session_idle_timeout: options.session_idle_timeout,
+            max_sessions: options.max_sessions,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// Forget the channels whose sender didn't send anything for a while, it may have
+    /// stopped without being able to notify us
+    fn evict_idle_sessions(&mut self, now: Instant) {
+        let timeout = self.session_idle_timeout;
+        self.sessions
+            .retain(|_, session| now.duration_since(session.last_activity) < timeout);
+    }
+}
+
+#[async_trait]
+impl Worker for ReliableReceiver {
+    type Message = Any;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let now = Instant::now();
+        self.evict_idle_sessions(now);
+
+        let sender_route = msg.return_route();
+        let sender = sender_route.recipient()?;
+
+        let (seq, message) = match ReliableMessage::decode(msg.payload())? {
+            ReliableMessage::Data {
+                seq,
+                onward_route,
+                return_route,
+                payload,
+            } => (
+                seq,
+                BufferedMessage {
+                    onward_route,
+                    return_route,
+                    payload,
+                },
+            ),
+            ReliableMessage::Close => {
+                self.sessions.remove(&sender);
+                return Ok(());
+            }
+            message => {
+                warn!(
+                    "ReliableReceiver {} ignored an unexpected message {:?}",
+                    ctx.address(),
+                    message
+                );
+                return Ok(());
+            }
+        };
+
+        if !self.sessions.contains_key(&sender) {
+            if self.sessions.len() >= self.max_sessions {
+                warn!(
+                    "ReliableReceiver {} dropped a message from {}, too many channels",
+                    ctx.address(),
+                    sender
+                );
+                return Ok(());
+            }
+            let session = Session::create(ctx, &self.flow_control_id).await?;
+            self.sessions.insert(sender.clone(), session);
+        }
+        let session = self.sessions.get_mut(&sender).expect("the session exists");
+        session.last_activity = now;
+
+        if seq == session.next {
+            session.deliver(&sender_route, message).await?;
+            session.next += 1;
+            while let Some(message) = session.buffer.remove(&session.next) {
+                session.deliver(&sender_route, message).await?;
+                session.next += 1;
+            }
+        } else if !session.buffer(seq, message) {
+            trace!("ReliableReceiver {} dropped message {}", ctx.address(), seq);
+        }
+
+        // Duplicates are acknowledged again, in case the previous acknowledgement was lost
+        ctx.send(sender_route, ReliableMessage::Ack { next: session.next })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(crate = "crate")]
+    async fn evict_idle_sessions__clock_moved_backwards__should_follow_the_monotonic_clock(
+        ctx: &mut Context,
+    ) -> Result<()> {
+        let timeout = Duration::from_secs(10);
+        let options = ReliableListenerOptions::new().with_session_idle_timeout(timeout);
+        let mut receiver = ReliableReceiver::new(&options);
+
+        // The last activity is more recent than the next clock reading, as if the clock
+        // had been moved backwards in between
+        let mut session = Session::create(ctx, &receiver.flow_control_id).await?;
+        let last_activity = Instant::now() + Duration::from_secs(3600);
+        session.last_activity = last_activity;
+        receiver.sessions.insert("sender".into(), session);
+
+        // The session is neither evicted early nor kept longer than the idle timeout
+        receiver.evict_idle_sessions(Instant::now());
+        assert_eq!(receiver.sessions.len(), 1);
+        receiver.evict_idle_sessions(last_activity + timeout - Duration::from_millis(1));
+        assert_eq!(receiver.sessions.len(), 1);
+        receiver.evict_idle_sessions(last_activity + timeout);
+        assert!(receiver.sessions.is_empty());
+
+        ctx.stop().await
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(crate = "crate")]
+    async fn buffer__message_too_far_ahead__should_be_dropped(ctx: &mut Context) -> Result<()> {
+        let options = ReliableListenerOptions::new();
+        let mut session = Session::create(ctx, &options.flow_control_id).await?;
+        session.next = 10;
+        let message = || BufferedMessage {
+            onward_route: Route::new().into(),
+            return_route: Route::new().into(),
+            payload: vec![],
+        };
+
+        // Already delivered messages and the ones outside of the window are dropped
+        assert!(!session.buffer(10, message()));
+        assert!(!session.buffer(3, message()));
+        assert!(!session.buffer(10 + MAX_OUT_OF_ORDER_MESSAGES as u64, message()));
+        assert!(!session.buffer(u64::MAX, message()));
+        assert!(session.buffer(11, message()));
+        assert!(session.buffer(9 + MAX_OUT_OF_ORDER_MESSAGES as u64, message()));
+        assert_eq!(session.buffer.len(), 2);
+
+        ctx.stop().await
+    }
+}
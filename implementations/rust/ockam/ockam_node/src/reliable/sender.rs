@@ -0,0 +1,265 @@
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::collections::{BTreeMap, BTreeSet};
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::{
+    async_trait, Address, AllowAll, AllowOnwardAddress, AllowSourceAddress, AllowSourceAddresses,
+    Any, Decodable, DenyAll, IncomingAccessControl, Mailbox, Mailboxes, Result, Route, Routed,
+    Worker,
+};
+use std::time::Instant;
+
+use crate::reliable::messages::ReliableMessage;
+use crate::reliable::ReliableChannelOptions;
+use crate::{Context, DelayedEvent, WorkerBuilder};
+
+/// A message waiting for its acknowledgement
+struct PendingMessage {
+    message: ReliableMessage,
+    retransmissions: u32,
+    backoff: Duration,
+    deadline: Instant,
+}
+
+/// Sending side of a [`crate::ReliableChannel`]
+///
+/// Messages sent to the main address are numbered and sent to the receiver from the remote
+/// address, which receives the acknowledgements as well as the replies to the delivered
+/// messages. Unacknowledged messages are retransmitted when the timer sends a tick to the
+/// internal address.
+///
+/// The remote address can only send to the first hop of the route to the receiver. Replies
+/// are forwarded from the main address, and only to the workers which sent a message through
+/// the channel.
+pub(super) struct ReliableSender {
+    address: Address,
+    remote_address: Address,
+    remote_route: Route,
+    reply_addresses: BTreeSet<Address>,
+    options: ReliableChannelOptions,
+    next_seq: u64,
+    pending: BTreeMap<u64, PendingMessage>,
+    timer: DelayedEvent<()>,
+    timer_deadline: Option<Instant>,
+    failed: Arc<Mutex<Option<u64>>>,
+}
+
+impl ReliableSender {
+    pub(super) async fn create(
+        ctx: &Context,
+        address: Address,
+        remote_route: Route,
+        options: ReliableChannelOptions,
+        failed: Arc<Mutex<Option<u64>>>,
+    ) -> Result<()> {
+        let remote_address = Address::random_tagged("ReliableSender.remote");
+        let internal_address = Address::random_tagged("ReliableSender.internal");
+
+        // Allow the acknowledgements coming back through a transport or a secure channel.
+        // Otherwise they come back from the first hop of the route
+        let next = remote_route.next()?.clone();
+        let flow_controls = ctx.flow_controls();
+        let remote_incoming_access_control: Arc<dyn IncomingAccessControl> = match flow_controls
+            .find_flow_control_with_producer_address(&next)
+            .map(|x| x.flow_control_id().clone())
+        {
+            Some(flow_control_id) => {
+                flow_controls.add_consumer(remote_address.clone(), &flow_control_id);
+                Arc::new(AllowAll)
+            }
+            None => Arc::new(AllowSourceAddress(next.clone())),
+        };
+
+        let timer = DelayedEvent::create(ctx, internal_address.clone(), ()).await?;
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(address.clone(), Arc::new(AllowAll), Arc::new(AllowAll)),
+            vec![
+                Mailbox::new(
+                    remote_address.clone(),
+                    remote_incoming_access_control,
+                    Arc::new(AllowOnwardAddress(next)),
+                ),
+                Mailbox::new(
+                    internal_address,
+                    Arc::new(AllowSourceAddresses(vec![timer.address()])),
+                    Arc::new(DenyAll),
+                ),
+            ],
+        );
+
+        let worker = Self {
+            address,
+            remote_address,
+            remote_route,
+            reply_addresses: BTreeSet::new(),
+            options,
+            next_seq: 0,
+            pending: BTreeMap::new(),
+            timer,
+            timer_deadline: None,
+            failed,
+        };
+
+        WorkerBuilder::new(worker)
+            .with_mailboxes(mailboxes)
+            .start(ctx)
+            .await
+    }
+
+    /// Number and send a message received on the main address
+    async fn handle_local(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut transport = msg.into_transport_message();
+        transport.onward_route.step()?;
+        if transport.onward_route.is_empty() {
+            warn!(
+                "ReliableSender {} dropped a message without a destination",
+                self.address
+            );
+            return Ok(());
+        }
+
+        if let Ok(reply_address) = transport.return_route.next() {
+            self.reply_addresses.insert(reply_address.clone());
+        }
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let message = ReliableMessage::Data {
+            seq,
+            onward_route: transport.onward_route,
+            return_route: transport.return_route,
+            payload: transport.payload,
+        };
+        self.send(ctx, message.clone()).await?;
+
+        let backoff = self.options.initial_backoff;
+        self.pending.insert(
+            seq,
+            PendingMessage {
+                message,
+                retransmissions: 0,
+                backoff,
+                deadline: Instant::now() + backoff,
+            },
+        );
+        self.schedule_timer().await
+    }
+
+    /// Process an acknowledgement, or forward a reply to a delivered message
+    async fn handle_remote(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        if msg.onward_route().len() > 1 {
+            let mut local_message = msg.into_local_message();
+            local_message.transport_mut().onward_route.step()?;
+            let next = local_message.transport().onward_route.next()?;
+            if !self.reply_addresses.contains(next) {
+                warn!(
+                    "ReliableSender {} dropped a reply to {}, which didn't send any message",
+                    self.address, next
+                );
+                return Ok(());
+            }
+            return ctx
+                .forward_from_address(local_message, self.address.clone())
+                .await;
+        }
+
+        match ReliableMessage::decode(msg.payload())? {
+            ReliableMessage::Ack { next } => {
+                self.pending = self.pending.split_off(&next);
+                if self.pending.is_empty() {
+                    self.timer.cancel();
+                    self.timer_deadline = None;
+                }
+            }
+            message => warn!(
+                "ReliableSender {} ignored an unexpected message {:?}",
+                self.address, message
+            ),
+        }
+        Ok(())
+    }
+
+    /// Retransmit the messages which were not acknowledged in time
+    async fn handle_timer(&mut self, ctx: &mut Context) -> Result<()> {
+        let now = Instant::now();
+        let mut retransmit = vec![];
+        for (seq, pending) in self.pending.iter_mut() {
+            if pending.deadline > now {
+                continue;
+            }
+            if pending.retransmissions >= self.options.max_retransmissions {
+                warn!(
+                    "ReliableSender {} is stopping: message {} was not acknowledged after {} retransmissions",
+                    self.address, seq, pending.retransmissions
+                );
+                *self.failed.lock().unwrap() = Some(*seq);
+                return ctx.stop_worker(self.address.clone()).await;
+            }
+            pending.retransmissions += 1;
+            pending.backoff = self.options.next_backoff(pending.backoff);
+            pending.deadline = now + pending.backoff;
+            retransmit.push(pending.message.clone());
+        }
+
+        for message in retransmit {
+            self.send(ctx, message).await?;
+        }
+
+        self.timer_deadline = None;
+        self.schedule_timer().await
+    }
+
+    /// Make sure that the timer fires for the earliest retransmission
+    async fn schedule_timer(&mut self) -> Result<()> {
+        let deadline = match self.pending.values().map(|p| p.deadline).min() {
+            Some(deadline) => deadline,
+            None => return Ok(()),
+        };
+        if matches!(self.timer_deadline, Some(current) if current <= deadline) {
+            return Ok(());
+        }
+
+        self.timer
+            .schedule(deadline.saturating_duration_since(Instant::now()))
+            .await?;
+        self.timer_deadline = Some(deadline);
+        Ok(())
+    }
+
+    async fn send(&self, ctx: &Context, message: ReliableMessage) -> Result<()> {
+        ctx.send_from_address(
+            self.remote_route.clone(),
+            message,
+            self.remote_address.clone(),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl Worker for ReliableSender {
+    type Message = Any;
+    type Context = Context;
+
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        self.timer.cancel();
+        if let Err(e) = self.send(ctx, ReliableMessage::Close).await {
+            debug!(
+                "ReliableSender {} couldn't notify the receiver: {}",
+                self.address, e
+            );
+        }
+        Ok(())
+    }
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let msg_addr = msg.msg_addr();
+        if msg_addr == self.address {
+            self.handle_local(ctx, msg).await
+        } else if msg_addr == self.remote_address {
+            self.handle_remote(ctx, msg).await
+        } else {
+            self.handle_timer(ctx).await
+        }
+    }
+}
@@ -17,8 +17,9 @@ use crate::channel_types::{router_channel, MessageSender, RouterReceiver, SmallS
 use crate::{
     error::{NodeError, NodeReason},
     relay::CtrlSignal,
-    NodeMessage, NodeReplyResult, RouterReply, ShutdownType,
+    NodeMessage, NodeReplyResult, RouterReply, ShutdownType, WorkerKind,
 };
+use core::time::Duration;
 use ockam_core::compat::{collections::BTreeMap, sync::Arc};
 use ockam_core::flow_control::FlowControls;
 use ockam_core::{Address, RelayMessage, Result, TransportType};
@@ -97,6 +98,8 @@ impl Router {
                 AddressMeta {
                     processor: false,
                     detached: true,
+                    kind: WorkerKind::application(),
+                    max_message_size: None,
                 },
             ),
         );
@@ -182,20 +185,48 @@ impl Router {
                 senders,
                 detached,
                 mailbox_count,
+                max_message_size,
+                kind,
                 ref reply,
-            } => start_worker::exec(self, addrs, senders, detached, mailbox_count, reply).await?,
+            } => {
+                start_worker::exec(
+                    self,
+                    addrs,
+                    senders,
+                    detached,
+                    mailbox_count,
+                    max_message_size,
+                    kind,
+                    reply,
+                )
+                .await?
+            }
             StopWorker(ref addr, ref detached, ref reply) => {
                 stop_worker::exec(self, addr, *detached, reply).await?
             }
 
             //// ==! Basic processor control
-            StartProcessor(addr, senders, ref reply) => {
-                start_processor::exec(self, addr, senders, reply).await?
+            StartProcessor(addr, senders, kind, ref reply) => {
+                start_processor::exec(self, addr, senders, kind, reply).await?
             }
             StopProcessor(ref addr, ref reply) => stop_processor::exec(self, addr, reply).await?,
 
             //// ==! Core node controls
-            StopNode(ShutdownType::Graceful(timeout), reply) => {
+            StopNode(ShutdownType::Graceful(seconds), reply) => {
+                let timeout = Duration::from_secs(seconds as u64);
+                if shutdown::graceful(self, timeout, reply).await? {
+                    info!("No more workers left.  Goodbye!");
+                    if let Some(sender) = self.state.stop_reply() {
+                        sender
+                            .send(RouterReply::ok())
+                            .await
+                            .map_err(|_| NodeError::NodeState(NodeReason::Unknown).internal())?;
+                        return Ok(true);
+                    };
+                }
+            }
+            StopNode(ShutdownType::Ordered(timeout), reply) => {
+                self.map.stop_transports_last();
                 if shutdown::graceful(self, timeout, reply).await? {
                     info!("No more workers left.  Goodbye!");
                     if let Some(sender) = self.state.stop_reply() {
@@ -214,6 +245,9 @@ impl Router {
 
             AbortNode => {
                 if let Some(sender) = self.state.stop_reply() {
+                    for addr in self.map.address_records_map().keys() {
+                        warn!("Worker '{}' didn't stop in time, forcing it to stop", addr);
+                    }
                     sender
                         .send(RouterReply::ok())
                         .await
@@ -248,6 +282,11 @@ impl Router {
                 .await
                 .map_err(|_| NodeError::NodeState(NodeReason::Unknown).internal())?,
 
+            ListWorkersInfo(sender) => sender
+                .send(RouterReply::workers_info(self.map.workers_info()))
+                .await
+                .map_err(|_| NodeError::NodeState(NodeReason::Unknown).internal())?,
+
             SetCluster(addr, label, reply) => {
                 debug!("Setting cluster on address {}", addr);
                 let msg = self.map.set_cluster(label, addr);
@@ -2,7 +2,7 @@ use crate::channel_types::{MessageSender, SmallSender};
 use crate::relay::CtrlSignal;
 use crate::{
     error::{NodeError, NodeReason},
-    NodeReplyResult, RouterReply,
+    NodeReplyResult, RouterReply, WorkerInfo, WorkerKind,
 };
 use core::sync::atomic::{AtomicUsize, Ordering};
 use ockam_core::{
@@ -16,6 +16,9 @@ use ockam_core::{
     Address, RelayMessage, Result,
 };
 
+/// Prefix of the names of the transport clusters
+const TRANSPORT_CLUSTER_PREFIX: &str = "_internals.transport.";
+
 /// Address states and associated logic
 pub struct InternalMap {
     /// Registry of primary address to worker address record state
@@ -145,6 +148,32 @@ impl InternalMap {
         RouterReply::ok()
     }
 
+    /// Describe all the registered workers
+    ///
+    /// Since the router handles one message at a time, the result is a consistent
+    /// snapshot of the registry, even while workers are started and stopped
+    pub(super) fn workers_info(&self) -> Vec<WorkerInfo> {
+        self.address_records_map
+            .iter()
+            .map(|(addr, rec)| {
+                let cluster = self
+                    .clusters
+                    .iter()
+                    .find(|(_, set)| set.contains(addr))
+                    .map(|(label, _)| label.clone());
+                WorkerInfo::new(
+                    addr.clone(),
+                    rec.address_set.clone(),
+                    rec.meta.kind.clone(),
+                    rec.meta.processor,
+                    rec.meta.detached,
+                    cluster,
+                    rec.routed_messages.load(Ordering::Relaxed),
+                )
+            })
+            .collect()
+    }
+
     /// Set an address as ready and return the list of waiting pollers
     pub(super) fn set_ready(&mut self, addr: Address) -> Result<Vec<SmallSender<NodeReplyResult>>> {
         let addr_record = self
@@ -161,6 +190,14 @@ impl InternalMap {
             .map_or(false, |rec| rec.ready(reply))
     }
 
+    /// Reorder the clusters so that the transport clusters are the last ones
+    /// to be stopped, keeping the creation order otherwise
+    pub(super) fn stop_transports_last(&mut self) {
+        // Clusters are stopped from the end of the list
+        self.cluster_order
+            .sort_by_key(|label| !label.starts_with(TRANSPORT_CLUSTER_PREFIX));
+    }
+
     /// Retrieve the next cluster in reverse-initialisation order
     pub(super) fn next_cluster(&mut self) -> Option<Vec<&mut AddressRecord>> {
         let name = self.cluster_order.pop()?;
@@ -229,6 +266,8 @@ impl InternalMap {
 pub struct AddressMeta {
     pub processor: bool,
     pub detached: bool,
+    pub kind: WorkerKind,
+    pub max_message_size: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -240,6 +279,7 @@ pub struct AddressRecord {
     ready: ReadyState,
     meta: AddressMeta,
     msg_count: Arc<AtomicUsize>,
+    routed_messages: AtomicUsize,
 }
 
 impl AddressRecord {
@@ -251,6 +291,10 @@ impl AddressRecord {
         self.sender.clone().expect("No such sender!")
     }
 
+    pub fn max_message_size(&self) -> Option<usize> {
+        self.meta.max_message_size
+    }
+
     pub fn drop_sender(&mut self) {
         self.sender = None;
     }
@@ -269,12 +313,14 @@ impl AddressRecord {
             state: AddressState::Running,
             ready: ReadyState::Initialising(vec![]),
             msg_count,
+            routed_messages: AtomicUsize::new(0),
             meta,
         }
     }
 
     pub fn increment_msg_count(&self) {
         self.msg_count.fetch_add(1, Ordering::Acquire);
+        self.routed_messages.fetch_add(1, Ordering::Relaxed);
     }
 
     /// Signal this worker to stop -- it will no longer be able to receive messages
@@ -4,6 +4,7 @@ use crate::{
     error::{NodeError, NodeReason},
     NodeReplyResult, RouterReply,
 };
+use core::time::Duration;
 use ockam_core::{Address, Result};
 
 /// Register a stop ACK
@@ -51,7 +52,7 @@ async fn stop_next_cluster(r: &mut Router) -> Result<bool> {
 #[cfg_attr(not(feature = "std"), allow(unused_variables))]
 pub(super) async fn graceful(
     router: &mut Router,
-    seconds: u8,
+    timeout: Duration,
     reply: SmallSender<NodeReplyResult>,
 ) -> Result<bool> {
     // Mark the router as shutting down to prevent spawning
@@ -84,13 +85,11 @@ pub(super) async fn graceful(
     #[cfg(feature = "std")]
     {
         use crate::NodeMessage;
-        use core::time::Duration;
         use tokio::{task, time};
 
         let sender = router.sender();
-        let dur = Duration::from_secs(seconds as u64);
         task::spawn(async move {
-            time::sleep(dur).await;
+            time::sleep(timeout).await;
             warn!("Shutdown timeout reached; aborting node!");
             if sender.send(NodeMessage::AbortNode).await.is_err() {
                 error!("Failed to send node abort signal to router");
@@ -107,8 +106,9 @@ pub(super) async fn graceful(
 /// When triggering an `immediate` shutdown, all worker handles are
 /// signalled to terminate, allowing workers to run their `async fn
 /// shutdown(...)` hook.  However: the router will not wait for them!
-/// Messages sent during the shutdown phase may not be delivered and
-/// shutdown hooks may be suddenly interrupted by thread-deallocation.
+/// Messages sent during the shutdown phase may not be delivered.  The
+/// executor still gives the shutdown hooks a limited time to complete
+/// before the runtime is dropped.
 pub(super) async fn immediate(
     router: &mut Router,
     reply: SmallSender<NodeReplyResult>,
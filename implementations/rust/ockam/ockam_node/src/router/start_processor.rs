@@ -2,7 +2,7 @@ use super::{AddressMeta, AddressRecord, NodeState, Router, SenderPair};
 use crate::channel_types::SmallSender;
 use crate::{
     error::{NodeError, NodeReason},
-    NodeReplyResult, RouterReply,
+    NodeReplyResult, RouterReply, WorkerKind,
 };
 #[cfg(feature = "std")]
 use ockam_core::env::get_env;
@@ -13,10 +13,11 @@ pub(super) async fn exec(
     router: &mut Router,
     addrs: Address,
     senders: SenderPair,
+    kind: WorkerKind,
     reply: &SmallSender<NodeReplyResult>,
 ) -> Result<()> {
     match router.state.node_state() {
-        NodeState::Running => start(router, addrs, senders, reply).await,
+        NodeState::Running => start(router, addrs, senders, kind, reply).await,
         NodeState::Stopping(_) => reject(reply).await,
         NodeState::Dead => unreachable!(),
     }?;
@@ -27,6 +28,7 @@ async fn start(
     router: &mut Router,
     addr: Address,
     senders: SenderPair,
+    kind: WorkerKind,
     reply: &SmallSender<NodeReplyResult>,
 ) -> Result<()> {
     router.check_addr_not_exist(&addr, reply).await?;
@@ -48,6 +50,8 @@ async fn start(
         AddressMeta {
             processor: true,
             detached: false,
+            kind,
+            max_message_size: None,
         },
     );
 
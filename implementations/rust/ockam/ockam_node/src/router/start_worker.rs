@@ -2,7 +2,7 @@ use super::{AddressMeta, AddressRecord, NodeState, Router, SenderPair};
 use crate::channel_types::SmallSender;
 use crate::{
     error::{NodeError, NodeReason},
-    NodeReplyResult, RouterReason, RouterReply,
+    NodeReplyResult, RouterReason, RouterReply, WorkerKind,
 };
 use core::sync::atomic::AtomicUsize;
 #[cfg(feature = "std")]
@@ -19,10 +19,24 @@ pub(super) async fn exec(
     senders: SenderPair,
     detached: bool,
     metrics: Arc<AtomicUsize>,
+    max_message_size: Option<usize>,
+    kind: WorkerKind,
     reply: &SmallSender<NodeReplyResult>,
 ) -> Result<()> {
     match router.state.node_state() {
-        NodeState::Running => start(router, addrs, senders, detached, metrics, reply).await,
+        NodeState::Running => {
+            start(
+                router,
+                addrs,
+                senders,
+                detached,
+                metrics,
+                max_message_size,
+                kind,
+                reply,
+            )
+            .await
+        }
         NodeState::Stopping(_) => reject(reply).await,
         NodeState::Dead => unreachable!(),
     }?;
@@ -35,6 +49,8 @@ async fn start(
     senders: SenderPair,
     detached: bool,
     metrics: Arc<AtomicUsize>,
+    max_message_size: Option<usize>,
+    kind: WorkerKind,
     reply: &SmallSender<NodeReplyResult>,
 ) -> Result<()> {
     let primary_addr = addrs
@@ -58,6 +74,8 @@ async fn start(
         AddressMeta {
             processor: false,
             detached,
+            kind,
+            max_message_size,
         },
     );
 
@@ -0,0 +1,231 @@
+use core::fmt;
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::collections::VecDeque;
+use ockam_core::{Address, Result, Worker};
+use std::time::Instant;
+
+use crate::{Context, WorkerBuilder};
+
+/// Default number of restarts allowed within [`DEFAULT_RESTART_WINDOW`]
+pub const DEFAULT_MAX_RESTARTS: u32 = 5;
+
+/// Default duration of the window in which restarts are counted
+pub const DEFAULT_RESTART_WINDOW: Duration = Duration::from_secs(60);
+
+/// When a supervised worker is restarted after a failure of its message handler
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Stop the worker when its handler returns an error or panics
+    Never,
+    /// Restart the worker when its handler returns an error or panics
+    Always,
+    /// Restart the worker when its handler returns an error, stop it when its handler panics
+    OnError,
+}
+
+/// How a message handler failed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Failure {
+    Error,
+    Panic,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Error => write!(f, "handler error"),
+            Failure::Panic => write!(f, "handler panic"),
+        }
+    }
+}
+
+/// What to do with a worker after a failure of its message handler
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Decision {
+    Restart,
+    Stop,
+}
+
+/// Supervision of a [`Worker`], see [`Context::start_supervised_worker`]
+///
+/// Restarting a worker replaces it with a fresh instance built by the factory of the
+/// supervisor, since the state of the failed instance may be inconsistent:
+///
+///  1. [`Worker::shutdown`] is called on the failed instance, to release its resources
+///  2. a new instance is built by the factory
+///  3. [`Worker::initialize`] is called on the new instance, which is stopped if it fails
+///
+/// A restarted worker keeps its addresses and its mailbox, so that the messages sent to it
+/// while it is restarted are not lost. The message which made it fail is not handled again.
+///
+/// A worker which is not restarted, because of its [`RestartPolicy`] or because it failed too
+/// often, is stopped like with [`Context::stop_worker`], which calls its shutdown function.
+pub struct Supervisor<W> {
+    policy: RestartPolicy,
+    max_restarts: u32,
+    window: Duration,
+    restarts: VecDeque<Instant>,
+    factory: Box<dyn FnMut() -> W + Send>,
+}
+
+impl<W> fmt::Debug for Supervisor<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Supervisor")
+            .field("policy", &self.policy)
+            .field("max_restarts", &self.max_restarts)
+            .field("window", &self.window)
+            .finish()
+    }
+}
+
+impl<W> Supervisor<W> {
+    /// Supervise a worker with the given [`RestartPolicy`], restarting it with the instances
+    /// built by `factory`. The worker is stopped if it is restarted more than
+    /// [`DEFAULT_MAX_RESTARTS`] times within [`DEFAULT_RESTART_WINDOW`]
+    pub fn new(policy: RestartPolicy, factory: impl FnMut() -> W + Send + 'static) -> Self {
+        Self {
+            policy,
+            max_restarts: DEFAULT_MAX_RESTARTS,
+            window: DEFAULT_RESTART_WINDOW,
+            restarts: VecDeque::new(),
+            factory: Box::new(factory),
+        }
+    }
+
+    /// Stop the worker instead of restarting it once it was restarted `max_restarts` times
+    /// within the last `window`
+    pub fn with_max_restarts(mut self, max_restarts: u32, window: Duration) -> Self {
+        self.max_restarts = max_restarts;
+        self.window = window;
+        self
+    }
+
+    /// Restart policy of the worker
+    pub fn policy(&self) -> RestartPolicy {
+        self.policy
+    }
+}
+
+impl<W> Supervisor<W> {
+    /// Decide if the worker is restarted after a failure
+    pub(crate) fn on_failure(&mut self, failure: Failure) -> Decision {
+        self.on_failure_at(failure, Instant::now())
+    }
+
+    pub(crate) fn on_failure_at(&mut self, failure: Failure, now: Instant) -> Decision {
+        let restart = match (self.policy, failure) {
+            (RestartPolicy::Always, _) | (RestartPolicy::OnError, Failure::Error) => true,
+            (RestartPolicy::Never, _) | (RestartPolicy::OnError, Failure::Panic) => false,
+        };
+        if !restart {
+            return Decision::Stop;
+        }
+
+        while matches!(self.restarts.front(), Some(t) if now.duration_since(*t) >= self.window) {
+            self.restarts.pop_front();
+        }
+        if self.restarts.len() >= self.max_restarts as usize {
+            return Decision::Stop;
+        }
+        self.restarts.push_back(now);
+
+        Decision::Restart
+    }
+
+    /// Build the instance replacing a failed worker
+    pub(crate) fn new_worker(&mut self) -> W {
+        (self.factory)()
+    }
+}
+
+impl Context {
+    /// Start a new worker instance at the given address, supervised by `supervisor`
+    ///
+    /// Unlike the workers started with [`start_worker()`](Self::start_worker), which log the
+    /// errors returned by their message handler and die when it panics, a supervised worker is
+    /// restarted or stopped according to its [`RestartPolicy`]. See [`Supervisor`] for the
+    /// steps of a restart.
+    ///
+    /// ```rust
+    /// use core::time::Duration;
+    /// use ockam_core::{Result, Worker, worker};
+    /// use ockam_node::{Context, RestartPolicy, Supervisor};
+    ///
+    /// #[derive(Default)]
+    /// struct MyWorker {
+    ///     received: usize,
+    /// }
+    ///
+    /// #[worker]
+    /// impl Worker for MyWorker {
+    ///     type Context = Context;
+    ///     type Message = String;
+    /// }
+    ///
+    /// async fn start_my_worker(ctx: &mut Context) -> Result<()> {
+    ///     let supervisor = Supervisor::new(RestartPolicy::Always, MyWorker::default)
+    ///         .with_max_restarts(3, Duration::from_secs(10));
+    ///     ctx.start_supervised_worker("my-worker-address", MyWorker::default(), supervisor)
+    ///         .await
+    /// }
+    /// ```
+    pub async fn start_supervised_worker<W>(
+        &self,
+        address: impl Into<Address>,
+        worker: W,
+        supervisor: Supervisor<W>,
+    ) -> Result<()>
+    where
+        W: Worker<Context = Context>,
+    {
+        WorkerBuilder::new(worker)
+            .with_address(address)
+            .with_supervisor(supervisor)
+            .start(self)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restarts_are_limited_within_the_window() {
+        let mut built = 0;
+        let mut supervisor = Supervisor::new(RestartPolicy::OnError, || 0u8)
+            .with_max_restarts(2, Duration::from_secs(10));
+
+        let start = Instant::now();
+        for _ in 0..2 {
+            assert_eq!(
+                supervisor.on_failure_at(Failure::Error, start),
+                Decision::Restart
+            );
+            built += supervisor.new_worker() + 1;
+        }
+        assert_eq!(
+            supervisor.on_failure_at(Failure::Error, start),
+            Decision::Stop
+        );
+        assert_eq!(
+            supervisor.on_failure_at(Failure::Error, start + Duration::from_secs(10)),
+            Decision::Restart
+        );
+        assert_eq!(built, 2);
+    }
+
+    #[test]
+    fn policies() {
+        let mut never = Supervisor::new(RestartPolicy::Never, || ());
+        assert_eq!(never.on_failure(Failure::Error), Decision::Stop);
+
+        let mut on_error = Supervisor::new(RestartPolicy::OnError, || ());
+        assert_eq!(on_error.on_failure(Failure::Error), Decision::Restart);
+        assert_eq!(on_error.on_failure(Failure::Panic), Decision::Stop);
+
+        let mut always = Supervisor::new(RestartPolicy::Always, || ());
+        assert_eq!(always.on_failure(Failure::Panic), Decision::Restart);
+    }
+}
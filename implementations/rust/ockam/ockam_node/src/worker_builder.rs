@@ -1,11 +1,15 @@
 use crate::debugger;
 use crate::error::{NodeError, NodeReason};
-use crate::{relay::WorkerRelay, Context, NodeMessage};
-use ockam_core::compat::sync::Arc;
+#[cfg(feature = "std")]
+use crate::Supervisor;
+use crate::{
+    relay::WorkerRelay, Context, DispatchBatching, MailboxCapacity, NodeMessage, WorkerKind,
+};
+use ockam_core::compat::{sync::Arc, vec::Vec};
 use ockam_core::{
     errcode::{Kind, Origin},
-    Address, AllowAll, Error, IncomingAccessControl, Mailboxes, OutgoingAccessControl, Result,
-    Worker,
+    Address, AllowAll, Error, IncomingAccessControl, Mailbox, Mailboxes, OutgoingAccessControl,
+    Result, Worker,
 };
 
 /// Start a [`Worker`] with a custom configuration
@@ -38,8 +42,15 @@ where
         WorkerBuilderOneAddress {
             incoming_ac: Arc::new(AllowAll),
             outgoing_ac: Arc::new(AllowAll),
+            mailbox_capacity: MailboxCapacity::default(),
+            dispatch_batching: DispatchBatching::default(),
+            max_message_size: None,
+            kind: WorkerKind::application(),
+            #[cfg(feature = "std")]
+            supervisor: None,
             worker: self.worker,
             address: address.into(),
+            aliases: vec![],
         }
     }
 
@@ -47,6 +58,12 @@ where
     pub fn with_mailboxes(self, mailboxes: Mailboxes) -> WorkerBuilderMultipleAddresses<W> {
         WorkerBuilderMultipleAddresses {
             mailboxes,
+            mailbox_capacity: MailboxCapacity::default(),
+            dispatch_batching: DispatchBatching::default(),
+            max_message_size: None,
+            kind: WorkerKind::application(),
+            #[cfg(feature = "std")]
+            supervisor: None,
             worker: self.worker,
         }
     }
@@ -57,6 +74,12 @@ where
     W: Worker<Context = Context>,
 {
     mailboxes: Mailboxes,
+    mailbox_capacity: MailboxCapacity,
+    dispatch_batching: DispatchBatching,
+    max_message_size: Option<usize>,
+    kind: WorkerKind,
+    #[cfg(feature = "std")]
+    supervisor: Option<Supervisor<W>>,
     worker: W,
 }
 
@@ -64,9 +87,53 @@ impl<W> WorkerBuilderMultipleAddresses<W>
 where
     W: Worker<Context = Context>,
 {
+    /// Set the [`MailboxCapacity`] shared by all the addresses of the worker
+    pub fn with_mailbox_capacity(mut self, mailbox_capacity: MailboxCapacity) -> Self {
+        self.mailbox_capacity = mailbox_capacity;
+        self
+    }
+
+    /// Set the [`DispatchBatching`]
+    pub fn with_dispatch_batching(mut self, dispatch_batching: DispatchBatching) -> Self {
+        self.dispatch_batching = dispatch_batching;
+        self
+    }
+
+    /// Set the maximum size of the encoded messages which can be sent to this worker,
+    /// larger messages are rejected with an error by [`Context::send`] and
+    /// [`Context::forward`]. There is no limit by default
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = Some(max_message_size);
+        self
+    }
+
+    /// Set the [`WorkerKind`] reported by [`Context::list_workers_info`]
+    pub fn with_kind(mut self, kind: impl Into<WorkerKind>) -> Self {
+        self.kind = kind.into();
+        self
+    }
+
+    /// Supervise the worker, see [`Supervisor`]
+    #[cfg(feature = "std")]
+    pub fn with_supervisor(mut self, supervisor: Supervisor<W>) -> Self {
+        self.supervisor = Some(supervisor);
+        self
+    }
+
     /// Consume this builder and start a new Ockam [`Worker`] from the given context
     pub async fn start(self, context: &Context) -> Result<()> {
-        start(context, self.mailboxes, self.worker).await
+        start(
+            context,
+            self.mailboxes,
+            self.mailbox_capacity,
+            self.dispatch_batching,
+            self.max_message_size,
+            self.kind,
+            #[cfg(feature = "std")]
+            self.supervisor,
+            self.worker,
+        )
+        .await
     }
 }
 
@@ -76,7 +143,14 @@ where
 {
     incoming_ac: Arc<dyn IncomingAccessControl>,
     outgoing_ac: Arc<dyn OutgoingAccessControl>,
+    mailbox_capacity: MailboxCapacity,
+    dispatch_batching: DispatchBatching,
+    max_message_size: Option<usize>,
+    kind: WorkerKind,
+    #[cfg(feature = "std")]
+    supervisor: Option<Supervisor<W>>,
     address: Address,
+    aliases: Vec<Address>,
     worker: W,
 }
 
@@ -86,9 +160,22 @@ where
 {
     /// Consume this builder and start a new Ockam [`Worker`] from the given context
     pub async fn start(self, context: &Context) -> Result<()> {
+        let aliases = self
+            .aliases
+            .into_iter()
+            .map(|alias| Mailbox::new(alias, self.incoming_ac.clone(), self.outgoing_ac.clone()))
+            .collect();
+        let main_mailbox = Mailbox::new(self.address, self.incoming_ac, self.outgoing_ac);
+
         start(
             context,
-            Mailboxes::main(self.address, self.incoming_ac, self.outgoing_ac),
+            Mailboxes::new(main_mailbox, aliases),
+            self.mailbox_capacity,
+            self.dispatch_batching,
+            self.max_message_size,
+            self.kind,
+            #[cfg(feature = "std")]
+            self.supervisor,
             self.worker,
         )
         .await
@@ -99,6 +186,16 @@ impl<W> WorkerBuilderOneAddress<W>
 where
     W: Worker<Context = Context>,
 {
+    /// Also register the worker at the given address, with the same access controls
+    ///
+    /// The messages sent to any address of the worker are handled by the same instance,
+    /// [`Routed::msg_addr`](ockam_core::Routed::msg_addr) tells which address a message was
+    /// sent to. Stopping the worker at any of its addresses unregisters all of them.
+    pub fn with_alias(mut self, alias: impl Into<Address>) -> Self {
+        self.aliases.push(alias.into());
+        self
+    }
+
     /// Set [`IncomingAccessControl`]
     pub fn with_incoming_access_control(
         mut self,
@@ -134,10 +231,52 @@ where
         self.outgoing_ac = outgoing_access_control.clone();
         self
     }
+
+    /// Set the [`MailboxCapacity`]
+    pub fn with_mailbox_capacity(mut self, mailbox_capacity: MailboxCapacity) -> Self {
+        self.mailbox_capacity = mailbox_capacity;
+        self
+    }
+
+    /// Set the [`DispatchBatching`]
+    pub fn with_dispatch_batching(mut self, dispatch_batching: DispatchBatching) -> Self {
+        self.dispatch_batching = dispatch_batching;
+        self
+    }
+
+    /// Set the maximum size of the encoded messages which can be sent to this worker,
+    /// larger messages are rejected with an error by [`Context::send`] and
+    /// [`Context::forward`]. There is no limit by default
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = Some(max_message_size);
+        self
+    }
+
+    /// Set the [`WorkerKind`] reported by [`Context::list_workers_info`]
+    pub fn with_kind(mut self, kind: impl Into<WorkerKind>) -> Self {
+        self.kind = kind.into();
+        self
+    }
+
+    /// Supervise the worker, see [`Supervisor`]
+    #[cfg(feature = "std")]
+    pub fn with_supervisor(mut self, supervisor: Supervisor<W>) -> Self {
+        self.supervisor = Some(supervisor);
+        self
+    }
 }
 
 /// Consume this builder and start a new Ockam [`Worker`] from the given context
-async fn start<W>(context: &Context, mailboxes: Mailboxes, worker: W) -> Result<()>
+async fn start<W>(
+    context: &Context,
+    mailboxes: Mailboxes,
+    mailbox_capacity: MailboxCapacity,
+    dispatch_batching: DispatchBatching,
+    max_message_size: Option<usize>,
+    kind: WorkerKind,
+    #[cfg(feature = "std")] supervisor: Option<Supervisor<W>>,
+    worker: W,
+) -> Result<()>
 where
     W: Worker<Context = Context>,
 {
@@ -151,16 +290,30 @@ where
     let addresses = mailboxes.addresses();
 
     // Pass it to the context
-    let (ctx, sender, ctrl_rx) = context.copy_with_mailboxes(mailboxes);
+    let (ctx, sender, ctrl_rx) = context.copy_with_mailboxes(mailboxes, mailbox_capacity);
 
     debugger::log_inherit_context("WORKER", context, &ctx);
 
     // Then initialise the worker message relay
-    WorkerRelay::init(context.runtime(), worker, ctx, ctrl_rx);
+    WorkerRelay::init(
+        context.runtime(),
+        worker,
+        ctx,
+        ctrl_rx,
+        dispatch_batching,
+        #[cfg(feature = "std")]
+        supervisor,
+    );
 
     // Send start request to router
-    let (msg, mut rx) =
-        NodeMessage::start_worker(addresses, sender, false, context.mailbox_count());
+    let (msg, mut rx) = NodeMessage::start_worker(
+        addresses,
+        sender,
+        false,
+        context.mailbox_count(),
+        max_message_size,
+        kind,
+    );
     context
         .sender()
         .send(msg)
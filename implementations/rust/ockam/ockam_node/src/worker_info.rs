@@ -0,0 +1,127 @@
+use core::fmt;
+use ockam_core::compat::{borrow::Cow, string::String, vec::Vec};
+use ockam_core::Address;
+
+/// Kind of a worker or processor registered on a node, declared when it is started with
+/// [`WorkerBuilder`] or [`ProcessorBuilder`]
+///
+/// A kind is an opaque tag, like `"tcp-sender"`: the crates defining workers choose the kinds
+/// of their own workers. Workers started without a kind are reported as
+/// [`WorkerKind::application`].
+///
+/// [`WorkerBuilder`]: crate::WorkerBuilder
+/// [`ProcessorBuilder`]: crate::ProcessorBuilder
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WorkerKind(Cow<'static, str>);
+
+impl WorkerKind {
+    /// Constructor
+    pub fn new(kind: impl Into<String>) -> Self {
+        Self(Cow::Owned(kind.into()))
+    }
+
+    /// Constructor usable in constants
+    pub const fn from_static(kind: &'static str) -> Self {
+        Self(Cow::Borrowed(kind))
+    }
+
+    /// Kind of the workers which didn't declare one
+    pub fn application() -> Self {
+        Self::from_static("application")
+    }
+
+    /// The tag of this kind
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for WorkerKind {
+    fn default() -> Self {
+        Self::application()
+    }
+}
+
+impl From<&'static str> for WorkerKind {
+    fn from(kind: &'static str) -> Self {
+        Self::from_static(kind)
+    }
+}
+
+impl fmt::Display for WorkerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Information about a worker or processor registered on a node,
+/// see [`Context::list_workers_info`]
+///
+/// [`Context::list_workers_info`]: crate::Context::list_workers_info
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkerInfo {
+    address: Address,
+    addresses: Vec<Address>,
+    kind: WorkerKind,
+    processor: bool,
+    detached: bool,
+    cluster: Option<String>,
+    routed_messages: usize,
+}
+
+impl WorkerInfo {
+    pub(crate) fn new(
+        address: Address,
+        addresses: Vec<Address>,
+        kind: WorkerKind,
+        processor: bool,
+        detached: bool,
+        cluster: Option<String>,
+        routed_messages: usize,
+    ) -> Self {
+        Self {
+            address,
+            addresses,
+            kind,
+            processor,
+            detached,
+            cluster,
+            routed_messages,
+        }
+    }
+
+    /// Primary address
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// All the addresses, including the primary one
+    pub fn addresses(&self) -> &[Address] {
+        &self.addresses
+    }
+
+    /// Kind of the worker
+    pub fn kind(&self) -> &WorkerKind {
+        &self.kind
+    }
+
+    /// Is this a [`Processor`](ockam_core::Processor)
+    pub fn is_processor(&self) -> bool {
+        self.processor
+    }
+
+    /// Is this a detached context, which has no relay, like the one the node is started with
+    pub fn is_detached(&self) -> bool {
+        self.detached
+    }
+
+    /// Cluster of the worker, see [`Context::set_cluster`](crate::Context::set_cluster)
+    pub fn cluster(&self) -> Option<&str> {
+        self.cluster.as_deref()
+    }
+
+    /// Number of messages routed to the worker since it was started
+    pub fn routed_messages(&self) -> usize {
+        self.routed_messages
+    }
+}
@@ -5,13 +5,23 @@ use ockam_core::compat::{
     string::{String, ToString},
     sync::Arc,
 };
-use ockam_core::{async_trait, Address, AllowAll, Any, Decodable, DenyAll, Message, LOCAL};
-use ockam_core::{route, Processor, Result, Routed, Worker};
+use ockam_core::errcode::Kind;
+use ockam_core::flow_control::{FlowControlOutgoingAccessControl, FlowControls};
+use ockam_core::{
+    async_trait, Address, AllowAll, AllowSourceAddresses, Any, Codec, Correlated, CorrelationId,
+    Decodable, DenyAll, IncomingAccessControl, LocalMessage, Mailboxes, Message, RelayMessage,
+    TransportMessage, LOCAL,
+};
+use ockam_core::{route, Processor, Result, Route, Routed, Worker};
 use ockam_node::compat::futures::FutureExt;
-use ockam_node::{Context, MessageReceiveOptions, NodeBuilder};
+use ockam_node::{
+    Context, DispatchBatching, MailboxCapacity, MessageReceiveOptions, MessageSendReceiveOptions,
+    Metrics, NodeBuilder, OverflowPolicy, ReliableChannelOptions, ReliableListenerOptions,
+    RestartPolicy, Supervisor, WorkerBuilder, WorkerKind,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicI8, AtomicU32};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tokio::time::sleep;
 use tracing::info;
 
@@ -65,6 +75,66 @@ fn start_and_shutdown_node__many_iterations__should_not_fail() {
             .unwrap()
     }
 }
+#[allow(non_snake_case)]
+#[test]
+fn send__message_exceeding_max_message_size__should_fail() {
+    let (ctx, mut executor) = NodeBuilder::new().with_max_message_size(1024).build();
+    executor
+        .execute(async move {
+            let res: Result<()> = async {
+                let mut child_ctx = ctx.new_detached("child", AllowAll, AllowAll).await?;
+                assert_eq!(child_ctx.max_message_size(), 1024);
+
+                ctx.send(route!["child"], "a".repeat(1000)).await?;
+                assert_eq!(child_ctx.receive::<String>().await?.body().len(), 1000);
+
+                let err = ctx
+                    .send(route!["child"], "a".repeat(2000))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code().kind, Kind::ResourceExhausted);
+
+                Ok(())
+            }
+            .await;
+
+            ctx.stop().await?;
+            res
+        })
+        .unwrap()
+        .unwrap();
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send__message_exceeding_worker_max_message_size__should_fail(
+    ctx: &mut Context,
+) -> Result<()> {
+    WorkerBuilder::new(BlackHoleWorker)
+        .with_address("small")
+        .with_max_message_size(1024)
+        .start(ctx)
+        .await?;
+
+    ctx.send(route!["small"], "a".repeat(100)).await?;
+
+    let err = ctx
+        .send(route!["small"], "a".repeat(2000))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::ResourceExhausted);
+
+    // The size is checked when a message is forwarded as well
+    let msg = LocalMessage::new(
+        TransportMessage::v1(route!["small"], route![ctx.address()], vec![0; 2000]),
+        vec![],
+    );
+    let err = ctx.forward(msg).await.unwrap_err();
+    assert_eq!(err.code().kind, Kind::ResourceExhausted);
+
+    ctx.stop().await
+}
+
 struct SimpleWorker {
     initialize_was_called: Arc<AtomicBool>,
     shutdown_was_called: Arc<AtomicBool>,
@@ -132,6 +202,153 @@ async fn simple_worker__run_node_lifecycle__worker_lifecycle_should_be_full(
     Ok(())
 }
 
+struct CleanupWorker {
+    cluster: Option<&'static str>,
+    shutdown_delay: Duration,
+    shutdown_calls: Arc<AtomicU32>,
+}
+
+impl CleanupWorker {
+    fn new(shutdown_calls: Arc<AtomicU32>) -> Self {
+        Self {
+            cluster: None,
+            shutdown_delay: Duration::ZERO,
+            shutdown_calls,
+        }
+    }
+}
+
+#[async_trait]
+impl Worker for CleanupWorker {
+    type Message = String;
+    type Context = Context;
+
+    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        if let Some(cluster) = self.cluster {
+            ctx.set_cluster(cluster).await?;
+        }
+        Ok(())
+    }
+
+    async fn shutdown(&mut self, _ctx: &mut Self::Context) -> Result<()> {
+        sleep(self.shutdown_delay).await;
+        self.shutdown_calls.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn handle_message(
+        &mut self,
+        _ctx: &mut Self::Context,
+        _msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        // Never returns, the worker can only be stopped by interrupting it
+        core::future::pending().await
+    }
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn worker_shutdown_hook__every_stop_path__should_run_once() {
+    let stopped_worker = Arc::new(AtomicU32::new(0));
+    let blocked_worker = Arc::new(AtomicU32::new(0));
+    let running_worker = Arc::new(AtomicU32::new(0));
+    let (ctx, mut executor) = NodeBuilder::new().no_logging().build();
+    let (stopped, blocked, running) = (
+        stopped_worker.clone(),
+        blocked_worker.clone(),
+        running_worker.clone(),
+    );
+    executor
+        .execute(async move {
+            let res: Result<()> = async {
+                ctx.start_worker("stopped", CleanupWorker::new(stopped.clone()))
+                    .await?;
+                ctx.start_worker("blocked", CleanupWorker::new(blocked.clone()))
+                    .await?;
+                ctx.start_worker("running", CleanupWorker::new(running.clone()))
+                    .await?;
+
+                // Stopping a worker
+                ctx.stop_worker("stopped").await?;
+                // Stopping a worker which is handling a message
+                ctx.send(route!["blocked"], "block".to_string()).await?;
+                sleep(Duration::from_millis(50)).await;
+                ctx.stop_worker("blocked").await?;
+                sleep(Duration::from_millis(100)).await;
+                assert_eq!(stopped.load(Ordering::Relaxed), 1);
+                assert_eq!(blocked.load(Ordering::Relaxed), 1);
+                assert_eq!(running.load(Ordering::Relaxed), 0);
+                Ok(())
+            }
+            .await;
+
+            // Stopping the node
+            ctx.stop().await?;
+            res
+        })
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(stopped_worker.load(Ordering::Relaxed), 1);
+    assert_eq!(blocked_worker.load(Ordering::Relaxed), 1);
+    assert_eq!(running_worker.load(Ordering::Relaxed), 1);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn worker_shutdown_hook__node_force_stopped__should_run_once() {
+    let slow_worker = Arc::new(AtomicU32::new(0));
+    let clustered_worker = Arc::new(AtomicU32::new(0));
+    let (ctx, mut executor) = NodeBuilder::new().no_logging().build();
+    let (slow, clustered) = (slow_worker.clone(), clustered_worker.clone());
+    executor
+        .execute(async move {
+            // The shutdown of this worker outlasts the node shutdown timeout,
+            // so the node is force-stopped before the clustered worker is stopped
+            let worker = CleanupWorker {
+                shutdown_delay: Duration::from_secs(2),
+                ..CleanupWorker::new(slow)
+            };
+            ctx.start_worker("slow", worker).await?;
+            let worker = CleanupWorker {
+                cluster: Some("cleanup-cluster"),
+                ..CleanupWorker::new(clustered)
+            };
+            ctx.start_worker("clustered", worker).await?;
+            ctx.send(route!["clustered"], "block".to_string()).await?;
+
+            ctx.stop_timeout(1).await
+        })
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(slow_worker.load(Ordering::Relaxed), 1);
+    assert_eq!(clustered_worker.load(Ordering::Relaxed), 1);
+}
+
+#[allow(non_snake_case)]
+#[test]
+fn worker_shutdown_hook__node_stopped_immediately__should_run_once() {
+    let shutdown_calls = Arc::new(AtomicU32::new(0));
+    let (ctx, mut executor) = NodeBuilder::new().no_logging().build();
+    let calls = shutdown_calls.clone();
+    executor
+        .execute(async move {
+            let worker = CleanupWorker {
+                shutdown_delay: Duration::from_millis(200),
+                ..CleanupWorker::new(calls)
+            };
+            ctx.start_worker("worker", worker).await?;
+            ctx.send(route!["worker"], "block".to_string()).await?;
+
+            ctx.stop_now().await
+        })
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(shutdown_calls.load(Ordering::Relaxed), 1);
+}
+
 struct DummyProcessor;
 
 #[async_trait]
@@ -526,6 +743,115 @@ async fn use_context_send_and_receive(ctx: &mut Context) -> Result<()> {
     ctx.stop().await
 }
 
+struct CorrelatedEchoWorker;
+
+#[async_trait]
+impl Worker for CorrelatedEchoWorker {
+    type Context = Context;
+    type Message = Correlated;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Correlated>) -> Result<()> {
+        let return_route = msg.return_route();
+        let body = msg.correlated_body::<String>()?;
+
+        // Unrelated messages arriving first must be skipped by the caller
+        ctx.send(return_route.clone(), "unrelated".to_string())
+            .await?;
+        ctx.send(
+            return_route.clone(),
+            Correlated::new(CorrelationId::random(), &"stale".to_string())?,
+        )
+        .await?;
+
+        ctx.send(return_route, msg.correlated_response(&body)?)
+            .await
+    }
+}
+
+#[ockam_macros::test]
+async fn use_context_send_and_receive_correlated(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("CorrelatedEchoWorker", CorrelatedEchoWorker)
+        .await?;
+
+    let response: String = ctx
+        .send_and_receive_correlated("CorrelatedEchoWorker", "Hello".to_string())
+        .await?;
+    assert_eq!(response, "Hello");
+
+    ctx.stop().await
+}
+
+/// Holds correlated requests until two of them arrived, then answers them in reverse order.
+/// Every response is sent to every requester, as if they shared the same reply path
+#[derive(Default)]
+struct SharedReplyWorker {
+    pending: Vec<(Route, Correlated)>,
+}
+
+#[async_trait]
+impl Worker for SharedReplyWorker {
+    type Context = Context;
+    type Message = Correlated;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Correlated>) -> Result<()> {
+        self.pending.push((msg.return_route(), msg.body()));
+        if self.pending.len() < 2 {
+            return Ok(());
+        }
+
+        let return_routes: Vec<Route> = self.pending.iter().map(|(r, _)| r.clone()).collect();
+        for (_, request) in self.pending.drain(..).rev() {
+            let body = request.body::<String>()?;
+            let response = Correlated::new(request.correlation_id(), &format!("{body} answered"))?;
+            for return_route in &return_routes {
+                ctx.send(return_route.clone(), response.clone()).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_and_receive_correlated__concurrent_requests_answered_out_of_order__should_match_responses(
+    ctx: &mut Context,
+) -> Result<()> {
+    ctx.start_worker("shared_reply", SharedReplyWorker::default())
+        .await?;
+    let alice = ctx.new_detached("alice", AllowAll, AllowAll).await?;
+    let bob = ctx.new_detached("bob", AllowAll, AllowAll).await?;
+
+    let (alice_response, bob_response) = tokio::join!(
+        alice.send_and_receive_correlated::<String, String>("shared_reply", "alice".to_string()),
+        bob.send_and_receive_correlated::<String, String>("shared_reply", "bob".to_string()),
+    );
+    assert_eq!(alice_response?, "alice answered");
+    assert_eq!(bob_response?, "bob answered");
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_and_receive_correlated__no_response__should_time_out(
+    ctx: &mut Context,
+) -> Result<()> {
+    // Receives the request but never answers it
+    let _silent_ctx = ctx.new_detached("silent", AllowAll, AllowAll).await?;
+
+    let res = ctx
+        .send_and_receive_correlated_extended::<String, String>(
+            "silent",
+            "Hello".to_string(),
+            MessageSendReceiveOptions::new().with_timeout(Duration::from_millis(100)),
+        )
+        .await;
+    assert!(res.is_err());
+
+    ctx.stop().await
+}
+
 struct DummyWorker;
 
 #[async_trait]
@@ -560,3 +886,921 @@ async fn starting_worker_with_dup_address_should_fail(ctx: &mut Context) -> Resu
         .is_err());
     ctx.stop().await
 }
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send__full_mailbox_with_block_policy__should_wait(ctx: &mut Context) -> Result<()> {
+    let capacity = MailboxCapacity::new(2, OverflowPolicy::Block);
+    let mut slow_ctx = ctx
+        .new_detached_with_mailbox_capacity("slow", AllowAll, AllowAll, capacity)
+        .await?;
+
+    ctx.send("slow", "1".to_string()).await?;
+    ctx.send("slow", "2".to_string()).await?;
+
+    // The producer is blocked until the consumer takes a message out of the mailbox
+    let mut sender_ctx = ctx.new_detached("fast", AllowAll, AllowAll).await?;
+    let blocked = tokio::spawn(async move {
+        sender_ctx.send("slow", "3".to_string()).await?;
+        sender_ctx.stop().await
+    });
+    sleep(Duration::from_millis(100)).await;
+    assert!(!blocked.is_finished());
+
+    assert_eq!(slow_ctx.receive::<String>().await?.body(), "1");
+    blocked.await.unwrap()?;
+    assert_eq!(slow_ctx.receive::<String>().await?.body(), "2");
+    assert_eq!(slow_ctx.receive::<String>().await?.body(), "3");
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send__full_mailbox_with_drop_newest_policy__should_fail(ctx: &mut Context) -> Result<()> {
+    let capacity = MailboxCapacity::new(2, OverflowPolicy::DropNewest);
+    let mut slow_ctx = ctx
+        .new_detached_with_mailbox_capacity("slow", AllowAll, AllowAll, capacity)
+        .await?;
+
+    ctx.send("slow", "1".to_string()).await?;
+    ctx.send("slow", "2".to_string()).await?;
+    let err = ctx.send("slow", "3".to_string()).await.unwrap_err();
+    assert_eq!(err.code().kind, Kind::ResourceExhausted);
+
+    assert_eq!(slow_ctx.receive::<String>().await?.body(), "1");
+    assert_eq!(slow_ctx.receive::<String>().await?.body(), "2");
+    let res = slow_ctx
+        .receive_extended::<String>(MessageReceiveOptions::new().with_timeout_secs(1))
+        .await;
+    assert!(res.is_err(), "the last message should have been dropped");
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send__full_mailbox_with_drop_oldest_policy__should_drop_first_message(
+    ctx: &mut Context,
+) -> Result<()> {
+    let capacity = MailboxCapacity::new(2, OverflowPolicy::DropOldest);
+    let mut slow_ctx = ctx
+        .new_detached_with_mailbox_capacity("slow", AllowAll, AllowAll, capacity)
+        .await?;
+
+    ctx.send("slow", "1".to_string()).await?;
+    ctx.send("slow", "2".to_string()).await?;
+    ctx.send("slow", "3".to_string()).await?;
+
+    assert_eq!(slow_ctx.receive::<String>().await?.body(), "2");
+    assert_eq!(slow_ctx.receive::<String>().await?.body(), "3");
+
+    ctx.stop().await
+}
+
+struct FloodedWorker {
+    handled: Arc<AtomicU32>,
+    expected: u32,
+    flooded: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl Worker for FloodedWorker {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Self::Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        // Let the whole flood of messages reach the mailbox before handling it
+        while !self.flooded.load(Ordering::Acquire) {
+            sleep(Duration::from_millis(1)).await;
+        }
+
+        if self.handled.fetch_add(1, Ordering::Relaxed) + 1 == self.expected {
+            ctx.send(msg.return_route(), "done".to_string()).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Start a worker, fill its mailbox with `messages` messages and let it handle them.
+/// Return the number of messages handled by the worker so far
+async fn flood_worker(
+    ctx: &Context,
+    address: &str,
+    batching: &DispatchBatching,
+    messages: u32,
+) -> Result<Arc<AtomicU32>> {
+    let handled = Arc::new(AtomicU32::new(0));
+    let flooded = Arc::new(AtomicBool::new(false));
+    WorkerBuilder::new(FloodedWorker {
+        handled: handled.clone(),
+        expected: messages,
+        flooded: flooded.clone(),
+    })
+    .with_address(address)
+    .with_mailbox_capacity(MailboxCapacity::new(
+        messages as usize,
+        OverflowPolicy::Block,
+    ))
+    .with_dispatch_batching(batching.clone())
+    .start(ctx)
+    .await?;
+
+    for _ in 0..messages {
+        ctx.send(address, "message".to_string()).await?;
+    }
+    flooded.store(true, Ordering::Release);
+
+    Ok(handled)
+}
+
+/// Wait until the stats of a worker account for all its messages
+async fn wait_for_stats(batching: &DispatchBatching, messages: u32) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while batching.stats().messages() < messages as usize {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap()
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 30000)]
+async fn dispatch_batching__high_volume__should_use_fewer_scheduling_rounds(
+    ctx: &mut Context,
+) -> Result<()> {
+    let messages = 10_000;
+    let mut results = vec![];
+
+    for (address, batching) in [
+        ("unbatched", DispatchBatching::disabled()),
+        ("batched", DispatchBatching::new(64)),
+    ] {
+        flood_worker(ctx, address, &batching, messages).await?;
+        let start = Instant::now();
+        assert_eq!(ctx.receive::<String>().await?.body(), "done");
+        let elapsed = start.elapsed();
+        wait_for_stats(&batching, messages).await;
+
+        info!(
+            "{}: {} messages in {:?} ({:.0} msg/s), {:.1} messages per batch",
+            address,
+            messages,
+            elapsed,
+            messages as f64 / elapsed.as_secs_f64(),
+            batching.stats().average_batch_size()
+        );
+        results.push(batching.stats());
+    }
+
+    let (unbatched, batched) = (&results[0], &results[1]);
+
+    // Without batching each message costs a scheduling round
+    assert_eq!(unbatched.messages(), messages as usize);
+    assert_eq!(unbatched.batches(), messages as usize);
+    assert_eq!(unbatched.full_batches(), 0);
+
+    // The messages were all waiting in the mailbox, so they are handled 64 at a time
+    assert_eq!(batched.messages(), messages as usize);
+    assert!(batched.batches() <= (messages as usize + 63) / 64 + 1);
+    assert!(batched.full_batches() >= messages as usize / 64 - 1);
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 30000)]
+async fn dispatch_batching__flooded_workers__should_not_starve_other_workers(
+    ctx: &mut Context,
+) -> Result<()> {
+    let messages = 20_000;
+    ctx.start_worker("echo", DummyWorker).await?;
+
+    let mut flooded = vec![];
+    for i in 0..4 {
+        let batching = DispatchBatching::new(16);
+        let handled = flood_worker(ctx, &format!("flooded_{i}"), &batching, messages).await?;
+        flooded.push((batching, handled));
+    }
+
+    // The echo worker still gets to run while the other workers are busy
+    let reply: String = ctx
+        .send_and_receive_extended(
+            "echo",
+            "ping".to_string(),
+            MessageSendReceiveOptions::new().with_timeout(Duration::from_secs(5)),
+        )
+        .await?
+        .body();
+    assert_eq!(reply, "ping");
+
+    // And every flooded worker handles all its messages
+    for _ in 0..flooded.len() {
+        assert_eq!(ctx.receive::<String>().await?.body(), "done");
+    }
+    for (batching, handled) in flooded {
+        assert_eq!(handled.load(Ordering::Relaxed), messages);
+        wait_for_stats(&batching, messages).await;
+        assert!(batching.stats().full_batches() > 0);
+    }
+
+    ctx.stop().await
+}
+
+#[derive(Default)]
+struct EchoMetrics {
+    sent: AtomicU32,
+    received: AtomicU32,
+    handled: AtomicU32,
+}
+
+impl Metrics for EchoMetrics {
+    fn message_sent(&self, from: &Address, _bytes: usize) {
+        if from.address() == "echo" {
+            self.sent.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn message_received(&self, to: &Address, _bytes: usize) {
+        if to.address() == "echo" {
+            self.received.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn message_handled(&self, worker: &Address, _duration: Duration) {
+        if worker.address() == "echo" {
+            self.handled.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn metrics__installed_recorder__should_receive_worker_events(
+    ctx: &mut Context,
+) -> Result<()> {
+    assert!(!ctx.has_metrics());
+    let metrics = Arc::new(EchoMetrics::default());
+    ctx.set_metrics(metrics.clone());
+    assert!(ctx.has_metrics());
+
+    ctx.start_worker("echo", DummyWorker).await?;
+    for _ in 0..3 {
+        let reply: String = ctx.send_and_receive("echo", "Hello".to_string()).await?;
+        assert_eq!(reply, "Hello");
+    }
+    // The handling time is recorded once the reply is sent
+    sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(metrics.sent.load(Ordering::Relaxed), 3);
+    assert_eq!(metrics.received.load(Ordering::Relaxed), 3);
+    assert_eq!(metrics.handled.load(Ordering::Relaxed), 3);
+
+    // No more events are recorded once the recorder is removed
+    ctx.remove_metrics();
+    let _: String = ctx.send_and_receive("echo", "Hello".to_string()).await?;
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(metrics.sent.load(Ordering::Relaxed), 3);
+    assert_eq!(metrics.received.load(Ordering::Relaxed), 3);
+    assert_eq!(metrics.handled.load(Ordering::Relaxed), 3);
+
+    ctx.stop().await
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Message)]
+struct Reading {
+    sensor: String,
+    value: u32,
+}
+
+/// Decodes each message with the codec it was sent with and replies with the codec name
+struct CodecWorker;
+
+#[async_trait]
+impl Worker for CodecWorker {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let reading: Reading = msg.decode_with_codec()?;
+        assert_eq!(reading.sensor, "temperature");
+        ctx.send(
+            msg.return_route(),
+            format!("{:?} {}", msg.codec()?, reading.value),
+        )
+        .await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_with_codec__mixed_codecs__should_decode_each_message(
+    ctx: &mut Context,
+) -> Result<()> {
+    ctx.start_worker("codec_worker", CodecWorker).await?;
+
+    let codecs = [Codec::Json, Codec::Cbor, Codec::Bare];
+    for (value, codec) in codecs.iter().enumerate() {
+        let reading = Reading {
+            sensor: "temperature".to_string(),
+            value: value as u32,
+        };
+        ctx.send_with_codec("codec_worker", reading, *codec).await?;
+    }
+
+    // Messages sent with the default encoding are decoded with the default codec
+    ctx.send(
+        "codec_worker",
+        Reading {
+            sensor: "temperature".to_string(),
+            value: 3,
+        },
+    )
+    .await?;
+
+    let mut replies = vec![];
+    for _ in 0..4 {
+        replies.push(ctx.receive::<String>().await?.body());
+    }
+    assert_eq!(replies, vec!["Json 0", "Cbor 1", "Bare 2", "Bare 3"]);
+
+    ctx.stop().await
+}
+
+/// Forwards every message to the next hop of its onward route
+struct ForwardingWorker;
+
+#[async_trait]
+impl Worker for ForwardingWorker {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut local_msg = msg.into_local_message();
+        let transport = local_msg.transport_mut();
+        transport.onward_route.step()?;
+        transport.return_route.modify().prepend(ctx.address());
+        ctx.forward(local_msg).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_traced__forwarding_workers__should_collect_each_hop(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("hop1", ForwardingWorker).await?;
+    ctx.start_worker("hop2", ForwardingWorker).await?;
+    ctx.start_worker("echo", DummyWorker).await?;
+
+    let mut receiver = ctx
+        .send_traced(route!["hop1", "hop2", "echo"], "Hello".to_string())
+        .await?;
+
+    let trace = receiver.receive().await?;
+    assert_eq!(trace.collector(), &receiver.collector());
+    assert_eq!(
+        trace.addresses(),
+        vec![ctx.address(), "hop1".into(), "hop2".into(), "echo".into()]
+    );
+    let timestamps: Vec<u64> = trace.hops().iter().map(|h| h.timestamp()).collect();
+    assert!(timestamps.windows(2).all(|w| w[0] <= w[1]));
+
+    // The message itself is delivered as usual
+    let reply = ctx.receive::<String>().await?.body();
+    assert_eq!(reply, "Hello");
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn allow_source_addresses__message_from_unlisted_source__should_not_be_delivered(
+    ctx: &mut Context,
+) -> Result<()> {
+    let mut bob = ctx
+        .new_detached("bob", AllowSourceAddresses::new(["alice"]), AllowAll)
+        .await?;
+    let alice = ctx.new_detached("alice", DenyAll, AllowAll).await?;
+    let mallory = ctx.new_detached("mallory", DenyAll, AllowAll).await?;
+
+    alice.send("bob", "Hello".to_string()).await?;
+    let msg = bob.receive::<String>().await?;
+    assert_eq!(msg.body(), "Hello");
+
+    mallory.send("bob", "Hello".to_string()).await?;
+    let res = bob
+        .receive_extended::<String>(
+            MessageReceiveOptions::new().with_timeout(Duration::from_millis(100)),
+        )
+        .await;
+    assert!(res.is_err(), "messages from mallory should not pass");
+
+    ctx.stop().await
+}
+
+/// Forwards messages like [`ForwardingWorker`] but drops every third message
+struct LossyWorker {
+    count: u32,
+}
+
+#[async_trait]
+impl Worker for LossyWorker {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        self.count += 1;
+        if self.count % 3 == 0 {
+            return Ok(());
+        }
+        ForwardingWorker.handle_message(ctx, msg).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_reliable__lossy_route__should_deliver_in_order(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("lossy", LossyWorker { count: 0 }).await?;
+    let options = ReliableListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("receiver", &options.spawner_flow_control_id());
+    ctx.start_reliable_listener("reliable_listener", options)
+        .await?;
+    let mut receiver = ctx.new_detached("receiver", AllowAll, AllowAll).await?;
+
+    let channel = ctx
+        .create_reliable_channel(
+            route!["lossy", "reliable_listener"],
+            ReliableChannelOptions::new().with_initial_backoff(Duration::from_millis(50)),
+        )
+        .await?;
+
+    for n in 0..10 {
+        ctx.send_reliable(&channel, route!["receiver"], n.to_string())
+            .await?;
+    }
+
+    let mut received = vec![];
+    for _ in 0..10 {
+        received.push(receiver.receive::<String>().await?.body());
+    }
+    let expected: Vec<String> = (0..10).map(|n| n.to_string()).collect();
+    assert_eq!(received, expected);
+    channel.check()?;
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_reliable__destination_not_consumer__should_not_be_delivered(
+    ctx: &mut Context,
+) -> Result<()> {
+    let options = ReliableListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("receiver", &options.spawner_flow_control_id());
+    ctx.start_reliable_listener("reliable_listener", options)
+        .await?;
+    let mut receiver = ctx.new_detached("receiver", AllowAll, AllowAll).await?;
+    let mut other = ctx.new_detached("other", AllowAll, AllowAll).await?;
+
+    let channel = ctx
+        .create_reliable_channel(route!["reliable_listener"], ReliableChannelOptions::new())
+        .await?;
+
+    ctx.send_reliable(&channel, route!["other"], "Hello".to_string())
+        .await?;
+    ctx.send_reliable(&channel, route!["receiver"], "Hello".to_string())
+        .await?;
+
+    // Only the consumers of the listener flow control receive the messages
+    assert_eq!(receiver.receive::<String>().await?.body(), "Hello");
+    let res = other
+        .receive_extended::<String>(
+            MessageReceiveOptions::new().with_timeout(Duration::from_millis(100)),
+        )
+        .await;
+    assert!(res.is_err(), "the message should not be delivered");
+    channel.check()?;
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_reliable__too_many_channels__should_drop_new_channels(
+    ctx: &mut Context,
+) -> Result<()> {
+    let options = ReliableListenerOptions::new().with_max_sessions(1);
+    ctx.flow_controls()
+        .add_consumer("receiver", &options.spawner_flow_control_id());
+    ctx.start_reliable_listener("reliable_listener", options)
+        .await?;
+    let mut receiver = ctx.new_detached("receiver", AllowAll, AllowAll).await?;
+
+    let channel_options = ReliableChannelOptions::new()
+        .with_initial_backoff(Duration::from_millis(10))
+        .with_max_retransmissions(2);
+    let first = ctx
+        .create_reliable_channel(route!["reliable_listener"], channel_options.clone())
+        .await?;
+    let second = ctx
+        .create_reliable_channel(route!["reliable_listener"], channel_options)
+        .await?;
+
+    ctx.send_reliable(&first, route!["receiver"], "first".to_string())
+        .await?;
+    assert_eq!(receiver.receive::<String>().await?.body(), "first");
+
+    ctx.send_reliable(&second, route!["receiver"], "second".to_string())
+        .await?;
+    let res = receiver
+        .receive_extended::<String>(
+            MessageReceiveOptions::new().with_timeout(Duration::from_millis(500)),
+        )
+        .await;
+    assert!(res.is_err(), "the second channel should not be tracked");
+
+    let err = ctx
+        .send_reliable(&second, route!["receiver"], "second".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::Timeout);
+    first.check()?;
+
+    ctx.stop().await
+}
+
+/// Drops every message
+struct BlackHoleWorker;
+
+#[async_trait]
+impl Worker for BlackHoleWorker {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, _ctx: &mut Context, _msg: Routed<Any>) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn send_reliable__unreachable_listener__should_fail(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("black_hole", BlackHoleWorker).await?;
+
+    let channel = ctx
+        .create_reliable_channel(
+            route!["black_hole"],
+            ReliableChannelOptions::new()
+                .with_initial_backoff(Duration::from_millis(10))
+                .with_max_retransmissions(2),
+        )
+        .await?;
+
+    ctx.send_reliable(&channel, route!["receiver"], "Hello".to_string())
+        .await?;
+    sleep(Duration::from_millis(500)).await;
+
+    let err = ctx
+        .send_reliable(&channel, route!["receiver"], "Hello".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::Timeout);
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn payload_bytes__large_message__should_borrow_the_payload(ctx: &mut Context) -> Result<()> {
+    let mut child_ctx = ctx.new_detached("child", AllowAll, AllowAll).await?;
+    let data: Vec<u8> = (0..4 * 1024 * 1024).map(|n| n as u8).collect();
+
+    ctx.send(route!["child"], data.clone()).await?;
+    let msg = child_ctx.receive::<Any>().await?;
+
+    let bytes = msg.payload_bytes()?;
+    assert_eq!(bytes, data.as_slice());
+
+    // The bytes are a view into the message payload
+    let payload = msg.payload();
+    assert_eq!(
+        bytes.as_ptr_range().end,
+        payload.as_ptr_range().end,
+        "the bytes must not be copied"
+    );
+
+    ctx.send(route!["child"], "Hello".to_string()).await?;
+    let msg = child_ctx.receive::<Any>().await?;
+    assert_eq!(msg.payload_bytes()?, b"Hello");
+
+    ctx.send(route!["child"], ()).await?;
+    let msg = child_ctx.receive::<Any>().await?;
+    assert!(msg.payload_bytes().is_err());
+
+    ctx.stop().await
+}
+
+/// Counts the messages it handled, fails on "fail" and panics on "panic"
+#[derive(Default)]
+struct FlakyWorker {
+    handled: u32,
+}
+
+#[async_trait]
+impl Worker for FlakyWorker {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        self.handled += 1;
+        match msg.as_body().as_str() {
+            "fail" => Err(ockam_core::Error::new(
+                ockam_core::errcode::Origin::Application,
+                Kind::Internal,
+                "failure",
+            )),
+            "panic" => panic!("FlakyWorker panicked"),
+            _ => ctx.send(msg.return_route(), self.handled.to_string()).await,
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn supervised_worker__failing_handler__should_be_restarted(ctx: &mut Context) -> Result<()> {
+    let restarts = Arc::new(AtomicU32::new(0));
+    let restarts_clone = restarts.clone();
+    let supervisor = Supervisor::new(RestartPolicy::Always, move || {
+        restarts_clone.fetch_add(1, Ordering::Relaxed);
+        FlakyWorker::default()
+    });
+    ctx.start_supervised_worker("flaky", FlakyWorker::default(), supervisor)
+        .await?;
+
+    let handled: String = ctx
+        .send_and_receive(route!["flaky"], "hello".to_string())
+        .await?;
+    assert_eq!(handled, "1");
+
+    for failure in ["fail", "panic"] {
+        ctx.send(route!["flaky"], failure.to_string()).await?;
+
+        // The worker is back at the same address, with a fresh state
+        let handled: String = ctx
+            .send_and_receive(route!["flaky"], "hello".to_string())
+            .await?;
+        assert_eq!(handled, "1");
+    }
+    assert_eq!(restarts.load(Ordering::Relaxed), 2);
+    assert!(ctx.list_workers().await?.contains(&"flaky".into()));
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn supervised_worker__too_many_restarts__should_be_stopped(ctx: &mut Context) -> Result<()> {
+    let supervisor = Supervisor::new(RestartPolicy::OnError, FlakyWorker::default)
+        .with_max_restarts(1, Duration::from_secs(60));
+    ctx.start_supervised_worker("flaky", FlakyWorker::default(), supervisor)
+        .await?;
+
+    ctx.send(route!["flaky"], "fail".to_string()).await?;
+    let handled: String = ctx
+        .send_and_receive(route!["flaky"], "hello".to_string())
+        .await?;
+    assert_eq!(handled, "1");
+
+    ctx.send(route!["flaky"], "fail".to_string()).await?;
+    let mut stopped = false;
+    for _ in 0..50 {
+        if !ctx.list_workers().await?.contains(&"flaky".into()) {
+            stopped = true;
+            break;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    assert!(
+        stopped,
+        "the worker should be stopped after its second failure"
+    );
+
+    ctx.stop().await
+}
+
+/// Replies with the address each message was sent to
+struct GatewayWorker;
+
+#[async_trait]
+impl Worker for GatewayWorker {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(msg.return_route(), msg.msg_addr().address().to_string())
+            .await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn worker_aliases__message_to_any_alias__should_reach_the_same_worker(
+    ctx: &mut Context,
+) -> Result<()> {
+    WorkerBuilder::new(GatewayWorker)
+        .with_address("gateway")
+        .with_alias("api-v1")
+        .with_alias("api-v2")
+        .start(ctx)
+        .await?;
+
+    for address in ["gateway", "api-v1", "api-v2"] {
+        let reply: String = ctx
+            .send_and_receive(route![address], "Hello".to_string())
+            .await?;
+        assert_eq!(reply, address);
+    }
+
+    // Stopping the worker at one alias unregisters all its addresses
+    ctx.stop_worker("api-v1").await?;
+    ctx.sleep(Duration::from_millis(50)).await;
+    for address in ["gateway", "api-v1", "api-v2"] {
+        assert!(ctx
+            .send(route![address], "Hello".to_string())
+            .await
+            .is_err());
+    }
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn flow_control_revocation__revoked_parent__should_stop_child_traffic(
+    ctx: &mut Context,
+) -> Result<()> {
+    let flow_controls = ctx.flow_controls().clone();
+    let tenant = FlowControls::generate_flow_control_id();
+    let session = flow_controls.generate_child_flow_control_id(&tenant);
+
+    let mut consumer = ctx.new_detached("consumer", AllowAll, AllowAll).await?;
+    flow_controls.add_consumer("consumer", &session);
+    let producer = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "producer",
+            Arc::new(AllowAll),
+            Arc::new(FlowControlOutgoingAccessControl::new(
+                &flow_controls,
+                session.clone(),
+                None,
+            )),
+        ))
+        .await?;
+
+    producer
+        .send(route!["consumer"], "Hello".to_string())
+        .await?;
+    assert_eq!(consumer.receive::<String>().await?.body(), "Hello");
+
+    flow_controls.revoke(&tenant);
+    producer
+        .send(route!["consumer"], "Hello".to_string())
+        .await?;
+    let res = consumer
+        .receive_extended::<String>(MessageReceiveOptions::new().with_timeout_secs(1))
+        .await;
+    assert!(
+        res.is_err(),
+        "messages of a revoked session must be dropped"
+    );
+
+    ctx.stop().await
+}
+
+/// Yields to the executor every other time it's called, so that a `receive` racing against
+/// a ready future is cancelled after it took a message from the mailbox
+#[derive(Debug, Default)]
+struct YieldingAccessControl {
+    yielded: AtomicBool,
+    yields: AtomicU32,
+}
+
+#[async_trait]
+impl IncomingAccessControl for YieldingAccessControl {
+    async fn is_authorized(&self, _relay_msg: &RelayMessage) -> Result<bool> {
+        if !self.yielded.fetch_xor(true, Ordering::Relaxed) {
+            self.yields.fetch_add(1, Ordering::Relaxed);
+            tokio::task::yield_now().await;
+        }
+        Ok(true)
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn receive__cancelled_by_select__should_not_drop_messages(ctx: &mut Context) -> Result<()> {
+    let access_control = Arc::new(YieldingAccessControl::default());
+    let mut child = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            access_control.clone(),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+
+    let count = 10;
+    for i in 0..count {
+        ctx.send(route!["child"], i.to_string()).await?;
+    }
+
+    let mut received = vec![];
+    let mut cancelled = 0;
+    for _ in 0..10 * count {
+        if received.len() == count as usize {
+            break;
+        }
+        tokio::select! {
+            biased;
+            msg = child.receive::<String>() => received.push(msg?.body()),
+            _ = core::future::ready(()) => cancelled += 1,
+        }
+    }
+
+    assert_eq!(
+        received,
+        (0..count).map(|i| i.to_string()).collect::<Vec<_>>()
+    );
+    assert!(cancelled > 0);
+    assert_eq!(access_control.yields.load(Ordering::Relaxed), count);
+
+    ctx.stop().await
+}
+
+struct KindWorker;
+
+#[async_trait]
+impl Worker for KindWorker {
+    type Message = String;
+    type Context = Context;
+
+    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        ctx.set_cluster("kind-cluster").await
+    }
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn list_workers_info__declared_kind__should_be_reported(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("dummy", DummyWorker).await?;
+    WorkerBuilder::new(KindWorker)
+        .with_address("listener")
+        .with_alias("listener-alias")
+        .with_kind("test-listener")
+        .start(ctx)
+        .await?;
+    for _ in 0..3 {
+        let _: String = ctx
+            .send_and_receive(route!["listener"], "Hello".to_string())
+            .await?;
+    }
+
+    let workers = ctx.list_workers_info().await?;
+    let dummy = workers
+        .iter()
+        .find(|w| w.address() == &"dummy".into())
+        .unwrap();
+    assert_eq!(dummy.kind(), &WorkerKind::application());
+    assert_eq!(dummy.cluster(), None);
+    assert!(!dummy.is_processor());
+    assert_eq!(dummy.routed_messages(), 0);
+
+    let listener = workers
+        .iter()
+        .find(|w| w.address() == &"listener".into())
+        .unwrap();
+    assert_eq!(listener.kind(), &WorkerKind::from_static("test-listener"));
+    assert_eq!(listener.kind().to_string(), "test-listener");
+    assert_eq!(listener.cluster(), Some("kind-cluster"));
+    assert_eq!(
+        listener.addresses(),
+        &["listener".into(), "listener-alias".into()]
+    );
+    assert_eq!(listener.routed_messages(), 3);
+
+    // The context of the test is detached
+    assert!(workers
+        .iter()
+        .any(|w| w.address() == &ctx.address() && w.is_detached()));
+
+    ctx.stop().await
+}
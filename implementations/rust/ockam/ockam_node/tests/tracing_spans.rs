@@ -0,0 +1,46 @@
+#![cfg(feature = "tracing_spans")]
+
+use core::time::Duration;
+use ockam_core::{async_trait, route, Result, Routed, Worker};
+use ockam_node::{Context, NodeBuilder};
+use tokio::time::sleep;
+
+/// Replies with the name of the span in which it handles a message, after awaiting
+struct SpanReporter;
+
+#[async_trait]
+impl Worker for SpanReporter {
+    type Context = Context;
+    type Message = String;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        sleep(Duration::from_millis(10)).await;
+        let name = tracing::Span::current()
+            .metadata()
+            .map(|m| m.name().to_string())
+            .unwrap_or_default();
+        ctx.send(msg.return_route(), name).await
+    }
+}
+
+// The spans are only recorded when a subscriber is installed, which is why this test doesn't
+// use `ockam_macros::test`, disabling the logs of the node
+#[allow(non_snake_case)]
+#[test]
+fn tracing_spans__awaiting_handler__should_run_in_message_span() {
+    tracing::subscriber::set_global_default(tracing_subscriber::registry()).unwrap();
+
+    let (ctx, mut executor) = NodeBuilder::new().no_logging().build();
+    executor
+        .execute(async move {
+            let res: Result<String> = async {
+                ctx.start_worker("span_reporter", SpanReporter).await?;
+                ctx.send_and_receive(route!["span_reporter"], "Hello".to_string())
+                    .await
+            }
+            .await;
+            ctx.stop().await.unwrap();
+            assert_eq!(res.unwrap(), "worker_message");
+        })
+        .unwrap();
+}
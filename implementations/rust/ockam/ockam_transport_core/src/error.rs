@@ -41,6 +41,28 @@ pub enum TransportError {
     /// Excessive length of header, possible DoS attack
     /// https://github.com/advisories/GHSA-9mcr-873m-xcxp
     AttackAttmept,
+    /// No listener is bound to the given socket address
+    ListenerNotFound,
+    /// No network interface has the given name
+    InterfaceNotFound,
+    /// The network interface has no address to bind to
+    InterfaceWithoutAddress,
+    /// The connection couldn't be established before the timeout
+    ConnectionTimeout,
+    /// The TLS handshake of the connection failed
+    TlsHandshake,
+    /// The certificate presented by the peer of a TLS connection couldn't be verified
+    TlsCertificateVerification,
+    /// The TLS connection negotiated a protocol version older than the required minimum
+    TlsVersion,
+    /// The TLS connection negotiated a cipher suite which isn't allowed
+    TlsCipherSuite,
+    /// The HTTP proxy of the connection rejected its credentials
+    ProxyAuthentication,
+    /// The HTTP proxy of the connection refused, or failed, to open a tunnel to the peer
+    ProxyConnect,
+    /// The host name of the peer couldn't be resolved
+    DnsResolution,
 }
 
 impl ockam_core::compat::error::Error for TransportError {}
@@ -63,6 +85,23 @@ impl core::fmt::Display for TransportError {
             Self::PortalInvalidState => write!(f, "portal entered invalid state"),
             Self::InvalidRouterResponseType => write!(f, "router responded with invalid type"),
             Self::AttackAttmept => write!(f, "excessive length of header, possible DoS attack"),
+            Self::ListenerNotFound => write!(f, "no listener is bound to this socket address"),
+            Self::InterfaceNotFound => write!(f, "no network interface has this name"),
+            Self::InterfaceWithoutAddress => {
+                write!(f, "the network interface has no address to bind to")
+            }
+            Self::ConnectionTimeout => write!(f, "timed out while establishing the connection"),
+            Self::TlsHandshake => write!(f, "the TLS handshake failed"),
+            Self::TlsCertificateVerification => {
+                write!(f, "the certificate of the TLS peer couldn't be verified")
+            }
+            Self::TlsVersion => write!(f, "the TLS version of the connection is not allowed"),
+            Self::TlsCipherSuite => {
+                write!(f, "the TLS cipher suite of the connection is not allowed")
+            }
+            Self::ProxyAuthentication => write!(f, "the HTTP proxy rejected the credentials"),
+            Self::ProxyConnect => write!(f, "the HTTP proxy couldn't open a tunnel to the peer"),
+            Self::DnsResolution => write!(f, "failed to resolve the host name of the peer"),
         }
     }
 }
@@ -88,6 +127,17 @@ impl From<TransportError> for Error {
             PortalInvalidState => Kind::Invalid,
             InvalidRouterResponseType => Kind::Invalid,
             AttackAttmept => Kind::Misuse,
+            ListenerNotFound => Kind::NotFound,
+            InterfaceNotFound => Kind::NotFound,
+            InterfaceWithoutAddress => Kind::NotFound,
+            ConnectionTimeout => Kind::Timeout,
+            TlsHandshake => Kind::Protocol,
+            TlsCertificateVerification => Kind::Invalid,
+            TlsVersion => Kind::Unsupported,
+            TlsCipherSuite => Kind::Unsupported,
+            ProxyAuthentication => Kind::Invalid,
+            ProxyConnect => Kind::Protocol,
+            DnsResolution => Kind::NotFound,
         };
 
         Error::new(Origin::Transport, kind, err)
@@ -0,0 +1,207 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use core::fmt;
+use core::time::Duration;
+use ockam_core::compat::net::SocketAddr;
+use ockam_core::Result;
+use ockam_transport_core::TransportError;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+/// Maximum duration of the `CONNECT` request to a proxy, when the connection has no timeout
+pub(crate) const PROXY_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum size of the response of a proxy to a `CONNECT` request
+const MAX_RESPONSE_SIZE: usize = 8 * 1024;
+
+/// Credentials sent to an HTTP proxy with the `Basic` authentication scheme,
+/// see [`TcpConnectionOptions::with_http_proxy`](crate::TcpConnectionOptions::with_http_proxy)
+#[derive(Clone)]
+pub struct HttpProxyCredentials {
+    username: String,
+    password: String,
+}
+
+impl HttpProxyCredentials {
+    /// Constructor
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    fn authorization(&self) -> String {
+        let credentials = format!("{}:{}", self.username, self.password);
+        format!("Basic {}", STANDARD.encode(credentials))
+    }
+}
+
+impl fmt::Debug for HttpProxyCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpProxyCredentials")
+            .field("username", &self.username)
+            .finish()
+    }
+}
+
+/// HTTP proxy tunneling outgoing connections with `CONNECT` requests
+#[derive(Clone, Debug)]
+pub(crate) struct HttpProxy {
+    address: String,
+    credentials: Option<HttpProxyCredentials>,
+}
+
+impl HttpProxy {
+    pub(crate) fn new(address: String, credentials: Option<HttpProxyCredentials>) -> Self {
+        Self {
+            address,
+            credentials,
+        }
+    }
+
+    /// `host:port` address of the proxy
+    pub(crate) fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Ask the proxy, over an established connection to `socket_address`, to open a tunnel
+    /// to `peer`, a `host:port` string. The stream can carry the traffic to `peer` once
+    /// this function returns
+    pub(crate) async fn tunnel(
+        &self,
+        stream: &mut TcpStream,
+        socket_address: SocketAddr,
+        peer: &str,
+        timeout: Duration,
+    ) -> Result<()> {
+        debug!(proxy = %socket_address, %peer, "Opening a tunnel through the HTTP proxy");
+        match tokio::time::timeout(timeout, self.connect(stream, peer)).await {
+            Ok(result) => result,
+            Err(_) => {
+                debug!(proxy = %socket_address, "Timed out waiting for the HTTP proxy");
+                Err(TransportError::ConnectionTimeout.into())
+            }
+        }
+    }
+
+    async fn connect(&self, stream: &mut TcpStream, peer: &str) -> Result<()> {
+        if !Self::is_valid_peer(peer) {
+            debug!(
+                ?peer,
+                "Refusing to send an invalid peer address to the HTTP proxy"
+            );
+            return Err(TransportError::InvalidAddress.into());
+        }
+
+        let mut request = format!("CONNECT {peer} HTTP/1.1\r\nHost: {peer}\r\n");
+        if let Some(credentials) = &self.credentials {
+            request.push_str(&format!(
+                "Proxy-Authorization: {}\r\n",
+                credentials.authorization()
+            ));
+        }
+        request.push_str("\r\n");
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(TransportError::from)?;
+
+        let response = Self::read_response_head(stream).await?;
+        match Self::status_code(&response)? {
+            200..=299 => Ok(()),
+            407 => {
+                debug!(%peer, "The HTTP proxy rejected the credentials");
+                Err(TransportError::ProxyAuthentication.into())
+            }
+            status => {
+                debug!(%peer, %status, "The HTTP proxy refused to open a tunnel");
+                Err(TransportError::ProxyConnect.into())
+            }
+        }
+    }
+
+    /// The peer is written as is in the request line and in the `Host` header: it must not
+    /// contain whitespace or control characters, like `\r\n`, which would let it inject
+    /// headers or another request
+    fn is_valid_peer(peer: &str) -> bool {
+        !peer.is_empty() && !peer.chars().any(|c| c.is_control() || c.is_whitespace())
+    }
+
+    /// Read the status line and the headers of the response, and nothing more, since the
+    /// bytes following them already belong to the tunnel
+    async fn read_response_head(stream: &mut TcpStream) -> Result<Vec<u8>> {
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_RESPONSE_SIZE {
+                return Err(TransportError::ProxyConnect.into());
+            }
+            let byte = match stream.read_u8().await {
+                Ok(byte) => byte,
+                Err(_) => return Err(TransportError::ProxyConnect.into()),
+            };
+            response.push(byte);
+        }
+
+        Ok(response)
+    }
+
+    /// Parse the status code of a `HTTP/1.x <code> <reason>` status line
+    fn status_code(response: &[u8]) -> Result<u16> {
+        let status_line = response
+            .split(|b| *b == b'\n')
+            .next()
+            .and_then(|line| core::str::from_utf8(line).ok())
+            .unwrap_or_default();
+        let mut parts = status_line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some(version), Some(code)) if version.starts_with("HTTP/1.") => code
+                .parse()
+                .map_err(|_| TransportError::ProxyConnect.into()),
+            _ => Err(TransportError::ProxyConnect.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_code() {
+        let code = |response: &str| HttpProxy::status_code(response.as_bytes()).ok();
+        assert_eq!(
+            code("HTTP/1.1 200 Connection established\r\n\r\n"),
+            Some(200)
+        );
+        assert_eq!(
+            code("HTTP/1.0 407 Proxy Authentication Required\r\nProxy-Authenticate: Basic\r\n\r\n"),
+            Some(407)
+        );
+        assert_eq!(code("SSH-2.0-OpenSSH\r\n\r\n"), None);
+        assert_eq!(code("HTTP/1.1 OK\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn invalid_peers() {
+        assert!(HttpProxy::is_valid_peer("example.com:4000"));
+        assert!(HttpProxy::is_valid_peer("[::1]:4000"));
+        assert!(!HttpProxy::is_valid_peer(""));
+        assert!(!HttpProxy::is_valid_peer(
+            "example.com:4000\r\nProxy-Authorization: Basic Zm9vOmJhcg=="
+        ));
+        assert!(!HttpProxy::is_valid_peer("example.com:4000\nHost: other"));
+        assert!(!HttpProxy::is_valid_peer("example.com:4000 HTTP/1.0"));
+        assert!(!HttpProxy::is_valid_peer("example.com\0:4000"));
+    }
+
+    #[test]
+    fn basic_authorization() {
+        let credentials = HttpProxyCredentials::new("Aladdin", "open sesame");
+        assert_eq!(
+            credentials.authorization(),
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+    }
+}
@@ -18,22 +18,43 @@ extern crate core;
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+mod http_proxy;
+mod memory;
 mod options;
 mod portal;
 mod registry;
+#[cfg(feature = "tls")]
+mod tls;
 mod transport;
 
+pub use http_proxy::HttpProxyCredentials;
+pub use memory::MemoryTransport;
 use ockam_core::TransportType;
+use ockam_node::WorkerKind;
 pub use options::{TcpConnectionOptions, TcpListenerOptions};
 pub use portal::{PortalInternalMessage, PortalMessage, MAX_PAYLOAD_SIZE};
 pub use registry::*;
 pub use transport::common::*;
 pub use transport::*;
 
+/// The TLS library used by [`TcpConnectionOptions::with_tls_client`] and
+/// [`TcpListenerOptions::with_tls`]
+#[cfg(feature = "tls")]
+pub use tokio_rustls::rustls;
+#[cfg(feature = "tls")]
+pub use tls::TlsRequirements;
+
 mod workers;
 pub(crate) use workers::*;
 
 pub(crate) const CLUSTER_NAME: &str = "_internals.transport.tcp";
 
+/// [`WorkerKind`] of the sender workers of TCP connections
+pub const TCP_SENDER_KIND: WorkerKind = WorkerKind::from_static("tcp-sender");
+/// [`WorkerKind`] of the receiver processors of TCP connections
+pub const TCP_RECEIVER_KIND: WorkerKind = WorkerKind::from_static("tcp-receiver");
+/// [`WorkerKind`] of the listener processors accepting TCP connections
+pub const TCP_LISTENER_KIND: WorkerKind = WorkerKind::from_static("tcp-listener");
+
 /// Transport type for TCP addresses
 pub const TCP: TransportType = TransportType::new(1);
@@ -0,0 +1,188 @@
+mod worker;
+
+use crate::transport::common::parse_socket_addr;
+use crate::workers::Addresses;
+use crate::{
+    TcpConnection, TcpConnectionMode, TcpConnectionOptions, TcpListener, TcpListenerOptions,
+};
+use core::time::Duration;
+use ockam_core::compat::collections::HashMap;
+use ockam_core::compat::net::{IpAddr, SocketAddr};
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::{Address, AsyncTryClone, Result};
+use ockam_node::Context;
+use ockam_transport_core::TransportError;
+use tracing::debug;
+use worker::MemoryConnectionWorker;
+
+/// First port given to the listeners bound to port 0
+const FIRST_EPHEMERAL_PORT: u16 = 49152;
+
+/// An in-memory transport with the same API as [`TcpTransport`](crate::TcpTransport)
+///
+/// Listeners are bound to socket addresses which only exist within this transport, and each
+/// connection is a pair of workers passing messages to each other on the node, without any
+/// socket. Flow controls and access controls are set up like for TCP connections, so tests
+/// can swap a `TcpTransport` for a `MemoryTransport` and run faster and deterministically.
+///
+/// A latency can be added with [`with_latency`](Self::with_latency) to simulate a slow link.
+///
+/// ```rust
+/// use ockam_transport_tcp::{MemoryTransport, TcpConnectionOptions, TcpListenerOptions};
+/// # use ockam_node::Context;
+/// # use ockam_core::Result;
+/// # async fn test(ctx: Context) -> Result<()> {
+/// let memory = MemoryTransport::create(&ctx).await?;
+/// let listener = memory.listen("127.0.0.1:0", TcpListenerOptions::new()).await?;
+/// let connection = memory
+///     .connect(listener.socket_string(), TcpConnectionOptions::new())
+///     .await?;
+/// # Ok(()) }
+/// ```
+#[derive(AsyncTryClone)]
+#[async_try_clone(crate = "ockam_core")]
+pub struct MemoryTransport {
+    ctx: Context,
+    listeners: Arc<Mutex<HashMap<SocketAddr, MemoryListener>>>,
+    latency: Option<Duration>,
+}
+
+struct MemoryListener {
+    address: Address,
+    options: TcpListenerOptions,
+}
+
+impl MemoryTransport {
+    /// Create an in-memory transport
+    pub async fn create(ctx: &Context) -> Result<Self> {
+        Ok(Self {
+            ctx: ctx.async_try_clone().await?,
+            listeners: Default::default(),
+            latency: None,
+        })
+    }
+
+    /// Delay each message by the given duration, for the connections created after this call.
+    /// The order of the messages is preserved
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Start listening to incoming connections
+    ///
+    /// As for TCP, binding to port 0 picks a free port, which is returned in the [`TcpListener`].
+    /// [`TcpListenerOptions::bind_to_interface`] is not supported.
+    pub async fn listen(
+        &self,
+        bind_addr: impl AsRef<str>,
+        options: TcpListenerOptions,
+    ) -> Result<TcpListener> {
+        if options.interface.is_some() {
+            return Err(TransportError::InterfaceNotFound.into());
+        }
+
+        let mut socket_address = parse_socket_addr(bind_addr.as_ref())?;
+        let mut listeners = self.listeners.lock().unwrap();
+        if socket_address.port() == 0 {
+            socket_address.set_port(free_port(&listeners, socket_address.ip())?);
+        } else if listeners.contains_key(&socket_address) {
+            return Err(TransportError::BindFailed.into());
+        }
+
+        let address = Address::random_tagged("MemoryListener");
+        options.setup_flow_control_for_listener(self.ctx.flow_controls(), &address);
+        let flow_control_id = options.spawner_flow_control_id();
+        listeners.insert(
+            socket_address,
+            MemoryListener {
+                address: address.clone(),
+                options,
+            },
+        );
+        debug!(addr = %socket_address, "Listening in memory");
+
+        Ok(TcpListener::new(address, socket_address, flow_control_id))
+    }
+
+    /// Stop a listener given its `Address`. Its connections are not closed
+    pub async fn stop_listener(&self, address: &Address) -> Result<()> {
+        let mut listeners = self.listeners.lock().unwrap();
+        let socket_address = listeners
+            .iter()
+            .find(|(_, listener)| &listener.address == address)
+            .map(|(socket_address, _)| *socket_address)
+            .ok_or(TransportError::ListenerNotFound)?;
+        listeners.remove(&socket_address);
+
+        Ok(())
+    }
+
+    /// Connect to a listener of this transport
+    ///
+    /// The connection can be used as soon as this function returns.
+    pub async fn connect(
+        &self,
+        peer: impl AsRef<str>,
+        options: TcpConnectionOptions,
+    ) -> Result<TcpConnection> {
+        let socket_address = parse_socket_addr(peer.as_ref())?;
+        let flow_controls = self.ctx.flow_controls();
+        let outgoing = Addresses::generate(TcpConnectionMode::Outgoing);
+        let incoming = Addresses::generate(TcpConnectionMode::Incoming);
+
+        // The listener side is set up like a connection accepted by a TCP listener
+        let incoming_access_control = {
+            let listeners = self.listeners.lock().unwrap();
+            let listener = listeners
+                .get(&socket_address)
+                .ok_or(TransportError::PeerNotFound)?;
+            let flow_control_id = listener
+                .options
+                .setup_flow_control_for_connection(flow_controls, &incoming);
+            listener
+                .options
+                .create_access_control(flow_controls, flow_control_id)
+        };
+
+        options.setup_flow_control(flow_controls, &outgoing);
+        let flow_control_id = options.flow_control_id.clone();
+        let outgoing_access_control = options.create_access_control(flow_controls);
+
+        MemoryConnectionWorker::start(
+            &self.ctx,
+            &incoming,
+            &outgoing,
+            incoming_access_control,
+            self.latency,
+        )
+        .await?;
+        MemoryConnectionWorker::start(
+            &self.ctx,
+            &outgoing,
+            &incoming,
+            outgoing_access_control,
+            self.latency,
+        )
+        .await?;
+
+        Ok(TcpConnection::new(
+            outgoing.sender_address().clone(),
+            outgoing.receiver_address().clone(),
+            socket_address,
+            TcpConnectionMode::Outgoing,
+            flow_control_id,
+        ))
+    }
+
+    /// Close a connection given its Sender `Address`. Both sides of the connection are closed
+    pub async fn disconnect(&self, address: impl Into<Address>) -> Result<()> {
+        self.ctx.stop_worker(address.into()).await
+    }
+}
+
+fn free_port(listeners: &HashMap<SocketAddr, MemoryListener>, ip: IpAddr) -> Result<u16> {
+    (FIRST_EPHEMERAL_PORT..=u16::MAX)
+        .find(|port| !listeners.contains_key(&SocketAddr::new(ip, *port)))
+        .ok_or_else(|| TransportError::Capacity.into())
+}
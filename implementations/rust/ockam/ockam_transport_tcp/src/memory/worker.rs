@@ -0,0 +1,211 @@
+use crate::options::TcpConnectionAccessControl;
+use crate::workers::Addresses;
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{
+    async_trait, Address, AllowOnwardAddress, AllowSourceAddress, Any, DenyAll, LocalMessage,
+    Mailbox, Mailboxes, MessageHeaders, Result, RouteTrace, Routed, Worker,
+};
+use ockam_node::{Context, WorkerBuilder};
+use ockam_transport_core::TransportError;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::time::{sleep_until, Instant};
+use tracing::{trace, warn};
+
+/// Messages waiting for the latency of a connection to elapse
+///
+/// They are held by a task which sends them back to the worker, at its delayed address,
+/// once their latency elapsed, so that the worker keeps handling messages in the meantime.
+struct DelayedMessages {
+    address: Address,
+    /// Address of the task sending the delayed messages
+    source: Address,
+    latency: Duration,
+    queue: UnboundedSender<(Instant, LocalMessage)>,
+}
+
+impl DelayedMessages {
+    async fn start(ctx: &Context, latency: Duration) -> Result<Self> {
+        let address = Address::random_tagged("MemoryConnectionWorker.delayed");
+        let source = Address::random_tagged("MemoryConnectionWorker.delay");
+        let delay_ctx = ctx
+            .new_detached_with_mailboxes(Mailboxes::main(
+                source.clone(),
+                Arc::new(DenyAll),
+                Arc::new(AllowOnwardAddress(address.clone())),
+            ))
+            .await?;
+
+        // A single task preserves the order of the messages, like a TCP connection.
+        // It stops when the worker is dropped
+        let (queue, mut delayed) = unbounded_channel::<(Instant, LocalMessage)>();
+        ctx.runtime().spawn(async move {
+            while let Some((deadline, msg)) = delayed.recv().await {
+                sleep_until(deadline).await;
+                if let Err(e) = delay_ctx.forward(msg).await {
+                    warn!("Failed to deliver a delayed message: {}", e);
+                }
+            }
+        });
+
+        Ok(Self {
+            address,
+            source,
+            latency,
+            queue,
+        })
+    }
+}
+
+/// One side of an in-memory connection
+///
+/// It plays the role of both the TCP sender and the TCP receiver: messages sent to its
+/// sender address are passed to the other side of the connection, which forwards them
+/// from its receiver address, with its own sender address prepended to the return route.
+pub(crate) struct MemoryConnectionWorker {
+    addresses: Addresses,
+    peer_addresses: Addresses,
+    delayed: Option<DelayedMessages>,
+}
+
+impl MemoryConnectionWorker {
+    pub(crate) async fn start(
+        ctx: &Context,
+        addresses: &Addresses,
+        peer_addresses: &Addresses,
+        access_control: TcpConnectionAccessControl,
+        latency: Option<Duration>,
+    ) -> Result<()> {
+        let delayed = match latency {
+            Some(latency) => Some(DelayedMessages::start(ctx, latency).await?),
+            None => None,
+        };
+
+        let sender_mailbox = Mailbox::new(
+            addresses.sender_address().clone(),
+            access_control.sender_incoming_access_control,
+            Arc::new(AllowOnwardAddress(
+                peer_addresses.receiver_internal_address().clone(),
+            )),
+        );
+        let receiver_mailbox = Mailbox::new(
+            addresses.receiver_address().clone(),
+            Arc::new(DenyAll),
+            access_control.receiver_outgoing_access_control,
+        );
+        let internal_mailbox = Mailbox::new(
+            addresses.receiver_internal_address().clone(),
+            Arc::new(AllowSourceAddress(peer_addresses.sender_address().clone())),
+            Arc::new(DenyAll),
+        );
+        let mut mailboxes = vec![receiver_mailbox, internal_mailbox];
+        if let Some(delayed) = &delayed {
+            mailboxes.push(Mailbox::new(
+                delayed.address.clone(),
+                Arc::new(AllowSourceAddress(delayed.source.clone())),
+                Arc::new(DenyAll),
+            ));
+        }
+
+        let worker = Self {
+            addresses: addresses.clone(),
+            peer_addresses: peer_addresses.clone(),
+            delayed,
+        };
+        WorkerBuilder::new(worker)
+            .with_mailboxes(Mailboxes::new(sender_mailbox, mailboxes))
+            .start(ctx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Pass a message to the other side of the connection
+    async fn forward_to_peer(&self, ctx: &Context, msg: LocalMessage) -> Result<()> {
+        let mut msg = msg;
+        msg.transport_mut()
+            .onward_route
+            .modify()
+            .prepend(self.peer_addresses.receiver_internal_address().clone());
+        ctx.forward_from_address(msg, self.addresses.sender_address().clone())
+            .await
+    }
+}
+
+#[async_trait]
+impl Worker for MemoryConnectionWorker {
+    type Context = Context;
+    type Message = Any;
+
+    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        ctx.set_cluster(crate::CLUSTER_NAME).await
+    }
+
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        // Both sides of a connection are closed together. The other side may already be stopped
+        let _ = ctx
+            .stop_worker(self.peer_addresses.sender_address().clone())
+            .await;
+
+        Ok(())
+    }
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let recipient = msg.msg_addr();
+        // As with TCP, only the route trace and the headers are kept when the message
+        // crosses the connection
+        let mut local_info = match RouteTrace::find_info(msg.local_message())? {
+            Some(trace) => vec![trace.to_local_info()?],
+            None => vec![],
+        };
+        if let Some(headers) = MessageHeaders::find_info(msg.local_message())? {
+            local_info.push(headers.to_local_info()?);
+        }
+        let mut msg = msg.into_transport_message();
+        msg.onward_route.step()?;
+
+        if &recipient == self.addresses.sender_address() {
+            if msg.onward_route.next().is_err() {
+                trace!("Dropping a message without onward route");
+                return Ok(());
+            }
+
+            match &self.delayed {
+                Some(delayed) => {
+                    // The message comes back to our delayed address once the latency elapsed
+                    msg.onward_route.modify().prepend(delayed.address.clone());
+                    delayed
+                        .queue
+                        .send((
+                            Instant::now() + delayed.latency,
+                            LocalMessage::new(msg, local_info),
+                        ))
+                        .map_err(|_| TransportError::ConnectionDrop.into())
+                }
+                None => {
+                    self.forward_to_peer(ctx, LocalMessage::new(msg, local_info))
+                        .await
+                }
+            }
+        } else if self.delayed.as_ref().map(|d| &d.address) == Some(&recipient) {
+            self.forward_to_peer(ctx, LocalMessage::new(msg, local_info))
+                .await
+        } else {
+            // Insert our sender address into the return route so that
+            // replies go back through the connection
+            msg.return_route
+                .modify()
+                .prepend(self.addresses.sender_address().clone());
+
+            ctx.forward_from_address(
+                LocalMessage::new(msg, local_info),
+                self.addresses.receiver_address().clone(),
+            )
+            .await
+        }
+    }
+}
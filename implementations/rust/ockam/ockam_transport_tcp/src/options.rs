@@ -1,7 +1,17 @@
-use crate::workers::Addresses;
+use crate::http_proxy::{HttpProxy, HttpProxyCredentials, PROXY_CONNECT_TIMEOUT};
+#[cfg(feature = "tls")]
+use crate::tls::{TlsClient, TlsRequirements, TlsServer};
+use crate::transport::common::resolve_peer_at_connect;
+use crate::workers::{split_tcp_stream, Addresses, TcpReadHalf, TcpWriteHalf};
+use crate::{IpVersionPreference, TcpSendWorker, DEFAULT_DNS_TIMEOUT};
+use core::time::Duration;
+use ockam_core::compat::net::SocketAddr;
 use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
-use ockam_core::{Address, AllowAll, IncomingAccessControl, OutgoingAccessControl};
+use ockam_core::{Address, AllowAll, IncomingAccessControl, OutgoingAccessControl, Result};
+use tokio::net::TcpStream;
+#[cfg(feature = "tls")]
+use tokio_rustls::rustls;
 
 pub(crate) struct TcpConnectionAccessControl {
     pub sender_incoming_access_control: Arc<dyn IncomingAccessControl>,
@@ -13,6 +23,18 @@ pub(crate) struct TcpConnectionAccessControl {
 pub struct TcpConnectionOptions {
     pub(super) consumer: Vec<FlowControlId>,
     pub(crate) flow_control_id: FlowControlId,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) dns_timeout: Duration,
+    pub(crate) ip_version_preference: IpVersionPreference,
+    pub(crate) rate_limit: Option<u64>,
+    pub(crate) read_rate_limit: Option<u64>,
+    pub(crate) http_proxy: Option<HttpProxy>,
+    pub(crate) message_extensions: bool,
+    pub(crate) custom_flow_control_id: bool,
+    #[cfg(feature = "tls")]
+    pub(crate) tls: Option<TlsClient>,
+    #[cfg(feature = "tls")]
+    pub(crate) tls_requirements: TlsRequirements,
 }
 
 impl TcpConnectionOptions {
@@ -22,6 +44,18 @@ impl TcpConnectionOptions {
         Self {
             consumer: vec![],
             flow_control_id: FlowControls::generate_flow_control_id(),
+            connect_timeout: None,
+            dns_timeout: DEFAULT_DNS_TIMEOUT,
+            ip_version_preference: IpVersionPreference::default(),
+            rate_limit: None,
+            read_rate_limit: None,
+            http_proxy: None,
+            message_extensions: false,
+            custom_flow_control_id: false,
+            #[cfg(feature = "tls")]
+            tls: None,
+            #[cfg(feature = "tls")]
+            tls_requirements: TlsRequirements::new(),
         }
     }
 
@@ -32,13 +66,176 @@ impl TcpConnectionOptions {
         self
     }
 
-    /// Getter for freshly generated [`FlowControlId`]
+    /// Fail the connection with [`TransportError::ConnectionTimeout`](ockam_transport_core::TransportError::ConnectionTimeout)
+    /// if it can't be established within the given duration.
+    /// By default, the connection attempt lasts until the OS gives up
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Fail the connection with [`TransportError::DnsResolution`](ockam_transport_core::TransportError::DnsResolution)
+    /// if the host name of the peer can't be resolved within the given duration.
+    /// By default, [`DEFAULT_DNS_TIMEOUT`].
+    ///
+    /// A host name is resolved on every connection attempt, so that a new connection to a peer
+    /// behind a load balancer follows its DNS records
+    pub fn with_dns_timeout(mut self, timeout: Duration) -> Self {
+        self.dns_timeout = timeout;
+        self
+    }
+
+    /// Select the IP version of the address used when the host name of the peer resolves to
+    /// both IPv4 and IPv6 addresses. By default, an IPv4 address is used
+    pub fn with_ip_version_preference(mut self, preference: IpVersionPreference) -> Self {
+        self.ip_version_preference = preference;
+        self
+    }
+
+    /// Pace the writes to the connection to at most `bytes_per_sec` bytes per second, after
+    /// an initial burst of up to one second worth of bytes.
+    ///
+    /// Messages sent above that rate wait in the mailbox of the sender, which applies the
+    /// node's backpressure once it is full. A message larger than the rate is still sent,
+    /// delaying the next ones accordingly.
+    ///
+    /// Rate limited connections are never shared, [`crate::TcpTransport::connect_pooled`]
+    /// always establishes a new one
+    pub fn with_rate_limit(mut self, bytes_per_sec: u64) -> Self {
+        self.rate_limit = Some(bytes_per_sec);
+        self
+    }
+
+    /// Pace the reads from the connection to at most `bytes_per_sec` bytes per second, after
+    /// an initial burst of up to one second worth of bytes. The peer is slowed down by the
+    /// TCP flow control once the socket buffers are full
+    pub fn with_read_rate_limit(mut self, bytes_per_sec: u64) -> Self {
+        self.read_rate_limit = Some(bytes_per_sec);
+        self
+    }
+
+    /// Connect through the HTTP proxy listening at `proxy_address`, a `host:port` string.
+    ///
+    /// The connection is established to the proxy, which is asked to open a tunnel to the peer
+    /// with a `CONNECT` request, sending the given credentials if any. The connection fails with
+    /// [`TransportError::ProxyAuthentication`](ockam_transport_core::TransportError::ProxyAuthentication)
+    /// if the proxy rejects the credentials and with
+    /// [`TransportError::ProxyConnect`](ockam_transport_core::TransportError::ProxyConnect)
+    /// if it doesn't accept the request for any other reason.
+    ///
+    /// The peer name is resolved by the proxy, the socket address of the resulting
+    /// [`crate::TcpConnection`] is the one of the proxy. A TLS handshake, if enabled, runs
+    /// with the peer through the tunnel.
+    ///
+    /// Proxied connections are never shared, [`crate::TcpTransport::connect_pooled`] always
+    /// establishes a new one
+    pub fn with_http_proxy(
+        mut self,
+        proxy_address: impl Into<String>,
+        credentials: Option<HttpProxyCredentials>,
+    ) -> Self {
+        self.http_proxy = Some(HttpProxy::new(proxy_address.into(), credentials));
+        self
+    }
+
+    /// Send the extensions of the messages, like their [`ockam_core::RouteTrace`] and
+    /// [`ockam_core::MessageHeaders`], to the peer.
+    ///
+    /// Only enable this when the peer supports
+    /// [`ockam_core::EXTENDED_TRANSPORT_MESSAGE_VERSION`]: older nodes drop the messages
+    /// carrying extensions as malformed. By default the extensions are not sent, while the
+    /// extensions sent by the peer are always accepted.
+    ///
+    /// Connections sending extensions are never shared,
+    /// [`crate::TcpTransport::connect_pooled`] always establishes a new one
+    pub fn with_message_extensions(mut self) -> Self {
+        self.message_extensions = true;
+        self
+    }
+
+    /// Run a TLS handshake with the given configuration once the connection is established.
+    /// The server certificate must be valid for the host name or IP address given to
+    /// [`crate::TcpTransport::connect`], otherwise the connection fails with
+    /// [`TransportError::TlsCertificateVerification`](ockam_transport_core::TransportError::TlsCertificateVerification).
+    ///
+    /// TLS connections are never shared, [`crate::TcpTransport::connect_pooled`] always
+    /// establishes a new one
+    #[cfg(feature = "tls")]
+    pub fn with_tls_client(mut self, config: Arc<rustls::ClientConfig>) -> Self {
+        self.tls = Some(TlsClient::new(config));
+        self
+    }
+
+    /// Close the TLS connections which don't meet the `requirements` once their handshake
+    /// completed, even if the rustls configuration given to
+    /// [`TcpConnectionOptions::with_tls_client`] allowed them
+    #[cfg(feature = "tls")]
+    pub fn with_tls_requirements(mut self, requirements: TlsRequirements) -> Self {
+        self.tls_requirements = requirements;
+        self
+    }
+
+    /// Mark this Tcp Receiver as a Producer with the given [`FlowControlId`] instead of a
+    /// random one, e.g. a child generated with
+    /// [`FlowControls::generate_child_flow_control_id`] so that the connection is revoked
+    /// along with its parent.
+    ///
+    /// Connections with a given [`FlowControlId`] are never shared,
+    /// [`crate::TcpTransport::connect_pooled`] always establishes a new one
+    pub fn with_flow_control_id(mut self, flow_control_id: FlowControlId) -> Self {
+        self.flow_control_id = flow_control_id;
+        self.custom_flow_control_id = true;
+        self
+    }
+
+    /// Getter for freshly generated [`FlowControlId`], or the one given to
+    /// [`TcpConnectionOptions::with_flow_control_id`]
     pub fn flow_control_id(&self) -> FlowControlId {
         self.flow_control_id.clone()
     }
 }
 
 impl TcpConnectionOptions {
+    /// Establish a TCP connection to `peer`, through the HTTP proxy if one is set, and
+    /// return it with the socket address it is connected to
+    pub(crate) async fn connect_stream(&self, peer: &str) -> Result<(SocketAddr, TcpStream)> {
+        let proxy = match &self.http_proxy {
+            Some(proxy) => proxy,
+            None => {
+                let socket = self.resolve_peer(peer).await?;
+                let stream = TcpSendWorker::connect(socket, self.connect_timeout).await?;
+                return Ok((socket, stream));
+            }
+        };
+
+        let socket = self.resolve_peer(proxy.address()).await?;
+        let mut stream = TcpSendWorker::connect(socket, self.connect_timeout).await?;
+        let timeout = self.connect_timeout.unwrap_or(PROXY_CONNECT_TIMEOUT);
+        proxy.tunnel(&mut stream, socket, peer, timeout).await?;
+
+        Ok((socket, stream))
+    }
+
+    /// Resolve the `host:port` address of the peer, or of the proxy, according to these options
+    pub(crate) async fn resolve_peer(&self, peer: &str) -> Result<SocketAddr> {
+        resolve_peer_at_connect(peer, self.ip_version_preference, self.dns_timeout).await
+    }
+
+    /// Split an established connection to `peer`, after the TLS handshake if TLS is enabled
+    #[cfg_attr(not(feature = "tls"), allow(unused_variables))]
+    pub(crate) async fn split_stream(
+        &self,
+        stream: TcpStream,
+        peer: &str,
+    ) -> Result<(TcpReadHalf, TcpWriteHalf)> {
+        #[cfg(feature = "tls")]
+        if let Some(tls) = &self.tls {
+            return tls.connect(stream, peer, &self.tls_requirements).await;
+        }
+
+        Ok(split_tcp_stream(stream))
+    }
+
     pub(crate) fn setup_flow_control(&self, flow_controls: &FlowControls, addresses: &Addresses) {
         flow_controls.add_producer(
             addresses.receiver_address().clone(),
@@ -71,6 +268,14 @@ impl TcpConnectionOptions {
 #[derive(Debug)]
 pub struct TcpListenerOptions {
     pub(crate) flow_control_id: FlowControlId,
+    pub(crate) interface: Option<String>,
+    pub(crate) rate_limit: Option<u64>,
+    pub(crate) read_rate_limit: Option<u64>,
+    pub(crate) message_extensions: bool,
+    #[cfg(feature = "tls")]
+    pub(crate) tls: Option<TlsServer>,
+    #[cfg(feature = "tls")]
+    pub(crate) tls_requirements: TlsRequirements,
 }
 
 impl TcpListenerOptions {
@@ -81,9 +286,66 @@ impl TcpListenerOptions {
     pub fn new() -> Self {
         Self {
             flow_control_id: FlowControls::generate_flow_control_id(),
+            interface: None,
+            rate_limit: None,
+            read_rate_limit: None,
+            message_extensions: false,
+            #[cfg(feature = "tls")]
+            tls: None,
+            #[cfg(feature = "tls")]
+            tls_requirements: TlsRequirements::new(),
         }
     }
 
+    /// Bind the listener to the current address of the network interface with the given name,
+    /// e.g. `wg0`, instead of the IP address given to [`crate::TcpTransport::listen`].
+    /// Only the port of that address is used.
+    ///
+    /// The interface is resolved when the listener is started, which fails if the interface
+    /// doesn't exist or has no address. That way the listener can't be exposed on another
+    /// interface by mistake.
+    pub fn bind_to_interface(mut self, name: &str) -> Self {
+        self.interface = Some(name.to_string());
+        self
+    }
+
+    /// Pace the writes to every accepted connection to at most `bytes_per_sec` bytes per
+    /// second, see [`TcpConnectionOptions::with_rate_limit`]
+    pub fn with_rate_limit(mut self, bytes_per_sec: u64) -> Self {
+        self.rate_limit = Some(bytes_per_sec);
+        self
+    }
+
+    /// Pace the reads from every accepted connection to at most `bytes_per_sec` bytes per
+    /// second, see [`TcpConnectionOptions::with_read_rate_limit`]
+    pub fn with_read_rate_limit(mut self, bytes_per_sec: u64) -> Self {
+        self.read_rate_limit = Some(bytes_per_sec);
+        self
+    }
+
+    /// Send the extensions of the messages to the peers of every accepted connection,
+    /// see [`TcpConnectionOptions::with_message_extensions`]
+    pub fn with_message_extensions(mut self) -> Self {
+        self.message_extensions = true;
+        self
+    }
+
+    /// Run a TLS handshake with the given configuration on every accepted connection.
+    /// Connections whose handshake fails are closed, the listener keeps accepting new ones
+    #[cfg(feature = "tls")]
+    pub fn with_tls(mut self, config: Arc<rustls::ServerConfig>) -> Self {
+        self.tls = Some(TlsServer::new(config));
+        self
+    }
+
+    /// Close the accepted TLS connections which don't meet the `requirements`,
+    /// see [`TcpConnectionOptions::with_tls_requirements`]
+    #[cfg(feature = "tls")]
+    pub fn with_tls_requirements(mut self, requirements: TlsRequirements) -> Self {
+        self.tls_requirements = requirements;
+        self
+    }
+
     /// Getter for freshly generated [`FlowControlId`]
     pub fn spawner_flow_control_id(&self) -> FlowControlId {
         self.flow_control_id.clone()
@@ -91,6 +353,19 @@ impl TcpListenerOptions {
 }
 
 impl TcpListenerOptions {
+    /// Split an accepted connection, after the TLS handshake if TLS is enabled
+    pub(crate) async fn split_stream(
+        &self,
+        stream: TcpStream,
+    ) -> Result<(TcpReadHalf, TcpWriteHalf)> {
+        #[cfg(feature = "tls")]
+        if let Some(tls) = &self.tls {
+            return tls.accept(stream, &self.tls_requirements).await;
+        }
+
+        Ok(split_tcp_stream(stream))
+    }
+
     pub(crate) fn setup_flow_control_for_listener(
         &self,
         flow_controls: &FlowControls,
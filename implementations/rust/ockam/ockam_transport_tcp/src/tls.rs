@@ -0,0 +1,164 @@
+use crate::workers::{TcpReadHalf, TcpWriteHalf};
+use core::fmt;
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use ockam_transport_core::TransportError;
+use std::io;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{
+    self, CipherSuite, ClientConfig, CommonState, ProtocolVersion, ServerConfig, ServerName,
+};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+/// Maximum duration of the TLS handshake of a connection
+pub(crate) const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Minimum security of the TLS connections of a transport, checked once their handshake
+/// completed. A connection which doesn't meet it is closed
+#[derive(Clone, Debug, Default)]
+pub struct TlsRequirements {
+    min_version: Option<ProtocolVersion>,
+    cipher_suites: Option<Vec<CipherSuite>>,
+}
+
+impl TlsRequirements {
+    /// Accept any TLS version and cipher suite enabled by the rustls configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject the connections negotiating a TLS version older than `version`,
+    /// with [`TransportError::TlsVersion`]
+    pub fn with_min_version(mut self, version: ProtocolVersion) -> Self {
+        self.min_version = Some(version);
+        self
+    }
+
+    /// Reject the connections negotiating a cipher suite missing from `cipher_suites`,
+    /// with [`TransportError::TlsCipherSuite`]
+    pub fn with_cipher_suites(mut self, cipher_suites: &[CipherSuite]) -> Self {
+        self.cipher_suites = Some(cipher_suites.to_vec());
+        self
+    }
+
+    fn check(&self, connection: &CommonState) -> Result<()> {
+        if let Some(min_version) = self.min_version {
+            match connection.protocol_version() {
+                Some(version) if version.get_u16() >= min_version.get_u16() => {}
+                _ => return Err(TransportError::TlsVersion.into()),
+            }
+        }
+
+        if let Some(cipher_suites) = &self.cipher_suites {
+            match connection.negotiated_cipher_suite() {
+                Some(suite) if cipher_suites.contains(&suite.suite()) => {}
+                _ => return Err(TransportError::TlsCipherSuite.into()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// TLS configuration of outgoing connections
+#[derive(Clone)]
+pub(crate) struct TlsClient {
+    connector: TlsConnector,
+}
+
+impl fmt::Debug for TlsClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TlsClient")
+    }
+}
+
+impl TlsClient {
+    pub(crate) fn new(config: Arc<ClientConfig>) -> Self {
+        Self {
+            connector: TlsConnector::from(config),
+        }
+    }
+
+    /// Run the TLS handshake over an established TCP connection to `peer`, a `host:port`
+    /// string. The server certificate must be valid for `host`, and the negotiated
+    /// parameters must meet the `requirements`
+    pub(crate) async fn connect(
+        &self,
+        stream: TcpStream,
+        peer: &str,
+        requirements: &TlsRequirements,
+    ) -> Result<(TcpReadHalf, TcpWriteHalf)> {
+        let host = match peer.rsplit_once(':') {
+            Some((host, _port)) => host,
+            None => peer,
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let server_name = ServerName::try_from(host).map_err(|_| TransportError::InvalidAddress)?;
+
+        let stream = match tokio::time::timeout(
+            TLS_HANDSHAKE_TIMEOUT,
+            self.connector.connect(server_name, stream),
+        )
+        .await
+        {
+            Ok(stream) => stream.map_err(tls_error)?,
+            Err(_) => return Err(TransportError::ConnectionTimeout.into()),
+        };
+        requirements.check(stream.get_ref().1)?;
+        let (read_half, write_half) = tokio::io::split(stream);
+
+        Ok((Box::new(read_half), Box::new(write_half)))
+    }
+}
+
+/// TLS configuration of incoming connections
+#[derive(Clone)]
+pub(crate) struct TlsServer {
+    acceptor: TlsAcceptor,
+}
+
+impl fmt::Debug for TlsServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TlsServer")
+    }
+}
+
+impl TlsServer {
+    pub(crate) fn new(config: Arc<ServerConfig>) -> Self {
+        Self {
+            acceptor: TlsAcceptor::from(config),
+        }
+    }
+
+    /// Run the TLS handshake over an accepted TCP connection, the negotiated parameters
+    /// must meet the `requirements`
+    pub(crate) async fn accept(
+        &self,
+        stream: TcpStream,
+        requirements: &TlsRequirements,
+    ) -> Result<(TcpReadHalf, TcpWriteHalf)> {
+        let stream =
+            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, self.acceptor.accept(stream)).await {
+                Ok(stream) => stream.map_err(tls_error)?,
+                Err(_) => return Err(TransportError::ConnectionTimeout.into()),
+            };
+        requirements.check(stream.get_ref().1)?;
+        let (read_half, write_half) = tokio::io::split(stream);
+
+        Ok((Box::new(read_half), Box::new(write_half)))
+    }
+}
+
+/// Distinguish the certificate verification failures from the other TLS and I/O failures
+fn tls_error(err: io::Error) -> TransportError {
+    match err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<rustls::Error>())
+    {
+        Some(rustls::Error::InvalidCertificate(_)) => TransportError::TlsCertificateVerification,
+        Some(_) => TransportError::TlsHandshake,
+        None => TransportError::from(err),
+    }
+}
@@ -1,10 +1,17 @@
 use crate::TcpConnectionMode;
 use core::fmt;
 use core::fmt::Formatter;
+use core::time::Duration;
+use ockam_core::compat::io;
 use ockam_core::compat::net::{SocketAddr, ToSocketAddrs};
 use ockam_core::flow_control::FlowControlId;
 use ockam_core::{Address, Result};
 use ockam_transport_core::TransportError;
+use tracing::{debug, warn};
+
+/// Default maximum duration of the resolution of a host name, see
+/// [`TcpConnectionOptions::with_dns_timeout`](crate::TcpConnectionOptions::with_dns_timeout)
+pub const DEFAULT_DNS_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Result of [`TcpTransport::connect`] call.
 #[derive(Clone, Debug)]
@@ -143,14 +150,136 @@ pub fn resolve_peer(peer: String) -> Result<SocketAddr> {
     Err(TransportError::InvalidAddress.into())
 }
 
+/// IP version of the address used to connect to a host name which resolves to both
+/// IPv4 and IPv6 addresses
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IpVersionPreference {
+    /// Use an IPv4 address if there is one
+    #[default]
+    Ipv4,
+    /// Use an IPv6 address if there is one
+    Ipv6,
+}
+
+impl IpVersionPreference {
+    /// Select the address with the preferred IP version, or the first one if there is none
+    fn select(&self, addresses: &[SocketAddr]) -> Option<SocketAddr> {
+        let preferred = addresses.iter().find(|address| match self {
+            IpVersionPreference::Ipv4 => address.is_ipv4(),
+            IpVersionPreference::Ipv6 => address.is_ipv6(),
+        });
+        preferred.or(addresses.first()).copied()
+    }
+}
+
+/// Resolve the given `host:port` peer when connecting to it, without blocking the runtime.
+///
+/// The host name is resolved again on every call, so that a connection follows the DNS
+/// records of a peer whose address changes. Fails with [`TransportError::DnsResolution`]
+/// if the host name can't be resolved before the timeout
+pub(crate) async fn resolve_peer_at_connect(
+    peer: &str,
+    preference: IpVersionPreference,
+    timeout: Duration,
+) -> Result<SocketAddr> {
+    if let Ok(socket_address) = parse_socket_addr(peer) {
+        return Ok(socket_address);
+    }
+
+    let addresses = match tokio::time::timeout(timeout, tokio::net::lookup_host(peer)).await {
+        Ok(Ok(addresses)) => addresses.collect::<Vec<_>>(),
+        // The peer is not a `host:port` string
+        Ok(Err(e)) if e.kind() == io::ErrorKind::InvalidInput => {
+            return Err(TransportError::InvalidAddress.into())
+        }
+        Ok(Err(e)) => {
+            debug!(%peer, err = %e, "Failed to resolve the host name");
+            return Err(TransportError::DnsResolution.into());
+        }
+        Err(_) => {
+            debug!(%peer, "Timed out resolving the host name");
+            return Err(TransportError::DnsResolution.into());
+        }
+    };
+
+    match preference.select(&addresses) {
+        Some(socket_address) => {
+            debug!(%peer, addr = %socket_address, "Resolved the host name");
+            Ok(socket_address)
+        }
+        None => Err(TransportError::DnsResolution.into()),
+    }
+}
+
 pub(super) fn parse_socket_addr(s: &str) -> Result<SocketAddr> {
     Ok(s.parse().map_err(|_| TransportError::InvalidAddress)?)
 }
 
+/// Replace the IP address of `bind_addr` with the current address of a network interface.
+/// An address of the same family as `bind_addr` is preferred if the interface has several
+#[cfg(unix)]
+pub(super) fn resolve_interface_addr(interface: &str, bind_addr: SocketAddr) -> Result<SocketAddr> {
+    use nix::sys::socket::SockaddrStorage;
+    use ockam_core::compat::net::{SocketAddrV4, SocketAddrV6};
+
+    fn with_port(address: &SockaddrStorage, port: u16) -> Option<SocketAddr> {
+        if let Some(address) = address.as_sockaddr_in() {
+            let address = SocketAddrV4::from(*address);
+            return Some(SocketAddrV4::new(*address.ip(), port).into());
+        }
+        address.as_sockaddr_in6().map(|address| {
+            let address = SocketAddrV6::from(*address);
+            // Keep the scope id which is needed to bind to a link-local address
+            SocketAddrV6::new(*address.ip(), port, address.flowinfo(), address.scope_id()).into()
+        })
+    }
+
+    let mut interface_found = false;
+    let mut addresses = vec![];
+    for interface_address in nix::ifaddrs::getifaddrs().map_err(|_| TransportError::GenericIo)? {
+        if interface_address.interface_name != interface {
+            continue;
+        }
+        interface_found = true;
+        if let Some(address) = interface_address.address.as_ref() {
+            addresses.extend(with_port(address, bind_addr.port()));
+        }
+    }
+
+    if !interface_found {
+        warn!("There is no network interface named {}", interface);
+        return Err(TransportError::InterfaceNotFound.into());
+    }
+
+    addresses
+        .iter()
+        .find(|address| address.is_ipv4() == bind_addr.is_ipv4())
+        .or_else(|| addresses.first())
+        .cloned()
+        .ok_or_else(|| {
+            warn!("The network interface {} has no IP address", interface);
+            TransportError::InterfaceWithoutAddress.into()
+        })
+}
+
+/// Network interfaces can only be resolved on unix systems
+#[cfg(not(unix))]
+pub(super) fn resolve_interface_addr(
+    interface: &str,
+    _bind_addr: SocketAddr,
+) -> Result<SocketAddr> {
+    warn!(
+        "Resolving the network interface {} is not supported on this platform",
+        interface
+    );
+    Err(TransportError::InterfaceNotFound.into())
+}
+
 #[cfg(test)]
 mod test {
-    use crate::transport::common::parse_socket_addr;
+    use crate::transport::common::{parse_socket_addr, IpVersionPreference};
     use core::fmt::Debug;
+    use ockam_core::compat::net::SocketAddr;
     use ockam_core::{Error, Result};
     use ockam_transport_core::TransportError;
 
@@ -201,4 +330,16 @@ mod test {
         let result = parse_socket_addr("127.0.0.1:8080");
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn ip_version_preference() {
+        let ipv4: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+        let ipv6: SocketAddr = "[::1]:4000".parse().unwrap();
+
+        assert_eq!(IpVersionPreference::Ipv4.select(&[ipv6, ipv4]), Some(ipv4));
+        assert_eq!(IpVersionPreference::Ipv6.select(&[ipv4, ipv6]), Some(ipv6));
+        // Fall back to the other IP version
+        assert_eq!(IpVersionPreference::Ipv6.select(&[ipv4]), Some(ipv4));
+        assert_eq!(IpVersionPreference::Ipv4.select(&[]), None);
+    }
 }
@@ -1,11 +1,20 @@
-use crate::transport::common::{resolve_peer, TcpConnection};
+use crate::transport::common::TcpConnection;
+use crate::transport::PoolRelease;
 use crate::workers::{Addresses, TcpRecvProcessor, TcpSendWorker};
 use crate::{TcpConnectionMode, TcpConnectionOptions, TcpTransport};
 use ockam_core::{Address, Result};
+use tracing::debug;
 
 impl TcpTransport {
     /// Establish an outgoing TCP connection.
     ///
+    /// The peer is a `host:port` string, where the host is an IP address or a host name
+    /// resolved when connecting, see [`TcpConnectionOptions::with_dns_timeout`]. A failed
+    /// resolution is reported as
+    /// [`TransportError::DnsResolution`](ockam_transport_core::TransportError::DnsResolution),
+    /// and a peer refusing the connection as
+    /// [`TransportError::PeerNotFound`](ockam_transport_core::TransportError::PeerNotFound).
+    ///
     /// ```rust
     /// use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions, TcpTransport};
     /// # use ockam_node::Context;
@@ -21,16 +30,18 @@ impl TcpTransport {
         peer: impl Into<String>,
         options: TcpConnectionOptions,
     ) -> Result<TcpConnection> {
-        // Resolve peer address
-        let socket = resolve_peer(peer.into())?;
-
-        let (read_half, write_half) = TcpSendWorker::connect(socket).await?;
+        // The workers are only started once the connection is established
+        let peer = peer.into();
+        let (socket, stream) = options.connect_stream(&peer).await?;
+        let (read_half, write_half) = options.split_stream(stream, &peer).await?;
 
         let mode = TcpConnectionMode::Outgoing;
         let addresses = Addresses::generate(mode);
 
         options.setup_flow_control(self.ctx.flow_controls(), &addresses);
         let flow_control_id = options.flow_control_id.clone();
+        let (rate_limit, read_rate_limit) = (options.rate_limit, options.read_rate_limit);
+        let message_extensions = options.message_extensions;
         let access_control = options.create_access_control(self.ctx.flow_controls());
 
         TcpSendWorker::start(
@@ -42,6 +53,8 @@ impl TcpTransport {
             mode,
             access_control.sender_incoming_access_control,
             &flow_control_id,
+            rate_limit,
+            message_extensions,
         )
         .await?;
 
@@ -54,6 +67,7 @@ impl TcpTransport {
             mode,
             &flow_control_id,
             access_control.receiver_outgoing_access_control,
+            read_rate_limit,
         )
         .await?;
 
@@ -66,8 +80,90 @@ impl TcpTransport {
         ))
     }
 
+    /// Establish an outgoing TCP connection, or reuse an open one.
+    ///
+    /// A connection created by this function is reused by the subsequent calls for the same
+    /// peer and the same [`TcpConnectionOptions`] consumers, so that a connection is never
+    /// shared with a user which trusts different flow controls.
+    /// Since a reused connection keeps its own [`FlowControlId`](ockam_core::flow_control::FlowControlId),
+    /// the one returned in [`TcpConnection`] must be used instead of the one in the options.
+    ///
+    /// Concurrent calls for the same peer and consumers wait for a single connection to be
+    /// established, and share it.
+    ///
+    /// Connections with a TLS configuration, a rate limit, an HTTP proxy, message extensions
+    /// or a custom [`FlowControlId`](ockam_core::flow_control::FlowControlId) are never shared:
+    /// for them this function falls back to [`connect`](Self::connect) and always
+    /// establishes a new connection.
+    ///
+    /// Each call must be balanced by a call to [`disconnect`](Self::disconnect). The connection
+    /// is closed when its last user disconnects, unless it can be kept idle according to
+    /// [`with_pool_size`](Self::with_pool_size).
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpConnectionOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let tcp = TcpTransport::create(&ctx).await?.with_pool_size(4);
+    /// let connection1 = tcp.connect_pooled("127.0.0.1:5000", TcpConnectionOptions::new()).await?;
+    /// let connection2 = tcp.connect_pooled("127.0.0.1:5000", TcpConnectionOptions::new()).await?;
+    /// assert_eq!(connection1.sender_address(), connection2.sender_address());
+    /// # Ok(()) }
+    /// ```
+    pub async fn connect_pooled(
+        &self,
+        peer: impl Into<String>,
+        options: TcpConnectionOptions,
+    ) -> Result<TcpConnection> {
+        let peer = peer.into();
+        #[cfg(feature = "tls")]
+        let shareable = options.tls.is_none();
+        #[cfg(not(feature = "tls"))]
+        let shareable = true;
+        if !shareable
+            || options.rate_limit.is_some()
+            || options.read_rate_limit.is_some()
+            || options.http_proxy.is_some()
+            || options.message_extensions
+            || options.custom_flow_control_id
+        {
+            debug!(%peer, "Connection options can't be shared, establishing a new connection");
+            return self.connect(peer, options).await;
+        }
+
+        let socket = options.resolve_peer(&peer).await?;
+
+        // Concurrent callers wait for the first one to establish the connection
+        let _dial = self.pool.lock_dial(socket, &options.consumer).await;
+
+        if let Some(connection) = self.pool.acquire(socket, &options.consumer, &self.registry) {
+            debug!(addr = %socket, "Reusing pooled connection {}", connection.sender_address());
+            return Ok(connection);
+        }
+
+        let consumers = options.consumer.clone();
+        let connection = self.connect(socket.to_string(), options).await?;
+        self.pool.add(connection.clone(), &consumers);
+
+        Ok(connection)
+    }
+
+    /// Number of users of a connection created with [`connect_pooled`](Self::connect_pooled)
+    /// which didn't disconnect yet, given its Sender `Address`
+    pub fn pooled_connection_users(&self, address: &Address) -> usize {
+        self.pool.references(address)
+    }
+
     /// Interrupt an active TCP connection given its Sender `Address`
+    ///
+    /// A connection created with [`connect_pooled`](Self::connect_pooled) is only
+    /// interrupted when its last user disconnects.
     pub async fn disconnect(&self, address: impl Into<Address>) -> Result<()> {
-        self.ctx.stop_worker(address.into()).await
+        let address = address.into();
+        match self.pool.release(&address) {
+            PoolRelease::InUse | PoolRelease::Idle => Ok(()),
+            PoolRelease::NotPooled | PoolRelease::Close => self.ctx.stop_worker(address).await,
+        }
     }
 }
@@ -4,6 +4,7 @@ use ockam_node::Context;
 use ockam_transport_core::Transport;
 use std::sync::Arc;
 
+use crate::transport::TcpConnectionPool;
 use crate::{TcpConnectionOptions, TcpRegistry, TcpTransport, TCP};
 
 impl TcpTransport {
@@ -21,6 +22,7 @@ impl TcpTransport {
         let tcp = Self {
             ctx: ctx.async_try_clone().await?,
             registry: TcpRegistry::default(),
+            pool: TcpConnectionPool::default(),
         };
         // make the TCP transport available in the list of supported transports for
         // later address resolution when socket addresses will need to be instantiated as TCP
@@ -39,6 +41,16 @@ impl TcpTransport {
     pub fn registry(&self) -> &TcpRegistry {
         &self.registry
     }
+
+    /// Set the maximum number of idle connections kept open by
+    /// [`connect_pooled`](Self::connect_pooled) once all their users disconnected.
+    /// Defaults to 0: a pooled connection is closed when its last user disconnects.
+    ///
+    /// The setting is shared by all the clones of this `TcpTransport`.
+    pub fn with_pool_size(self, pool_size: usize) -> Self {
+        self.pool.set_pool_size(pool_size);
+        self
+    }
 }
 
 #[async_trait]
@@ -1,7 +1,15 @@
-use crate::transport::common::{parse_socket_addr, TcpListener};
+use crate::transport::common::{parse_socket_addr, resolve_interface_addr, TcpListener};
 use crate::workers::TcpListenProcessor;
-use crate::{TcpListenerOptions, TcpTransport};
+use crate::{TcpConnectionMode, TcpListenerOptions, TcpSenderInfo, TcpTransport};
+use core::time::Duration;
+use ockam_core::flow_control::FlowControlId;
 use ockam_core::{Address, Result};
+use ockam_transport_core::TransportError;
+use tokio::time::Instant;
+use tracing::debug;
+
+/// How often accepted connections are checked while draining a listener
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 impl TcpTransport {
     /// Start listening to incoming connections on an existing transport
@@ -26,6 +34,10 @@ impl TcpTransport {
     ) -> Result<TcpListener> {
         let flow_control_id = options.flow_control_id.clone();
         let bind_addr = parse_socket_addr(bind_addr.as_ref())?;
+        let bind_addr = match &options.interface {
+            Some(interface) => resolve_interface_addr(interface, bind_addr)?,
+            None => bind_addr,
+        };
         // Could be different from the bind_addr, e.g., if binding to port 0\
         let (socket_addr, address) =
             TcpListenProcessor::start(&self.ctx, self.registry.clone(), bind_addr, options).await?;
@@ -37,4 +49,87 @@ impl TcpTransport {
     pub async fn stop_listener(&self, address: &Address) -> Result<()> {
         self.ctx.stop_processor(address.clone()).await
     }
+
+    /// Gracefully stop the TCP listener bound to the given socket address
+    ///
+    /// The listening socket is closed first, so that new connections are refused by the OS
+    /// and the port can be bound again, and the listener is removed from the registry.
+    /// Then, if a `drain_timeout` is given, the connections which were accepted by that
+    /// listener are given that much time to finish their exchanges and be closed.
+    /// The accepted connections which are still open after that are closed.
+    ///
+    /// ```rust
+    /// use core::time::Duration;
+    /// use ockam_transport_tcp::{TcpListenerOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let tcp = TcpTransport::create(&ctx).await?;
+    /// let listener = tcp.listen("127.0.0.1:8000", TcpListenerOptions::new()).await?;
+    /// tcp.stop_listener_gracefully(listener.socket_string(), Some(Duration::from_secs(5)))
+    ///     .await?;
+    /// # Ok(()) }
+    pub async fn stop_listener_gracefully(
+        &self,
+        socket_address: impl AsRef<str>,
+        drain_timeout: Option<Duration>,
+    ) -> Result<()> {
+        let socket_address = parse_socket_addr(socket_address.as_ref())?;
+        let listener = self
+            .registry
+            .get_all_listeners()
+            .into_iter()
+            .find(|l| l.socket_address() == socket_address)
+            .ok_or(TransportError::ListenerNotFound)?;
+
+        self.ctx.stop_processor(listener.address().clone()).await?;
+        self.registry.remove_listener_processor(listener.address());
+
+        if let Some(drain_timeout) = drain_timeout {
+            // Polling takes longer than the sum of its sleeps, only the deadline is reliable
+            let deadline = Instant::now() + drain_timeout;
+            while !self
+                .accepted_connections(listener.flow_control_id())
+                .is_empty()
+            {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                self.ctx
+                    .sleep(DRAIN_POLL_INTERVAL.min(deadline - now))
+                    .await;
+            }
+        }
+
+        for connection in self.accepted_connections(listener.flow_control_id()) {
+            debug!(
+                "Closing connection {} accepted by the stopped listener {}",
+                connection.socket_address(),
+                socket_address
+            );
+            let _ = self.ctx.stop_worker(connection.address().clone()).await;
+        }
+
+        Ok(())
+    }
+
+    /// Open connections which were accepted by the listener with the given [`FlowControlId`]
+    fn accepted_connections(&self, listener_flow_control_id: &FlowControlId) -> Vec<TcpSenderInfo> {
+        self.registry
+            .get_all_sender_workers()
+            .into_iter()
+            .filter(|sender| matches!(sender.mode(), TcpConnectionMode::Incoming))
+            .filter(|sender| {
+                self.ctx
+                    .flow_controls()
+                    .get_flow_control_with_producer(sender.receiver_address())
+                    .map(|producer| {
+                        producer.spawner_flow_control_id().as_ref()
+                            == Some(listener_flow_control_id)
+                    })
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
 }
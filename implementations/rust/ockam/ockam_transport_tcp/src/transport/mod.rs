@@ -2,12 +2,15 @@ pub(crate) mod common;
 mod connection;
 mod lifecycle;
 mod listener;
+mod pool;
 mod portals;
 
 pub use common::*;
 
 pub use crate::portal::options::*;
 
+pub(crate) use pool::*;
+
 use crate::TcpRegistry;
 use ockam_core::{async_trait, AsyncTryClone, Result};
 use ockam_node::{Context, HasContext};
@@ -27,6 +30,10 @@ use ockam_node::{Context, HasContext};
 /// This step is optional because the underlying TcpRouter is capable of lazily
 /// establishing a connection upon arrival of an initial message.
 ///
+/// Applications connecting repeatedly to the same peers can use
+/// [`tcp.connect_pooled()`](crate::TcpTransport::connect_pooled) instead, which shares
+/// a single connection between all the users with the same [`TcpConnectionOptions`](crate::TcpConnectionOptions).
+///
 /// ```rust
 /// use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions, TcpTransport};
 /// # use ockam_node::Context;
@@ -55,6 +62,7 @@ use ockam_node::{Context, HasContext};
 pub struct TcpTransport {
     ctx: Context,
     registry: TcpRegistry,
+    pool: TcpConnectionPool,
 }
 
 /// This trait adds a `create_tcp_transport` method to any struct returning a Context.
@@ -0,0 +1,204 @@
+use crate::{TcpConnection, TcpRegistry};
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::net::SocketAddr;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::flow_control::FlowControlId;
+use ockam_core::Address;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// A connection shared by several users of [`TcpTransport::connect_pooled`](crate::TcpTransport::connect_pooled)
+struct PooledConnection {
+    connection: TcpConnection,
+    /// Sorted [`FlowControlId`]s the connection's sender is a consumer of
+    consumers: Vec<FlowControlId>,
+    /// Number of users which didn't disconnect yet, 0 if the connection is idle
+    references: usize,
+    /// The sender of the connection was seen in the [`TcpRegistry`]. It registers itself
+    /// once started, which may happen after the connection was added to the pool
+    registered: bool,
+}
+
+impl PooledConnection {
+    fn is_idle(&self) -> bool {
+        self.references == 0
+    }
+}
+
+/// Peer and sorted consumers of a pooled connection
+type DialKey = (SocketAddr, Vec<FlowControlId>);
+
+#[derive(Default)]
+struct ConnectionPoolState {
+    connections: Vec<PooledConnection>,
+    /// Maximum number of idle connections kept open
+    pool_size: usize,
+    /// Connections being established, the callers waiting for one of them reuse it
+    /// instead of establishing their own
+    dials: BTreeMap<DialKey, Arc<AsyncMutex<()>>>,
+}
+
+/// Exclusive right to establish a pooled connection, see [`TcpConnectionPool::lock_dial`]
+pub(crate) struct DialGuard {
+    pool: TcpConnectionPool,
+    key: DialKey,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl Drop for DialGuard {
+    fn drop(&mut self) {
+        let mut state = self.pool.state.lock().unwrap();
+        // Only the map and this guard hold the slot if nobody else is waiting for it
+        let idle = state
+            .dials
+            .get(&self.key)
+            .map(|slot| Arc::strong_count(slot) <= 2)
+            .unwrap_or(false);
+        if idle {
+            state.dials.remove(&self.key);
+        }
+    }
+}
+
+/// Outcome of releasing a reference to a pooled connection
+pub(crate) enum PoolRelease {
+    /// The connection was not created by the pool
+    NotPooled,
+    /// The connection is still used by someone else
+    InUse,
+    /// The connection is not used anymore, but is kept open for later reuse
+    Idle,
+    /// The connection is not used anymore and must be closed
+    Close,
+}
+
+/// Connections created with [`TcpTransport::connect_pooled`](crate::TcpTransport::connect_pooled)
+#[derive(Default, Clone)]
+pub(crate) struct TcpConnectionPool {
+    state: Arc<Mutex<ConnectionPoolState>>,
+}
+
+impl TcpConnectionPool {
+    pub(crate) fn set_pool_size(&self, pool_size: usize) {
+        self.state.lock().unwrap().pool_size = pool_size;
+    }
+
+    /// Return an open connection to the given socket address, with the same trust options,
+    /// and increment its reference count
+    pub(crate) fn acquire(
+        &self,
+        socket_address: SocketAddr,
+        consumers: &[FlowControlId],
+        registry: &TcpRegistry,
+    ) -> Option<TcpConnection> {
+        let consumers = sorted(consumers);
+        let senders = registry.get_all_sender_workers();
+
+        let mut state = self.state.lock().unwrap();
+        // Forget about the connections which were closed
+        state.connections.retain_mut(|c| {
+            let registered = senders
+                .iter()
+                .any(|s| s.address() == c.connection.sender_address());
+            let closed = c.registered && !registered;
+            c.registered |= registered;
+            !closed
+        });
+
+        let pooled = state.connections.iter_mut().find(|c| {
+            c.connection.socket_address() == &socket_address && c.consumers == consumers
+        })?;
+        pooled.references += 1;
+
+        Some(pooled.connection.clone())
+    }
+
+    /// Wait until no other caller is establishing a connection to the given socket address,
+    /// with the same trust options. The connection established while holding the returned
+    /// guard must be added to the pool before it's dropped, so that the next callers reuse it
+    pub(crate) async fn lock_dial(
+        &self,
+        socket_address: SocketAddr,
+        consumers: &[FlowControlId],
+    ) -> DialGuard {
+        let key = (socket_address, sorted(consumers));
+        let slot = self
+            .state
+            .lock()
+            .unwrap()
+            .dials
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let guard = slot.lock_owned().await;
+
+        DialGuard {
+            pool: self.clone(),
+            key,
+            _guard: guard,
+        }
+    }
+
+    /// Add a newly created connection, with one reference
+    pub(crate) fn add(&self, connection: TcpConnection, consumers: &[FlowControlId]) {
+        self.state
+            .lock()
+            .unwrap()
+            .connections
+            .push(PooledConnection {
+                connection,
+                consumers: sorted(consumers),
+                references: 1,
+                registered: false,
+            });
+    }
+
+    /// Decrement the reference count of a connection
+    pub(crate) fn release(&self, sender_address: &Address) -> PoolRelease {
+        let mut state = self.state.lock().unwrap();
+        let pool_size = state.pool_size;
+        let idle = state.connections.iter().filter(|c| c.is_idle()).count();
+
+        let index = match state
+            .connections
+            .iter()
+            .position(|c| c.connection.sender_address() == sender_address)
+        {
+            Some(index) => index,
+            None => return PoolRelease::NotPooled,
+        };
+
+        let pooled = &mut state.connections[index];
+        if pooled.references > 1 {
+            pooled.references -= 1;
+            return PoolRelease::InUse;
+        }
+
+        // Disconnecting an idle connection closes it
+        if !pooled.is_idle() && idle < pool_size {
+            pooled.references = 0;
+            return PoolRelease::Idle;
+        }
+
+        state.connections.remove(index);
+        PoolRelease::Close
+    }
+
+    /// Number of users of a pooled connection
+    pub(crate) fn references(&self, sender_address: &Address) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .connections
+            .iter()
+            .find(|c| c.connection.sender_address() == sender_address)
+            .map(|c| c.references)
+            .unwrap_or(0)
+    }
+}
+
+fn sorted(consumers: &[FlowControlId]) -> Vec<FlowControlId> {
+    let mut consumers = consumers.to_vec();
+    consumers.sort();
+    consumers.dedup();
+    consumers
+}
@@ -1,6 +1,9 @@
 use crate::portal::TcpInletListenProcessor;
-use crate::transport::common::{parse_socket_addr, resolve_peer};
-use crate::{portal::TcpOutletListenWorker, TcpInletOptions, TcpOutletOptions, TcpTransport};
+use crate::transport::common::{parse_socket_addr, resolve_peer_at_connect};
+use crate::{
+    portal::TcpOutletListenWorker, IpVersionPreference, TcpInletOptions, TcpOutletOptions,
+    TcpTransport, DEFAULT_DNS_TIMEOUT,
+};
 use ockam_core::compat::net::SocketAddr;
 use ockam_core::{Address, Result, Route};
 
@@ -10,6 +13,10 @@ impl TcpTransport {
     /// Messages sent to Inlet from Outlet (using return route) will be streamed to Tcp connection.
     /// Pair of corresponding Inlet and Outlet is called Portal.
     ///
+    /// Every connection accepted by the Inlet is piped to its own connection from the Outlet
+    /// to the target, so concurrent connections can share the same route, for example a
+    /// secure channel. When either of these connections is closed, the other one is closed too.
+    ///
     /// ```rust
     /// use ockam_transport_tcp::{TcpInletOptions, TcpTransport};
     /// # use ockam_node::Context;
@@ -82,8 +89,13 @@ impl TcpTransport {
         peer: impl Into<String>,
         options: TcpOutletOptions,
     ) -> Result<()> {
-        // Resolve peer address
-        let peer_addr = resolve_peer(peer.into())?;
+        // Resolve peer address, without blocking the runtime
+        let peer_addr = resolve_peer_at_connect(
+            &peer.into(),
+            IpVersionPreference::default(),
+            DEFAULT_DNS_TIMEOUT,
+        )
+        .await?;
         TcpOutletListenWorker::start(
             &self.ctx,
             self.registry.clone(),
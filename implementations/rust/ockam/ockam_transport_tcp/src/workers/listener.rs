@@ -1,11 +1,14 @@
 use crate::workers::{Addresses, TcpRecvProcessor};
 use crate::{TcpConnectionMode, TcpListenerInfo, TcpListenerOptions, TcpRegistry, TcpSendWorker};
+use ockam_core::compat::sync::Arc;
+#[cfg(feature = "tls")]
+use ockam_core::DenyAll;
 use ockam_core::{async_trait, compat::net::SocketAddr};
 use ockam_core::{Address, Processor, Result};
-use ockam_node::Context;
+use ockam_node::{Context, ProcessorBuilder};
 use ockam_transport_core::TransportError;
-use tokio::net::TcpListener;
-use tracing::debug;
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, warn};
 
 /// A TCP Listen processor
 ///
@@ -16,7 +19,7 @@ pub(crate) struct TcpListenProcessor {
     registry: TcpRegistry,
     inner: TcpListener,
     socket_address: SocketAddr,
-    options: TcpListenerOptions,
+    options: Arc<TcpListenerOptions>,
 }
 
 impl TcpListenProcessor {
@@ -39,10 +42,14 @@ impl TcpListenProcessor {
             registry,
             inner,
             socket_address: saddr,
-            options,
+            options: Arc::new(options),
         };
 
-        ctx.start_processor(address.clone(), processor).await?;
+        ProcessorBuilder::new(processor)
+            .with_address(address.clone())
+            .with_kind(crate::TCP_LISTENER_KIND)
+            .start(ctx)
+            .await?;
 
         Ok((saddr, address))
     }
@@ -77,44 +84,113 @@ impl Processor for TcpListenProcessor {
         let (stream, peer) = self.inner.accept().await.map_err(TransportError::from)?;
         debug!("TCP connection accepted");
 
+        #[cfg(feature = "tracing_spans")]
+        let span = tracing::info_span!(
+            "tcp_accept",
+            listener = %ctx.address(),
+            %peer,
+            session_id = tracing::field::Empty,
+        );
+
+        // A TLS handshake waits for the peer, it's run in its own task so that a slow
+        // or unresponsive peer doesn't delay the next connections
+        #[cfg(feature = "tls")]
+        if self.options.tls.is_some() {
+            let child_ctx = ctx
+                .new_detached(
+                    Address::random_tagged("TcpListenProcessor.accept"),
+                    DenyAll,
+                    DenyAll,
+                )
+                .await?;
+            let registry = self.registry.clone();
+            let options = self.options.clone();
+            let accepted = async move {
+                if let Err(err) = Self::accept(&child_ctx, registry, options, stream, peer).await {
+                    warn!(%peer, "Failed to start an incoming TCP connection: {}", err);
+                }
+            };
+            #[cfg(feature = "tracing_spans")]
+            let accepted = tracing::Instrument::instrument(accepted, span);
+            ctx.runtime().spawn(accepted);
+
+            return Ok(true);
+        }
+
+        let accepted = Self::accept(
+            ctx,
+            self.registry.clone(),
+            self.options.clone(),
+            stream,
+            peer,
+        );
+        #[cfg(feature = "tracing_spans")]
+        let accepted = tracing::Instrument::instrument(accepted, span);
+        accepted.await?;
+
+        Ok(true)
+    }
+}
+
+impl TcpListenProcessor {
+    /// Start the workers of an accepted connection
+    async fn accept(
+        ctx: &Context,
+        registry: TcpRegistry,
+        options: Arc<TcpListenerOptions>,
+        stream: TcpStream,
+        peer: SocketAddr,
+    ) -> Result<()> {
+        let (read_half, write_half) = match options.split_stream(stream).await {
+            Ok(halves) => halves,
+            Err(err) => {
+                warn!(%peer, "Closing incoming TCP connection: {}", err);
+                return Ok(());
+            }
+        };
+
         let mode = TcpConnectionMode::Incoming;
         let addresses = Addresses::generate(mode);
 
-        let receiver_flow_control_id = self
-            .options
-            .setup_flow_control_for_connection(ctx.flow_controls(), &addresses);
-        let access_control = self
-            .options
-            .create_access_control(ctx.flow_controls(), receiver_flow_control_id.clone());
-
-        let (read_half, write_half) = stream.into_split();
+        let receiver_flow_control_id =
+            options.setup_flow_control_for_connection(ctx.flow_controls(), &addresses);
+        let access_control =
+            options.create_access_control(ctx.flow_controls(), receiver_flow_control_id.clone());
+        #[cfg(feature = "tracing_spans")]
+        tracing::Span::current().record(
+            "session_id",
+            tracing::field::display(&receiver_flow_control_id),
+        );
 
         // Worker to receive messages from the Node and send them over the wire
         TcpSendWorker::start(
             ctx,
-            self.registry.clone(),
+            registry.clone(),
             write_half,
             &addresses,
             peer,
             mode,
             access_control.sender_incoming_access_control,
             &receiver_flow_control_id,
+            options.rate_limit,
+            options.message_extensions,
         )
         .await?;
 
         // Processor to receive messages over the wire and forward them to the node
         TcpRecvProcessor::start(
             ctx,
-            self.registry.clone(),
+            registry,
             read_half,
             &addresses,
             peer,
             mode,
             &receiver_flow_control_id,
             access_control.receiver_outgoing_access_control,
+            options.read_rate_limit,
         )
         .await?;
 
-        Ok(true)
+        Ok(())
     }
 }
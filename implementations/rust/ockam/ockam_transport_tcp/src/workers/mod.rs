@@ -2,8 +2,12 @@ mod addresses;
 mod listener;
 mod receiver;
 mod sender;
+mod stream;
+mod throttle;
 
 pub(crate) use addresses::*;
 pub(crate) use listener::*;
 pub(crate) use receiver::*;
 pub(crate) use sender::*;
+pub(crate) use stream::*;
+pub(crate) use throttle::*;
@@ -1,5 +1,7 @@
-use crate::workers::Addresses;
+use crate::workers::{Addresses, TcpReadHalf, Throttle, MAX_MESSAGE_SIZE};
 use crate::{TcpConnectionMode, TcpReceiverInfo, TcpRegistry, TcpSendWorkerMsg};
+use core::panic::AssertUnwindSafe;
+use futures::FutureExt;
 use ockam_core::compat::net::SocketAddr;
 use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::FlowControlId;
@@ -9,7 +11,7 @@ use ockam_core::{
 use ockam_core::{Decodable, LocalMessage, Processor, Result, TransportMessage};
 use ockam_node::{Context, ProcessorBuilder};
 use ockam_transport_core::TransportError;
-use tokio::{io::AsyncReadExt, net::tcp::OwnedReadHalf};
+use tokio::io::AsyncReadExt;
 use tracing::{error, info, trace};
 
 /// A TCP receiving message processor
@@ -22,22 +24,24 @@ use tracing::{error, info, trace};
 /// the node message system.
 pub(crate) struct TcpRecvProcessor {
     registry: TcpRegistry,
-    read_half: OwnedReadHalf,
+    read_half: TcpReadHalf,
     socket_address: SocketAddr,
     addresses: Addresses,
     mode: TcpConnectionMode,
     flow_control_id: FlowControlId,
+    throttle: Option<Throttle>,
 }
 
 impl TcpRecvProcessor {
     /// Create a new `TcpRecvProcessor`
     fn new(
         registry: TcpRegistry,
-        read_half: OwnedReadHalf,
+        read_half: TcpReadHalf,
         socket_address: SocketAddr,
         addresses: Addresses,
         mode: TcpConnectionMode,
         flow_control_id: FlowControlId,
+        read_rate_limit: Option<u64>,
     ) -> Self {
         Self {
             registry,
@@ -46,6 +50,7 @@ impl TcpRecvProcessor {
             addresses,
             mode,
             flow_control_id,
+            throttle: read_rate_limit.map(Throttle::new),
         }
     }
 
@@ -53,12 +58,13 @@ impl TcpRecvProcessor {
     pub async fn start(
         ctx: &Context,
         registry: TcpRegistry,
-        read_half: OwnedReadHalf,
+        read_half: TcpReadHalf,
         addresses: &Addresses,
         socket_address: SocketAddr,
         mode: TcpConnectionMode,
         flow_control_id: &FlowControlId,
         receiver_outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+        read_rate_limit: Option<u64>,
     ) -> Result<()> {
         let receiver = TcpRecvProcessor::new(
             registry,
@@ -67,6 +73,7 @@ impl TcpRecvProcessor {
             addresses.clone(),
             mode,
             flow_control_id.clone(),
+            read_rate_limit,
         );
 
         let mailbox = Mailbox::new(
@@ -83,6 +90,7 @@ impl TcpRecvProcessor {
         );
         ProcessorBuilder::new(receiver)
             .with_mailboxes(Mailboxes::new(mailbox, vec![internal]))
+            .with_kind(crate::TCP_RECEIVER_KIND)
             .start(ctx)
             .await?;
 
@@ -114,6 +122,33 @@ impl Processor for TcpRecvProcessor {
         Ok(())
     }
 
+    /// Get the next message from the connection.
+    ///
+    /// A panic would leave the sender and the registry entries of a dead connection behind,
+    /// the whole connection is stopped instead
+    async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
+        match AssertUnwindSafe(self.receive(ctx)).catch_unwind().await {
+            Ok(result) => result,
+            Err(_) => {
+                error!(
+                    "Receiver of the connection to peer '{}' panicked; dropping stream",
+                    self.socket_address
+                );
+
+                ctx.send_from_address(
+                    self.addresses.sender_internal_address().clone(),
+                    TcpSendWorkerMsg::ConnectionClosed,
+                    self.addresses.receiver_internal_address().clone(),
+                )
+                .await?;
+
+                Ok(false)
+            }
+        }
+    }
+}
+
+impl TcpRecvProcessor {
     /// Get the next message from the connection if there are any
     /// available and forward it to the next hop in the route.
     ///
@@ -125,7 +160,7 @@ impl Processor for TcpRecvProcessor {
     ///    Context to avoid spawning a zombie task.
     /// 3. We must also stop the TcpReceive loop when the worker gets
     ///    killed by the user or node.
-    async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
+    async fn receive(&mut self, ctx: &mut Context) -> Result<bool> {
         // Run in a loop until TcpWorkerPair::stop() is called
         // First read a message length header...
         let len = match self.read_half.read_u16().await {
@@ -150,6 +185,28 @@ impl Processor for TcpRecvProcessor {
 
         trace!("Received message header for {} bytes", len);
 
+        // The 16-bit header can't announce more than MAX_MESSAGE_SIZE bytes, so the maximum
+        // message size of the node only rejects frames when it is configured below that.
+        // The rest of the stream can't be parsed once a message is skipped
+        let max_frame_size = ctx.max_message_size().min(MAX_MESSAGE_SIZE);
+        if len as usize > max_frame_size {
+            error!(
+                "Peer '{}' sent a message of {} bytes, exceeding the maximum message size of {} bytes; dropping stream",
+                self.socket_address,
+                len,
+                max_frame_size
+            );
+
+            ctx.send_from_address(
+                self.addresses.sender_internal_address().clone(),
+                TcpSendWorkerMsg::ConnectionClosed,
+                self.addresses.receiver_internal_address().clone(),
+            )
+            .await?;
+
+            return Ok(false);
+        }
+
         // Allocate a buffer of that size
         let mut buf = vec![0; len as usize];
 
@@ -162,8 +219,19 @@ impl Processor for TcpRecvProcessor {
             }
         }
 
+        // Count the length header as well
+        ctx.record_metrics(|m| {
+            m.transport_bytes_received(self.addresses.sender_address(), buf.len() + 2)
+        });
+        if let Some(throttle) = self.throttle.as_mut() {
+            throttle.acquire(buf.len() + 2).await;
+        }
+
         // Deserialize the message now
-        let mut msg = TransportMessage::decode(&buf).map_err(|_| TransportError::RecvBadMessage)?;
+        let msg = TransportMessage::decode(&buf).map_err(|_| TransportError::RecvBadMessage)?;
+        let (mut msg, extensions) = msg
+            .take_extensions()
+            .map_err(|_| TransportError::RecvBadMessage)?;
 
         // Heartbeat message
         if msg.onward_route.next().is_err() {
@@ -180,12 +248,13 @@ impl Processor for TcpRecvProcessor {
         trace!("Message onward route: {}", msg.onward_route);
         trace!("Message return route: {}", msg.return_route);
 
+        // The receiver is recorded as a hop of a traced message
+        let mut local_message = LocalMessage::new(msg, extensions);
+        local_message.trace_hop(self.addresses.receiver_address())?;
+
         // Forward the message to the next hop in the route
-        ctx.forward_from_address(
-            LocalMessage::new(msg, vec![]),
-            self.addresses.receiver_address().clone(),
-        )
-        .await?;
+        ctx.forward_from_address(local_message, self.addresses.receiver_address().clone())
+            .await?;
 
         Ok(true)
     }
@@ -1,7 +1,9 @@
-use crate::workers::Addresses;
+use crate::workers::{Addresses, TcpWriteHalf, Throttle};
 use crate::{TcpConnectionMode, TcpRegistry, TcpSenderInfo};
 use cfg_if::cfg_if;
+use core::panic::AssertUnwindSafe;
 use core::time::Duration;
+use futures::FutureExt;
 use ockam_core::flow_control::FlowControlId;
 use ockam_core::{
     async_trait,
@@ -17,10 +19,13 @@ use ockam_transport_core::TransportError;
 use serde::{Deserialize, Serialize};
 use socket2::{SockRef, TcpKeepalive};
 use tokio::io::AsyncWriteExt;
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::TcpStream;
 use tracing::{debug, info, trace, warn};
 
+/// Maximum size of an encoded message sent over a TCP connection, limited by its
+/// 16-bit length prefix
+pub(crate) const MAX_MESSAGE_SIZE: usize = u16::MAX as usize;
+
 #[derive(Serialize, Deserialize, Message, Clone)]
 pub(crate) enum TcpSendWorkerMsg {
     ConnectionClosed,
@@ -36,23 +41,27 @@ pub(crate) enum TcpSendWorkerMsg {
 /// to dispatch to a remote peer.
 pub(crate) struct TcpSendWorker {
     registry: TcpRegistry,
-    write_half: OwnedWriteHalf,
+    write_half: TcpWriteHalf,
     socket_address: SocketAddr,
     addresses: Addresses,
     mode: TcpConnectionMode,
     receiver_flow_control_id: FlowControlId,
     rx_should_be_stopped: bool,
+    throttle: Option<Throttle>,
+    message_extensions: bool,
 }
 
 impl TcpSendWorker {
     /// Create a new `TcpSendWorker`
     fn new(
         registry: TcpRegistry,
-        write_half: OwnedWriteHalf,
+        write_half: TcpWriteHalf,
         socket_address: SocketAddr,
         addresses: Addresses,
         mode: TcpConnectionMode,
         receiver_flow_control_id: FlowControlId,
+        rate_limit: Option<u64>,
+        message_extensions: bool,
     ) -> Self {
         Self {
             registry,
@@ -62,6 +71,8 @@ impl TcpSendWorker {
             receiver_flow_control_id,
             mode,
             rx_should_be_stopped: true,
+            throttle: rate_limit.map(Throttle::new),
+            message_extensions,
         }
     }
 }
@@ -73,12 +84,14 @@ impl TcpSendWorker {
     pub(crate) async fn start(
         ctx: &Context,
         registry: TcpRegistry,
-        write_half: OwnedWriteHalf,
+        write_half: TcpWriteHalf,
         addresses: &Addresses,
         socket_address: SocketAddr,
         mode: TcpConnectionMode,
         sender_incoming_access_control: Arc<dyn IncomingAccessControl>,
         receiver_flow_control_id: &FlowControlId,
+        rate_limit: Option<u64>,
+        message_extensions: bool,
     ) -> Result<()> {
         trace!("Creating new TCP worker pair");
         let sender_worker = Self::new(
@@ -88,6 +101,8 @@ impl TcpSendWorker {
             addresses.clone(),
             mode,
             receiver_flow_control_id.clone(),
+            rate_limit,
+            message_extensions,
         );
 
         let main_mailbox = Mailbox::new(
@@ -106,6 +121,8 @@ impl TcpSendWorker {
 
         WorkerBuilder::new(sender_worker)
             .with_mailboxes(Mailboxes::new(main_mailbox, vec![internal_mailbox]))
+            .with_max_message_size(MAX_MESSAGE_SIZE)
+            .with_kind(crate::TCP_SENDER_KIND)
             .start(ctx)
             .await?;
 
@@ -121,9 +138,21 @@ impl TcpSendWorker {
 
     pub(crate) async fn connect(
         socket_address: SocketAddr,
-    ) -> Result<(OwnedReadHalf, OwnedWriteHalf)> {
+        timeout: Option<Duration>,
+    ) -> Result<TcpStream> {
         debug!(addr = %socket_address, "Connecting");
-        let connection = match TcpStream::connect(socket_address).await {
+        let connect = TcpStream::connect(socket_address);
+        let connection = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, connect).await {
+                Ok(connection) => connection,
+                Err(_) => {
+                    debug!(addr = %socket_address, "Timed out connecting");
+                    return Err(TransportError::ConnectionTimeout.into());
+                }
+            },
+            None => connect.await,
+        };
+        let connection = match connection {
             Ok(c) => {
                 debug!(addr = %socket_address, "Connected");
                 c
@@ -147,7 +176,7 @@ impl TcpSendWorker {
         let socket = SockRef::from(&connection);
         socket.set_tcp_keepalive(&keepalive).unwrap();
 
-        Ok(connection.into_split())
+        Ok(connection)
     }
 }
 
@@ -183,13 +212,32 @@ impl Worker for TcpSendWorker {
         Ok(())
     }
 
-    // TcpSendWorker will receive messages from the TcpRouter to send
-    // across the TcpStream to our friend
+    /// Send a message across the connection.
+    ///
+    /// A panic would leave the receiver and the registry entries of a dead connection behind,
+    /// the whole connection is stopped instead
     async fn handle_message(
         &mut self,
         ctx: &mut Context,
         msg: Routed<Self::Message>,
     ) -> Result<()> {
+        match AssertUnwindSafe(self.send(ctx, msg)).catch_unwind().await {
+            Ok(result) => result,
+            Err(_) => {
+                error!(
+                    "Sender of the connection to peer {} panicked, stopping it",
+                    self.socket_address
+                );
+                self.stop(ctx).await
+            }
+        }
+    }
+}
+
+impl TcpSendWorker {
+    // TcpSendWorker will receive messages from the TcpRouter to send
+    // across the TcpStream to our friend
+    async fn send(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
         let recipient = msg.msg_addr();
         if &recipient == self.addresses.sender_internal_address() {
             let msg = TcpSendWorkerMsg::decode(msg.payload())?;
@@ -209,19 +257,49 @@ impl Worker for TcpSendWorker {
                 }
             }
         } else {
+            // The extensions, like the route trace, are only sent to peers which support them
+            let extensions = if self.message_extensions {
+                msg.local_message().extensions()
+            } else {
+                vec![]
+            };
             let mut msg = msg.into_transport_message();
             // Remove our own address from the route so the other end
             // knows what to do with the incoming message
             msg.onward_route.step()?;
-            // Create a message buffer with prepended length
-            let msg = prepare_message(msg)?;
+            let msg = msg.with_extensions(extensions)?;
+            // Create a message buffer with prepended length. Messages which don't fit are
+            // rejected when they are sent, unless the extensions made them larger
+            let msg = match prepare_message(msg) {
+                Ok(msg) => msg,
+                Err(err) => {
+                    warn!(
+                        "Dropping a message to peer {}: {}",
+                        self.socket_address, err
+                    );
+                    return Ok(());
+                }
+            };
+
+            if let Some(throttle) = self.throttle.as_mut() {
+                throttle.acquire(msg.len()).await;
+            }
 
-            if self.write_half.write_all(msg.as_slice()).await.is_err() {
+            // A TLS stream may buffer the written data until it's flushed
+            let written = match self.write_half.write_all(msg.as_slice()).await {
+                Ok(()) => self.write_half.flush().await,
+                Err(err) => Err(err),
+            };
+            if written.is_err() {
                 warn!("Failed to send message to peer {}", self.socket_address);
                 self.stop(ctx).await?;
 
                 return Ok(());
             }
+
+            ctx.record_metrics(|m| {
+                m.transport_bytes_sent(self.addresses.sender_address(), msg.len())
+            });
         }
 
         Ok(())
@@ -232,9 +310,12 @@ impl Worker for TcpSendWorker {
 /// `TransportMessage`'s payload
 ///
 /// The length-prefix is encoded as a big-endian 16-bit unsigned
-/// integer.
+/// integer, messages which don't fit are rejected.
 fn prepare_message(msg: TransportMessage) -> Result<Vec<u8>> {
     let mut msg_buf = msg.encode().map_err(|_| TransportError::SendBadMessage)?;
+    if msg_buf.len() > MAX_MESSAGE_SIZE {
+        return Err(TransportError::Capacity.into());
+    }
 
     // Create a buffer that includes the message length in big endian
     let mut len = (msg_buf.len() as u16).to_be_bytes().to_vec();
@@ -251,3 +332,141 @@ fn prepare_message(msg: TransportMessage) -> Result<Vec<u8>> {
 
     Ok(msg_buf)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workers::{TcpReadHalf, TcpRecvProcessor};
+    use core::pin::Pin;
+    use core::task::{self, Poll};
+    use ockam_core::flow_control::FlowControls;
+    use ockam_core::{route, AllowAll};
+    use std::io;
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    struct PanickingStream;
+
+    impl AsyncRead for PanickingStream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut task::Context<'_>,
+            _buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            panic!("read failure")
+        }
+    }
+
+    impl AsyncWrite for PanickingStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut task::Context<'_>,
+            _buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            panic!("write failure")
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: Pin<&mut Self>,
+            _cx: &mut task::Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    async fn start_pair(
+        ctx: &Context,
+        registry: &TcpRegistry,
+        read_half: TcpReadHalf,
+        write_half: TcpWriteHalf,
+    ) -> Result<Addresses> {
+        let mode = TcpConnectionMode::Outgoing;
+        let addresses = Addresses::generate(mode);
+        let socket_address: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+        let flow_control_id = FlowControls::generate_flow_control_id();
+
+        TcpSendWorker::start(
+            ctx,
+            registry.clone(),
+            write_half,
+            &addresses,
+            socket_address,
+            mode,
+            Arc::new(AllowAll),
+            &flow_control_id,
+            None,
+            false,
+        )
+        .await?;
+        TcpRecvProcessor::start(
+            ctx,
+            registry.clone(),
+            read_half,
+            &addresses,
+            socket_address,
+            mode,
+            &flow_control_id,
+            Arc::new(AllowAll),
+            None,
+        )
+        .await?;
+
+        Ok(addresses)
+    }
+
+    async fn wait_until_stopped(ctx: &Context, registry: &TcpRegistry, addresses: &Addresses) {
+        for _ in 0..500 {
+            let workers = ctx.list_workers().await.unwrap();
+            if registry.get_all_sender_workers().is_empty()
+                && registry.get_all_receiver_processors().is_empty()
+                && !workers.contains(addresses.sender_address())
+            {
+                return;
+            }
+            ctx.sleep(Duration::from_millis(10)).await;
+        }
+        panic!("the halves of the connection are still running");
+    }
+
+    #[ockam_macros::test]
+    async fn panicking_half_should_stop_the_connection(ctx: &mut Context) -> Result<()> {
+        let registry = TcpRegistry::default();
+
+        // The sender panics when it writes a message
+        let (stream, _peer) = tokio::io::duplex(64);
+        let (read_half, _) = tokio::io::split(stream);
+        let addresses = start_pair(
+            ctx,
+            &registry,
+            Box::new(read_half),
+            Box::new(PanickingStream),
+        )
+        .await?;
+        assert_eq!(registry.get_all_sender_workers().len(), 1);
+        assert_eq!(registry.get_all_receiver_processors().len(), 1);
+
+        ctx.send(
+            route![addresses.sender_address().clone(), "peer"],
+            "Hello".to_string(),
+        )
+        .await?;
+        wait_until_stopped(ctx, &registry, &addresses).await;
+
+        // The receiver panics when it reads from the connection
+        let (stream, _peer) = tokio::io::duplex(64);
+        let (_, write_half) = tokio::io::split(stream);
+        let addresses = start_pair(
+            ctx,
+            &registry,
+            Box::new(PanickingStream),
+            Box::new(write_half),
+        )
+        .await?;
+        wait_until_stopped(ctx, &registry, &addresses).await;
+
+        ctx.stop().await
+    }
+}
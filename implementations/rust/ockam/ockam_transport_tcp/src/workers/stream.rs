@@ -0,0 +1,14 @@
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+
+/// Reading half of a connection: a TCP stream, or a TLS stream on top of it
+pub(crate) type TcpReadHalf = Box<dyn AsyncRead + Send + Unpin>;
+
+/// Writing half of a connection: a TCP stream, or a TLS stream on top of it
+pub(crate) type TcpWriteHalf = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Split a TCP stream used without TLS
+pub(crate) fn split_tcp_stream(stream: TcpStream) -> (TcpReadHalf, TcpWriteHalf) {
+    let (read_half, write_half) = stream.into_split();
+    (Box::new(read_half), Box::new(write_half))
+}
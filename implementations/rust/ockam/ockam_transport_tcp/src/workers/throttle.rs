@@ -0,0 +1,60 @@
+use core::time::Duration;
+use tokio::time::{sleep, Instant};
+
+/// Paces the bytes written to, or read from, a connection to a maximum rate
+///
+/// Up to one second worth of bytes can be transferred in a burst after an idle period.
+/// A transfer exceeding the available budget is never split or blocked: it goes through,
+/// and the next transfer waits until the budget is paid back. That way a message larger
+/// than the rate can still be transferred.
+pub(crate) struct Throttle {
+    bytes_per_sec: f64,
+    budget: f64,
+    updated_at: Instant,
+}
+
+impl Throttle {
+    pub(crate) fn new(bytes_per_sec: u64) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1) as f64;
+        Self {
+            bytes_per_sec,
+            budget: bytes_per_sec,
+            updated_at: Instant::now(),
+        }
+    }
+
+    /// Wait until `len` bytes can be transferred
+    pub(crate) async fn acquire(&mut self, len: usize) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.budget = (self.budget + elapsed * self.bytes_per_sec).min(self.bytes_per_sec);
+        self.updated_at = now;
+
+        if self.budget < 0.0 {
+            sleep(Duration::from_secs_f64(-self.budget / self.bytes_per_sec)).await;
+            self.budget = 0.0;
+            self.updated_at = Instant::now();
+        }
+        self.budget -= len as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn transfers_are_paced_after_the_burst() {
+        let mut throttle = Throttle::new(1000);
+        let start = Instant::now();
+
+        // The burst is available right away, a large transfer goes through as well
+        throttle.acquire(1000).await;
+        throttle.acquire(3000).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // The next transfer waits until the 3000 bytes are paid back
+        throttle.acquire(1).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(3));
+    }
+}
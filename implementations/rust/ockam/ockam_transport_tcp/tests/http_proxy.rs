@@ -0,0 +1,138 @@
+use ockam_core::{route, Result, Routed, Worker};
+use ockam_node::Context;
+use ockam_transport_core::TransportError;
+use ockam_transport_tcp::{
+    HttpProxyCredentials, TcpConnectionOptions, TcpListenerOptions, TcpTransport,
+};
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// `Basic` authorization of the `user:secret` credentials
+const AUTHORIZATION: &str = "Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=";
+
+pub struct Echoer;
+
+#[ockam_core::worker]
+impl Worker for Echoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+/// Start an HTTP proxy which requires the `user:secret` credentials and only opens
+/// tunnels to `allowed_peer`
+async fn start_proxy(allowed_peer: String) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut client, _) = listener.accept().await.unwrap();
+            let allowed_peer = allowed_peer.clone();
+            tokio::spawn(async move {
+                let mut request = vec![];
+                while !request.ends_with(b"\r\n\r\n") {
+                    request.push(client.read_u8().await.unwrap());
+                }
+                let request = String::from_utf8(request).unwrap();
+
+                let response: &[u8] = if !request.contains(AUTHORIZATION) {
+                    b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n"
+                } else if !request.starts_with(&format!("CONNECT {allowed_peer} HTTP/1.1\r\n")) {
+                    b"HTTP/1.1 403 Forbidden\r\n\r\n"
+                } else {
+                    b"HTTP/1.1 200 Connection established\r\n\r\n"
+                };
+                client.write_all(response).await.unwrap();
+                if !response.starts_with(b"HTTP/1.1 200") {
+                    return;
+                }
+
+                let mut peer = TcpStream::connect(allowed_peer).await.unwrap();
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut peer).await;
+            });
+        }
+    });
+
+    address
+}
+
+#[ockam_macros::test]
+async fn send_receive_through_http_proxy(ctx: &mut Context) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+    let proxy = start_proxy(listener.socket_string()).await;
+
+    let connection = transport
+        .connect(
+            listener.socket_string(),
+            TcpConnectionOptions::new().with_http_proxy(
+                proxy.to_string(),
+                Some(HttpProxyCredentials::new("user", "secret")),
+            ),
+        )
+        .await?;
+    assert_eq!(connection.socket_address(), &proxy);
+
+    let reply: String = ctx
+        .send_and_receive(
+            route![connection.sender_address().clone(), "echoer"],
+            "Hello through the proxy".to_string(),
+        )
+        .await?;
+    assert_eq!(reply, "Hello through the proxy");
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn http_proxy_errors(ctx: &mut Context) -> Result<()> {
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport
+        .listen("127.0.0.1:0", TcpListenerOptions::new())
+        .await?;
+    let proxy = start_proxy(listener.socket_string()).await;
+
+    let code = |err: TransportError| ockam_core::Error::from(err).code();
+
+    // Wrong credentials
+    let res = transport
+        .connect(
+            listener.socket_string(),
+            TcpConnectionOptions::new().with_http_proxy(
+                proxy.to_string(),
+                Some(HttpProxyCredentials::new("user", "wrong")),
+            ),
+        )
+        .await;
+    assert_eq!(
+        res.err().map(|e| e.code()),
+        Some(code(TransportError::ProxyAuthentication))
+    );
+
+    // The proxy refuses to open a tunnel to this peer
+    let res = transport
+        .connect(
+            "127.0.0.1:1",
+            TcpConnectionOptions::new().with_http_proxy(
+                proxy.to_string(),
+                Some(HttpProxyCredentials::new("user", "secret")),
+            ),
+        )
+        .await;
+    assert_eq!(
+        res.err().map(|e| e.code()),
+        Some(code(TransportError::ProxyConnect))
+    );
+
+    ctx.stop().await
+}
@@ -1,8 +1,11 @@
 use core::time::Duration;
 use ockam_core::compat::rand::{self, Rng};
+use ockam_core::errcode::Kind;
 use ockam_core::{route, Result, Routed, Worker};
 use ockam_node::Context;
-use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions, TcpTransport};
+use ockam_transport_tcp::{
+    TcpConnectionMode, TcpConnectionOptions, TcpListenerOptions, TcpTransport,
+};
 
 pub struct Echoer;
 
@@ -187,3 +190,295 @@ async fn tcp_lifecycle__stop_listener__should_stop_accepting_connections(
 
     Ok(())
 }
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn tcp_lifecycle__stop_listener_gracefully__should_drain_connections_and_release_port(
+    ctx: &mut Context,
+) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let tx_address = transport
+        .connect(listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let reply: String = ctx
+        .send_and_receive(route![tx_address.clone(), "echoer"], "Hello".to_string())
+        .await?;
+    assert_eq!(reply, "Hello", "Should receive the same message");
+
+    let start = std::time::Instant::now();
+    transport
+        .stop_listener_gracefully(listener.socket_string(), Some(Duration::from_millis(100)))
+        .await?;
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed >= Duration::from_millis(100) && elapsed < Duration::from_secs(1),
+        "The open connection should be drained until the timeout: {elapsed:?}"
+    );
+    assert!(transport.registry().get_all_listeners().is_empty());
+
+    let res = transport
+        .connect(listener.socket_string(), TcpConnectionOptions::new())
+        .await;
+    assert!(
+        res.is_err(),
+        "Should not accept connection after listener is stopped"
+    );
+
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert!(
+        transport
+            .registry()
+            .get_all_sender_workers()
+            .iter()
+            .all(|sender| sender.address() != tx_address.sender_address()),
+        "The accepted connection should be closed after the drain timeout"
+    );
+
+    let res = transport
+        .stop_listener_gracefully(listener.socket_string(), None)
+        .await;
+    assert!(res.is_err(), "The listener is already stopped");
+
+    transport
+        .listen(listener.socket_string(), TcpListenerOptions::new())
+        .await?;
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn tcp_lifecycle__connect_pooled__should_reuse_connection(ctx: &mut Context) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let connection1 = transport
+        .connect_pooled(listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let connection2 = transport
+        .connect_pooled(listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    assert_eq!(connection1.sender_address(), connection2.sender_address());
+    assert_eq!(
+        transport.pooled_connection_users(connection1.sender_address()),
+        2
+    );
+
+    transport.disconnect(connection1.clone()).await?;
+    let reply: String = ctx
+        .send_and_receive(route![connection2.clone(), "echoer"], "Hello".to_string())
+        .await?;
+    assert_eq!(reply, "Hello", "The connection should still be open");
+
+    transport.disconnect(connection2.clone()).await?;
+    let res = ctx
+        .send(route![connection2.clone(), "echoer"], "Hello".to_string())
+        .await;
+    assert!(res.is_err(), "The last user should close the connection");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn tcp_lifecycle__concurrent_connect_pooled__should_establish_one_connection(
+    ctx: &mut Context,
+) -> Result<()> {
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport
+        .listen("127.0.0.1:0", TcpListenerOptions::new())
+        .await?;
+
+    let (connection1, connection2) = tokio::join!(
+        transport.connect_pooled(listener.socket_string(), TcpConnectionOptions::new()),
+        transport.connect_pooled(listener.socket_string(), TcpConnectionOptions::new()),
+    );
+    let (connection1, connection2) = (connection1?, connection2?);
+    assert_eq!(connection1.sender_address(), connection2.sender_address());
+    assert_eq!(
+        transport.pooled_connection_users(connection1.sender_address()),
+        2
+    );
+    ctx.sleep(Duration::from_millis(50)).await; // Wait for workers to add themselves to the registry
+    let outgoing = transport
+        .registry()
+        .get_all_sender_workers()
+        .into_iter()
+        .filter(|s| matches!(s.mode(), TcpConnectionMode::Outgoing))
+        .count();
+    assert_eq!(outgoing, 1, "A single connection should be established");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn tcp_lifecycle__connect_pooled_with_other_consumers__should_not_reuse_connection(
+    ctx: &mut Context,
+) -> Result<()> {
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport
+        .listen("127.0.0.1:0", TcpListenerOptions::new())
+        .await?;
+
+    let session_options = TcpConnectionOptions::new();
+    let session_flow_control_id = session_options.flow_control_id();
+
+    let connection1 = transport
+        .connect_pooled(listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let connection2 = transport
+        .connect_pooled(
+            listener.socket_string(),
+            TcpConnectionOptions::new().as_consumer(&session_flow_control_id),
+        )
+        .await?;
+    assert_ne!(connection1.sender_address(), connection2.sender_address());
+
+    let connection3 = transport
+        .connect_pooled(
+            listener.socket_string(),
+            TcpConnectionOptions::new().as_consumer(&session_flow_control_id),
+        )
+        .await?;
+    assert_eq!(connection2.sender_address(), connection3.sender_address());
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn tcp_lifecycle__connect_pooled_with_pool_size__should_keep_idle_connection(
+    ctx: &mut Context,
+) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?.with_pool_size(1);
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let connection1 = transport
+        .connect_pooled(listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    transport.disconnect(connection1.clone()).await?;
+    assert_eq!(
+        transport.pooled_connection_users(connection1.sender_address()),
+        0
+    );
+
+    let connection2 = transport
+        .connect_pooled(listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    assert_eq!(connection1.sender_address(), connection2.sender_address());
+
+    let reply: String = ctx
+        .send_and_receive(route![connection2.clone(), "echoer"], "Hello".to_string())
+        .await?;
+    assert_eq!(reply, "Hello", "The idle connection should be reused");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[cfg(target_os = "linux")]
+#[ockam_macros::test]
+async fn tcp_lifecycle__listen_on_interface__should_bind_to_interface_address(
+    ctx: &mut Context,
+) -> Result<()> {
+    let options = TcpListenerOptions::new().bind_to_interface("lo");
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("0.0.0.0:0", options).await?;
+    assert_eq!(listener.socket_address().ip().to_string(), "127.0.0.1");
+    assert_ne!(listener.socket_address().port(), 0);
+
+    let tx_address = transport
+        .connect(listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let reply: String = ctx
+        .send_and_receive(route![tx_address, "echoer"], "Hello".to_string())
+        .await?;
+    assert_eq!(reply, "Hello", "Should receive the same message");
+
+    let res = transport
+        .listen(
+            "0.0.0.0:0",
+            TcpListenerOptions::new().bind_to_interface("ockam-missing0"),
+        )
+        .await;
+    assert_eq!(res.err().map(|e| e.code().kind), Some(Kind::NotFound));
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn tcp_lifecycle__connect_with_timeout__should_not_hang(ctx: &mut Context) -> Result<()> {
+    let transport = TcpTransport::create(ctx).await?;
+
+    // Non-routable address (TEST-NET-1): the connection either times out or fails right away
+    // if the network is unreachable
+    let start = std::time::Instant::now();
+    let res = transport
+        .connect(
+            "192.0.2.1:4000",
+            TcpConnectionOptions::new().with_connect_timeout(Duration::from_millis(200)),
+        )
+        .await;
+    assert!(res.is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(transport.registry().get_all_sender_workers().is_empty());
+    assert!(transport
+        .registry()
+        .get_all_receiver_processors()
+        .is_empty());
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use core::time::Duration;
+use ockam_core::{route, AllowAll, Result, Routed, Worker};
+use ockam_node::{Context, MessageSendReceiveOptions};
+use ockam_transport_tcp::{MemoryTransport, TcpConnectionOptions, TcpListenerOptions};
+use std::time::Instant;
+
+pub struct Echoer;
+
+#[ockam_core::worker]
+impl Worker for Echoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn memory__connect__should_deliver_messages_to_consumers_only(
+    ctx: &mut Context,
+) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+    ctx.start_worker("other_echoer", Echoer).await?;
+
+    let memory = MemoryTransport::create(ctx).await?;
+    let listener = memory.listen("127.0.0.1:0", options).await?;
+    assert_ne!(listener.socket_address().port(), 0);
+
+    let connection = memory
+        .connect(listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let reply: String = ctx
+        .send_and_receive(route![connection.clone(), "echoer"], "Hello".to_string())
+        .await?;
+    assert_eq!(reply, "Hello", "Should receive the same message");
+
+    let res: Result<String> = ctx
+        .send_and_receive_extended(
+            route![connection.clone(), "other_echoer"],
+            "Hello".to_string(),
+            MessageSendReceiveOptions::new().with_timeout(Duration::from_millis(100)),
+        )
+        .await
+        .map(|m| m.body());
+    assert!(
+        res.is_err(),
+        "Only consumers of the listener should receive messages"
+    );
+
+    let res = memory
+        .listen(listener.socket_string(), TcpListenerOptions::new())
+        .await;
+    assert!(res.is_err(), "The address is already in use");
+
+    memory.stop_listener(listener.processor_address()).await?;
+    let res = memory
+        .connect(listener.socket_string(), TcpConnectionOptions::new())
+        .await;
+    assert!(res.is_err(), "The listener is stopped");
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn memory__with_latency__should_delay_messages(ctx: &mut Context) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let memory = MemoryTransport::create(ctx)
+        .await?
+        .with_latency(Duration::from_millis(100));
+    let listener = memory.listen("127.0.0.1:0", options).await?;
+    let connection = memory
+        .connect(listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+
+    let start = Instant::now();
+    let reply: String = ctx
+        .send_and_receive(route![connection.clone(), "echoer"], "Hello".to_string())
+        .await?;
+    assert_eq!(reply, "Hello");
+    // The reply is delayed on the way back as well
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    // Messages are delayed concurrently, and in order
+    let mut receiver = ctx.new_detached("receiver", AllowAll, AllowAll).await?;
+    let start = Instant::now();
+    for i in 0..5 {
+        receiver
+            .send(route![connection.clone(), "echoer"], i.to_string())
+            .await?;
+    }
+    for i in 0..5 {
+        let reply = receiver.receive::<String>().await?;
+        assert_eq!(reply.body(), i.to_string());
+    }
+    assert!(start.elapsed() < Duration::from_millis(5 * 200));
+
+    memory.disconnect(connection.clone()).await?;
+    let res: Result<String> = ctx
+        .send_and_receive_extended(
+            route![connection, "echoer"],
+            "Hello".to_string(),
+            MessageSendReceiveOptions::new().with_timeout(Duration::from_millis(300)),
+        )
+        .await
+        .map(|m| m.body());
+    assert!(res.is_err(), "The connection is closed");
+
+    ctx.stop().await
+}
@@ -1,7 +1,17 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
 use ockam_core::compat::rand::{self, Rng};
-use ockam_core::{route, Result, Routed, Worker};
-use ockam_node::Context;
-use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions, TcpTransport};
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::Kind;
+use ockam_core::{route, Address, AllowAll, MessageHeaders, Result, Routed, Worker};
+use ockam_node::{Context, Metrics, NodeBuilder};
+use ockam_transport_core::TransportError;
+use ockam_transport_tcp::{
+    IpVersionPreference, TcpConnection, TcpConnectionOptions, TcpListenerOptions, TcpTransport,
+};
+use std::error::Error as _;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
 
 pub struct Echoer;
 
@@ -52,3 +62,266 @@ async fn send_receive(ctx: &mut Context) -> Result<()> {
 
     Ok(())
 }
+
+#[ockam_macros::test]
+async fn send_receive_headers_with_message_extensions(ctx: &mut Context) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    let mut receiver = ctx.new_detached("receiver", AllowAll, AllowAll).await?;
+    ctx.flow_controls()
+        .add_consumer("receiver", &options.spawner_flow_control_id());
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+    let headers = MessageHeaders::new().with("tenant", "acme")?;
+
+    // The headers are not sent by default, older peers can't decode them
+    let connection = transport
+        .connect(listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    ctx.send_with_headers(
+        route![connection.clone(), "receiver"],
+        "Hello".to_string(),
+        headers.clone(),
+    )
+    .await?;
+    let msg = receiver.receive::<String>().await?;
+    assert!(msg.headers()?.is_empty());
+
+    let connection = transport
+        .connect(
+            listener.socket_string(),
+            TcpConnectionOptions::new().with_message_extensions(),
+        )
+        .await?;
+    ctx.send_with_headers(
+        route![connection, "receiver"],
+        "Hello".to_string(),
+        headers.clone(),
+    )
+    .await?;
+    let msg = receiver.receive::<String>().await?;
+    assert_eq!(msg.headers()?, headers);
+
+    ctx.stop().await
+}
+
+#[derive(Default)]
+struct TransportMetrics {
+    bytes_sent: AtomicUsize,
+    bytes_received: AtomicUsize,
+}
+
+impl Metrics for TransportMetrics {
+    fn transport_bytes_sent(&self, _connection: &Address, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn transport_bytes_received(&self, _connection: &Address, bytes: usize) {
+        self.bytes_received.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+#[ockam_macros::test]
+async fn send_receive_metrics(ctx: &mut Context) -> Result<()> {
+    let metrics = Arc::new(TransportMetrics::default());
+    ctx.set_metrics(metrics.clone());
+
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let addr = transport
+        .connect(listener.socket_string(), TcpConnectionOptions::new())
+        .await?
+        .sender_address()
+        .clone();
+
+    let msg = "a".repeat(1000);
+    let reply = ctx
+        .send_and_receive::<String>(route![addr, "echoer"], msg.clone())
+        .await?;
+    assert_eq!(reply, msg);
+    // The bytes are recorded by the sender after they were written
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    // Both ends of the connection belong to this node,
+    // so every byte written by one side was read by the other one
+    let bytes_sent = metrics.bytes_sent.load(Ordering::Relaxed);
+    assert!(bytes_sent > 2 * msg.len());
+    assert_eq!(bytes_sent, metrics.bytes_received.load(Ordering::Relaxed));
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn send_receive_message_too_large_for_tcp(ctx: &mut Context) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let addr = transport
+        .connect(listener.socket_string(), TcpConnectionOptions::new())
+        .await?
+        .sender_address()
+        .clone();
+
+    // A message which doesn't fit in a TCP frame is rejected when it's sent
+    let err = ctx
+        .send(route![addr.clone(), "echoer"], "a".repeat(70_000))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code().kind, Kind::ResourceExhausted);
+
+    // The connection can still be used
+    let reply = ctx
+        .send_and_receive::<String>(route![addr, "echoer"], "Hello".to_string())
+        .await?;
+    assert_eq!(reply, "Hello");
+
+    ctx.stop().await
+}
+
+#[test]
+fn receive_message_exceeding_max_message_size() {
+    let (ctx, mut executor) = NodeBuilder::new().with_max_message_size(1024).build();
+    executor
+        .execute(async move {
+            let res: Result<()> = async {
+                let transport = TcpTransport::create(&ctx).await?;
+                let listener = transport
+                    .listen("127.0.0.1:0", TcpListenerOptions::new())
+                    .await?;
+
+                // The peer announces a message larger than the maximum message size
+                let mut stream = TcpStream::connect(listener.socket_address()).await.unwrap();
+                stream.write_u16(2048).await.unwrap();
+
+                // The connection is closed before the message is read
+                let mut buf = [0u8; 16];
+                let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+                    .await
+                    .expect("the connection should be closed");
+                assert!(matches!(read, Ok(0) | Err(_)));
+
+                Ok(())
+            }
+            .await;
+
+            ctx.stop().await?;
+            res
+        })
+        .unwrap()
+        .unwrap();
+}
+
+#[ockam_macros::test(timeout = 20000)]
+async fn send_receive_rate_limited(ctx: &mut Context) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("receiver", &options.spawner_flow_control_id());
+    let mut receiver = ctx
+        .new_detached("receiver", ockam_core::AllowAll, ockam_core::AllowAll)
+        .await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    // 300 KB are sent at 100 KB/s, the first 100 KB are sent right away
+    let connection = transport
+        .connect(
+            listener.socket_string(),
+            TcpConnectionOptions::new().with_rate_limit(100_000),
+        )
+        .await?;
+
+    let start = std::time::Instant::now();
+    for _ in 0..30 {
+        ctx.send(
+            route![connection.sender_address().clone(), "receiver"],
+            "a".repeat(10_000),
+        )
+        .await?;
+    }
+    for _ in 0..30 {
+        receiver.receive::<String>().await?;
+    }
+    let elapsed = start.elapsed();
+
+    assert!(
+        elapsed >= Duration::from_millis(1500),
+        "the rate limit should be honored, took {:?}",
+        elapsed
+    );
+    assert!(
+        elapsed < Duration::from_secs(6),
+        "the messages should be paced, not blocked, took {:?}",
+        elapsed
+    );
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn send_receive_by_host_name(ctx: &mut Context) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+    let port = listener.socket_address().port();
+
+    // The host name is resolved when connecting
+    let connection = transport
+        .connect(
+            format!("localhost:{port}"),
+            TcpConnectionOptions::new().with_ip_version_preference(IpVersionPreference::Ipv4),
+        )
+        .await?;
+    assert_eq!(connection.socket_address(), listener.socket_address());
+    let reply: String = ctx
+        .send_and_receive(
+            route![connection.sender_address().clone(), "echoer"],
+            "Hello".to_string(),
+        )
+        .await?;
+    assert_eq!(reply, "Hello");
+
+    // Resolution failures are not reported as refused connections. Some of these errors
+    // have the same code, so the transport errors themselves are compared
+    let transport_error = |res: Result<TcpConnection>| {
+        res.err().and_then(|err| {
+            err.source()
+                .and_then(|cause| cause.downcast_ref::<TransportError>())
+                .copied()
+        })
+    };
+    let res = transport
+        .connect(
+            "ockam-test.invalid:4000",
+            TcpConnectionOptions::new().with_dns_timeout(Duration::from_secs(2)),
+        )
+        .await;
+    assert_eq!(transport_error(res), Some(TransportError::DnsResolution));
+
+    let res = transport
+        .connect("localhost", TcpConnectionOptions::new())
+        .await;
+    assert_eq!(transport_error(res), Some(TransportError::InvalidAddress));
+
+    let res = transport
+        .connect("127.0.0.1:1", TcpConnectionOptions::new())
+        .await;
+    assert_eq!(transport_error(res), Some(TransportError::PeerNotFound));
+
+    ctx.stop().await
+}
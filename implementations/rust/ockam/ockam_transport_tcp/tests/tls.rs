@@ -0,0 +1,257 @@
+#![cfg(feature = "tls")]
+
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{route, Result, Routed, Worker};
+use ockam_node::Context;
+use ockam_transport_core::TransportError;
+use ockam_transport_tcp::rustls::{
+    self, Certificate, CipherSuite, ClientConfig, PrivateKey, ProtocolVersion, RootCertStore,
+    ServerConfig, SupportedCipherSuite, SupportedProtocolVersion,
+};
+use ockam_transport_tcp::{
+    TcpConnectionOptions, TcpListenerOptions, TcpTransport, TlsRequirements,
+};
+
+const CA_CERTIFICATE: &[u8] = include_bytes!("tls/ca.pem");
+const SERVER_CERTIFICATE: &[u8] = include_bytes!("tls/server.pem");
+const SERVER_KEY: &[u8] = include_bytes!("tls/server.key.pem");
+
+pub struct Echoer;
+
+#[ockam_core::worker]
+impl Worker for Echoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+fn certificates(pem: &[u8]) -> Vec<Certificate> {
+    rustls_pemfile::certs(&mut &pem[..])
+        .unwrap()
+        .into_iter()
+        .map(Certificate)
+        .collect()
+}
+
+fn server_config() -> Arc<ServerConfig> {
+    restricted_server_config(rustls::DEFAULT_CIPHER_SUITES, rustls::ALL_VERSIONS)
+}
+
+fn restricted_server_config(
+    cipher_suites: &[SupportedCipherSuite],
+    versions: &[&'static SupportedProtocolVersion],
+) -> Arc<ServerConfig> {
+    let key = rustls_pemfile::pkcs8_private_keys(&mut &SERVER_KEY[..])
+        .unwrap()
+        .remove(0);
+    let config = ServerConfig::builder()
+        .with_cipher_suites(cipher_suites)
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(versions)
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(certificates(SERVER_CERTIFICATE), PrivateKey(key))
+        .unwrap();
+
+    Arc::new(config)
+}
+
+fn client_config(trusted: &[u8]) -> Arc<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    for certificate in certificates(trusted) {
+        roots.add(&certificate).unwrap();
+    }
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    Arc::new(config)
+}
+
+#[ockam_macros::test]
+async fn send_receive_over_tls(ctx: &mut Context) -> Result<()> {
+    let options = TcpListenerOptions::new().with_tls(server_config());
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let peer = format!("localhost:{}", listener.socket_address().port());
+    let connection = transport
+        .connect(
+            peer,
+            TcpConnectionOptions::new().with_tls_client(client_config(CA_CERTIFICATE)),
+        )
+        .await?;
+
+    let reply: String = ctx
+        .send_and_receive(
+            route![connection.sender_address().clone(), "echoer"],
+            "Hello over TLS".to_string(),
+        )
+        .await?;
+    assert_eq!(reply, "Hello over TLS");
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn connect_with_untrusted_certificate_should_fail(ctx: &mut Context) -> Result<()> {
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport
+        .listen(
+            "127.0.0.1:0",
+            TcpListenerOptions::new().with_tls(server_config()),
+        )
+        .await?;
+
+    // The server certificate is not a trust anchor, only its issuer is
+    let res = transport
+        .connect(
+            listener.socket_string(),
+            TcpConnectionOptions::new().with_tls_client(client_config(SERVER_CERTIFICATE)),
+        )
+        .await;
+    let expected: ockam_core::Error = TransportError::TlsCertificateVerification.into();
+    assert_eq!(res.err().map(|e| e.code()), Some(expected.code()));
+
+    // The listener is still running after the failed handshake
+    transport
+        .connect(
+            listener.socket_string(),
+            TcpConnectionOptions::new().with_tls_client(client_config(CA_CERTIFICATE)),
+        )
+        .await?;
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn slow_tls_handshake_should_not_block_the_listener(ctx: &mut Context) -> Result<()> {
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport
+        .listen(
+            "127.0.0.1:0",
+            TcpListenerOptions::new().with_tls(server_config()),
+        )
+        .await?;
+
+    // This peer never starts its handshake
+    let _stalled = tokio::net::TcpStream::connect(listener.socket_address())
+        .await
+        .unwrap();
+
+    let peer = format!("localhost:{}", listener.socket_address().port());
+    let connection = tokio::time::timeout(
+        Duration::from_secs(5),
+        transport.connect(
+            peer,
+            TcpConnectionOptions::new().with_tls_client(client_config(CA_CERTIFICATE)),
+        ),
+    )
+    .await;
+    assert!(matches!(connection, Ok(Ok(_))));
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn connect_below_min_tls_version_should_fail(ctx: &mut Context) -> Result<()> {
+    let transport = TcpTransport::create(ctx).await?;
+    let requirements = TlsRequirements::new().with_min_version(ProtocolVersion::TLSv1_3);
+
+    // This listener only offers TLS 1.2
+    let tls12_listener = transport
+        .listen(
+            "127.0.0.1:0",
+            TcpListenerOptions::new().with_tls(restricted_server_config(
+                rustls::DEFAULT_CIPHER_SUITES,
+                &[&rustls::version::TLS12],
+            )),
+        )
+        .await?;
+    let peer = format!("localhost:{}", tls12_listener.socket_address().port());
+    let res = transport
+        .connect(
+            peer,
+            TcpConnectionOptions::new()
+                .with_tls_client(client_config(CA_CERTIFICATE))
+                .with_tls_requirements(requirements.clone()),
+        )
+        .await;
+    let err = res.err().unwrap();
+    let expected: ockam_core::Error = TransportError::TlsVersion.into();
+    assert_eq!(err.code(), expected.code());
+    assert!(err.to_string().contains(&TransportError::TlsVersion.to_string()));
+
+    // This listener meets the minimum
+    let listener = transport
+        .listen(
+            "127.0.0.1:0",
+            TcpListenerOptions::new().with_tls(server_config()),
+        )
+        .await?;
+    let peer = format!("localhost:{}", listener.socket_address().port());
+    transport
+        .connect(
+            peer,
+            TcpConnectionOptions::new()
+                .with_tls_client(client_config(CA_CERTIFICATE))
+                .with_tls_requirements(requirements),
+        )
+        .await?;
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn connect_with_disallowed_cipher_suite_should_fail(ctx: &mut Context) -> Result<()> {
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport
+        .listen(
+            "127.0.0.1:0",
+            TcpListenerOptions::new().with_tls(restricted_server_config(
+                &[rustls::cipher_suite::TLS13_AES_128_GCM_SHA256],
+                &[&rustls::version::TLS13],
+            )),
+        )
+        .await?;
+    let peer = format!("localhost:{}", listener.socket_address().port());
+
+    let res = transport
+        .connect(
+            peer.clone(),
+            TcpConnectionOptions::new()
+                .with_tls_client(client_config(CA_CERTIFICATE))
+                .with_tls_requirements(
+                    TlsRequirements::new()
+                        .with_cipher_suites(&[CipherSuite::TLS13_CHACHA20_POLY1305_SHA256]),
+                ),
+        )
+        .await;
+    let err = res.err().unwrap();
+    let expected: ockam_core::Error = TransportError::TlsCipherSuite.into();
+    assert_eq!(err.code(), expected.code());
+    assert!(err.to_string().contains(&TransportError::TlsCipherSuite.to_string()));
+
+    transport
+        .connect(
+            peer,
+            TcpConnectionOptions::new()
+                .with_tls_client(client_config(CA_CERTIFICATE))
+                .with_tls_requirements(TlsRequirements::new().with_cipher_suites(&[
+                    CipherSuite::TLS13_AES_128_GCM_SHA256,
+                    CipherSuite::TLS13_CHACHA20_POLY1305_SHA256,
+                ])),
+        )
+        .await?;
+
+    ctx.stop().await
+}
@@ -23,14 +23,17 @@
 //!
 //! // Now we can write the main function that will run the previous worker. In this case, our worker will be listening for new connections on port 8000 until the process is manually killed.
 //!
-//! use ockam_transport_websocket::WebSocketTransport;
+//! use ockam_transport_websocket::{WebSocketListenerOptions, WebSocketTransport};
 //! use ockam_node::NodeBuilder;
 //! use ockam_macros::node;
 //!
 //! #[ockam_macros::node(crate = "ockam_node")]
 //! async fn main(mut ctx: Context) -> Result<()> {//!
 //!     let ws = WebSocketTransport::create(&ctx).await?;
-//!     ws.listen("localhost:8000").await?; // Listen on port 8000
+//!     let listener = ws.listen("127.0.0.1:8000", WebSocketListenerOptions::new()).await?; // Listen on port 8000
+//!
+//!     // Messages coming from WebSocket connections need to be explicitly allowed
+//!     ctx.flow_controls().add_consumer("my_worker", listener.flow_control_id());
 //!
 //!     // Start a worker, of type MyWorker, at address "my_worker"
 //!     ctx.start_worker("my_worker", MyWorker).await?;
@@ -50,11 +53,13 @@
 //!
 //! #[ockam_macros::node(crate = "ockam_node")]
 //! async fn main(mut ctx: Context) -> Result<()> {
-//!     use ockam_node::MessageReceiveOptions;
-//! let ws = WebSocketTransport::create(&ctx).await?;
+//!     let ws = WebSocketTransport::create(&ctx).await?;
 //!
 //!     // Define the route to the server's worker.
-//!     let r = route![(WS, "localhost:8000"), "my_worker"];
+//!     // The WS address is replaced by a freshly established connection.
+//!     let r = ctx
+//!         .resolve_transport_route(route![(WS, "127.0.0.1:8000"), "my_worker"])
+//!         .await?;
 //!
 //!     // Now you can send messages to the worker.
 //!     ctx.send(r, "Hello Ockam!".to_string()).await?;
@@ -85,16 +90,15 @@ extern crate core;
 #[macro_use]
 extern crate tracing;
 
-use std::net::SocketAddr;
+use ockam_core::TransportType;
 
-use ockam_core::{Result, TransportType};
-use ockam_transport_core::TransportError;
+pub use options::{WebSocketConnectionOptions, WebSocketListenerOptions};
+pub use registry::*;
 pub use transport::*;
 
-use crate::router::{WebSocketRouter, WebSocketRouterHandle};
-
 mod error;
-mod router;
+mod options;
+mod registry;
 mod transport;
 mod workers;
 
@@ -102,9 +106,3 @@ mod workers;
 pub const WS: TransportType = TransportType::new(3);
 
 pub(crate) const CLUSTER_NAME: &str = "_internals.transport.ws";
-
-fn parse_socket_addr<S: AsRef<str>>(s: S) -> Result<SocketAddr> {
-    Ok(s.as_ref()
-        .parse()
-        .map_err(|_| TransportError::InvalidAddress)?)
-}
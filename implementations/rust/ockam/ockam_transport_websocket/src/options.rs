@@ -0,0 +1,133 @@
+use crate::workers::Addresses;
+use ockam_core::compat::sync::Arc;
+use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
+use ockam_core::{Address, AllowAll, IncomingAccessControl, OutgoingAccessControl};
+
+pub(crate) struct WebSocketConnectionAccessControl {
+    pub sender_incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub receiver_outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+}
+
+/// Trust Options for a WebSocket connection
+#[derive(Debug)]
+pub struct WebSocketConnectionOptions {
+    pub(super) consumer: Vec<FlowControlId>,
+    pub(crate) flow_control_id: FlowControlId,
+}
+
+impl WebSocketConnectionOptions {
+    #[allow(clippy::new_without_default)]
+    /// Mark this WebSocket Receiver as a Producer with a random [`FlowControlId`]
+    pub fn new() -> Self {
+        Self {
+            consumer: vec![],
+            flow_control_id: FlowControls::generate_flow_control_id(),
+        }
+    }
+
+    /// Mark that this Connection is a Consumer for to the given [`FlowControlId`]
+    pub fn as_consumer(mut self, id: &FlowControlId) -> Self {
+        self.consumer.push(id.clone());
+
+        self
+    }
+
+    /// Getter for freshly generated [`FlowControlId`]
+    pub fn flow_control_id(&self) -> FlowControlId {
+        self.flow_control_id.clone()
+    }
+}
+
+impl WebSocketConnectionOptions {
+    pub(crate) fn setup_flow_control(&self, flow_controls: &FlowControls, addresses: &Addresses) {
+        flow_controls.add_producer(
+            addresses.receiver_address().clone(),
+            &self.flow_control_id,
+            None,
+            vec![addresses.sender_address().clone()],
+        );
+
+        for id in &self.consumer {
+            flow_controls.add_consumer(addresses.sender_address().clone(), id);
+        }
+    }
+
+    pub(crate) fn create_access_control(
+        self,
+        flow_controls: &FlowControls,
+    ) -> WebSocketConnectionAccessControl {
+        WebSocketConnectionAccessControl {
+            sender_incoming_access_control: Arc::new(AllowAll),
+            receiver_outgoing_access_control: Arc::new(FlowControlOutgoingAccessControl::new(
+                flow_controls,
+                self.flow_control_id,
+                None,
+            )),
+        }
+    }
+}
+
+/// Trust Options for a WebSocket listener
+#[derive(Debug)]
+pub struct WebSocketListenerOptions {
+    pub(crate) flow_control_id: FlowControlId,
+}
+
+impl WebSocketListenerOptions {
+    /// Mark this WebSocket Listener as a Spawner with given [`FlowControlId`].
+    /// NOTE: Spawned connections get fresh random [`FlowControlId`], however they are still marked
+    /// with Spawner's [`FlowControlId`]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            flow_control_id: FlowControls::generate_flow_control_id(),
+        }
+    }
+
+    /// Getter for freshly generated [`FlowControlId`]
+    pub fn spawner_flow_control_id(&self) -> FlowControlId {
+        self.flow_control_id.clone()
+    }
+}
+
+impl WebSocketListenerOptions {
+    pub(crate) fn setup_flow_control_for_listener(
+        &self,
+        flow_controls: &FlowControls,
+        address: &Address,
+    ) {
+        flow_controls.add_spawner(address.clone(), &self.flow_control_id);
+    }
+
+    pub(crate) fn setup_flow_control_for_connection(
+        &self,
+        flow_controls: &FlowControls,
+        addresses: &Addresses,
+    ) -> FlowControlId {
+        let flow_control_id = FlowControls::generate_flow_control_id();
+
+        flow_controls.add_producer(
+            addresses.receiver_address().clone(),
+            &flow_control_id,
+            Some(&self.flow_control_id),
+            vec![addresses.sender_address().clone()],
+        );
+
+        flow_control_id
+    }
+
+    pub(crate) fn create_access_control(
+        &self,
+        flow_controls: &FlowControls,
+        flow_control_id: FlowControlId,
+    ) -> WebSocketConnectionAccessControl {
+        WebSocketConnectionAccessControl {
+            sender_incoming_access_control: Arc::new(AllowAll),
+            receiver_outgoing_access_control: Arc::new(FlowControlOutgoingAccessControl::new(
+                flow_controls,
+                flow_control_id,
+                Some(self.flow_control_id.clone()),
+            )),
+        }
+    }
+}
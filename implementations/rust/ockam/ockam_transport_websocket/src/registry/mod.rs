@@ -0,0 +1,70 @@
+mod common;
+
+pub use common::*;
+
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::Address;
+
+#[derive(Default)]
+struct InternalRegistry {
+    listener_processors: Vec<WebSocketListenerInfo>,
+    sender_workers: Vec<WebSocketSenderInfo>,
+    receiver_processors: Vec<WebSocketReceiverInfo>,
+}
+
+/// Registry of all active workers and processors in WebSocket Transport to ease their lifecycle
+/// management
+#[derive(Default, Clone)]
+pub struct WebSocketRegistry {
+    registry: Arc<RwLock<InternalRegistry>>,
+}
+
+impl WebSocketRegistry {
+    /// Return all active sender workers
+    pub fn get_all_sender_workers(&self) -> Vec<WebSocketSenderInfo> {
+        self.registry.read().unwrap().sender_workers.clone()
+    }
+
+    /// Return all active receiver processors
+    pub fn get_all_receiver_processors(&self) -> Vec<WebSocketReceiverInfo> {
+        self.registry.read().unwrap().receiver_processors.clone()
+    }
+
+    /// Return all active listeners
+    pub fn get_all_listeners(&self) -> Vec<WebSocketListenerInfo> {
+        self.registry.read().unwrap().listener_processors.clone()
+    }
+}
+
+impl WebSocketRegistry {
+    pub(crate) fn add_listener_processor(&self, info: WebSocketListenerInfo) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.listener_processors.push(info);
+        }
+    }
+    pub(crate) fn remove_listener_processor(&self, addr: &Address) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.listener_processors.retain(|x| x.address() != addr);
+        }
+    }
+    pub(crate) fn add_sender_worker(&self, info: WebSocketSenderInfo) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.sender_workers.push(info);
+        }
+    }
+    pub(crate) fn remove_sender_worker(&self, addr: &Address) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.sender_workers.retain(|x| x.address() != addr);
+        }
+    }
+    pub(crate) fn add_receiver_processor(&self, info: WebSocketReceiverInfo) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.receiver_processors.push(info);
+        }
+    }
+    pub(crate) fn remove_receiver_processor(&self, addr: &Address) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.receiver_processors.retain(|x| x.address() != addr);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Address, AsyncTryClone, Error, Result, TransportType};
+use ockam_node::Context;
+use ockam_transport_core::Transport;
+
+use crate::{WebSocketConnectionOptions, WebSocketRegistry, WebSocketTransport, WS};
+
+impl WebSocketTransport {
+    /// Create a WebSocket transport
+    ///
+    /// ```rust
+    /// use ockam_transport_websocket::WebSocketTransport;
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let ws = WebSocketTransport::create(&ctx).await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn create(ctx: &Context) -> Result<Self> {
+        let ws = Self {
+            ctx: ctx.async_try_clone().await?,
+            registry: WebSocketRegistry::default(),
+        };
+        // make the WebSocket transport available in the list of supported transports for
+        // later address resolution when socket addresses will need to be instantiated as
+        // WebSocket worker addresses
+        ctx.register_transport(Arc::new(ws.async_try_clone().await?));
+        Ok(ws)
+    }
+}
+
+impl WebSocketTransport {
+    /// Getter
+    pub fn ctx(&self) -> &Context {
+        &self.ctx
+    }
+    /// Registry of all active connections
+    pub fn registry(&self) -> &WebSocketRegistry {
+        &self.registry
+    }
+}
+
+#[async_trait]
+impl Transport for WebSocketTransport {
+    fn transport_type(&self) -> TransportType {
+        WS
+    }
+
+    async fn resolve_address(&self, address: Address) -> Result<Address> {
+        if address.transport_type() == WS {
+            Ok(self
+                .connect(
+                    address.address().to_string(),
+                    WebSocketConnectionOptions::new(),
+                )
+                .await?
+                .into())
+        } else {
+            Err(Error::new(
+                Origin::Transport,
+                Kind::NotFound,
+                format!(
+                    "this address can not be resolved by a WebSocket transport {}",
+                    address
+                ),
+            ))
+        }
+    }
+}
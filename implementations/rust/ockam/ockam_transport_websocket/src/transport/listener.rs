@@ -0,0 +1,46 @@
+use ockam_core::{Address, Result};
+
+use crate::transport::common::{parse_socket_addr, WebSocketListener};
+use crate::workers::WebSocketListenProcessor;
+use crate::{WebSocketListenerOptions, WebSocketTransport};
+
+impl WebSocketTransport {
+    /// Start listening to incoming connections on an existing transport
+    ///
+    /// Returns the local address that this transport is bound to.
+    ///
+    /// This can be useful, for example, when binding to port 0 to figure out
+    /// which port was actually bound.
+    ///
+    /// ```rust
+    /// use ockam_transport_websocket::{WebSocketListenerOptions, WebSocketTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let ws = WebSocketTransport::create(&ctx).await?;
+    /// ws.listen("127.0.0.1:8000", WebSocketListenerOptions::new()).await?;
+    /// # Ok(()) }
+    pub async fn listen(
+        &self,
+        bind_addr: impl AsRef<str>,
+        options: WebSocketListenerOptions,
+    ) -> Result<WebSocketListener> {
+        let flow_control_id = options.flow_control_id.clone();
+        let bind_addr = parse_socket_addr(bind_addr.as_ref())?;
+        // Could be different from the bind_addr, e.g., if binding to port 0
+        let (socket_addr, address) =
+            WebSocketListenProcessor::start(&self.ctx, self.registry.clone(), bind_addr, options)
+                .await?;
+
+        Ok(WebSocketListener::new(
+            address,
+            socket_addr,
+            flow_control_id,
+        ))
+    }
+
+    /// Interrupt an active WebSocket listener given its `Address`
+    pub async fn stop_listener(&self, address: &Address) -> Result<()> {
+        self.ctx.stop_processor(address.clone()).await
+    }
+}
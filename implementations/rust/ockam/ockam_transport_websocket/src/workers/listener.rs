@@ -1,46 +1,59 @@
-use std::net::SocketAddr;
+use core::time::Duration;
+use futures_util::StreamExt;
+use tokio::net::{TcpListener, TcpStream};
 
-use tokio::net::TcpListener;
-
-use ockam_core::{async_trait, Address, AllowAll, Processor, Result};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, compat::net::SocketAddr, Address, DenyAll, Processor, Result};
 use ockam_node::Context;
 use ockam_transport_core::TransportError;
 
-use crate::{error::WebSocketError, workers::WorkerPair, WebSocketRouterHandle};
+use crate::error::WebSocketError;
+use crate::workers::{Addresses, WebSocketRecvProcessor, WebSocketSendWorker};
+use crate::{
+    WebSocketConnectionMode, WebSocketListenerInfo, WebSocketListenerOptions, WebSocketRegistry,
+};
+
+/// Maximum duration of the WebSocket handshake of an accepted connection
+const WS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
-/// A worker that runs in the background as a `Processor` waiting for incoming
-/// clients' connections.
+/// A WebSocket Listen processor
 ///
-/// When a new connection is established, a new `WorkerPair` is spawned and
-/// registered by the router.
+/// WebSocket listen processors are created by `WebSocketTransport`
+/// after a call is made to
+/// [`WebSocketTransport::listen`](crate::WebSocketTransport::listen).
 pub(crate) struct WebSocketListenProcessor {
+    registry: WebSocketRegistry,
     inner: TcpListener,
-    router_handle: WebSocketRouterHandle,
+    socket_address: SocketAddr,
+    options: Arc<WebSocketListenerOptions>,
 }
 
 impl WebSocketListenProcessor {
-    /// Create and start a new instance bound to the given `addr`.
     pub(crate) async fn start(
         ctx: &Context,
-        router_handle: WebSocketRouterHandle,
+        registry: WebSocketRegistry,
         addr: SocketAddr,
-    ) -> Result<SocketAddr> {
+        options: WebSocketListenerOptions,
+    ) -> Result<(SocketAddr, Address)> {
         debug!("Binding WebSocketListener to {}", addr);
         let inner = TcpListener::bind(addr)
             .await
             .map_err(TransportError::from)?;
         let saddr = inner.local_addr().map_err(TransportError::from)?;
+
+        let address = Address::random_tagged("WebSocketListenProcessor");
+        options.setup_flow_control_for_listener(ctx.flow_controls(), &address);
+
         let processor = Self {
+            registry,
             inner,
-            router_handle,
+            socket_address: saddr,
+            options: Arc::new(options),
         };
-        let waddr = Address::random_tagged("WebSocketListenProcessor");
-        ctx.start_processor_with_access_control(
-            waddr, processor, AllowAll, // FIXME: @ac
-            AllowAll, // FIXME: @ac
-        )
-        .await?;
-        Ok(saddr)
+
+        ctx.start_processor(address.clone(), processor).await?;
+
+        Ok((saddr, address))
     }
 }
 
@@ -49,7 +62,22 @@ impl Processor for WebSocketListenProcessor {
     type Context = Context;
 
     async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
-        ctx.set_cluster(crate::CLUSTER_NAME).await
+        ctx.set_cluster(crate::CLUSTER_NAME).await?;
+
+        self.registry
+            .add_listener_processor(WebSocketListenerInfo::new(
+                ctx.address(),
+                self.socket_address,
+                self.options.flow_control_id.clone(),
+            ));
+
+        Ok(())
+    }
+
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        self.registry.remove_listener_processor(&ctx.address());
+
+        Ok(())
     }
 
     async fn process(&mut self, ctx: &mut Self::Context) -> Result<bool> {
@@ -57,18 +85,100 @@ impl Processor for WebSocketListenProcessor {
 
         // Wait for an incoming connection
         let (tcp_stream, peer) = self.inner.accept().await.map_err(TransportError::from)?;
-        let ws_stream = tokio_tungstenite::accept_async(tcp_stream)
-            .await
-            .map_err(WebSocketError::from)?;
         debug!("TCP connection accepted");
 
-        // Spawn a connection worker for it
-        let pair = WorkerPair::from_server(ctx, ws_stream, peer, vec![]).await?;
-
-        // Register the connection with the local TcpRouter
-        self.router_handle.register(&pair).await?;
-        debug!("TCP connection registered");
+        // The WebSocket handshake waits for the peer, it's run in its own task so that a slow
+        // or unresponsive peer doesn't delay the next connections
+        let child_ctx = ctx
+            .new_detached(
+                Address::random_tagged("WebSocketListenProcessor.accept"),
+                DenyAll,
+                DenyAll,
+            )
+            .await?;
+        let registry = self.registry.clone();
+        let options = self.options.clone();
+        ctx.runtime().spawn(async move {
+            if let Err(err) = Self::accept(&child_ctx, registry, options, tcp_stream, peer).await {
+                warn!(
+                    "Failed to start an incoming WebSocket connection from {}: {}",
+                    peer, err
+                );
+            }
+        });
 
         Ok(true)
     }
 }
+
+impl WebSocketListenProcessor {
+    /// Run the WebSocket handshake of an accepted connection, then start its workers
+    async fn accept(
+        ctx: &Context,
+        registry: WebSocketRegistry,
+        options: Arc<WebSocketListenerOptions>,
+        tcp_stream: TcpStream,
+        peer: SocketAddr,
+    ) -> Result<()> {
+        let ws_stream = match tokio::time::timeout(
+            WS_HANDSHAKE_TIMEOUT,
+            tokio_tungstenite::accept_async(tcp_stream),
+        )
+        .await
+        {
+            Ok(Ok(ws_stream)) => ws_stream,
+            Ok(Err(e)) => {
+                // A failed handshake only affects that peer
+                warn!(
+                    "WebSocket handshake with {} failed: {}",
+                    peer,
+                    WebSocketError::from(e)
+                );
+                return Ok(());
+            }
+            Err(_) => {
+                warn!("WebSocket handshake with {} timed out", peer);
+                return Ok(());
+            }
+        };
+        debug!("WebSocket connection accepted");
+
+        let mode = WebSocketConnectionMode::Incoming;
+        let addresses = Addresses::generate(mode);
+
+        let receiver_flow_control_id =
+            options.setup_flow_control_for_connection(ctx.flow_controls(), &addresses);
+        let access_control =
+            options.create_access_control(ctx.flow_controls(), receiver_flow_control_id.clone());
+
+        let (ws_sink, ws_stream) = ws_stream.split();
+
+        // Worker to receive messages from the Node and send them over the wire
+        WebSocketSendWorker::start(
+            ctx,
+            registry.clone(),
+            ws_sink,
+            &addresses,
+            peer,
+            mode,
+            access_control.sender_incoming_access_control,
+            &receiver_flow_control_id,
+        )
+        .await?;
+
+        // Processor to receive messages over the wire and forward them to the node
+        WebSocketRecvProcessor::start(
+            ctx,
+            registry,
+            ws_stream,
+            &addresses,
+            peer,
+            mode,
+            &receiver_flow_control_id,
+            access_control.receiver_outgoing_access_control,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,19 +1,20 @@
-use std::net::SocketAddr;
-
 use futures_util::stream::SplitStream;
 use futures_util::StreamExt;
-use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::protocol::Message as WebSocketMessage;
 
-use crate::WebSocketAddress;
+use ockam_core::compat::{net::SocketAddr, sync::Arc};
+use ockam_core::flow_control::FlowControlId;
 use ockam_core::{
-    async_trait, Address, Decodable, LocalMessage, Processor, Result, TransportMessage,
+    async_trait, AllowOnwardAddress, Decodable, DenyAll, LocalMessage, Mailbox, Mailboxes,
+    OutgoingAccessControl, Processor, Result, TransportMessage,
 };
-use ockam_node::Context;
+use ockam_node::{Context, ProcessorBuilder};
 use ockam_transport_core::TransportError;
 
-use crate::workers::AsyncStream;
+use crate::workers::{Addresses, AsyncStream, WebSocketSendWorkerMsg, WebSocketStream};
+use crate::{WebSocketConnectionMode, WebSocketReceiverInfo, WebSocketRegistry};
 
-/// A WebSocket receiving message worker.
+/// A WebSocket receiving message processor.
 ///
 /// This half of the worker is created when spawning a new connection
 /// worker pair, and listens for messages received to the WebSocket stream
@@ -22,20 +23,85 @@ pub(crate) struct WebSocketRecvProcessor<S>
 where
     S: AsyncStream,
 {
+    registry: WebSocketRegistry,
     ws_stream: SplitStream<WebSocketStream<S>>,
-    peer_addr: Address,
+    socket_address: SocketAddr,
+    addresses: Addresses,
+    mode: WebSocketConnectionMode,
+    flow_control_id: FlowControlId,
 }
 
 impl<S> WebSocketRecvProcessor<S>
 where
     S: AsyncStream,
 {
-    pub(crate) fn new(ws_stream: SplitStream<WebSocketStream<S>>, peer: SocketAddr) -> Self {
+    /// Create a new `WebSocketRecvProcessor`
+    fn new(
+        registry: WebSocketRegistry,
+        ws_stream: SplitStream<WebSocketStream<S>>,
+        socket_address: SocketAddr,
+        addresses: Addresses,
+        mode: WebSocketConnectionMode,
+        flow_control_id: FlowControlId,
+    ) -> Self {
         Self {
+            registry,
             ws_stream,
-            peer_addr: WebSocketAddress::from(peer).into(),
+            socket_address,
+            addresses,
+            mode,
+            flow_control_id,
         }
     }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn start(
+        ctx: &Context,
+        registry: WebSocketRegistry,
+        ws_stream: SplitStream<WebSocketStream<S>>,
+        addresses: &Addresses,
+        socket_address: SocketAddr,
+        mode: WebSocketConnectionMode,
+        flow_control_id: &FlowControlId,
+        receiver_outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+    ) -> Result<()> {
+        let receiver = Self::new(
+            registry,
+            ws_stream,
+            socket_address,
+            addresses.clone(),
+            mode,
+            flow_control_id.clone(),
+        );
+
+        let mailbox = Mailbox::new(
+            addresses.receiver_address().clone(),
+            Arc::new(DenyAll),
+            receiver_outgoing_access_control,
+        );
+        let internal = Mailbox::new(
+            addresses.receiver_internal_address().clone(),
+            Arc::new(DenyAll),
+            Arc::new(AllowOnwardAddress(
+                addresses.sender_internal_address().clone(),
+            )),
+        );
+        ProcessorBuilder::new(receiver)
+            .with_mailboxes(Mailboxes::new(mailbox, vec![internal]))
+            .start(ctx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn notify_sender_connection_closed(&self, ctx: &Context) -> Result<()> {
+        ctx.send_from_address(
+            self.addresses.sender_internal_address().clone(),
+            WebSocketSendWorkerMsg::ConnectionClosed,
+            self.addresses.receiver_internal_address().clone(),
+        )
+        .await
+    }
 }
 
 #[async_trait]
@@ -46,56 +112,85 @@ where
     type Context = Context;
 
     async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
-        ctx.set_cluster(crate::CLUSTER_NAME).await
+        ctx.set_cluster(crate::CLUSTER_NAME).await?;
+
+        self.registry
+            .add_receiver_processor(WebSocketReceiverInfo::new(
+                ctx.address(),
+                self.addresses.sender_address().clone(),
+                self.socket_address,
+                self.mode,
+                self.flow_control_id.clone(),
+            ));
+
+        Ok(())
+    }
+
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        self.registry.remove_receiver_processor(&ctx.address());
+
+        Ok(())
     }
 
     /// Get next message from the WebSocket stream if there is
     /// any available, and forward it to the next hop in the route.
+    ///
+    /// Fragmented messages are reassembled by the WebSocket protocol
+    /// implementation, so every binary message carries exactly one
+    /// `TransportMessage`.
     async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
         // Get next message from the stream or abort if the stream is
         // either closed or exhausted.
-        let ws_msg = match self.ws_stream.next().await {
-            Some(res) => match res {
-                Ok(ws_msg) => ws_msg,
-                Err(_e) => {
-                    info!(
-                        "Connection to peer '{}' was closed; dropping stream",
-                        self.peer_addr
-                    );
-                    return Ok(false);
-                }
-            },
-            None => {
+        let encoded_msg = match self.ws_stream.next().await {
+            Some(Ok(WebSocketMessage::Binary(data))) => data,
+            // Control frames are answered by the protocol implementation
+            Some(Ok(WebSocketMessage::Ping(_))) | Some(Ok(WebSocketMessage::Pong(_))) => {
+                return Ok(true)
+            }
+            Some(Ok(WebSocketMessage::Text(_))) | Some(Ok(WebSocketMessage::Frame(_))) => {
+                warn!(
+                    "Unexpected non-binary message from peer '{}'; dropping it",
+                    self.socket_address
+                );
+                return Ok(true);
+            }
+            Some(Ok(WebSocketMessage::Close(_))) | Some(Err(_)) | None => {
                 info!(
-                    "Stream connected to peer '{}' is exhausted; dropping stream",
-                    self.peer_addr
+                    "Connection to peer '{}' was closed; dropping stream",
+                    self.socket_address
                 );
+
+                self.notify_sender_connection_closed(ctx).await?;
+
                 return Ok(false);
             }
         };
 
-        // Extract message payload
-        let encoded_msg = ws_msg.into_data();
-
         // Deserialize the message
         let mut msg =
             TransportMessage::decode(&encoded_msg).map_err(|_| TransportError::RecvBadMessage)?;
 
         // Heartbeat message
         if msg.onward_route.next().is_err() {
-            trace!("Got heartbeat message from: {}", self.peer_addr);
+            trace!("Got heartbeat message from: {}", self.socket_address);
+            return Ok(true);
         }
 
         // Insert the peer address into the return route so that
         // reply routing can be properly resolved
-        msg.return_route.modify().prepend(self.peer_addr.clone());
+        msg.return_route
+            .modify()
+            .prepend(self.addresses.sender_address().clone());
 
-        // Some verbose logging we may want to remove
         trace!("Message onward route: {}", msg.onward_route);
         trace!("Message return route: {}", msg.return_route);
 
         // Forward the message to the next hop in the route
-        ctx.forward(LocalMessage::new(msg, Vec::new())).await?;
+        ctx.forward_from_address(
+            LocalMessage::new(msg, Vec::new()),
+            self.addresses.receiver_address().clone(),
+        )
+        .await?;
 
         Ok(true)
     }
@@ -1,311 +1,273 @@
-use std::net::SocketAddr;
-use std::sync::Arc;
-use std::time::Duration;
-
-use futures_util::stream::{SplitSink, SplitStream};
-use futures_util::{SinkExt, StreamExt};
+use futures_util::stream::SplitSink;
+use futures_util::SinkExt;
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::{Data, OpCode};
+use tokio_tungstenite::tungstenite::protocol::frame::Frame;
 use tokio_tungstenite::tungstenite::protocol::Message as WebSocketMessage;
 
-use crate::error::WebSocketError;
+use ockam_core::compat::{net::SocketAddr, sync::Arc};
+use ockam_core::flow_control::FlowControlId;
 use ockam_core::{
-    async_trait, route, Address, AllowAll, Any, Decodable, Encodable, LocalMessage, Mailbox,
-    Mailboxes, Result, Routed, TransportMessage, Worker,
+    async_trait, AllowSourceAddress, Any, Decodable, DenyAll, Encodable, IncomingAccessControl,
+    Mailbox, Mailboxes, Message, Result, Routed, TransportMessage, Worker,
 };
-use ockam_node::{Context, DelayedEvent, WorkerBuilder};
+use ockam_node::{Context, WorkerBuilder};
 use ockam_transport_core::TransportError;
 
-use crate::workers::{
-    AsyncStream, TcpClientStream, TcpServerStream, WebSocketRecvProcessor, WebSocketStream,
-};
-use crate::WebSocketAddress;
-
-/// Transmit and receive peers of a WebSocket connection.
-#[derive(Debug)]
-pub(crate) struct WorkerPair {
-    hostnames: Vec<String>,
-    peer: Address,
-    tx_addr: Address,
+use crate::workers::{Addresses, AsyncStream, WebSocketStream};
+use crate::{WebSocketConnectionMode, WebSocketRegistry, WebSocketSenderInfo};
+
+/// Maximum payload of a single WebSocket frame. Bigger messages are split into a binary frame
+/// followed by continuation frames, which the receiving side reassembles into one message
+pub(crate) const MAX_FRAME_PAYLOAD_SIZE: usize = 64 * 1024;
+
+#[derive(Serialize, Deserialize, Message, Clone)]
+pub(crate) enum WebSocketSendWorkerMsg {
+    ConnectionClosed,
 }
 
-impl WorkerPair {
-    pub(crate) fn hostnames(&self) -> &[String] {
-        &self.hostnames
-    }
-    pub(crate) fn peer(&self) -> Address {
-        self.peer.clone()
-    }
-    pub(crate) fn tx_addr(&self) -> Address {
-        self.tx_addr.clone()
+/// A WebSocket sending message worker.
+///
+/// This half of the worker is created when spawning a new connection
+/// worker pair, and listens for messages from the node message system
+/// to dispatch to a remote peer.
+pub(crate) struct WebSocketSendWorker<S>
+where
+    S: AsyncStream,
+{
+    registry: WebSocketRegistry,
+    ws_sink: SplitSink<WebSocketStream<S>, WebSocketMessage>,
+    socket_address: SocketAddr,
+    addresses: Addresses,
+    mode: WebSocketConnectionMode,
+    receiver_flow_control_id: FlowControlId,
+    rx_should_be_stopped: bool,
+}
+
+impl<S> WebSocketSendWorker<S>
+where
+    S: AsyncStream,
+{
+    /// Create a new `WebSocketSendWorker`
+    fn new(
+        registry: WebSocketRegistry,
+        ws_sink: SplitSink<WebSocketStream<S>, WebSocketMessage>,
+        socket_address: SocketAddr,
+        addresses: Addresses,
+        mode: WebSocketConnectionMode,
+        receiver_flow_control_id: FlowControlId,
+    ) -> Self {
+        Self {
+            registry,
+            ws_sink,
+            socket_address,
+            addresses,
+            mode,
+            receiver_flow_control_id,
+            rx_should_be_stopped: true,
+        }
     }
 
-    /// Spawn instances of `WebSocketSendWorker` and `WebSocketRecvProcessor` and
-    /// returns a `WorkerPair` instance that will be registered by the `WebSocketRouter`.
-    ///
-    /// The WebSocket stream is created when the `WebSocketSendWorker` is initialized.
-    pub(crate) async fn from_client(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn start(
         ctx: &Context,
-        peer: SocketAddr,
-        hostnames: Vec<String>,
-    ) -> Result<WorkerPair> {
-        trace!("Creating new WS worker pair");
-
-        let internal_addr = Address::random_tagged("WebSocketSender.internal.from_client");
-        let sender = WebSocketSendWorker::<TcpClientStream>::new(
-            peer,
-            internal_addr.clone(),
-            DelayedEvent::create(ctx, internal_addr.clone(), vec![]).await?,
+        registry: WebSocketRegistry,
+        ws_sink: SplitSink<WebSocketStream<S>, WebSocketMessage>,
+        addresses: &Addresses,
+        socket_address: SocketAddr,
+        mode: WebSocketConnectionMode,
+        sender_incoming_access_control: Arc<dyn IncomingAccessControl>,
+        receiver_flow_control_id: &FlowControlId,
+    ) -> Result<()> {
+        trace!("Creating new WebSocket worker pair");
+        let sender_worker = Self::new(
+            registry,
+            ws_sink,
+            socket_address,
+            addresses.clone(),
+            mode,
+            receiver_flow_control_id.clone(),
         );
 
-        let tx_addr = Address::random_tagged("WebSocketSender.tx_addr.from_client");
-
-        let mailboxes = Mailboxes::new(
-            Mailbox::new(
-                tx_addr.clone(),
-                Arc::new(AllowAll), // FIXME: @ac
-                Arc::new(AllowAll), // FIXME: @ac
-            ),
-            vec![Mailbox::new(
-                internal_addr,
-                Arc::new(AllowAll), // FIXME: @ac
-                Arc::new(AllowAll), // FIXME: @ac
-            )],
+        let main_mailbox = Mailbox::new(
+            addresses.sender_address().clone(),
+            sender_incoming_access_control,
+            Arc::new(DenyAll),
         );
-        WorkerBuilder::new(sender)
-            .with_mailboxes(mailboxes)
-            .start(ctx)
-            .await?;
 
-        // Return a handle to the worker pair
-        Ok(WorkerPair {
-            hostnames,
-            peer: WebSocketAddress::from(peer).into(),
-            tx_addr,
-        })
-    }
-
-    /// Spawn instances of `WebSocketSendWorker` and `WebSocketRecvProcessor` and
-    /// returns a `WorkerPair` instance that will be registered by the `WebSocketRouter`.
-    pub(crate) async fn from_server(
-        ctx: &Context,
-        stream: WebSocketStream<TcpServerStream>,
-        peer: SocketAddr,
-        hostnames: Vec<String>,
-    ) -> Result<WorkerPair> {
-        trace!("Creating new WS worker pair");
-
-        let internal_addr = Address::random_tagged("WebSocketSender.internal.from_server");
-        let sender = WebSocketSendWorker::<TcpServerStream>::new(
-            stream,
-            peer,
-            internal_addr.clone(),
-            DelayedEvent::create(ctx, internal_addr.clone(), vec![]).await?,
+        let internal_mailbox = Mailbox::new(
+            addresses.sender_internal_address().clone(),
+            Arc::new(AllowSourceAddress(
+                addresses.receiver_internal_address().clone(),
+            )),
+            Arc::new(DenyAll),
         );
 
-        let tx_addr = Address::random_tagged("WebSocketSender.tx_addr.from_server");
-        let mailboxes = Mailboxes::new(
-            Mailbox::new(
-                tx_addr.clone(),
-                Arc::new(AllowAll), // FIXME: @ac
-                Arc::new(AllowAll), // FIXME: @ac
-            ),
-            vec![Mailbox::new(
-                internal_addr,
-                Arc::new(AllowAll), // FIXME: @ac
-                Arc::new(AllowAll), // FIXME: @ac
-            )],
-        );
-        WorkerBuilder::new(sender)
-            .with_mailboxes(mailboxes)
+        WorkerBuilder::new(sender_worker)
+            .with_mailboxes(Mailboxes::new(main_mailbox, vec![internal_mailbox]))
             .start(ctx)
             .await?;
 
-        // Return a handle to the worker pair
-        Ok(WorkerPair {
-            hostnames,
-            peer: WebSocketAddress::from(peer).into(),
-            tx_addr,
-        })
+        Ok(())
     }
-}
 
-/// A WebSocket sending message worker.
-///
-/// This half of the worker is created when spawning a new connection
-/// worker pair, and listens for messages from the node message system
-/// to dispatch to a remote peer.
-pub(crate) struct WebSocketSendWorker<S>
-where
-    S: AsyncStream,
-{
-    ws_stream: Option<SplitStream<WebSocketStream<S>>>,
-    ws_sink: Option<SplitSink<WebSocketStream<S>, WebSocketMessage>>,
-    peer: SocketAddr,
-    internal_addr: Address,
-    heartbeat: DelayedEvent<Vec<u8>>,
-    heartbeat_interval: Option<Duration>,
+    async fn stop(&self, ctx: &Context) -> Result<()> {
+        ctx.stop_worker(self.addresses.sender_address().clone())
+            .await
+    }
 }
 
-impl<S> WebSocketSendWorker<S>
+#[async_trait]
+impl<S> Worker for WebSocketSendWorker<S>
 where
     S: AsyncStream,
 {
-    async fn handle_initialize(&mut self, ctx: &mut Context) -> Result<()> {
-        if let Some(ws_stream) = self.ws_stream.take() {
-            let rx_addr = Address::random_tagged("WebSocketSendWorker.rx_addr");
-            let receiver = WebSocketRecvProcessor::new(ws_stream, self.peer);
-            ctx.start_processor_with_access_control(
-                rx_addr.clone(),
-                receiver,
-                AllowAll, // FIXME: @ac
-                AllowAll, // FIXME: @ac
-            )
-            .await?;
-        } else {
-            return Err(TransportError::GenericIo.into());
-        }
+    type Context = Context;
+    type Message = Any;
 
+    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
         ctx.set_cluster(crate::CLUSTER_NAME).await?;
-        self.schedule_heartbeat().await?;
+
+        self.registry.add_sender_worker(WebSocketSenderInfo::new(
+            self.addresses.sender_address().clone(),
+            self.addresses.receiver_address().clone(),
+            self.socket_address,
+            self.mode,
+            self.receiver_flow_control_id.clone(),
+        ));
+
         Ok(())
     }
 
-    async fn schedule_heartbeat(&mut self) -> Result<()> {
-        let heartbeat_interval = match &self.heartbeat_interval {
-            Some(hi) => *hi,
-            None => return Ok(()),
-        };
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        self.registry
+            .remove_sender_worker(self.addresses.sender_address());
 
-        self.heartbeat.schedule(heartbeat_interval).await
-    }
+        let _ = self.ws_sink.close().await;
 
-    /// Receive messages from the `WebSocketRouter` to send
-    /// across the `WebSocketStream` to the next remote peer.
-    async fn handle_msg(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
-        self.heartbeat.cancel();
+        if self.rx_should_be_stopped {
+            let _ = ctx
+                .stop_processor(self.addresses.receiver_address().clone())
+                .await;
+        }
 
-        let ws_sink = if let Some(ws_sink) = &mut self.ws_sink {
-            ws_sink
-        } else {
-            return Err(TransportError::PeerNotFound.into());
-        };
+        Ok(())
+    }
 
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
         let recipient = msg.msg_addr();
-        if recipient == self.internal_addr {
-            let msg = TransportMessage::v1(route![], route![], vec![]);
-            // Sending empty heartbeat
-            if ws_sink
-                .send(WebSocketMessage::from(msg.encode()?))
-                .await
-                .is_err()
-            {
-                warn!("Failed to send heartbeat to peer {}", self.peer);
-                ctx.stop_worker(ctx.address()).await?;
-
-                return Ok(());
+        if &recipient == self.addresses.sender_internal_address() {
+            let msg = WebSocketSendWorkerMsg::decode(msg.payload())?;
+
+            match msg {
+                WebSocketSendWorkerMsg::ConnectionClosed => {
+                    info!(
+                        "Stopping sender due to closed connection {}",
+                        self.socket_address
+                    );
+                    // No need to stop Receiver as it notified us about connection drop and will
+                    // stop itself
+                    self.rx_should_be_stopped = false;
+                    self.stop(ctx).await?;
+
+                    return Ok(());
+                }
             }
-            debug!("Sent heartbeat to peer {}", self.peer);
         } else {
-            let mut msg = LocalMessage::decode(msg.payload())?.into_transport_message();
-
+            let mut msg = msg.into_transport_message();
             // Remove our own address from the route so the other end
             // knows what to do with the incoming message
             msg.onward_route.step()?;
 
-            let msg = WebSocketMessage::from(msg.encode()?);
-            if ws_sink.send(msg).await.is_err() {
-                warn!("Failed to send message to peer {}", self.peer);
-                ctx.stop_worker(ctx.address()).await?;
-                return Ok(());
-            }
-            debug!("Sent message to peer {}", self.peer);
-        }
+            for frame in prepare_frames(msg)? {
+                if self.ws_sink.feed(frame).await.is_err() {
+                    warn!("Failed to send message to peer {}", self.socket_address);
+                    self.stop(ctx).await?;
 
-        self.schedule_heartbeat().await?;
+                    return Ok(());
+                }
+            }
 
-        Ok(())
-    }
-}
+            if self.ws_sink.flush().await.is_err() {
+                warn!("Failed to send message to peer {}", self.socket_address);
+                self.stop(ctx).await?;
 
-impl WebSocketSendWorker<TcpServerStream> {
-    fn new(
-        stream: WebSocketStream<TcpServerStream>,
-        peer: SocketAddr,
-        internal_addr: Address,
-        heartbeat: DelayedEvent<Vec<u8>>,
-    ) -> Self {
-        let (ws_sink, ws_stream) = stream.split();
-        Self {
-            ws_sink: Some(ws_sink),
-            ws_stream: Some(ws_stream),
-            peer,
-            internal_addr,
-            heartbeat,
-            heartbeat_interval: None,
-        }
-    }
-}
+                return Ok(());
+            }
 
-impl WebSocketSendWorker<TcpClientStream> {
-    fn new(peer: SocketAddr, internal_addr: Address, heartbeat: DelayedEvent<Vec<u8>>) -> Self {
-        Self {
-            ws_stream: None,
-            ws_sink: None,
-            peer,
-            internal_addr,
-            heartbeat,
-            heartbeat_interval: None,
+            debug!("Sent message to peer {}", self.socket_address);
         }
-    }
 
-    async fn initialize_stream(&mut self) -> Result<()> {
-        if self.ws_stream.is_none() {
-            let peer = WebSocketAddress::from(self.peer).to_string();
-            let (stream, _) = tokio_tungstenite::connect_async(peer)
-                .await
-                .map_err(WebSocketError::from)?;
-            let (ws_sink, ws_stream) = stream.split();
-            self.ws_sink = Some(ws_sink);
-            self.ws_stream = Some(ws_stream);
-        }
         Ok(())
     }
 }
 
-#[async_trait]
-impl Worker for WebSocketSendWorker<TcpServerStream> {
-    type Message = Any;
-    type Context = Context;
+/// Map one `TransportMessage` to one WebSocket binary message.
+///
+/// Messages which don't fit into a single frame are fragmented into a binary frame followed
+/// by continuation frames, as defined by RFC 6455 section 5.4.
+pub(crate) fn prepare_frames(msg: TransportMessage) -> Result<Vec<WebSocketMessage>> {
+    let msg_buf = msg.encode().map_err(|_| TransportError::SendBadMessage)?;
 
-    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
-        self.handle_initialize(ctx).await?;
-        Ok(())
+    if msg_buf.len() <= MAX_FRAME_PAYLOAD_SIZE {
+        return Ok(vec![WebSocketMessage::Binary(msg_buf)]);
     }
 
-    async fn handle_message(
-        &mut self,
-        ctx: &mut Context,
-        msg: Routed<Self::Message>,
-    ) -> Result<()> {
-        self.handle_msg(ctx, msg).await
-    }
+    let chunks = msg_buf.chunks(MAX_FRAME_PAYLOAD_SIZE);
+    let last = chunks.len() - 1;
+
+    let frames = chunks
+        .enumerate()
+        .map(|(i, chunk)| {
+            let opcode = if i == 0 {
+                OpCode::Data(Data::Binary)
+            } else {
+                OpCode::Data(Data::Continue)
+            };
+            WebSocketMessage::Frame(Frame::message(chunk.to_vec(), opcode, i == last))
+        })
+        .collect();
+
+    Ok(frames)
 }
 
-#[async_trait]
-impl Worker for WebSocketSendWorker<TcpClientStream> {
-    type Message = Any;
-    type Context = Context;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::route;
 
-    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
-        self.initialize_stream().await?;
-        self.handle_initialize(ctx).await?;
-        Ok(())
+    #[test]
+    fn small_message_is_sent_as_a_single_binary_frame() {
+        let msg = TransportMessage::v1(route!["a"], route![], vec![1; 10]);
+        let frames = prepare_frames(msg).unwrap();
+
+        assert_eq!(frames.len(), 1);
+        assert!(matches!(frames[0], WebSocketMessage::Binary(_)));
     }
 
-    async fn handle_message(
-        &mut self,
-        ctx: &mut Context,
-        msg: Routed<Self::Message>,
-    ) -> Result<()> {
-        self.handle_msg(ctx, msg).await
+    #[test]
+    fn oversized_message_is_fragmented() {
+        let msg = TransportMessage::v1(route!["a"], route![], vec![1; 3 * MAX_FRAME_PAYLOAD_SIZE]);
+        let frames = prepare_frames(msg).unwrap();
+
+        assert_eq!(frames.len(), 4);
+        for (i, frame) in frames.iter().enumerate() {
+            match frame {
+                WebSocketMessage::Frame(frame) => {
+                    assert_eq!(frame.header().is_final, i == frames.len() - 1);
+                    let expected_opcode = if i == 0 {
+                        OpCode::Data(Data::Binary)
+                    } else {
+                        OpCode::Data(Data::Continue)
+                    };
+                    assert_eq!(frame.header().opcode, expected_opcode);
+                }
+                _ => panic!("expected a raw frame"),
+            }
+        }
     }
 }
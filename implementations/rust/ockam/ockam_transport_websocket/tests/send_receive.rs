@@ -1,23 +1,50 @@
 use ockam_core::compat::rand::{self, Rng};
-use ockam_core::{route, Result, Routed, Worker};
-use ockam_node::Context;
-use ockam_transport_websocket::{WebSocketTransport, WS};
+use ockam_core::{route, Address, AllowAll, Result, Routed, Worker};
+use ockam_node::{Context, MessageReceiveOptions};
+use ockam_transport_websocket::{
+    WebSocketConnectionOptions, WebSocketListenerOptions, WebSocketTransport, WS,
+};
+
+pub struct Echoer;
+
+#[ockam_core::worker]
+impl Worker for Echoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
 
-#[ignore]
 #[ockam_macros::test]
 async fn send_receive(ctx: &mut Context) -> Result<()> {
-    let transport = WebSocketTransport::create(ctx).await?;
-    let listener_address = transport.listen("127.0.0.1:0").await?;
+    let options = WebSocketListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
     ctx.start_worker("echoer", Echoer).await?;
 
+    let transport = WebSocketTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let addr = transport
+        .connect(listener.socket_string(), WebSocketConnectionOptions::new())
+        .await?
+        .sender_address()
+        .clone();
+
     // Sender
     {
-        let msg: String = rand::thread_rng()
-            .sample_iter(&rand::distributions::Alphanumeric)
-            .take(256)
-            .map(char::from)
-            .collect();
-        let r = route![(WS, listener_address.to_string()), "echoer"];
+        let msg = random_string(256);
+        let r = route![addr, "echoer"];
         let reply = ctx.send_and_receive::<String>(r, msg.clone()).await?;
 
         assert_eq!(reply, msg, "Should receive the same message");
@@ -30,14 +57,104 @@ async fn send_receive(ctx: &mut Context) -> Result<()> {
     Ok(())
 }
 
-pub struct Echoer;
+#[ockam_macros::test]
+async fn send_receive_with_lazy_connection(ctx: &mut Context) -> Result<()> {
+    let options = WebSocketListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
 
-#[ockam_core::worker]
-impl Worker for Echoer {
-    type Message = String;
-    type Context = Context;
+    let transport = WebSocketTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
 
-    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
-        ctx.send(msg.return_route(), msg.body()).await
+    let msg = random_string(256);
+    let r = ctx
+        .resolve_transport_route(route![(WS, listener.socket_string()), "echoer"])
+        .await?;
+    let reply = ctx.send_and_receive::<String>(r, msg.clone()).await?;
+
+    assert_eq!(reply, msg, "Should receive the same message");
+    assert_eq!(transport.registry().get_all_sender_workers().len(), 2);
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
     }
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn send_receive_oversized_message(ctx: &mut Context) -> Result<()> {
+    let options = WebSocketListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = WebSocketTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let addr = transport
+        .connect(listener.socket_string(), WebSocketConnectionOptions::new())
+        .await?
+        .sender_address()
+        .clone();
+
+    // Bigger than a single frame, so it's fragmented on the wire
+    let msg = random_string(1024 * 1024);
+    let r = route![addr, "echoer"];
+    let reply = ctx.send_and_receive::<String>(r, msg.clone()).await?;
+
+    assert_eq!(reply, msg, "Should receive the same message");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn message_flow__listener_without_consumer__should_not_pass(ctx: &mut Context) -> Result<()> {
+    let transport = WebSocketTransport::create(ctx).await?;
+    let listener = transport
+        .listen("127.0.0.1:0", WebSocketListenerOptions::new())
+        .await?;
+
+    let connection = transport
+        .connect(listener.socket_string(), WebSocketConnectionOptions::new())
+        .await?;
+
+    // Not a consumer of the listener's flow control
+    let address = Address::random_local();
+    let mut receiving_ctx = ctx
+        .new_detached(address.clone(), AllowAll, AllowAll)
+        .await?;
+
+    ctx.send(
+        route![connection.sender_address().clone(), address.clone()],
+        "Hello".to_string(),
+    )
+    .await?;
+    let res = receiving_ctx
+        .receive_extended::<String>(MessageReceiveOptions::new().with_timeout_secs(1))
+        .await;
+    assert!(res.is_err(), "Messages should not pass for given route");
+
+    ctx.flow_controls()
+        .add_consumer(address.clone(), listener.flow_control_id());
+
+    ctx.send(
+        route![connection.sender_address().clone(), address],
+        "Hello".to_string(),
+    )
+    .await?;
+    let msg = receiving_ctx.receive::<String>().await?.body();
+    assert_eq!(msg, "Hello");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
 }
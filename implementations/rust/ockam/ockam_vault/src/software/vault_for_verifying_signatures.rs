@@ -111,3 +111,60 @@ impl SoftwareVaultForVerifyingSignatures {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SigningKeyType, SoftwareVaultForSigning, VaultForSigning};
+
+    #[tokio::test]
+    async fn test_eddsa_curve25519_sign_and_verify() -> Result<()> {
+        let signing_vault = SoftwareVaultForSigning::create();
+        let verifying_vault = SoftwareVaultForVerifyingSignatures::create();
+
+        let key = signing_vault
+            .generate_signing_secret_key(SigningKeyType::EdDSACurve25519)
+            .await?;
+        let public_key = signing_vault.get_verifying_public_key(&key).await?;
+        assert!(matches!(public_key, VerifyingPublicKey::EdDSACurve25519(_)));
+
+        let signature = signing_vault.sign(&key, b"hello").await?;
+        assert!(matches!(signature, Signature::EdDSACurve25519(_)));
+
+        assert!(
+            verifying_vault
+                .verify_signature(&public_key, b"hello", &signature)
+                .await?
+        );
+        assert!(
+            !verifying_vault
+                .verify_signature(&public_key, b"tampered", &signature)
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_mismatched_key_type_fails() -> Result<()> {
+        let signing_vault = SoftwareVaultForSigning::create();
+        let verifying_vault = SoftwareVaultForVerifyingSignatures::create();
+
+        let eddsa_key = signing_vault
+            .generate_signing_secret_key(SigningKeyType::EdDSACurve25519)
+            .await?;
+        let ecdsa_key = signing_vault
+            .generate_signing_secret_key(SigningKeyType::ECDSASHA256CurveP256)
+            .await?;
+        let ecdsa_public_key = signing_vault.get_verifying_public_key(&ecdsa_key).await?;
+
+        let signature = signing_vault.sign(&eddsa_key, b"hello").await?;
+
+        let result = verifying_vault
+            .verify_signature(&ecdsa_public_key, b"hello", &signature)
+            .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use ockam_core::errcode::{Kind, Origin};
+use thiserror::Error;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("pkcs11 module could not be loaded: {0}")]
+    LoadModule(String),
+    #[error("pkcs11 slot {0} was not found or has no token")]
+    SlotNotFound(u64),
+    #[error("pkcs11 session could not be opened: {0}")]
+    OpenSession(String),
+    #[error("pkcs11 login failed: {0}")]
+    Login(String),
+    #[error("pkcs11 token is disconnected, the operation can be retried: {0}")]
+    TokenDisconnected(String),
+    #[error("pkcs11 error creating new key: {0}")]
+    Create(String),
+    #[error("pkcs11 error signing message: {0}")]
+    Sign(String),
+    #[error("pkcs11 error exporting public key: {0}")]
+    Export(String),
+    #[error("pkcs11 error deleting key: {0}")]
+    Delete(String),
+    #[error("pkcs11 error listing keys: {0}")]
+    ListKeys(String),
+    #[error("key type is not supported")]
+    UnsupportedKeyType,
+    #[error("public key is incorrect")]
+    InvalidPublicKey,
+    #[error("signature is incorrect")]
+    InvalidSignature,
+    #[error("key was not found")]
+    KeyNotFound,
+    #[error("invalid handle")]
+    InvalidHandle,
+    #[error("pkcs11 operation was interrupted: {0}")]
+    Interrupted(String),
+}
+
+impl From<Error> for ockam_core::Error {
+    fn from(e: Error) -> Self {
+        let kind = match e {
+            // The token may be reconnected, see the `Kind::Timeout` documentation
+            Error::TokenDisconnected(_) => Kind::Timeout,
+            Error::UnsupportedKeyType => Kind::Unsupported,
+            Error::KeyNotFound | Error::SlotNotFound(_) => Kind::NotFound,
+            Error::InvalidHandle => Kind::Invalid,
+            _ => Kind::Io,
+        };
+        ockam_core::Error::new(Origin::Vault, kind, e)
+    }
+}
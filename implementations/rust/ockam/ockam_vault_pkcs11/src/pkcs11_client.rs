@@ -0,0 +1,358 @@
+use crate::error::Error;
+use core::fmt;
+use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::error::{Error as CryptokiError, RvError};
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::types::AuthPin;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::rand::random;
+use ockam_core::compat::sync::Mutex;
+use ockam_core::Result;
+use ockam_vault::{
+    ECDSASHA256CurveP256PublicKey, ECDSASHA256CurveP256Signature, HandleToSecret, Signature,
+    SigningSecretKeyHandle, VerifyingPublicKey,
+};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use tracing as log;
+
+/// DER encoding of the NIST P-256 curve OID, used as `CKA_EC_PARAMS`
+const P256_EC_PARAMS: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+/// Length of the `CKA_ID` of the keys created by this client
+const KEY_ID_LENGTH: usize = 16;
+
+/// PKCS#11 modules loaded by this process, a module can only be initialized once
+static MODULES: Mutex<BTreeMap<PathBuf, Pkcs11>> = Mutex::new(BTreeMap::new());
+
+/// PKCS#11 token configuration.
+#[derive(Clone)]
+pub struct Pkcs11Config {
+    module_path: PathBuf,
+    slot: u64,
+    pin: String,
+}
+
+impl Pkcs11Config {
+    /// Create a new configuration for a PKCS#11 token
+    pub fn new(module_path: impl Into<PathBuf>, slot: u64, pin: impl Into<String>) -> Self {
+        Self {
+            module_path: module_path.into(),
+            slot,
+            pin: pin.into(),
+        }
+    }
+
+    /// Path of the PKCS#11 module (shared library) provided by the token vendor
+    pub fn module_path(&self) -> &PathBuf {
+        &self.module_path
+    }
+
+    /// Id of the slot containing the token
+    pub fn slot(&self) -> u64 {
+        self.slot
+    }
+}
+
+impl fmt::Debug for Pkcs11Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pkcs11Config")
+            .field("module_path", &self.module_path)
+            .field("slot", &self.slot)
+            .field("pin", &"<redacted>")
+            .finish()
+    }
+}
+
+/// PKCS#11 client.
+///
+/// All the calls to the token go through a single logged-in session. If the token
+/// gets disconnected, the session is dropped, the failing call returns a
+/// [`Error::TokenDisconnected`] error, and a new session is opened on the next call.
+///
+/// The calls to the token are blocking, [`crate::Pkcs11SigningVault`] runs them with
+/// `tokio::task::spawn_blocking`.
+pub struct Pkcs11Client {
+    pkcs11: Pkcs11,
+    config: Pkcs11Config,
+    session: Mutex<Option<Session>>,
+}
+
+impl Pkcs11Client {
+    /// Load the PKCS#11 module and log into the token.
+    /// Fails if the module can't be loaded, the slot doesn't exist or the PIN is incorrect.
+    ///
+    /// The module is only loaded and initialized once per process, the clients for the
+    /// same module share it.
+    pub fn new(config: Pkcs11Config) -> Result<Pkcs11Client> {
+        let pkcs11 = Self::load_module(&config)?;
+        let session = Self::open_session(&pkcs11, &config)?;
+
+        Ok(Self {
+            pkcs11,
+            config,
+            session: Mutex::new(Some(session)),
+        })
+    }
+
+    fn load_module(config: &Pkcs11Config) -> Result<Pkcs11> {
+        let mut modules = MODULES.lock().unwrap();
+        if let Some(pkcs11) = modules.get(&config.module_path) {
+            return Ok(pkcs11.clone());
+        }
+
+        let pkcs11 =
+            Pkcs11::new(&config.module_path).map_err(|err| Error::LoadModule(err.to_string()))?;
+        match pkcs11.initialize(CInitializeArgs::OsThreads) {
+            Ok(()) => {}
+            // The module was initialized by another library of this process
+            Err(CryptokiError::Pkcs11(RvError::CryptokiAlreadyInitialized, _)) => {
+                log::debug!(module = ?config.module_path, "pkcs11 module already initialized");
+            }
+            Err(err) => return Err(Error::LoadModule(err.to_string()).into()),
+        }
+
+        modules.insert(config.module_path.clone(), pkcs11.clone());
+        Ok(pkcs11)
+    }
+
+    fn open_session(pkcs11: &Pkcs11, config: &Pkcs11Config) -> Result<Session> {
+        let slot = pkcs11
+            .get_slots_with_token()
+            .map_err(|err| Error::OpenSession(err.to_string()))?
+            .into_iter()
+            .find(|slot| slot.id() == config.slot)
+            .ok_or(Error::SlotNotFound(config.slot))?;
+
+        let session = pkcs11
+            .open_rw_session(slot)
+            .map_err(|err| Error::OpenSession(err.to_string()))?;
+
+        session
+            .login(UserType::User, Some(&AuthPin::new(config.pin.clone())))
+            .map_err(|err| {
+                log::error!(slot = config.slot, %err, "failed to log into the pkcs11 token");
+                Error::Login(err.to_string())
+            })?;
+
+        log::debug!(slot = config.slot, "logged into the pkcs11 token");
+        Ok(session)
+    }
+
+    /// Run an operation with the current session, opening a new one if the previous one
+    /// was lost
+    fn with_session<T>(
+        &self,
+        operation: impl FnOnce(&Session) -> core::result::Result<T, CryptokiError>,
+        to_error: impl FnOnce(String) -> Error,
+    ) -> Result<T> {
+        let mut session = self.session.lock().unwrap();
+        if session.is_none() {
+            *session = Some(
+                Self::open_session(&self.pkcs11, &self.config)
+                    .map_err(|err| Error::TokenDisconnected(err.to_string()))?,
+            );
+        }
+
+        match operation(session.as_ref().unwrap()) {
+            Ok(result) => Ok(result),
+            Err(err) if Self::is_disconnection(&err) => {
+                log::warn!(slot = self.config.slot, %err, "pkcs11 token disconnected");
+                *session = None;
+                Err(Error::TokenDisconnected(err.to_string()).into())
+            }
+            Err(err) => Err(to_error(err.to_string()).into()),
+        }
+    }
+
+    fn is_disconnection(err: &CryptokiError) -> bool {
+        matches!(
+            err,
+            CryptokiError::Pkcs11(
+                RvError::DeviceRemoved
+                    | RvError::DeviceError
+                    | RvError::TokenNotPresent
+                    | RvError::SessionClosed
+                    | RvError::SessionHandleInvalid
+                    | RvError::UserNotLoggedIn,
+                _
+            )
+        )
+    }
+
+    fn cast_handle_to_key_id(handle: &SigningSecretKeyHandle) -> Result<Vec<u8>> {
+        match handle {
+            SigningSecretKeyHandle::EdDSACurve25519(_) => Err(Error::InvalidHandle.into()),
+            SigningSecretKeyHandle::ECDSASHA256CurveP256(handle) => Ok(handle.value().clone()),
+        }
+    }
+
+    fn find_key(
+        session: &Session,
+        class: ObjectClass,
+        key_id: &[u8],
+    ) -> core::result::Result<Option<ObjectHandle>, CryptokiError> {
+        Ok(session
+            .find_objects(&[Attribute::Class(class), Attribute::Id(key_id.to_vec())])?
+            .into_iter()
+            .next())
+    }
+
+    /// Create a new NIST P-256 key-pair on the token and return its handle.
+    pub fn create_key(&self) -> Result<SigningSecretKeyHandle> {
+        log::trace!("create new key");
+        let key_id = random::<[u8; KEY_ID_LENGTH]>().to_vec();
+        let public_key_template = [
+            Attribute::Token(true),
+            Attribute::Verify(true),
+            Attribute::EcParams(P256_EC_PARAMS.to_vec()),
+            Attribute::Id(key_id.clone()),
+        ];
+        let private_key_template = [
+            Attribute::Token(true),
+            Attribute::Private(true),
+            Attribute::Sensitive(true),
+            Attribute::Extractable(false),
+            Attribute::Sign(true),
+            Attribute::Id(key_id.clone()),
+        ];
+
+        self.with_session(
+            |session| {
+                session.generate_key_pair(
+                    &Mechanism::EccKeyPairGen,
+                    &public_key_template,
+                    &private_key_template,
+                )
+            },
+            Error::Create,
+        )?;
+
+        log::debug!(key_id = ?key_id, "created new key");
+        Ok(SigningSecretKeyHandle::ECDSASHA256CurveP256(
+            HandleToSecret::new(key_id),
+        ))
+    }
+
+    /// Delete a key-pair from the token.
+    pub fn delete_key(&self, key: &SigningSecretKeyHandle) -> Result<bool> {
+        let key_id = Self::cast_handle_to_key_id(key)?;
+        log::trace!(key_id = ?key_id, "delete key");
+
+        let objects = self.with_session(
+            |session| {
+                session
+                    .find_objects(&[Attribute::Id(key_id.clone())])?
+                    .into_iter()
+                    .try_fold(0, |deleted, object| {
+                        session.destroy_object(object)?;
+                        Ok(deleted + 1)
+                    })
+            },
+            Error::Delete,
+        )?;
+
+        Ok(objects > 0)
+    }
+
+    /// Get the public key part of a key-pair stored on the token.
+    pub fn public_key(&self, key: &SigningSecretKeyHandle) -> Result<VerifyingPublicKey> {
+        let key_id = Self::cast_handle_to_key_id(key)?;
+        log::trace!(key_id = ?key_id, "get public key");
+
+        let attributes = self.with_session(
+            |session| match Self::find_key(session, ObjectClass::PUBLIC_KEY, &key_id)? {
+                Some(public_key) => session
+                    .get_attributes(
+                        public_key,
+                        &[AttributeType::EcParams, AttributeType::EcPoint],
+                    )
+                    .map(Some),
+                None => Ok(None),
+            },
+            Error::Export,
+        )?;
+        let attributes = attributes.ok_or(Error::KeyNotFound)?;
+
+        let mut ec_params = None;
+        let mut ec_point = None;
+        for attribute in attributes {
+            match attribute {
+                Attribute::EcParams(params) => ec_params = Some(params),
+                Attribute::EcPoint(point) => ec_point = Some(point),
+                _ => {}
+            }
+        }
+
+        if ec_params.as_deref() != Some(P256_EC_PARAMS) {
+            return Err(Error::UnsupportedKeyType.into());
+        }
+
+        let ec_point = ec_point.ok_or(Error::InvalidPublicKey)?;
+        // CKA_EC_POINT is a DER-encoded OCTET STRING, some tokens return the raw point
+        let point = match ec_point.as_slice() {
+            [0x04, 0x41, point @ ..] if point.len() == 65 => point,
+            point => point,
+        };
+        let public_key = point.try_into().map_err(|_| Error::InvalidPublicKey)?;
+
+        Ok(VerifyingPublicKey::ECDSASHA256CurveP256(
+            ECDSASHA256CurveP256PublicKey(public_key),
+        ))
+    }
+
+    /// Sign a message with a key-pair stored on the token.
+    pub fn sign(&self, key: &SigningSecretKeyHandle, message: &[u8]) -> Result<Signature> {
+        let key_id = Self::cast_handle_to_key_id(key)?;
+        log::trace!(key_id = ?key_id, "sign message");
+
+        // CKM_ECDSA signs a digest and returns the raw r || s signature
+        let digest = Sha256::digest(message);
+        let signature = self.with_session(
+            |session| match Self::find_key(session, ObjectClass::PRIVATE_KEY, &key_id)? {
+                Some(key) => session.sign(&Mechanism::Ecdsa, key, &digest).map(Some),
+                None => Ok(None),
+            },
+            Error::Sign,
+        )?;
+
+        let signature = signature.ok_or(Error::KeyNotFound)?;
+        let signature = signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::InvalidSignature)?;
+
+        Ok(Signature::ECDSASHA256CurveP256(
+            ECDSASHA256CurveP256Signature(signature),
+        ))
+    }
+
+    /// List the P-256 key-pairs stored on the token.
+    pub fn list_keys(&self) -> Result<Vec<SigningSecretKeyHandle>> {
+        log::trace!("list keys");
+        let key_ids = self.with_session(
+            |session| {
+                let mut key_ids = vec![];
+                for object in session.find_objects(&[
+                    Attribute::Class(ObjectClass::PRIVATE_KEY),
+                    Attribute::KeyType(KeyType::EC),
+                ])? {
+                    for attribute in session.get_attributes(object, &[AttributeType::Id])? {
+                        if let Attribute::Id(key_id) = attribute {
+                            key_ids.push(key_id)
+                        }
+                    }
+                }
+                Ok(key_ids)
+            },
+            Error::ListKeys,
+        )?;
+
+        Ok(key_ids
+            .into_iter()
+            .map(|key_id| SigningSecretKeyHandle::ECDSASHA256CurveP256(HandleToSecret::new(key_id)))
+            .collect())
+    }
+}
@@ -0,0 +1,180 @@
+use crate::error::Error;
+use crate::pkcs11_client::{Pkcs11Client, Pkcs11Config};
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::{async_trait, Result};
+use ockam_vault::{
+    Signature, SigningKeyType, SigningSecretKeyHandle, VaultError, VaultForSigning,
+    VerifyingPublicKey,
+};
+use tokio::task::spawn_blocking;
+use tracing::error;
+
+struct Pkcs11KeyPair {
+    key: SigningSecretKeyHandle,
+    public_key: VerifyingPublicKey,
+}
+
+/// Security module implementation using a PKCS#11 token
+///
+/// Only signing keys are kept on the token. Use it as the `identity_vault` of an
+/// `ockam_identity::Vault` to keep performing the secure channels cryptography locally.
+pub struct Pkcs11SigningVault {
+    client: Arc<Pkcs11Client>,
+    // Store mapping from PublicKey to the key id on the token in memory
+    // This is fetched at the Vault initialization
+    // and is updated locally during add/delete operations
+    // WARNING: The assumption is that there is no concurrent access to the same keys from
+    // different places.
+    keys: Arc<RwLock<Vec<Pkcs11KeyPair>>>,
+}
+
+impl Pkcs11SigningVault {
+    /// Create a new PKCS#11 security module
+    ///
+    /// Returns an error if the PKCS#11 module can't be loaded or the login to the token fails.
+    pub async fn create(config: Pkcs11Config) -> Result<Self> {
+        let (client, key_pairs) = spawn_blocking(move || Self::load_keys(config))
+            .await
+            .map_err(|err| Error::Interrupted(err.to_string()))??;
+
+        Ok(Self {
+            client: Arc::new(client),
+            keys: Arc::new(RwLock::new(key_pairs)),
+        })
+    }
+
+    fn load_keys(config: Pkcs11Config) -> Result<(Pkcs11Client, Vec<Pkcs11KeyPair>)> {
+        let client = Pkcs11Client::new(config)?;
+
+        let mut key_pairs: Vec<Pkcs11KeyPair> = vec![];
+        // Fetch list of all keys, then fetch the public key for each key
+        let keys = client.list_keys()?;
+
+        for key in keys {
+            match client.public_key(&key) {
+                Ok(public_key) => key_pairs.push(Pkcs11KeyPair { key, public_key }),
+                // The token may contain keys which were not created by Ockam, for example keys
+                // using a different curve. Therefore, the best strategy is to just skip that key
+                Err(err) => error!("Error exporting public key: {err}"),
+            }
+        }
+
+        Ok((client, key_pairs))
+    }
+
+    /// Run a blocking call to the token without blocking the async runtime
+    async fn call<T: Send + 'static>(
+        &self,
+        operation: impl FnOnce(&Pkcs11Client) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let client = self.client.clone();
+        spawn_blocking(move || operation(&client))
+            .await
+            .map_err(|err| Error::Interrupted(err.to_string()))?
+    }
+
+    /// Return list of all keys
+    pub fn keys(&self) -> Vec<SigningSecretKeyHandle> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .map(|x| x.key.clone())
+            .collect()
+    }
+
+    /// Return number of keys
+    pub async fn number_of_keys(&self) -> Result<usize> {
+        Ok(self.keys.read().unwrap().len())
+    }
+}
+
+#[async_trait]
+impl VaultForSigning for Pkcs11SigningVault {
+    async fn sign(
+        &self,
+        signing_secret_key_handle: &SigningSecretKeyHandle,
+        data: &[u8],
+    ) -> Result<Signature> {
+        let key = signing_secret_key_handle.clone();
+        let data = data.to_vec();
+        self.call(move |client| client.sign(&key, &data)).await
+    }
+
+    async fn generate_signing_secret_key(
+        &self,
+        signing_key_type: SigningKeyType,
+    ) -> Result<SigningSecretKeyHandle> {
+        if signing_key_type != SigningKeyType::ECDSASHA256CurveP256 {
+            return Err(VaultError::InvalidKeyType.into());
+        }
+
+        let (key, public_key) = self
+            .call(|client| {
+                let key = client.create_key()?;
+                let public_key = client.public_key(&key)?;
+                Ok((key, public_key))
+            })
+            .await?;
+
+        self.keys.write().unwrap().push(Pkcs11KeyPair {
+            key: key.clone(),
+            public_key,
+        });
+
+        Ok(key)
+    }
+
+    async fn get_verifying_public_key(
+        &self,
+        signing_secret_key_handle: &SigningSecretKeyHandle,
+    ) -> Result<VerifyingPublicKey> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .find_map(|x| {
+                if &x.key == signing_secret_key_handle {
+                    Some(x.public_key.clone())
+                } else {
+                    None
+                }
+            })
+            .ok_or(Error::KeyNotFound.into())
+    }
+
+    async fn get_secret_key_handle(
+        &self,
+        verifying_public_key: &VerifyingPublicKey,
+    ) -> Result<SigningSecretKeyHandle> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .find_map(|x| {
+                if &x.public_key == verifying_public_key {
+                    Some(x.key.clone())
+                } else {
+                    None
+                }
+            })
+            .ok_or(Error::KeyNotFound.into())
+    }
+
+    async fn delete_signing_secret_key(
+        &self,
+        signing_secret_key_handle: SigningSecretKeyHandle,
+    ) -> Result<bool> {
+        let key = signing_secret_key_handle.clone();
+        if self.call(move |client| client.delete_key(&key)).await? {
+            self.keys
+                .write()
+                .unwrap()
+                .retain(|x| x.key != signing_secret_key_handle);
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use ockam_core::errcode::Kind;
+use ockam_core::Result;
+use ockam_vault::{
+    SigningKeyType, SoftwareVaultForVerifyingSignatures, VaultForSigning,
+    VaultForVerifyingSignatures,
+};
+use ockam_vault_pkcs11::{Pkcs11Config, Pkcs11SigningVault};
+
+/// These tests need to be executed with a PKCS#11 token, for example SoftHSM,
+/// configured with the following environment variables
+/// PKCS11_MODULE: path to the PKCS#11 module (e.g. /usr/lib/softhsm/libsofthsm2.so)
+/// PKCS11_SLOT: id of the slot containing the token
+/// PKCS11_PIN: user PIN of the token
+fn config_from_env(pin: Option<&str>) -> Pkcs11Config {
+    let module = std::env::var("PKCS11_MODULE").expect("PKCS11_MODULE is not set");
+    let slot = std::env::var("PKCS11_SLOT")
+        .expect("PKCS11_SLOT is not set")
+        .parse()
+        .expect("PKCS11_SLOT is not a number");
+    let pin = match pin {
+        Some(pin) => pin.to_string(),
+        None => std::env::var("PKCS11_PIN").expect("PKCS11_PIN is not set"),
+    };
+    Pkcs11Config::new(module, slot, pin)
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_sign_verify() -> Result<()> {
+    let signing_vault = Pkcs11SigningVault::create(config_from_env(None)).await?;
+    let handle = signing_vault
+        .generate_signing_secret_key(SigningKeyType::ECDSASHA256CurveP256)
+        .await?;
+    let message = b"hello world";
+    let signature = signing_vault.sign(&handle, message.as_slice()).await?;
+    let public_key = signing_vault.get_verifying_public_key(&handle).await?;
+
+    let verifier = SoftwareVaultForVerifyingSignatures::new();
+    assert!(
+        verifier
+            .verify_signature(&public_key, message, &signature)
+            .await?
+    );
+
+    signing_vault.delete_signing_secret_key(handle).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_keys_management() -> Result<()> {
+    let signing_vault = Pkcs11SigningVault::create(config_from_env(None)).await?;
+
+    let number_of_keys1 = signing_vault.number_of_keys().await?;
+
+    let handle = signing_vault
+        .generate_signing_secret_key(SigningKeyType::ECDSASHA256CurveP256)
+        .await?;
+
+    let number_of_keys2 = signing_vault.number_of_keys().await?;
+    assert_eq!(number_of_keys1 + 1, number_of_keys2);
+
+    // Keys are persisted on the token
+    let signing_vault2 = Pkcs11SigningVault::create(config_from_env(None)).await?;
+    assert!(signing_vault2.keys().contains(&handle));
+
+    let public_key = signing_vault.get_verifying_public_key(&handle).await?;
+
+    let handle2 = signing_vault.get_secret_key_handle(&public_key).await?;
+    assert_eq!(handle, handle2);
+
+    signing_vault.delete_signing_secret_key(handle).await?;
+    let number_of_keys3 = signing_vault.number_of_keys().await?;
+    assert_eq!(number_of_keys2, number_of_keys3 + 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_unsupported_key_type() -> Result<()> {
+    let signing_vault = Pkcs11SigningVault::create(config_from_env(None)).await?;
+
+    let res = signing_vault
+        .generate_signing_secret_key(SigningKeyType::EdDSACurve25519)
+        .await;
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_incorrect_pin() -> Result<()> {
+    let res = Pkcs11SigningVault::create(config_from_env(Some("incorrect pin"))).await;
+    let err = res.err().expect("the login should fail");
+    assert_eq!(err.code().kind, Kind::Io);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_module() -> Result<()> {
+    let config = Pkcs11Config::new("/nonexistent/libpkcs11.so", 0, "1234");
+    let res = Pkcs11SigningVault::create(config).await;
+    assert!(res.is_err());
+
+    Ok(())
+}